@@ -2,7 +2,66 @@
 
 use pagegraph::graph::PageGraph;
 
-pub fn main(graph: &PageGraph, filter_rules: Vec<String>) {
-    let matching_elements = graph.resources_matching_filters(graph, filter_rules);
-    println!("{}", serde_json::to_string(&matching_elements).unwrap())
+use adblock::Engine;
+use adblock::resources::Resource;
+
+use std::path::Path;
+
+use crate::output::{self, Format};
+
+/// Builds an engine for the filterlist at `filterlist_path`, reusing the serialized engine at
+/// `cache_path` if it was written after the filterlist was last modified. Otherwise, the engine is
+/// built from `filter_rules` and serialized to `cache_path` for subsequent runs.
+pub fn cached_engine(filter_rules: &[String], filterlist_path: &str, cache_path: &str) -> Engine {
+    let modified = |path: &str| Path::new(path).metadata().and_then(|metadata| metadata.modified()).ok();
+    let cache_is_fresh = match (modified(cache_path), modified(filterlist_path)) {
+        (Some(cache_time), Some(filterlist_time)) => cache_time >= filterlist_time,
+        _ => false,
+    };
+
+    if cache_is_fresh {
+        let data = std::fs::read(cache_path).unwrap_or_else(|e| panic!("Could not read {}: {}", cache_path, e));
+        let mut engine = Engine::new(true);
+        match engine.deserialize(&data) {
+            Ok(()) => return engine,
+            Err(e) => eprintln!("Rebuilding engine, since the cached engine could not be deserialized: {:?}", e),
+        }
+    }
+
+    let engine = Engine::from_rules_debug(filter_rules, Default::default());
+    let data = engine.serialize_raw().expect("Could not serialize engine");
+    if let Err(e) = std::fs::write(cache_path, data) {
+        eprintln!("Could not write engine cache {}: {}", cache_path, e);
+    }
+    engine
+}
+
+/// Reads a JSON list of redirect resources, in the format used by `adblock-resources`.
+pub fn read_resources(path: &str) -> Vec<Resource> {
+    let data = std::fs::read(path).unwrap_or_else(|e| panic!("Could not read {}: {}", path, e));
+    serde_json::from_slice(&data).unwrap_or_else(|e| panic!("Could not parse resources from {}: {}", path, e))
+}
+
+/// What to report about the filters matching the graph.
+pub enum Report {
+    /// Each matching resource, with its requests.
+    Resources,
+    /// Each matching type of request to each resource, with the filters involved.
+    Detailed,
+    /// The page and its frames, matched against `$document` and `$subdocument` rules.
+    Frames,
+    /// Redirected requests, using the resources already loaded into the engine.
+    Redirects,
+    /// Redirected requests, using empty placeholders for the resources named by these rules.
+    PlaceholderRedirects(Vec<String>),
+}
+
+pub fn main(graph: &PageGraph, engine: &Engine, report: Report, format: Format) {
+    match report {
+        Report::Resources => output::print(&graph.resources_matching_engine(engine, false), format),
+        Report::Detailed => output::print(&graph.resources_matching_engine_detailed(engine), format),
+        Report::Frames => output::print(&graph.frames_matching_engine(engine), format),
+        Report::Redirects => output::print(&graph.redirected_requests(engine), format),
+        Report::PlaceholderRedirects(filter_rules) => output::print(&graph.redirects_matching_filters(filter_rules), format),
+    }
 }
@@ -0,0 +1,101 @@
+//! Runs a subcommand over every graph in a directory, printing one JSON line per graph with the
+//! file, the page's URL, and the subcommand's result.
+//!
+//! Each graph is handled by a separate run of this executable, so that a graph which can't be read
+//! or queried only fails its own line. Lines are printed as each run finishes, so they are not in
+//! any particular order.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc;
+
+use pagegraph::from_xml::read_descriptor;
+use rayon::prelude::*;
+use serde_json::value::RawValue;
+
+#[derive(serde::Serialize)]
+struct GraphResult {
+    file: String,
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Box<RawValue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Whether a file holds the graph of a remote frame, written as
+/// `page_graph_{frame_id}.{document}.graphml` alongside the graph of the page that embedded it.
+fn is_frame_graph(file_name: &str) -> bool {
+    let parts = file_name.strip_prefix("page_graph_")
+        .and_then(|rest| rest.strip_suffix(".graphml"))
+        .and_then(|rest| rest.split_once('.'));
+    match parts {
+        Some((frame_id, document)) => frame_id.len() == 32
+            && frame_id.chars().all(|c| c.is_ascii_hexdigit())
+            && !document.is_empty()
+            && document.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+/// Finds every page graph in a directory and its subdirectories, in path order, skipping the
+/// graphs of remote frames, which are merged into their page's graph when it is read.
+fn discover_graphs(dir: &Path) -> Vec<PathBuf> {
+    let mut graphs = vec![];
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir).unwrap_or_else(|e| {
+            eprintln!("Could not read {}: {}", dir.display(), e);
+            std::process::exit(1);
+        });
+        entries.filter_map(Result::ok).for_each(|entry| {
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if path.is_dir() {
+                pending.push(path);
+            } else if file_name.ends_with(".graphml") && !is_frame_graph(&file_name) {
+                graphs.push(path);
+            }
+        });
+    }
+    graphs.sort_unstable();
+    graphs
+}
+
+fn run(file: &Path, args: &[OsString]) -> GraphResult {
+    let file_str = file.to_string_lossy().into_owned();
+    // Only the graph's description is read here, leaving the rest of the graph to the subcommand.
+    let url = read_descriptor(&file_str).ok().map(|desc| desc.url);
+    let output = std::env::current_exe().and_then(|exe| Command::new(exe).arg("-f").arg(file).args(args).output());
+    let (result, error) = match output {
+        Ok(output) if output.status.success() => {
+            // Subcommands that print text rather than JSON have it kept as a string.
+            let stdout = String::from_utf8_lossy(&output.stdout).trim_end().to_string();
+            let result = RawValue::from_string(stdout.clone())
+                .or_else(|_| RawValue::from_string(serde_json::to_string(&stdout).unwrap()))
+                .unwrap();
+            (Some(result), None)
+        }
+        Ok(output) => (None, Some(String::from_utf8_lossy(&output.stderr).trim_end().to_string())),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    GraphResult { file: file_str, url, result, error }
+}
+
+/// Runs the subcommand given by `args`, which hold every command line argument other than the
+/// graph directory, over each graph in `dir`.
+pub fn main(dir: &Path, mut args: Vec<OsString>) {
+    // Subcommands that print text by default should print something that can be nested instead.
+    if !args.iter().any(|arg| arg == "--format" || arg.to_string_lossy().starts_with("--format=")) {
+        args.extend(["--format".into(), "json".into()]);
+    }
+    let graphs = discover_graphs(dir);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        let args = &args;
+        scope.spawn(move || graphs.par_iter().for_each_with(sender, |sender, file| sender.send(run(file, args)).unwrap()));
+        receiver.iter().for_each(|result| println!("{}", serde_json::to_string(&result).unwrap()));
+    });
+}
+
@@ -0,0 +1,15 @@
+//! Prints out the graph of Web API and JS builtin calls made by each script, as JSON or DOT.
+
+use pagegraph::graph::PageGraph;
+use pagegraph::export;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, as_dot: bool, format: Format) {
+    let call_graph = graph.js_call_graph();
+    if as_dot {
+        print!("{}", export::call_graph_to_dot(&call_graph, Default::default()));
+    } else {
+        output::print(&call_graph, format);
+    }
+}
@@ -0,0 +1,15 @@
+//! Prints out every script ranked by how central it is to the page's interactions, as JSON or
+//! CSV.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, format: Format) {
+    let scripts = graph.script_centrality();
+    if format == Format::Csv {
+        pagegraph::export::centrality_to_csv(&scripts, std::io::stdout().lock()).expect("Could not write CSV");
+    } else {
+        output::print(&scripts, format);
+    }
+}
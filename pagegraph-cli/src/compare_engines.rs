@@ -0,0 +1,32 @@
+//! Runs two adblock engines against the graph, and prints out an A/B classification for each
+//! request.
+
+use pagegraph::graph::PageGraph;
+
+use adblock::Engine;
+
+use std::io::Read;
+
+use crate::output::{self, Format};
+
+/// Builds an engine from either a newline-separated filterlist or an engine serialized with
+/// `Engine::serialize_raw`.
+pub fn engine_from_file(path: &str, serialized: bool) -> Engine {
+    let mut file = std::fs::File::open(path).unwrap_or_else(|e| panic!("Could not open {}: {}", path, e));
+    if serialized {
+        let mut data = vec![];
+        file.read_to_end(&mut data).expect("Could not read serialized engine");
+        let mut engine = Engine::new(true);
+        engine.deserialize(&data).expect("Could not deserialize engine");
+        engine
+    } else {
+        let mut rules = String::new();
+        file.read_to_string(&mut rules).expect("Could not read filterlist");
+        Engine::from_rules_debug(rules.lines(), Default::default())
+    }
+}
+
+pub fn main(graph: &PageGraph, engine_a: &Engine, engine_b: &Engine, format: Format) {
+    let comparisons = graph.compare_engines(engine_a, engine_b);
+    output::print(&comparisons, format);
+}
@@ -0,0 +1,10 @@
+//! Prints out the identifiers shared between third parties in request URLs, and the sites
+//! sharing them.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, format: Format) {
+    output::print(&graph.shared_identifiers(), format);
+}
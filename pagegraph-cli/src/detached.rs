@@ -0,0 +1,10 @@
+//! Prints out every element and text node that was created but never inserted into a document,
+//! and the script or parser that created it.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, format: Format) {
+    output::print(&graph.detached_elements(), format);
+}
@@ -0,0 +1,13 @@
+//! Prints out the changes between two captures of the same page as a JSON changeset.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(before: &PageGraph, after: &PageGraph, format: Format) {
+    let changes = pagegraph::diff::diff(before, after).unwrap_or_else(|e| {
+        eprintln!("Could not compare graphs: {}", e);
+        std::process::exit(1);
+    });
+    output::print(&changes, format);
+}
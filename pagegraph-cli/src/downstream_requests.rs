@@ -1,11 +1,13 @@
-//! Prints out all downstream network requests of a given edge from the graph.
+//! Prints out all downstream network requests of a given edge or node from the graph.
 
-use pagegraph::{graph::{EdgeId, PageGraph}, types::EdgeType};
+use pagegraph::{graph::{EdgeId, FrameId, NodeId, PageGraph}, types::EdgeType};
 use pagegraph::graph::DownstreamRequests;
 use pagegraph::types::{NodeType, RequestType};
 use std::collections::HashSet;
 
-pub fn main(graph: &PageGraph, edge_id: EdgeId, just_requests: bool) {
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, edge_id: EdgeId, just_requests: bool, max_depth: usize, format: Format) {
     let edge = graph.edges.get(&edge_id).unwrap();
     if just_requests {
         let mut request_ids = HashSet::new();
@@ -25,11 +27,15 @@ pub fn main(graph: &PageGraph, edge_id: EdgeId, just_requests: bool) {
                     }
                 }
             });
-        println!("{}", serde_json::to_string(&request_ids).unwrap());
+        output::print(&request_ids, format);
         return;
     }
     let all_downstream_requests = graph
-        .all_downstream_requests_nested(graph.edges.get(&edge_id).unwrap());
+        .all_downstream_requests_nested(graph.edges.get(&edge_id).unwrap(), max_depth)
+        .unwrap_or_else(|e| {
+            eprintln!("Could not find downstream requests of {}: {}", edge_id, e);
+            std::process::exit(1);
+        });
     let node = graph.target_node(edge);
     let url = match &node.node_type {
         NodeType::Resource { url } => url,
@@ -44,8 +50,47 @@ pub fn main(graph: &PageGraph, edge_id: EdgeId, just_requests: bool) {
                 node_id: node.id,
                 children: all_downstream_requests
             };
-            println!("{}", serde_json::to_string(&top_level).unwrap());
+            output::print(&top_level, format);
         },
         _ => panic!("Edge is not a RequestStart!")
     };
 }
+
+/// Reports the downstream requests of the `RequestStart` edge with the given request id. The root
+/// frame may be given either by its id or as `None`.
+pub fn main_request(graph: &PageGraph, request_id: usize, frame_id: Option<FrameId>, just_requests: bool, max_depth: usize, format: Format) {
+    let frame_id = frame_id.filter(|frame_id| *frame_id != graph.desc.frame_id);
+    let start = graph.edges_for_request_id(request_id, frame_id)
+        .into_iter()
+        .find(|edge| matches!(edge.edge_type, EdgeType::RequestStart { .. }))
+        .unwrap_or_else(|| {
+            eprintln!("No RequestStart edge for request id {} in {}", request_id, frame_id.map(|frame_id| format!("frame {}", frame_id)).unwrap_or_else(|| "the root frame".to_string()));
+            std::process::exit(1);
+        });
+    main(graph, start.id, just_requests, max_depth, format);
+}
+
+pub fn main_node(graph: &PageGraph, node_id: NodeId, just_requests: bool, max_depth: usize, format: Format) {
+    let node = graph.nodes.get(&node_id).unwrap_or_else(|| {
+        eprintln!("No node with id {} exists in the graph", node_id);
+        std::process::exit(1);
+    });
+    if just_requests {
+        let request_ids = graph.all_downstream_effects_of_node(node)
+            .into_iter()
+            .filter_map(|edge| match &edge.edge_type {
+                EdgeType::RequestStart { request_id, request_type: RequestType::Script, .. } => Some(request_id),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        output::print(&request_ids, format);
+        return;
+    }
+    let all_downstream_requests = graph
+        .all_downstream_requests_of_node_nested(node, max_depth)
+        .unwrap_or_else(|e| {
+            eprintln!("Could not find downstream requests of {}: {}", node_id, e);
+            std::process::exit(1);
+        });
+    output::print(&all_downstream_requests, format);
+}
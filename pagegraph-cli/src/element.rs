@@ -0,0 +1,82 @@
+//! Prints out every modification made to an HTML element, and the values its attributes held
+//! over time.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use pagegraph::graph::{NodeId, PageGraph};
+use pagegraph::types::{EdgeType, NodeType};
+
+use crate::output::{self, Format};
+
+#[derive(serde::Serialize)]
+struct Modification {
+    edge_id: String,
+    timestamp: Option<isize>,
+    edge_type: String,
+    actor: String,
+    /// The modified node, which is the element itself unless `--subtree` was given.
+    target: String,
+}
+
+#[derive(serde::Serialize)]
+struct AttributeValue {
+    timestamp: isize,
+    /// `None` if the attribute was deleted.
+    value: Option<String>,
+    actor: String,
+}
+
+#[derive(serde::Serialize)]
+struct ElementInfo {
+    node_id: String,
+    tag_name: String,
+    modifications: Vec<Modification>,
+    attributes: BTreeMap<String, Vec<AttributeValue>>,
+}
+
+pub fn main(graph: &PageGraph, node_id: NodeId, attr: Option<&str>, subtree: bool, format: Format) {
+    let element = graph.nodes.get(&node_id).expect("No node with the given id");
+    let tag_name = match &element.node_type {
+        NodeType::HtmlElement { tag_name, .. } => tag_name.clone(),
+        _ => panic!("Node is not an HTML element"),
+    };
+
+    let modifications = if subtree {
+        graph.all_subtree_modifications(node_id)
+    } else {
+        graph.all_html_element_modifications(node_id)
+    };
+    let modifications = modifications.into_iter().map(|(edge_id, edge)| Modification {
+        edge_id: format!("{}", edge_id),
+        timestamp: edge.edge_timestamp.map(|timestamp| timestamp.as_millis()),
+        edge_type: edge.edge_type.type_name().to_string(),
+        actor: format!("{}", edge.source),
+        target: format!("{}", edge.target),
+    }).collect::<Vec<_>>();
+
+    // Report the requested attribute, or every attribute that was ever set or deleted
+    let attrs = match attr {
+        Some(attr) => vec![attr.to_ascii_lowercase()],
+        None => graph.incoming_edges(element).filter_map(|edge| match &edge.edge_type {
+            EdgeType::SetAttribute { key, is_style: false, .. } |
+            EdgeType::DeleteAttribute { key, is_style: false } => Some(key.to_ascii_lowercase()),
+            _ => None,
+        }).collect::<BTreeSet<_>>().into_iter().collect(),
+    };
+    let attributes = attrs.into_iter().map(|attr| {
+        let history = graph.attribute_history(node_id, &attr).into_iter().map(|(timestamp, value, actor)| AttributeValue {
+            timestamp: timestamp.as_millis(),
+            value,
+            actor: format!("{}", actor),
+        }).collect();
+        (attr, history)
+    }).collect();
+
+    let info = ElementInfo {
+        node_id: format!("{}", node_id),
+        tag_name,
+        modifications,
+        attributes,
+    };
+    output::print(&info, format);
+}
@@ -0,0 +1,11 @@
+//! Prints out the requests, scripts, and storage accesses attributed to each entity in an entity
+//! map, as JSON.
+
+use pagegraph::entities::EntityMap;
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, entities: &EntityMap, format: Format) {
+    output::print(&graph.entity_report(entities), format);
+}
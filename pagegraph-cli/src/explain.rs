@@ -0,0 +1,31 @@
+//! Prints out the chain of actions that led to the request with a given request ID.
+
+use pagegraph::graph::{FrameId, NodeSummary, PageGraph};
+
+use crate::output::{self, Format};
+
+fn describe(node: &NodeSummary) -> String {
+    match &node.detail {
+        Some(detail) => format!("{} {} ({})", node.node_type, node.node_id, detail),
+        None => format!("{} {}", node.node_type, node.node_id),
+    }
+}
+
+pub fn main(graph: &PageGraph, request_id: usize, frame_id: Option<FrameId>, as_text: bool, format: Format) {
+    let explanation = graph.explain_request(request_id, frame_id).expect("No RequestStart edge for request id");
+
+    if !as_text {
+        output::print(&explanation, format);
+        return;
+    }
+
+    println!("Request {} for {} ({})", explanation.request_id, explanation.url, explanation.request_type.as_str());
+    if let Some(first) = explanation.chain.first() {
+        println!("  {}", describe(&first.actor));
+    }
+    explanation.chain.iter().enumerate().for_each(|(depth, step)| {
+        let indent = "  ".repeat(depth + 2);
+        let timestamp = step.timestamp.map(|timestamp| format!(" at {}", timestamp)).unwrap_or_default();
+        println!("{}{} {}{} -> {}", indent, step.action, step.edge_id, timestamp, describe(&step.target));
+    });
+}
@@ -0,0 +1,23 @@
+//! Writes the graph out in another format, into an output directory.
+
+use pagegraph::graph::PageGraph;
+use pagegraph::export;
+
+use std::path::Path;
+
+pub fn main(graph: &PageGraph, format: &str, out_dir: &Path) {
+    std::fs::create_dir_all(out_dir).expect("Could not create output directory");
+
+    let write = |file: &str, contents: String| {
+        std::fs::write(out_dir.join(file), contents).unwrap_or_else(|e| panic!("Could not write {}: {}", file, e));
+    };
+
+    match format {
+        "csv" => export::to_csv(graph, out_dir).expect("Could not write CSV files"),
+        "dot" => write("graph.dot", export::to_dot(graph, Default::default())),
+        "gexf" => write("graph.gexf", export::to_gexf(graph)),
+        "har" => write("graph.har", export::to_har(graph)),
+        "trace" => write("trace.json", export::to_trace_events(graph)),
+        _ => unreachable!(),
+    }
+}
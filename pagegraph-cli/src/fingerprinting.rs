@@ -0,0 +1,17 @@
+//! Prints out the scripts that appear to fingerprint the browser, with the calls supporting each
+//! technique they use, or just those fingerprinting with a canvas.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, min_score: f64, canvas_only: bool, format: Format) {
+    if canvas_only {
+        output::print(&graph.canvas_fingerprinting_scripts(), format);
+        return;
+    }
+    let scores = graph.fingerprinting_scores().into_iter()
+        .filter(|score| score.score >= min_score)
+        .collect::<Vec<_>>();
+    output::print(&scores, format);
+}
@@ -0,0 +1,34 @@
+//! Prints out the tree of documents, frame owners, and remote frames in the page.
+
+use pagegraph::graph::{FrameTreeNode, PageGraph};
+
+use crate::output::{self, Format};
+
+fn print_node(node: &FrameTreeNode, depth: usize) {
+    let mut line = format!("{}{} {}", "  ".repeat(depth), node.node_id, node.node_type);
+    if let Some(tag_name) = &node.tag_name {
+        line.push_str(&format!(" <{}>", tag_name));
+    }
+    if let Some(frame_id) = &node.frame_id {
+        line.push_str(&format!(" frame {}", frame_id));
+    }
+    match node.merged {
+        Some(true) => line.push_str(" (merged)"),
+        Some(false) => line.push_str(" (not merged)"),
+        None => {}
+    }
+    if let Some(url) = &node.url {
+        line.push_str(&format!(" {}", url));
+    }
+    println!("{}", line);
+    node.children.iter().for_each(|child| print_node(child, depth + 1));
+}
+
+/// Prints the tree as indented text, unless an output format is given.
+pub fn main(graph: &PageGraph, format: Option<Format>) {
+    let tree = graph.frame_tree();
+    match format {
+        Some(format) => output::print(&tree, format),
+        None => tree.iter().for_each(|root| print_node(root, 0)),
+    }
+}
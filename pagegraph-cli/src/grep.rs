@@ -0,0 +1,17 @@
+//! Prints out every resource, and optionally every script and DOM root, whose URL matches a
+//! pattern, or every script whose source does.
+
+use pagegraph::graph::PageGraph;
+use pagegraph::search::{Pattern, UrlSearchOptions};
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, pattern: Pattern, options: UrlSearchOptions, format: Format) {
+    let matches = graph.find_resources_matching(&pattern, &options);
+    output::print(&matches, format);
+}
+
+pub fn main_source(graph: &PageGraph, pattern: Pattern, decode_entities: bool, format: Format) {
+    let matches = graph.scripts_containing(&pattern, decode_entities);
+    output::print(&matches, format);
+}
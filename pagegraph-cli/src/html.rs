@@ -0,0 +1,35 @@
+//! Prints out the reconstructed final markup of the page, or of a single node within it.
+
+use pagegraph::graph::{NodeId, PageGraph};
+use pagegraph::types::NodeType;
+
+use crate::output::{self, Format};
+
+#[derive(serde::Serialize)]
+struct Markup {
+    node_id: String,
+    html: String,
+}
+
+/// Prints the markup as is, unless an output format is given.
+pub fn main(graph: &PageGraph, node_id: Option<NodeId>, format: Option<Format>) {
+    let tree = graph.final_dom();
+
+    // Without a node, print the document loaded at the page's URL, or failing that, the first one
+    let node_id = node_id.or_else(|| {
+        tree.roots.iter().copied().find(|root| matches!(&graph.nodes[root].node_type,
+            NodeType::DomRoot { url: Some(url), .. } if *url == graph.root_url()))
+            .or_else(|| tree.roots.first().copied())
+    });
+
+    match node_id.and_then(|node_id| Some((node_id, tree.to_html(node_id)?))) {
+        Some((node_id, html)) => match format {
+            Some(format) => output::print(&Markup { node_id: format!("{}", node_id), html }, format),
+            None => println!("{}", html),
+        },
+        None => {
+            eprintln!("No such element, text node, or document in the graph");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Prints out everything the graph records about a single node or edge.
+
+use std::convert::TryFrom;
+
+use pagegraph::graph::{Edge, EdgeId, HasFrameId, Node, NodeId, NodeSummary, PageGraph, Timestamp};
+use pagegraph::types::{EdgeType, NodeType};
+
+use crate::output::{self, Format};
+
+/// A node or edge id to look for, as parsed by [`parse_id`].
+pub enum ItemId {
+    Node(NodeId),
+    Edge(EdgeId),
+}
+
+/// Parses an id given on the command line. Ids prefixed with `n` or `e` refer to a node or an edge
+/// respectively, while unprefixed ids refer to either, with nodes checked first. Any id may be
+/// qualified with a frame id, as in `n123:0123...F`.
+pub fn parse_id(id: &str) -> Option<Vec<ItemId>> {
+    if id.starts_with('n') {
+        Some(vec![ItemId::Node(NodeId::try_from(id).ok()?)])
+    } else if id.starts_with('e') {
+        Some(vec![ItemId::Edge(EdgeId::try_from(id).ok()?)])
+    } else {
+        Some(vec![
+            ItemId::Node(NodeId::try_from(format!("n{}", id).as_str()).ok()?),
+            ItemId::Edge(EdgeId::try_from(format!("e{}", id).as_str()).ok()?),
+        ])
+    }
+}
+
+#[derive(serde::Serialize)]
+struct EdgeInfo<'a> {
+    edge_id: String,
+    frame_id: String,
+    timestamp: Option<Timestamp>,
+    edge_type: &'a str,
+    attributes: &'a EdgeType,
+    source: NodeSummary,
+    target: NodeSummary,
+}
+
+#[derive(serde::Serialize)]
+struct NodeInfo<'a> {
+    node_id: String,
+    frame_id: String,
+    timestamp: Timestamp,
+    node_type: &'a str,
+    attributes: &'a NodeType,
+    incoming_edges: Vec<EdgeInfo<'a>>,
+    outgoing_edges: Vec<EdgeInfo<'a>>,
+    /// Resources and scripts directly connected to the node, in node id order.
+    connected_resources: Vec<NodeSummary>,
+    connected_scripts: Vec<NodeSummary>,
+}
+
+enum Found<'a> {
+    Node(&'a Node),
+    Edge(&'a Edge),
+}
+
+impl Found<'_> {
+    fn id(&self) -> String {
+        match self {
+            Found::Node(node) => format!("{}", node.id),
+            Found::Edge(edge) => format!("{}", edge.id),
+        }
+    }
+}
+
+/// Finds the items with exactly the given id, or if it names no frame and matches nothing in the
+/// root frame, the items with the same id in any merged frame.
+fn find<'a>(graph: &'a PageGraph, item_id: &ItemId) -> Vec<Found<'a>> {
+    match item_id {
+        ItemId::Node(node_id) => match graph.nodes.get(node_id) {
+            Some(node) => vec![Found::Node(node)],
+            None if node_id.get_frame_id().is_none() => graph.nodes.values()
+                .filter(|node| node.id.get_frame_id().is_some_and(|frame_id| node_id.copy_for_frame_document(&frame_id, node.id.get_document()) == node.id))
+                .map(Found::Node)
+                .collect(),
+            None => vec![],
+        },
+        ItemId::Edge(edge_id) => match graph.edges.get(edge_id) {
+            Some(edge) => vec![Found::Edge(edge)],
+            None if edge_id.get_frame_id().is_none() => graph.edges.values()
+                .filter(|edge| edge.id.get_frame_id().is_some_and(|frame_id| edge_id.copy_for_frame_document(&frame_id, edge.id.get_document()) == edge.id))
+                .map(Found::Edge)
+                .collect(),
+            None => vec![],
+        },
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Identified<'a> {
+    Node(NodeInfo<'a>),
+    Edge(EdgeInfo<'a>),
+}
+
+fn edge_info<'a>(graph: &'a PageGraph, edge: &'a Edge) -> EdgeInfo<'a> {
+    EdgeInfo {
+        edge_id: format!("{}", edge.id),
+        frame_id: format!("{}", edge.id.get_frame_id().unwrap_or(graph.desc.frame_id)),
+        timestamp: edge.edge_timestamp,
+        edge_type: edge.edge_type.type_name(),
+        attributes: &edge.edge_type,
+        source: NodeSummary::new(graph.source_node(edge)),
+        target: NodeSummary::new(graph.target_node(edge)),
+    }
+}
+
+fn node_info<'a>(graph: &'a PageGraph, node: &'a Node) -> NodeInfo<'a> {
+    let mut neighbors = graph.incoming_edges(node).map(|edge| graph.source_node(edge))
+        .chain(graph.outgoing_edges(node).map(|edge| graph.target_node(edge)))
+        .collect::<Vec<_>>();
+    neighbors.sort_unstable_by_key(|neighbor| neighbor.id);
+    neighbors.dedup_by_key(|neighbor| neighbor.id);
+    let connected = |matches: fn(&NodeType) -> bool| neighbors.iter()
+        .filter(|neighbor| matches(&neighbor.node_type))
+        .map(|neighbor| NodeSummary::new(neighbor))
+        .collect::<Vec<_>>();
+
+    NodeInfo {
+        node_id: format!("{}", node.id),
+        frame_id: format!("{}", node.id.get_frame_id().unwrap_or(graph.desc.frame_id)),
+        timestamp: node.node_timestamp,
+        node_type: node.node_type.type_name(),
+        attributes: &node.node_type,
+        incoming_edges: graph.incoming_edges(node).map(|edge| edge_info(graph, edge)).collect(),
+        outgoing_edges: graph.outgoing_edges(node).map(|edge| edge_info(graph, edge)).collect(),
+        connected_resources: connected(|node_type| matches!(node_type, NodeType::Resource { .. })),
+        connected_scripts: connected(|node_type| matches!(node_type, NodeType::Script { .. })),
+    }
+}
+
+fn print_text(graph: &PageGraph, found: Found) {
+    match found {
+        Found::Node(node) => {
+            println!("Node {}", node.id);
+            println!("Timestamp: {}", node.node_timestamp);
+            println!("Type: {:?}", node.node_type);
+
+            println!();
+            println!("Incoming edges");
+            graph.incoming_edges(node).for_each(|edge| {
+                println!("  {:?}", edge.id);
+                println!("    Timestamp: {:?}", edge.edge_timestamp);
+                println!("    Type: {:?}", edge.edge_type);
+            });
+
+            println!();
+            println!("Outgoing edges");
+            graph.outgoing_edges(node).for_each(|edge| {
+                println!("  {:?}", edge.id);
+                println!("    Timestamp: {:?}", edge.edge_timestamp);
+                println!("    Type: {:?}", edge.edge_type);
+            });
+        }
+        Found::Edge(edge) => {
+            println!("Edge {}", edge.id);
+            println!("Timestamp: {:?}", edge.edge_timestamp);
+            println!("Type: {:?}", edge.edge_type);
+
+            println!();
+            println!("Source node");
+            let source_node = graph.source_node(edge);
+            println!("  {:?}", source_node.id);
+            println!("    Timestamp: {:?}", source_node.node_timestamp);
+            println!("    Type: {:?}", source_node.node_type);
+
+            println!();
+            println!("Target node");
+            let target_node = graph.target_node(edge);
+            println!("  {:?}", target_node.id);
+            println!("    Timestamp: {:?}", target_node.node_timestamp);
+            println!("    Type: {:?}", target_node.node_type);
+        }
+    }
+}
+
+/// Prints the item as text, unless an output format is given.
+pub fn main(graph: &PageGraph, id: &str, item_ids: &[ItemId], format: Option<Format>) {
+    let mut found = item_ids.iter()
+        .map(|item_id| find(graph, item_id))
+        .find(|found| !found.is_empty())
+        .unwrap_or_default();
+    let message = match found.len() {
+        0 => format!("No node or edge with id {} was found in this graph.", id),
+        1 => String::new(),
+        _ => {
+            let mut candidates = found.iter().map(Found::id).collect::<Vec<_>>();
+            candidates.sort_unstable();
+            format!("Id {} matches items in several merged frames, qualify it with one of: {}", id, candidates.join(", "))
+        }
+    };
+    if found.len() != 1 {
+        if format.is_some() {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+        println!("{}", message);
+        return;
+    }
+    let found = found.remove(0);
+
+    match format {
+        Some(format) => {
+            let identified = match found {
+                Found::Node(node) => Identified::Node(node_info(graph, node)),
+                Found::Edge(edge) => Identified::Edge(edge_info(graph, edge)),
+            };
+            output::print(&identified, format);
+        }
+        None => print_text(graph, found),
+    }
+}
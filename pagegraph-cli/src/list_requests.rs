@@ -0,0 +1,31 @@
+//! Prints out every request id in the graph, with the frame and document, URL, type, and final status of its
+//! request, as JSON.
+
+use pagegraph::{graph::PageGraph, types::RequestType};
+
+use crate::output::{self, Format};
+
+#[derive(serde::Serialize)]
+struct RequestSummary {
+    request_id: usize,
+    /// The merged remote frame the request was made in, or `None` for the root frame.
+    frame_id: Option<String>,
+    /// The document of the frame the request was made in, in navigation order.
+    document: usize,
+    url: String,
+    request_type: RequestType,
+    /// The status the request finished with, or `None` if it never finished.
+    status: Option<String>,
+}
+
+pub fn main(graph: &PageGraph, format: Format) {
+    let requests = graph.all_request_ids().into_iter().map(|(request_id, frame_id, document, url, request_type, status)| RequestSummary {
+        request_id,
+        frame_id: frame_id.map(|frame_id| format!("{}", frame_id)),
+        document,
+        url,
+        request_type,
+        status,
+    }).collect::<Vec<_>>();
+    output::print(&requests, format);
+}
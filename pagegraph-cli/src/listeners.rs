@@ -0,0 +1,10 @@
+//! Prints out every event listener registered during the recording, and whether each was removed
+//! or fired.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, format: Format) {
+    output::print(&graph.event_listeners(), format);
+}
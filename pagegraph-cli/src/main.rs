@@ -1,7 +1,7 @@
 //! CLI for pagegraph-rust
 
-use pagegraph::from_xml::read_from_file;
-use pagegraph::graph::{EdgeId, FrameId};
+use pagegraph::from_xml::{merge_frames, read_from_file_with_options, FilesystemFrameLoader, ParseOptions};
+use pagegraph::graph::{EdgeId, FrameId, PageGraph};
 
 use clap::{App, Arg, SubCommand};
 use std::fs::File;
@@ -10,6 +10,95 @@ use std::io::{BufReader, BufRead};
 mod adblock_rules;
 mod request_id_info;
 mod downstream_requests;
+mod compare_engines;
+mod report;
+mod export;
+mod explain;
+mod diff;
+mod suggest_rules;
+mod script;
+mod storage;
+mod cookie_sync;
+mod fingerprinting;
+mod call_graph;
+mod listeners;
+mod html;
+mod element;
+mod detached;
+mod pixels;
+mod frames;
+mod requests;
+mod list_requests;
+mod resource;
+mod grep;
+mod entities;
+mod weight;
+mod third_parties;
+mod stats;
+mod timeline;
+mod centrality;
+mod path;
+mod identify;
+mod output;
+mod batch;
+
+/// Reads a newline-separated list of adblock rules from a file.
+fn read_filterlist(path: &str) -> Vec<String> {
+    let file = File::open(path).unwrap();
+    let reader = BufReader::new(file);
+    reader.lines()
+        .map(|l| l.expect("Could not parse line"))
+        .collect()
+}
+
+/// Reads an entity map from a file, exiting if it cannot be read.
+fn read_entity_map(path: &str) -> pagegraph::entities::EntityMap {
+    let json = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Could not read {}: {}", path, e);
+        std::process::exit(1);
+    });
+    pagegraph::entities::EntityMap::from_json(&json).unwrap_or_else(|e| {
+        eprintln!("{} {}", path, e);
+        std::process::exit(1);
+    })
+}
+
+/// Which parts of a page's graph to read.
+struct GraphScope {
+    /// Whether to merge in the graphs of remote frames recorded alongside it.
+    merge_frames: bool,
+    /// A single frame to keep, dropping everything recorded by other frames.
+    frame: Option<FrameId>,
+}
+
+/// Reads the parts of a graph selected by `scope`, exiting if it cannot be read.
+fn read_graph(graph_file: &str, parse_options: &ParseOptions, scope: &GraphScope) -> PageGraph {
+    let mut graph = read_from_file_with_options(graph_file, parse_options).unwrap_or_else(|e| {
+        eprintln!("Could not read {}: {}", graph_file, e);
+        std::process::exit(1);
+    });
+
+    if scope.merge_frames {
+        // We have to just ignore the remote frame's contents if we couldn't successfully record any.
+        let frame_loader = FilesystemFrameLoader::for_graph(graph_file, parse_options.clone());
+        merge_frames(&mut graph, &frame_loader).into_iter().for_each(|(remote_frame_id, e)| {
+            eprintln!("Skipping frame {}, which {}", remote_frame_id, e);
+        });
+    }
+
+    match scope.frame {
+        Some(frame_id) if frame_id == graph.desc.frame_id => graph.frame_subgraph(None),
+        Some(frame_id) => {
+            let frame = graph.frame_subgraph(Some(frame_id));
+            if frame.nodes.is_empty() {
+                eprintln!("Frame {} was not found in {}", frame_id, graph_file);
+                std::process::exit(1);
+            }
+            frame
+        }
+        None => graph,
+    }
+}
 
 fn main() {
     let matches = App::new("pagegraph-rust CLI")
@@ -19,11 +108,38 @@ fn main() {
             .value_name("FILE")
             .help("Set the graph to query")
             .takes_value(true)
-            .required(true))
+            .required_unless_present("graph_dir"))
+        .arg(Arg::with_name("graph_dir")
+            .long("graph-dir")
+            .value_name("DIR")
+            .help("Run the subcommand over every page graph in a directory, printing a JSON line for each")
+            .takes_value(true)
+            .conflicts_with("graph_file"))
+        .arg(Arg::with_name("no_merge_frames")
+            .long("no-merge-frames")
+            .help("Read only the page's own graph, without merging in the graphs of its remote frames")
+            .global(true)
+            .takes_value(false))
+        .arg(Arg::with_name("frame")
+            .long("frame")
+            .value_name("FRAME_ID")
+            .help("Restrict the graph to what was recorded by a single frame, which may be the page's own")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::with_name("strict")
+            .long("strict")
+            .help("Fail on node or edge types unknown to this version, rather than keeping them as unknown items")
+            .takes_value(false))
+        .arg(Arg::with_name("format")
+            .help("Output format for the results of the subcommand, which otherwise prints JSON, or text where that is its default")
+            .long("format")
+            .global(true)
+            .takes_value(true)
+            .possible_values(output::Format::NAMES))
         .subcommand(SubCommand::with_name("identify")
             .about("Check information about a particular node or edge id in the graph")
             .arg(Arg::with_name("id")
-                .help("Node or edge id")
+                .help("Node or edge id, optionally prefixed with n or e and qualified with a frame id")
                 .takes_value(true)
                 .required(true)))
         .subcommand(SubCommand::with_name("adblock_rules")
@@ -39,20 +155,67 @@ fn main() {
                 .long("list")
                 .required_unless("filter_rule")
                 .help("Set path to filterlist file (newline-separated adblock rules) to use")
-                .takes_value(true)))
+                .takes_value(true))
+            .arg(Arg::with_name("engine_cache")
+                .help("Cache the engine built from the filterlist in this file, reusing it while the filterlist is unchanged")
+                .long("engine-cache")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("path_to_filterlist"))
+            .arg(Arg::with_name("detailed")
+                .help("Report the matching filters, exceptions, and request start edges for each type of request to each resource")
+                .long("detailed")
+                .takes_value(false)
+                .required(false))
+            .arg(Arg::with_name("frames")
+                .help("Match the page and its frames against $document and $subdocument rules, rather than matching resources")
+                .long("frames")
+                .takes_value(false)
+                .conflicts_with("detailed")
+                .required(false))
+            .arg(Arg::with_name("redirects")
+                .help("Report the requests that $redirect rules would have answered with a replacement resource, and the activity that would not have occurred as a result")
+                .long("redirects")
+                .takes_value(false)
+                .conflicts_with_all(&["detailed", "frames"])
+                .required(false))
+            .arg(Arg::with_name("resources")
+                .help("Load redirect resources from this JSON file, rather than standing in an empty resource for each one named by the rules")
+                .long("resources")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("redirects")))
         .subcommand(SubCommand::with_name("downstream_requests")
-            .about("Find network requests initiated as a result of a given edge in the graph")
+            .about("Find network requests initiated as a result of a given edge or node in the graph")
             .arg(Arg::with_name("requests")
                 .help("Get just the list of downstream resource IDs")
                 .takes_value(false)
                 .short('r')
                 .long("requests")
                 .required(false))
-            .arg(Arg::with_name("edge_id")
-                .help("Edge id to check downstream requests for")
+            .arg(Arg::with_name("max_depth")
+                .help("Maximum depth of nested downstream requests to follow before giving up")
+                .long("max-depth")
+                .takes_value(true)
+                .value_name("DEPTH")
+                .default_value("64"))
+            .arg(Arg::with_name("id")
+                .help("Edge or node id to check downstream requests for")
                 .takes_value(true)
                 .value_name("ID")
-                .required(true)))
+                .required_unless_present("request_id"))
+            .arg(Arg::with_name("request_id")
+                .help("Blink request id to check downstream requests for, instead of an edge or node id")
+                .long("request-id")
+                .takes_value(true)
+                .value_name("REQUEST")
+                .conflicts_with("id"))
+            .arg(Arg::with_name("request_frame")
+                .help("Frame id that the request id is associated with, defaults to the root frame")
+                .long("request-frame")
+                .takes_value(true)
+                .value_name("FRAME")
+                .requires("request_id")))
         .subcommand(SubCommand::with_name("request_id_info")
             .about("Get all information from the graph associated with a particular Blink request id")
             .arg(Arg::with_name("request_id")
@@ -61,7 +224,7 @@ fn main() {
                 .value_name("REQUEST")
                 .required(true))
             .arg(Arg::with_name("source")
-                .help("Print just the escaped source")
+                .help("Print just the escaped source, preceded by comments tracing how the script came to run")
                 .takes_value(false)
                 .short('s')
                 .long("source")
@@ -70,94 +233,549 @@ fn main() {
                 .help("Optional frame id that the request id is associated with, defaults to the root frame")
                 .takes_value(true)
                 .value_name("FRAME")
+                .required(false))
+            .arg(Arg::with_name("all_frames")
+                .help("Print every request with this id across all frames, each tagged with its frame id")
+                .takes_value(false)
+                .long("all-frames")
+                .conflicts_with_all(&["source", "frame_id"])
+                .required(false)))
+        .subcommand(SubCommand::with_name("explain")
+            .about("Explain why the request with a particular Blink request id was made, as the chain of actions leading up to it")
+            .arg(Arg::with_name("request_id")
+                .help("Blink request id from the graph")
+                .takes_value(true)
+                .value_name("REQUEST")
+                .required(true))
+            .arg(Arg::with_name("text")
+                .help("Print the chain as indented text rather than JSON")
+                .takes_value(false)
+                .short('t')
+                .long("text")
+                .required(false))
+            .arg(Arg::with_name("frame_id")
+                .help("Optional frame id that the request id is associated with, defaults to the root frame")
+                .takes_value(true)
+                .value_name("FRAME")
+                .required(false)))
+        .subcommand(SubCommand::with_name("script")
+            .about("Summarize everything a script did: its requests, DOM changes, storage use, event listeners, API calls, and child scripts")
+            .arg(Arg::with_name("script_id")
+                .help("Node id of the script, e.g. n5")
+                .takes_value(true)
+                .value_name("SCRIPT")
+                .required(true))
+            .arg(Arg::with_name("text")
+                .help("Print the summary as text rather than JSON")
+                .takes_value(false)
+                .short('t')
+                .long("text")
+                .required(false)))
+        .subcommand(SubCommand::with_name("element")
+            .about("List every modification made to an HTML element, and the values its attributes held over time and who set them")
+            .arg(Arg::with_name("node_id")
+                .help("Node id of the element, e.g. n12")
+                .takes_value(true)
+                .value_name("NODE")
+                .required(true))
+            .arg(Arg::with_name("attr")
+                .help("Only report the history of this attribute")
+                .long("attr")
+                .takes_value(true)
+                .value_name("NAME")
+                .required(false))
+            .arg(Arg::with_name("subtree")
+                .help("List modifications made to every element and text node inside the element as well")
+                .long("subtree")
+                .takes_value(false)
+                .required(false)))
+        .subcommand(SubCommand::with_name("detached")
+            .about("List every element and text node that was created but never inserted into a document, with the script that created it"))
+        .subcommand(SubCommand::with_name("pixels")
+            .about("List elements styled to be invisible that made requests, like tracking pixels, with the scripts responsible")
+            .arg(Arg::with_name("all")
+                .help("Include invisible elements that made no requests")
+                .long("all")
+                .takes_value(false)
+                .required(false)))
+        .subcommand(SubCommand::with_name("frames")
+            .about("Print the tree of documents, frame owners, and remote frames in the page, with their URLs and whether each remote frame was merged")
+            .arg(Arg::with_name("json")
+                .help("Print the tree as JSON, the same as --format json")
+                .long("json")
+                .takes_value(false)
+                .required(false)))
+        .subcommand(SubCommand::with_name("requests")
+            .about("List every request in the order they started, with their timings, sizes, types, frames, and initiator chains")
+            .arg(Arg::with_name("csv")
+                .help("Print the requests as CSV rather than JSON, the same as --format csv")
+                .long("csv")
+                .takes_value(false)
+                .required(false)))
+        .subcommand(SubCommand::with_name("list_requests")
+            .about("List every request id in the graph, with the frame, URL, type, and final status of its request"))
+        .subcommand(SubCommand::with_name("resource")
+            .about("List every request made to a resource, with their statuses, sizes, response hashes, initiators, and any shields that blocked it")
+            .arg(Arg::with_name("resource")
+                .help("Node id of the resource, e.g. n12, or its URL")
+                .takes_value(true)
+                .value_name("RESOURCE")
+                .required(true)))
+        .subcommand(SubCommand::with_name("grep")
+            .about("Find resources, and optionally scripts and DOM roots, whose URLs match a pattern")
+            .arg(Arg::with_name("pattern")
+                .help("Text to search for in URLs, matched as a substring unless --regex or --glob is given")
+                .takes_value(true)
+                .value_name("PATTERN")
+                .required(true))
+            .arg(Arg::with_name("regex")
+                .help("Match the pattern as a regular expression")
+                .long("regex")
+                .short('e')
+                .takes_value(false)
+                .required(false))
+            .arg(Arg::with_name("glob")
+                .help("Match the pattern as a glob against the whole URL, where * matches any text and ? any single character")
+                .long("glob")
+                .short('g')
+                .takes_value(false)
+                .conflicts_with("regex")
+                .required(false))
+            .arg(Arg::with_name("scripts")
+                .help("Also search the URLs that scripts were fetched from")
+                .long("scripts")
+                .takes_value(false)
+                .required(false))
+            .arg(Arg::with_name("dom_roots")
+                .help("Also search the document URLs of DOM roots")
+                .long("dom-roots")
+                .takes_value(false)
+                .required(false))
+            .arg(Arg::with_name("source")
+                .help("Search the source of each script instead of URLs, printing the offsets of each match")
+                .long("source")
+                .short('s')
+                .takes_value(false)
+                .conflicts_with_all(&["scripts", "dom_roots"])
+                .required(false))
+            .arg(Arg::with_name("decode_entities")
+                .help("Decode HTML entities in script sources before searching them")
+                .long("decode-entities")
+                .takes_value(false)
+                .requires("source")
+                .required(false)))
+        .subcommand(SubCommand::with_name("entities")
+            .about("Attribute requests, response bytes, scripts, and storage accesses to the companies that own the domains involved")
+            .arg(Arg::with_name("entity_map")
+                .help("Entity map in Disconnect (entities.json) or Tracker Radar (entity_map.json) format")
+                .takes_value(true)
+                .value_name("ENTITY_MAP")
+                .required(true)))
+        .subcommand(SubCommand::with_name("weight")
+            .about("Sum the bytes of every response by resource type, party, frame, and entity, counting cached responses separately")
+            .arg(Arg::with_name("entity_map")
+                .help("Entity map in Disconnect (entities.json) or Tracker Radar (entity_map.json) format, to break the weight down by entity")
+                .long("entities")
+                .takes_value(true)
+                .value_name("ENTITY_MAP")
+                .required(false)))
+        .subcommand(SubCommand::with_name("third_parties")
+            .about("Summarize every third-party site contacted: how it was reached, what its scripts did, and the bytes it served"))
+        .subcommand(SubCommand::with_name("stats")
+            .about("Count the nodes and edges of each type, DOM elements created and retained, requests by type and status, scripts by origin, and frames"))
+        .subcommand(SubCommand::with_name("timeline")
+            .about("Count the network, DOM mutation, script execution, and storage activity in each span of time since navigation start")
+            .arg(Arg::with_name("bucket_ms")
+                .help("Length of each span of time, in milliseconds")
+                .long("bucket")
+                .takes_value(true)
+                .value_name("MS")
+                .default_value("100")))
+        .subcommand(SubCommand::with_name("centrality")
+            .about("Rank scripts by their PageRank and betweenness in the graph of interactions between scripts, resources, and elements")
+            .arg(Arg::with_name("csv")
+                .help("Print the scripts as CSV rather than JSON, the same as --format csv")
+                .long("csv")
+                .takes_value(false)
                 .required(false)))
+        .subcommand(SubCommand::with_name("path")
+            .about("Find the paths of edges leading from one node to another, shortest first")
+            .arg(Arg::with_name("from")
+                .help("Node id to start from, e.g. n123")
+                .takes_value(true)
+                .value_name("FROM")
+                .required(true))
+            .arg(Arg::with_name("to")
+                .help("Node id to end at, e.g. n456")
+                .takes_value(true)
+                .value_name("TO")
+                .required(true))
+            .arg(Arg::with_name("structure")
+                .help("Also follow Structure edges")
+                .long("structure")
+                .takes_value(false)
+                .required(false))
+            .arg(Arg::with_name("ordered")
+                .help("Only follow edges recorded no earlier than the previous edge in the path")
+                .long("ordered")
+                .takes_value(false)
+                .required(false))
+            .arg(Arg::with_name("undirected")
+                .help("Follow edges against their direction too")
+                .long("undirected")
+                .takes_value(false)
+                .required(false))
+            .arg(Arg::with_name("max_length")
+                .help("Most edges a path may have")
+                .long("max-length")
+                .takes_value(true)
+                .value_name("EDGES")
+                .default_value("6"))
+            .arg(Arg::with_name("max_paths")
+                .help("Stop after finding this many paths")
+                .long("max-paths")
+                .takes_value(true)
+                .value_name("PATHS")
+                .default_value("10")))
+        .subcommand(SubCommand::with_name("listeners")
+            .about("List every event listener registered, with its target, handling script, and whether it was removed or fired"))
+        .subcommand(SubCommand::with_name("html")
+            .about("Print the page's markup as reconstructed at the end of the recording")
+            .arg(Arg::with_name("node_id")
+                .help("Node id of an element, text node, or document to print instead of the page, e.g. n12")
+                .long("node-id")
+                .takes_value(true)
+                .value_name("NODE")
+                .required(false)))
+        .subcommand(SubCommand::with_name("storage")
+            .about("Report every cookie, local storage, and session storage access, grouped by key and by the script responsible")
+            .arg(Arg::with_name("cookies")
+                .help("Instead list each cookie set or read through document.cookie, with its parsed value and the script responsible")
+                .long("cookies")
+                .takes_value(false)
+                .required(false))
+            .arg(Arg::with_name("leaks")
+                .help("Instead list each value read from storage that was later sent in a request, as is, encoded, or hashed")
+                .long("leaks")
+                .takes_value(false)
+                .conflicts_with("cookies")
+                .required(false)))
+        .subcommand(SubCommand::with_name("cookie_sync")
+            .about("Find high-entropy identifiers sent to more than one third-party site in request URLs, and the scripts that sent them"))
+        .subcommand(SubCommand::with_name("fingerprinting")
+            .about("Score scripts by the fingerprinting techniques their Web API calls show them using, with the supporting calls")
+            .arg(Arg::with_name("min_score")
+                .help("Only report scripts scoring at least this much, between 0 and 1")
+                .long("min-score")
+                .takes_value(true)
+                .value_name("SCORE")
+                .default_value("0"))
+            .arg(Arg::with_name("canvas")
+                .help("Instead list the scripts fingerprinting with a canvas, by the heuristic used in prior measurement studies")
+                .long("canvas")
+                .takes_value(false)
+                .required(false)))
+        .subcommand(SubCommand::with_name("call_graph")
+            .about("Print the graph of Web API and JS builtin calls made by each script, with call counts and argument samples")
+            .arg(Arg::with_name("dot")
+                .help("Print the graph in Graphviz DOT format rather than JSON")
+                .long("dot")
+                .takes_value(false)
+                .required(false)))
+        .subcommand(SubCommand::with_name("compare_engines")
+            .alias("compare-engines")
+            .about("Classify every request according to whether it would be blocked by two different adblock engines")
+            .arg(Arg::with_name("engine_a")
+                .help("Filterlist file (newline-separated adblock rules) for engine A")
+                .takes_value(true)
+                .value_name("A")
+                .required(true))
+            .arg(Arg::with_name("engine_b")
+                .help("Filterlist file (newline-separated adblock rules) for engine B")
+                .takes_value(true)
+                .value_name("B")
+                .required(true))
+            .arg(Arg::with_name("serialized")
+                .help("Interpret both files as serialized adblock engines rather than filterlists")
+                .takes_value(false)
+                .long("serialized")
+                .required(false)))
+        .subcommand(SubCommand::with_name("diff")
+            .about("Compare the graph with another capture of the same page, printing the added, removed, and changed nodes and edges as JSON")
+            .arg(Arg::with_name("other_graph_file")
+                .help("Graph of the same page to compare against, treated as the later capture")
+                .takes_value(true)
+                .value_name("FILE")
+                .required(true)))
+        .subcommand(SubCommand::with_name("suggest_rules")
+            .about("Suggest candidate network filter rules for tracking behavior in the graph, with the evidence for each")
+            .arg(Arg::with_name("no_storage")
+                .help("Don't suggest blocking third-party scripts that access storage or cookies")
+                .long("no-storage")
+                .takes_value(false)
+                .required(false))
+            .arg(Arg::with_name("tracking_params")
+                .help("Comma-separated query parameters to suggest removing, instead of the default set")
+                .long("tracking-params")
+                .takes_value(true)
+                .value_name("PARAMS")
+                .required(false))
+            .arg(Arg::with_name("fan_out")
+                .help("Suggest blocking scripts that cause at least this many requests")
+                .long("fan-out")
+                .takes_value(true)
+                .value_name("COUNT")
+                .default_value("10"))
+            .arg(Arg::with_name("no_fan_out")
+                .help("Don't suggest blocking scripts because of the requests they cause")
+                .long("no-fan-out")
+                .takes_value(false)
+                .conflicts_with("fan_out")
+                .required(false))
+            .arg(Arg::with_name("filterlist")
+                .help("Print the rules as a filter list, with the evidence for each as comments")
+                .long("filterlist")
+                .takes_value(false)
+                .required(false)))
+        .subcommand(SubCommand::with_name("report")
+            .about("Run the standard suite of analyses, writing each as a JSON file into a directory")
+            .arg(Arg::with_name("out_dir")
+                .help("Directory to write reports and the index manifest into")
+                .short('o')
+                .long("out")
+                .takes_value(true)
+                .value_name("DIR")
+                .required(true))
+            .arg(Arg::with_name("path_to_filterlist")
+                .help("Filterlist file (newline-separated adblock rules) used to report triggered filters")
+                .short('l')
+                .long("list")
+                .takes_value(true)
+                .required(false)))
+        .subcommand(SubCommand::with_name("export")
+            .about("Write the graph out in another format")
+            .arg(Arg::with_name("export_format")
+                .help("Format to export to")
+                .long("to")
+                .takes_value(true)
+                .possible_values(["csv", "dot", "gexf", "har", "trace"])
+                .default_value("csv"))
+            .arg(Arg::with_name("out_dir")
+                .help("Directory to write the exported files into")
+                .short('o')
+                .long("out")
+                .takes_value(true)
+                .value_name("DIR")
+                .required(true)))
         .get_matches();
 
-    let graph_file = matches.value_of("graph_file").unwrap();
-
-    let mut graph = read_from_file(&graph_file);
+    let parse_options = ParseOptions { strict: matches.is_present("strict") };
+    let scope = GraphScope {
+        merge_frames: !matches.is_present("no_merge_frames"),
+        frame: matches.value_of("frame").map(|frame_id| {
+            use std::convert::TryFrom;
+            FrameId::try_from(frame_id).expect("Frame id should be parseable")
+        }),
+    };
 
-    graph.all_remote_frame_ids().into_iter().for_each(|remote_frame_id| {
-        let mut frame_path = std::path::Path::new(&graph_file).to_path_buf();
-        frame_path.set_file_name(format!("page_graph_{}.0.graphml", remote_frame_id));
-        if !frame_path.exists() {
-            // We have to just ignore the remote frame's contents if we couldn't successfully record any.
-            return;
+    if let Some(graph_dir) = matches.value_of("graph_dir") {
+        if matches!(matches.subcommand_name(), Some("report") | Some("export")) {
+            eprintln!("report and export write files, and can't be run over a directory of graphs");
+            std::process::exit(1);
         }
-        let frame_graph = read_from_file(frame_path.to_str().expect("failed to convert frame path to a string"));
-        graph.merge_frame(frame_graph, &remote_frame_id);
-    });
-
-    if let Some(matches) = matches.subcommand_matches("identify") {
-        let id = matches.value_of("id").unwrap().parse::<usize>().expect("Could not parse id as a number");
-
-        if let Some(node) = graph.nodes.get(&pagegraph::graph::NodeId::from(id)) {
-            println!("Node n{}", id);
-            println!("Timestamp: {}", node.node_timestamp);
-            println!("Type: {:?}", node.node_type);
-
-            println!("");
-            println!("Incoming edges");
-            graph.incoming_edges(node).for_each(|edge| {
-                println!("  {:?}", edge.id);
-                println!("    Timestamp: {:?}", edge.edge_timestamp);
-                println!("    Type: {:?}", edge.edge_type);
-            });
+        // Pass along everything but the directory, for each graph to be run with.
+        let mut args = std::env::args_os().skip(1).collect::<Vec<_>>();
+        match args.iter().position(|arg| arg == "--graph-dir") {
+            Some(i) => drop(args.drain(i..i + 2)),
+            None => args.retain(|arg| !arg.to_string_lossy().starts_with("--graph-dir=")),
+        }
+        batch::main(std::path::Path::new(graph_dir), args);
+        return;
+    }
 
-            println!("");
-            println!("Outgoing edges");
-            graph.outgoing_edges(node).for_each(|edge| {
-                println!("  {:?}", edge.id);
-                println!("    Timestamp: {:?}", edge.edge_timestamp);
-                println!("    Type: {:?}", edge.edge_type);
-            });
-        } else if let Some(edge) = graph.edges.get(&pagegraph::graph::EdgeId::from(id)) {
-            println!("Edge e{}", id);
-            println!("Timestamp: {:?}", edge.edge_timestamp);
-            println!("Type: {:?}", edge.edge_type);
+    let graph_file = matches.value_of("graph_file").unwrap();
 
-            println!("");
-            println!("Source node");
-            let source_node = graph.source_node(edge);
-            println!("  {:?}", source_node.id);
-            println!("    Timestamp: {:?}", source_node.node_timestamp);
-            println!("    Type: {:?}", source_node.node_type);
+    let graph = read_graph(graph_file, &parse_options, &scope);
+    let output_format = matches.value_of("format").map(output::Format::from_name);
+    let format = output_format.unwrap_or_default();
 
-            println!("");
-            println!("Target node");
-            let target_node = graph.target_node(edge);
-            println!("  {:?}", target_node.id);
-            println!("    Timestamp: {:?}", target_node.node_timestamp);
-            println!("    Type: {:?}", target_node.node_type);
-        } else {
-            println!("No node or edge with id {} was found in this graph.", id);
-        }
+    if let Some(matches) = matches.subcommand_matches("identify") {
+        let id = matches.value_of("id").unwrap();
+        let item_ids = identify::parse_id(id).expect("Could not parse id");
+        identify::main(&graph, id, &item_ids, output_format);
     } else if let Some(matches) = matches.subcommand_matches("adblock_rules") {
         let rule = matches.value_of("filter_rule");
         let filterlist = matches.value_of("path_to_filterlist");
         let filter_rules = if let Some(rule) = rule {
             vec![rule.to_string()]
         } else {
-            // open file
-            let file = File::open(filterlist
-                .expect("At least one of path_to_filterlist or filter_rule must be defined")).unwrap();
-            let reader = BufReader::new(file);
-            let rules: Vec<_> = reader.lines()
-                .map(|l| l.expect("Could not parse line"))
-                .collect();
-            rules
+            read_filterlist(filterlist.expect("At least one of path_to_filterlist or filter_rule must be defined"))
+        };
+        let mut engine = match (filterlist, matches.value_of("engine_cache")) {
+            (Some(filterlist), Some(cache)) => adblock_rules::cached_engine(&filter_rules, filterlist, cache),
+            _ => adblock::Engine::from_rules_debug(&filter_rules, Default::default()),
+        };
+        let report = if matches.is_present("redirects") {
+            match matches.value_of("resources") {
+                Some(resources) => {
+                    engine.use_resources(adblock_rules::read_resources(resources));
+                    adblock_rules::Report::Redirects
+                }
+                None => adblock_rules::Report::PlaceholderRedirects(filter_rules),
+            }
+        } else if matches.is_present("frames") {
+            adblock_rules::Report::Frames
+        } else if matches.is_present("detailed") {
+            adblock_rules::Report::Detailed
+        } else {
+            adblock_rules::Report::Resources
         };
-        adblock_rules::main(&graph, filter_rules);
+        adblock_rules::main(&graph, &engine, report, format);
     } else if let Some(matches) = matches.subcommand_matches("downstream_requests") {
         use std::convert::TryFrom;
         let just_requests = matches.is_present("requests");
-        let edge_id = EdgeId::try_from(matches.value_of("edge_id").unwrap()).expect("Provided edge id was invalid");
-        downstream_requests::main(&graph, edge_id, just_requests);
+        let max_depth = matches.value_of("max_depth").unwrap().parse::<usize>().expect("Max depth should be parseable as a number");
+        if let Some(request_id) = matches.value_of("request_id") {
+            let request_id = request_id.parse::<usize>().expect("Request id should be parseable as a number");
+            let frame_id = matches.value_of("request_frame").map(|frame_id_str| FrameId::try_from(frame_id_str).expect("Frame id should be parseable"));
+            downstream_requests::main_request(&graph, request_id, frame_id, just_requests, max_depth, format);
+        } else if let Ok(node_id) = pagegraph::graph::NodeId::try_from(matches.value_of("id").unwrap()) {
+            downstream_requests::main_node(&graph, node_id, just_requests, max_depth, format);
+        } else {
+            let edge_id = EdgeId::try_from(matches.value_of("id").unwrap()).expect("Provided id was neither a valid edge nor node id");
+            downstream_requests::main(&graph, edge_id, just_requests, max_depth, format);
+        }
     } else if let Some(matches) = matches.subcommand_matches("request_id_info") {
         use std::convert::TryFrom;
         let request_id = matches.value_of("request_id").unwrap().parse::<usize>().expect("Request id should be parseable as a number");
         let just_source = matches.is_present("source");
         let frame_id: Option<FrameId> = matches.value_of("frame_id").map(|frame_id_str| FrameId::try_from(frame_id_str).expect("Frame id should be parseable"));
-        request_id_info::main(&graph, request_id, frame_id, just_source);
+        if matches.is_present("all_frames") {
+            request_id_info::main_all_frames(&graph, request_id, format);
+        } else {
+            request_id_info::main(&graph, request_id, frame_id, just_source, format);
+        }
+    } else if let Some(matches) = matches.subcommand_matches("explain") {
+        use std::convert::TryFrom;
+        let request_id = matches.value_of("request_id").unwrap().parse::<usize>().expect("Request id should be parseable as a number");
+        let as_text = matches.is_present("text");
+        let frame_id: Option<FrameId> = matches.value_of("frame_id").map(|frame_id_str| FrameId::try_from(frame_id_str).expect("Frame id should be parseable"));
+        explain::main(&graph, request_id, frame_id, as_text, format);
+    } else if let Some(matches) = matches.subcommand_matches("script") {
+        use std::convert::TryFrom;
+        let script_node = pagegraph::graph::NodeId::try_from(matches.value_of("script_id").unwrap()).expect("Provided script id was invalid");
+        script::main(&graph, script_node, matches.is_present("text"), format);
+    } else if let Some(matches) = matches.subcommand_matches("element") {
+        use std::convert::TryFrom;
+        let node_id = pagegraph::graph::NodeId::try_from(matches.value_of("node_id").unwrap()).expect("Provided node id was invalid");
+        element::main(&graph, node_id, matches.value_of("attr"), matches.is_present("subtree"), format);
+    } else if matches.subcommand_matches("detached").is_some() {
+        detached::main(&graph, format);
+    } else if let Some(matches) = matches.subcommand_matches("pixels") {
+        pixels::main(&graph, matches.is_present("all"), format);
+    } else if let Some(matches) = matches.subcommand_matches("frames") {
+        frames::main(&graph, output_format.or_else(|| matches.is_present("json").then_some(output::Format::Json)));
+    } else if let Some(matches) = matches.subcommand_matches("requests") {
+        requests::main(&graph, if matches.is_present("csv") { output::Format::Csv } else { format });
+    } else if matches.subcommand_matches("list_requests").is_some() {
+        list_requests::main(&graph, format);
+    } else if let Some(matches) = matches.subcommand_matches("resource") {
+        resource::main(&graph, matches.value_of("resource").unwrap(), format);
+    } else if let Some(matches) = matches.subcommand_matches("grep") {
+        use pagegraph::search::{Pattern, UrlSearchOptions};
+        let pattern = matches.value_of("pattern").unwrap();
+        let pattern = if matches.is_present("regex") {
+            Pattern::regex(pattern).expect("Pattern should be a valid regular expression")
+        } else if matches.is_present("glob") {
+            Pattern::glob(pattern)
+        } else {
+            Pattern::substring(pattern)
+        };
+        if matches.is_present("source") {
+            grep::main_source(&graph, pattern, matches.is_present("decode_entities"), format);
+        } else {
+            let options = UrlSearchOptions {
+                scripts: matches.is_present("scripts"),
+                dom_roots: matches.is_present("dom_roots"),
+            };
+            grep::main(&graph, pattern, options, format);
+        }
+    } else if let Some(matches) = matches.subcommand_matches("entities") {
+        entities::main(&graph, &read_entity_map(matches.value_of("entity_map").unwrap()), format);
+    } else if let Some(matches) = matches.subcommand_matches("weight") {
+        let entities = matches.value_of("entity_map").map(read_entity_map);
+        weight::main(&graph, entities.as_ref(), format);
+    } else if matches.subcommand_matches("third_parties").is_some() {
+        third_parties::main(&graph, format);
+    } else if matches.subcommand_matches("stats").is_some() {
+        stats::main(&graph, format);
+    } else if let Some(matches) = matches.subcommand_matches("timeline") {
+        let bucket_ms = matches.value_of("bucket_ms").unwrap().parse::<usize>().ok().filter(|bucket_ms| *bucket_ms > 0).expect("Bucket length should be a positive number");
+        timeline::main(&graph, bucket_ms, format);
+    } else if let Some(matches) = matches.subcommand_matches("centrality") {
+        centrality::main(&graph, if matches.is_present("csv") { output::Format::Csv } else { format });
+    } else if let Some(matches) = matches.subcommand_matches("path") {
+        use std::convert::TryFrom;
+        let source = pagegraph::graph::NodeId::try_from(matches.value_of("from").unwrap()).expect("Provided node id was invalid");
+        let target = pagegraph::graph::NodeId::try_from(matches.value_of("to").unwrap()).expect("Provided node id was invalid");
+        let constraints = pagegraph::paths::PathConstraints {
+            exclude_structure: !matches.is_present("structure"),
+            respect_timestamps: matches.is_present("ordered"),
+            undirected: matches.is_present("undirected"),
+            max_length: matches.value_of("max_length").unwrap().parse::<usize>().expect("Max length should be parseable as a number"),
+            max_paths: matches.value_of("max_paths").unwrap().parse::<usize>().expect("Max paths should be parseable as a number"),
+        };
+        path::main(&graph, source, target, constraints, format);
+    } else if matches.subcommand_matches("listeners").is_some() {
+        listeners::main(&graph, format);
+    } else if let Some(matches) = matches.subcommand_matches("html") {
+        use std::convert::TryFrom;
+        let node_id = matches.value_of("node_id").map(|node_id| pagegraph::graph::NodeId::try_from(node_id).expect("Provided node id was invalid"));
+        html::main(&graph, node_id, output_format);
+    } else if let Some(matches) = matches.subcommand_matches("storage") {
+        let report = if matches.is_present("leaks") {
+            storage::Report::Leaks
+        } else if matches.is_present("cookies") {
+            storage::Report::Cookies
+        } else {
+            storage::Report::Accesses
+        };
+        storage::main(&graph, report, format);
+    } else if matches.subcommand_matches("cookie_sync").is_some() {
+        cookie_sync::main(&graph, format);
+    } else if let Some(matches) = matches.subcommand_matches("fingerprinting") {
+        let min_score = matches.value_of("min_score").unwrap().parse::<f64>().expect("Minimum score should be parseable as a number");
+        fingerprinting::main(&graph, min_score, matches.is_present("canvas"), format);
+    } else if let Some(matches) = matches.subcommand_matches("call_graph") {
+        call_graph::main(&graph, matches.is_present("dot"), format);
+    } else if let Some(matches) = matches.subcommand_matches("compare_engines") {
+        let serialized = matches.is_present("serialized");
+        let engine_a = compare_engines::engine_from_file(matches.value_of("engine_a").unwrap(), serialized);
+        let engine_b = compare_engines::engine_from_file(matches.value_of("engine_b").unwrap(), serialized);
+        compare_engines::main(&graph, &engine_a, &engine_b, format);
+    } else if let Some(matches) = matches.subcommand_matches("suggest_rules") {
+        let mut options = pagegraph::suggest::SuggestOptions {
+            storage_access: !matches.is_present("no_storage"),
+            ..Default::default()
+        };
+        if let Some(params) = matches.value_of("tracking_params") {
+            options.tracking_params = params.split(',').filter(|param| !param.is_empty()).map(str::to_string).collect();
+        }
+        options.fan_out_threshold = if matches.is_present("no_fan_out") {
+            None
+        } else {
+            Some(matches.value_of("fan_out").unwrap().parse::<usize>().expect("Fan-out should be parseable as a number"))
+        };
+        suggest_rules::main(&graph, &options, matches.is_present("filterlist"), format);
+    } else if let Some(matches) = matches.subcommand_matches("diff") {
+        let other_graph = read_graph(matches.value_of("other_graph_file").unwrap(), &parse_options, &scope);
+        diff::main(&graph, &other_graph, format);
+    } else if let Some(matches) = matches.subcommand_matches("report") {
+        let out_dir = std::path::Path::new(matches.value_of("out_dir").unwrap());
+        let filter_rules = matches.value_of("path_to_filterlist").map(read_filterlist);
+        report::main(&graph, graph_file, out_dir, filter_rules);
+    } else if let Some(matches) = matches.subcommand_matches("export") {
+        let out_dir = std::path::Path::new(matches.value_of("out_dir").unwrap());
+        export::main(&graph, matches.value_of("export_format").unwrap(), out_dir);
     }
 }
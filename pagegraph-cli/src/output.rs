@@ -0,0 +1,157 @@
+//! Prints the results of subcommands in the format chosen with the global `--format` flag, so that
+//! every subcommand's output can be consumed the same way.
+//!
+//! Lists are written with one line or row per item. Anything else is written as a single item.
+//! For CSV and table output, the fields of each item become columns, with nested lists and objects
+//! written as JSON.
+
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::Serialize;
+use serde_json::value::RawValue;
+
+/// An output format for the results of a subcommand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// A single JSON document.
+    #[default]
+    Json,
+    /// One JSON document per line.
+    Jsonl,
+    /// Comma-separated values, with a header row.
+    Csv,
+    /// Columns aligned for reading in a terminal, with a header row.
+    Table,
+}
+
+impl Format {
+    /// The names accepted by `--format`, in the order they are listed in its help.
+    pub const NAMES: [&'static str; 4] = ["json", "jsonl", "csv", "table"];
+
+    /// Looks up a format by one of its [`NAMES`](Self::NAMES), which the command line parser has
+    /// already checked the name against.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "json" => Self::Json,
+            "jsonl" => Self::Jsonl,
+            "csv" => Self::Csv,
+            "table" => Self::Table,
+            _ => panic!("Unknown output format {}", name),
+        }
+    }
+}
+
+/// The fields of a JSON object, in the order they were written. Values are kept as raw JSON, so
+/// that numbers too large for `serde_json::Value`, like frame ids, are written out unchanged.
+struct Fields(Vec<(String, Box<RawValue>)>);
+
+impl<'de> Deserialize<'de> for Fields {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FieldsVisitor;
+
+        impl<'de> Visitor<'de> for FieldsVisitor {
+            type Value = Fields;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Fields, A::Error> {
+                let mut fields = vec![];
+                while let Some(field) = map.next_entry()? {
+                    fields.push(field);
+                }
+                Ok(Fields(fields))
+            }
+        }
+
+        deserializer.deserialize_map(FieldsVisitor)
+    }
+}
+
+/// Splits serialized JSON into the items of a list, or a single item if it is not a list.
+fn items(json: String) -> Vec<Box<RawValue>> {
+    if json.starts_with('[') {
+        serde_json::from_str(&json).unwrap()
+    } else {
+        vec![RawValue::from_string(json).unwrap()]
+    }
+}
+
+/// Splits serialized JSON into rows of named fields. Items that aren't objects are placed in a
+/// `value` column.
+fn rows(json: String) -> Vec<Fields> {
+    items(json).into_iter()
+        .map(|item| serde_json::from_str(item.get()).unwrap_or_else(|_| Fields(vec![("value".to_string(), item)])))
+        .collect()
+}
+
+/// Returns the names of every field used by any row, in the order they first appear.
+fn columns(rows: &[Fields]) -> Vec<&str> {
+    let mut columns = vec![];
+    rows.iter().flat_map(|row| row.0.iter()).for_each(|(name, _)| if !columns.contains(&name.as_str()) {
+        columns.push(name.as_str());
+    });
+    columns
+}
+
+/// Formats a field for display, leaving strings unquoted and nulls empty.
+fn cell(value: &RawValue) -> String {
+    match serde_json::from_str::<Option<String>>(value.get()) {
+        Ok(Some(text)) => text,
+        Ok(None) => String::new(),
+        Err(_) => value.get().to_string(),
+    }
+}
+
+/// Returns the cells of a row in the order of `columns`, leaving any missing fields empty.
+fn cells(row: &Fields, columns: &[&str]) -> Vec<String> {
+    columns.iter()
+        .map(|column| row.0.iter().find(|(name, _)| name == column).map(|(_, value)| cell(value)).unwrap_or_default())
+        .collect()
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn print_csv(rows: &[Fields]) {
+    let columns = columns(rows);
+    println!("{}", columns.iter().map(|column| csv_field(column)).collect::<Vec<_>>().join(","));
+    rows.iter().for_each(|row| {
+        println!("{}", cells(row, &columns).iter().map(|value| csv_field(value)).collect::<Vec<_>>().join(","));
+    });
+}
+
+fn print_table(rows: &[Fields]) {
+    let columns = columns(rows);
+    let lines = std::iter::once(columns.iter().map(|column| column.to_string()).collect::<Vec<_>>())
+        .chain(rows.iter().map(|row| cells(row, &columns)))
+        .collect::<Vec<_>>();
+    let widths = (0..columns.len())
+        .map(|i| lines.iter().map(|line| line[i].chars().count()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+    lines.iter().for_each(|line| {
+        let padded = line.iter().zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>();
+        println!("{}", padded.join("  ").trim_end());
+    });
+}
+
+/// Prints a subcommand's result in the given format.
+pub fn print<T: Serialize + ?Sized>(value: &T, format: Format) {
+    let json = serde_json::to_string(value).unwrap();
+    match format {
+        Format::Json => println!("{}", json),
+        Format::Jsonl => items(json).iter().for_each(|item| println!("{}", item.get())),
+        Format::Csv => print_csv(&rows(json)),
+        Format::Table => print_table(&rows(json)),
+    }
+}
@@ -0,0 +1,10 @@
+//! Prints out the paths of edges connecting two nodes, shortest first, as JSON.
+
+use pagegraph::graph::{NodeId, PageGraph};
+use pagegraph::paths::PathConstraints;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, source: NodeId, target: NodeId, constraints: PathConstraints, format: Format) {
+    output::print(&graph.paths_between(source, target, &constraints), format);
+}
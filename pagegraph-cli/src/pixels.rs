@@ -0,0 +1,13 @@
+//! Prints out the elements styled to be invisible that made requests, like tracking pixels, or
+//! every invisible element.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, all: bool, format: Format) {
+    let elements = graph.invisible_elements().into_iter()
+        .filter(|element| all || !element.requests.is_empty())
+        .collect::<Vec<_>>();
+    output::print(&elements, format);
+}
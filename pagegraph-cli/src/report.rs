@@ -0,0 +1,103 @@
+//! Runs the standard suite of analyses over the graph, writing each result as a JSON file into an
+//! output directory alongside an `index.json` manifest.
+
+use pagegraph::graph::PageGraph;
+use pagegraph::types::{EdgeKind, NodeKind, RequestType};
+
+use std::path::Path;
+
+#[derive(serde::Serialize)]
+struct Manifest<'a> {
+    graph_file: &'a str,
+    page_url: String,
+    reports: Vec<ManifestEntry>,
+}
+
+#[derive(serde::Serialize)]
+struct ManifestEntry {
+    name: &'static str,
+    file: String,
+}
+
+#[derive(serde::Serialize)]
+struct ScriptSummary<'a> {
+    node_id: String,
+    script_id: usize,
+    script_type: &'a str,
+    url: Option<&'a str>,
+    source_length: usize,
+}
+
+#[derive(serde::Serialize)]
+struct RequestSummary<'a> {
+    request_id: usize,
+    edge_id: String,
+    url: &'a str,
+    request_type: &'a RequestType,
+    state: &'static str,
+}
+
+fn scripts(graph: &PageGraph) -> Vec<ScriptSummary<'_>> {
+    graph.nodes_of_kind(NodeKind::Script).filter_map(|node| {
+        let script = node.as_script()?;
+        Some(ScriptSummary {
+            node_id: format!("{}", node.id),
+            script_id: script.script_id,
+            script_type: script.script_type,
+            url: script.url,
+            source_length: script.source.len(),
+        })
+    }).collect()
+}
+
+fn requests(graph: &PageGraph) -> Vec<RequestSummary<'_>> {
+    graph.edges_of_kind(EdgeKind::RequestStart).filter_map(|edge| {
+        let start = edge.as_request_start()?;
+        let resource = graph.target_node(edge);
+        let url = resource.as_resource()?;
+        let state = graph.outgoing_edges(resource)
+            .filter(|edge| edge.request_id() == Some(start.request_id))
+            .fold("pending", |state, edge| match edge.edge_type.kind() {
+                EdgeKind::RequestComplete => "complete",
+                EdgeKind::RequestError => "error",
+                _ => state,
+            });
+        Some(RequestSummary {
+            request_id: start.request_id,
+            edge_id: format!("{}", edge.id),
+            url,
+            request_type: start.request_type,
+            state,
+        })
+    }).collect()
+}
+
+fn write_report<T: serde::Serialize>(out_dir: &Path, reports: &mut Vec<ManifestEntry>, name: &'static str, value: &T) {
+    let file = format!("{}.json", name);
+    std::fs::write(out_dir.join(&file), serde_json::to_string(value).unwrap())
+        .unwrap_or_else(|e| panic!("Could not write {}: {}", file, e));
+    reports.push(ManifestEntry { name, file });
+}
+
+pub fn main(graph: &PageGraph, graph_file: &str, out_dir: &Path, filter_rules: Option<Vec<String>>) {
+    std::fs::create_dir_all(out_dir).expect("Could not create output directory");
+
+    let mut reports = vec![];
+    write_report(out_dir, &mut reports, "stats", &graph.stats());
+    write_report(out_dir, &mut reports, "privacy", &graph.third_parties());
+    write_report(out_dir, &mut reports, "waterfall", &graph.request_waterfall());
+    write_report(out_dir, &mut reports, "scripts", &scripts(graph));
+    write_report(out_dir, &mut reports, "requests", &requests(graph));
+    write_report(out_dir, &mut reports, "storage", &graph.storage_report());
+    if let Some(filter_rules) = filter_rules {
+        write_report(out_dir, &mut reports, "triggered_filters", &graph.resources_matching_filters(graph, filter_rules));
+    }
+
+    let manifest = Manifest {
+        graph_file,
+        page_url: graph.root_url(),
+        reports,
+    };
+    std::fs::write(out_dir.join("index.json"), serde_json::to_string(&manifest).unwrap())
+        .expect("Could not write index.json");
+}
@@ -1,6 +1,8 @@
 //! Prints out all info from the graph about the given request ID.
 
-use pagegraph::{graph::{Edge, FrameId, HasFrameId, PageGraph}, types::{EdgeType, NodeType, RequestType}};
+use pagegraph::{graph::{Edge, FrameId, PageGraph, ProvenanceStep, RequestCompleteData, RequestErrorData, RequestStartData}, types::{EdgeType, RequestType}};
+
+use crate::output::{self, Format};
 
 /// Custom serializer for `RequestType`, so that `RequestInfo` can hold it directly rather than a
 /// string representation.
@@ -9,56 +11,95 @@ where S: serde::Serializer {
     serializer.serialize_str(request_type.as_str())
 }
 
-pub fn main(graph: &PageGraph, request_id_arg: usize, frame_id: Option<FrameId>, just_source: bool) {
-    #[derive(serde::Serialize)]
-    struct RequestInfo {
-        // RequestStart
-        #[serde(serialize_with = "serialize_request_type")]
-        request_type: RequestType,
-        //status: String,
-        //request_id: usize,
-
-        // Resource
-        url: String,
-
-        // RequestComplete
-        resource_type: String,
-        status: String,
-        source: String,
-        response_hash: Option<String>,
-        //request_id: usize,
-        headers: String,
-        size: String,
-    }
+/// Whether the request completed, failed or was blocked, or never finished during the recording.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RequestState {
+    Pending,
+    Complete,
+    Error,
+}
 
-    let mut start_edge: Option<&Edge> = None;
-    let mut complete_edge: Option<&Edge> = None;
+#[derive(serde::Serialize)]
+struct RequestInfo {
+    // The frame that the request id was recorded in, for requests found with `--all-frames`. Requests
+    // in the root frame are tagged with the graph's own frame id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frame_id: Option<String>,
+    // The document of the frame the request id was recorded in, for frames that navigated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document: Option<usize>,
 
-    graph.edges.iter().for_each(|(edge_id, e)| {
-        if edge_id.get_frame_id() != frame_id {
-            return;
-        }
+    // RequestStart
+    #[serde(serialize_with = "serialize_request_type")]
+    request_type: RequestType,
+    //status: String,
+    //request_id: usize,
+
+    // Resource
+    url: String,
+
+    // RequestComplete or RequestError, if the request finished
+    state: RequestState,
+    resource_type: Option<String>,
+    status: Option<String>,
+    source: Option<String>,
+    response_hash: Option<String>,
+    //request_id: usize,
+    headers: Option<String>,
+    size: Option<u64>,
+
+    // How the script came to run, back to the original fetched script or inline HTML
+    provenance: Vec<ProvenanceStep>,
+}
+
+/// Collects the information about a single request from the edges sharing its request id, or
+/// `None` if there is no RequestStart edge among them.
+fn request_info(graph: &PageGraph, edges: Vec<&Edge>) -> Option<RequestInfo> {
+    let mut start: Option<(&Edge, RequestStartData)> = None;
+    let mut complete: Option<(&Edge, RequestCompleteData)> = None;
+    let mut error: Option<(&Edge, RequestErrorData)> = None;
+
+    edges.into_iter().for_each(|e| {
         // There can be multiple request start and complete edges for the same request id, if they
         // represent requests to the same cached resource. However, the information retrieved here
         // should be identical, so we can use any matching edge.
-        match &e.edge_type {
-            EdgeType::RequestStart { request_id, .. } if *request_id == request_id_arg => {
-                start_edge = Some(e);
-            }
-            EdgeType::RequestComplete { request_id, .. } if *request_id == request_id_arg => {
-                complete_edge = Some(e);
-            }
-            _ => (),
+        if let Some(data) = e.as_request_start() {
+            start = Some((e, data));
+        } else if let Some(data) = e.as_request_complete() {
+            complete = Some((e, data));
+        } else if let Some(data) = e.as_request_error() {
+            error = Some((e, data));
         }
     });
 
-    let start_edge = start_edge.expect("No RequestStart edge for request id");
-    let complete_edge = complete_edge.expect("No RequestComplete edge for request id");
-
+    let (start_edge, start_data) = start?;
     let start_target = graph.target_node(start_edge);
-    let complete_source = graph.source_node(complete_edge);
 
-    assert_eq!(start_target.id, complete_source.id, "RequestStart and RequestComplete do not refer to the same Resource");
+    // Requests that failed or were blocked finish with a RequestError edge instead
+    let finish_edge = complete.as_ref().map(|(edge, _)| *edge).or_else(|| error.as_ref().map(|(edge, _)| *edge));
+    if let Some(finish_edge) = finish_edge {
+        assert_eq!(start_target.id, graph.source_node(finish_edge).id, "RequestStart and RequestComplete or RequestError do not refer to the same Resource");
+    }
+    let (state, resource_type, status, response_hash, headers, size) = match (&complete, &error) {
+        (Some((_, complete_data)), _) => (
+            RequestState::Complete,
+            Some(complete_data.resource_type.to_string()),
+            Some(complete_data.status.to_string()),
+            complete_data.response_hash.map(str::to_string),
+            Some(complete_data.headers.to_string()),
+            complete_data.size,
+        ),
+        (None, Some((_, error_data))) => (
+            RequestState::Error,
+            None,
+            Some(error_data.status.to_string()),
+            None,
+            Some(error_data.headers.to_string()),
+            error_data.size,
+        ),
+        (None, None) => (RequestState::Pending, None, None, None, None, None),
+    };
 
     // If the request corresponds to a script:
     // 1. Get the source node for RequestStart that corresponds to request ID
@@ -68,44 +109,55 @@ pub fn main(graph: &PageGraph, request_id_arg: usize, frame_id: Option<FrameId>,
     let execute_edge = graph.outgoing_edges(start_source)
         .filter(|edge| matches!(edge.edge_type, EdgeType::Execute {})).nth(0);
     let script_node = execute_edge.map(|x| graph.target_node(x));
-    // If the node is not a script node, then fail silently
-
-    let request_info = if let EdgeType::RequestComplete { resource_type, status, response_hash, headers, size, .. } = &complete_edge.edge_type {
-        if let EdgeType::RequestStart { request_type, .. } = &start_edge.edge_type {
-            if let NodeType::Resource { url } = &start_target.node_type {
-                let source = match script_node {
-                    None => panic!("Request ID does not correspond to a script!"), // fail
-                    Some(script_node) => {
-                        if let NodeType::Script { source, .. } = &script_node.node_type {
-                            source.clone()
-                        } else {
-                            unreachable!()
-                        }
-                    }
-                };
-                RequestInfo {
-                    request_type: request_type.clone(),
-                    url: url.clone(),
-                    resource_type: resource_type.clone(),
-                    status: status.clone(),
-                    source,
-                    response_hash: response_hash.clone(),
-                    headers: headers.clone(),
-                    size: size.clone(),
-                }
-            } else {
-                unreachable!()
-            }
-        } else {
-            unreachable!()
-        }
-    } else {
-        unreachable!()
+    // Requests that didn't load a script, such as those that failed, have no source
+    let (source, provenance) = match script_node {
+        None => (None, vec![]),
+        Some(script_node) => (
+            Some(script_node.as_script().expect("Execute edge does not target a script").source.to_string()),
+            graph.script_provenance(script_node.id),
+        ),
     };
 
+    Some(RequestInfo {
+        frame_id: None,
+        document: None,
+        request_type: start_data.request_type.clone(),
+        url: start_target.as_resource().expect("RequestStart does not target a Resource").to_string(),
+        state,
+        resource_type,
+        status,
+        source,
+        response_hash,
+        headers,
+        size,
+        provenance,
+    })
+}
+
+pub fn main(graph: &PageGraph, request_id_arg: usize, frame_id: Option<FrameId>, just_source: bool, format: Format) {
+    let request_info = request_info(graph, graph.edges_for_request_id(request_id_arg, frame_id))
+        .expect("No RequestStart edge for request id");
+
     if just_source {
-        println!("{}", html_escape::decode_html_entities(&request_info.source));
+        let source = request_info.source.as_deref().expect("Request ID does not correspond to a script!");
+        // The provenance chain is printed as comments, so that the output is still valid JavaScript
+        request_info.provenance.iter().for_each(|step| {
+            println!("// {} {} {}", step.script_node_id, step.origin.as_str(), step.script_url.as_deref().unwrap_or("(inline)"));
+        });
+        println!("{}", html_escape::decode_html_entities(source));
     } else {
-        println!("{}", serde_json::to_string(&request_info).unwrap());
+        output::print(&request_info, format);
     }
 }
+
+/// Prints the information for every request with the given id, in any frame, as a list.
+pub fn main_all_frames(graph: &PageGraph, request_id_arg: usize, format: Format) {
+    let requests = graph.requests_by_id_any_frame(request_id_arg).into_iter()
+        .filter_map(|(frame_id, document, edges)| request_info(graph, edges).map(|request_info| RequestInfo {
+            frame_id: Some(format!("{}", frame_id.unwrap_or(graph.desc.frame_id))),
+            document: Some(document),
+            ..request_info
+        }))
+        .collect::<Vec<_>>();
+    output::print(&requests, format);
+}
@@ -0,0 +1,14 @@
+//! Prints out every request made by the page, in the order they started, as JSON or CSV.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, format: Format) {
+    let waterfall = graph.request_waterfall();
+    if format == Format::Csv {
+        pagegraph::export::waterfall_to_csv(&waterfall, std::io::stdout().lock()).expect("Could not write CSV");
+    } else {
+        output::print(&waterfall, format);
+    }
+}
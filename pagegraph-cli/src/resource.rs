@@ -0,0 +1,26 @@
+//! Prints out every request made to a resource, given its node id or URL.
+
+use std::convert::TryFrom;
+
+use pagegraph::{graph::{NodeId, PageGraph}, types::NodeKind};
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, resource: &str, format: Format) {
+    // A URL may be requested from several frames, each with its own Resource node
+    let node_ids = match NodeId::try_from(resource) {
+        Ok(node_id) => vec![node_id],
+        Err(_) => {
+            let mut node_ids = graph.nodes_of_kind(NodeKind::Resource)
+                .filter(|node| node.as_resource() == Some(resource))
+                .map(|node| node.id)
+                .collect::<Vec<_>>();
+            node_ids.sort_unstable();
+            node_ids
+        }
+    };
+    let resources = node_ids.into_iter()
+        .map(|node_id| graph.resource_info(node_id).expect("Node is not a resource"))
+        .collect::<Vec<_>>();
+    output::print(&resources, format);
+}
@@ -0,0 +1,38 @@
+//! Prints out a summary of everything a given script did during the recording.
+
+use pagegraph::graph::{NodeId, PageGraph};
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, script_node: NodeId, as_text: bool, format: Format) {
+    let activity = graph.script_activity(script_node).expect("No script node with that id");
+
+    if !as_text {
+        output::print(&activity, format);
+        return;
+    }
+
+    match &activity.url {
+        Some(url) => println!("Script {} (script id {}, {})", activity.node_id, activity.script_id, url),
+        None => println!("Script {} (script id {}, inline)", activity.node_id, activity.script_id),
+    }
+    println!("  {} requests", activity.requests.len());
+    activity.requests.iter().for_each(|request| {
+        println!("    {} {} {} ({})", request.edge_id, request.request_id, request.url, request.request_type.as_str());
+    });
+    println!("  {} nodes created, {} modified, {} removed", activity.created_nodes.len(), activity.modified_nodes.len(), activity.removed_nodes.len());
+    println!("  {} storage keys read", activity.storage_reads.len());
+    activity.storage_reads.iter().for_each(|access| println!("    {} {}: {} times", access.storage, access.key, access.count));
+    println!("  {} storage keys written", activity.storage_writes.len());
+    activity.storage_writes.iter().for_each(|access| println!("    {} {}: {} times", access.storage, access.key, access.count));
+    println!("  {} event listeners added", activity.event_listeners.len());
+    activity.event_listeners.iter().for_each(|listener| {
+        println!("    {} on {}, dispatched {} times", listener.event, listener.target_node_id, listener.dispatches);
+    });
+    println!("  {} Web APIs called", activity.web_api_calls.len());
+    activity.web_api_calls.iter().for_each(|call| println!("    {}: {} times", call.target, call.count));
+    println!("  {} JS builtins called", activity.js_builtin_calls.len());
+    activity.js_builtin_calls.iter().for_each(|call| println!("    {}: {} times", call.target, call.count));
+    println!("  {} child scripts executed", activity.child_scripts.len());
+    activity.child_scripts.iter().for_each(|child| println!("    {}", child));
+}
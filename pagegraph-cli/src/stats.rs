@@ -0,0 +1,35 @@
+//! Prints out summary statistics about the graph, as JSON or as a plain-text table.
+
+use std::collections::BTreeMap;
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+fn print_breakdown(name: &str, counts: &BTreeMap<String, usize>) {
+    println!("{}", name);
+    counts.iter().for_each(|(key, count)| println!("  {:<32} {:>8}", key, count));
+}
+
+pub fn main(graph: &PageGraph, format: Format) {
+    let stats = graph.stats();
+    if format != Format::Table {
+        output::print(&stats, format);
+        return;
+    }
+
+    [
+        ("nodes", stats.nodes as u64),
+        ("edges", stats.edges as u64),
+        ("DOM elements created", stats.dom_elements_created as u64),
+        ("DOM elements retained", stats.dom_elements_retained as u64),
+        ("remote frames", stats.remote_frames as u64),
+        ("merged frames", stats.merged_frames as u64),
+        ("duration (ms)", stats.duration_ms),
+    ].iter().for_each(|(name, value)| println!("{:<34} {:>8}", name, value));
+    print_breakdown("nodes by type", &stats.nodes_by_type);
+    print_breakdown("edges by type", &stats.edges_by_type);
+    print_breakdown("requests by type", &stats.requests_by_type);
+    print_breakdown("requests by status", &stats.requests_by_status);
+    print_breakdown("scripts by origin", &stats.scripts_by_origin);
+}
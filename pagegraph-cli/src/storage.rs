@@ -0,0 +1,20 @@
+//! Prints out every access to cookies and web storage, grouped by key and by script, every
+//! cookie set or read by a script, or every stored value later sent in a request.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub enum Report {
+    Accesses,
+    Cookies,
+    Leaks,
+}
+
+pub fn main(graph: &PageGraph, report: Report, format: Format) {
+    match report {
+        Report::Accesses => output::print(&graph.storage_report(), format),
+        Report::Cookies => output::print(&graph.cookie_accesses(), format),
+        Report::Leaks => output::print(&graph.storage_leaks(), format),
+    }
+}
@@ -0,0 +1,30 @@
+//! Prints out candidate network filter rules for the tracking behavior recorded in a graph.
+
+use pagegraph::graph::PageGraph;
+use pagegraph::suggest::{Heuristic, SuggestOptions};
+
+use crate::output::{self, Format};
+
+fn describe(heuristic: Heuristic) -> &'static str {
+    match heuristic {
+        Heuristic::ThirdPartyStorageAccess => "third-party script accessed storage",
+        Heuristic::TrackingParameter => "request carried a tracking parameter",
+        Heuristic::HighFanOut => "script caused many requests",
+    }
+}
+
+pub fn main(graph: &PageGraph, options: &SuggestOptions, as_filterlist: bool, format: Format) {
+    let suggestions = graph.suggest_filters(options);
+
+    if !as_filterlist {
+        output::print(&suggestions, format);
+        return;
+    }
+
+    suggestions.iter().for_each(|suggestion| {
+        suggestion.evidence.iter().for_each(|evidence| {
+            println!("! {}: {} ({})", describe(evidence.heuristic), evidence.node_id, evidence.edge_ids.join(", "));
+        });
+        println!("{}", suggestion.rule);
+    });
+}
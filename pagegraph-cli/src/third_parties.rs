@@ -0,0 +1,10 @@
+//! Prints out every third-party site the page contacted, how it was reached, what its scripts
+//! did, and how many bytes it served, as JSON.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, format: Format) {
+    output::print(&graph.third_parties(), format);
+}
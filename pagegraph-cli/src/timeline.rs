@@ -0,0 +1,9 @@
+//! Prints out how much network, DOM, script, and storage activity happened over time, as JSON.
+
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, bucket_ms: usize, format: Format) {
+    output::print(&graph.activity_timeline(bucket_ms), format);
+}
@@ -0,0 +1,11 @@
+//! Prints out the bytes loaded by the page, by resource type, party, frame, and optionally entity,
+//! as JSON.
+
+use pagegraph::entities::EntityMap;
+use pagegraph::graph::PageGraph;
+
+use crate::output::{self, Format};
+
+pub fn main(graph: &PageGraph, entities: Option<&EntityMap>, format: Format) {
+    output::print(&graph.page_weight(entities), format);
+}
@@ -0,0 +1,144 @@
+//! Runs the CLI against graphs written out to a temporary directory.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+<key id="d0" for="node" attr.name="node type" attr.type="string"/>
+<key id="d1" for="node" attr.name="id" attr.type="string"/>
+<key id="d2" for="node" attr.name="timestamp" attr.type="string"/>
+<key id="d3" for="node" attr.name="url" attr.type="string"/>
+<key id="d4" for="node" attr.name="tag name" attr.type="string"/>
+<key id="d5" for="node" attr.name="is deleted" attr.type="string"/>
+<key id="d6" for="node" attr.name="node id" attr.type="string"/>
+<key id="d8" for="node" attr.name="script type" attr.type="string"/>
+<key id="d9" for="node" attr.name="script id" attr.type="string"/>
+<key id="d10" for="node" attr.name="source" attr.type="string"/>
+<key id="d12" for="node" attr.name="frame id" attr.type="string"/>
+<key id="d17" for="edge" attr.name="edge type" attr.type="string"/>
+<key id="d18" for="edge" attr.name="id" attr.type="string"/>
+<key id="d19" for="edge" attr.name="timestamp" attr.type="string"/>
+<key id="d20" for="edge" attr.name="parent" attr.type="string"/>
+<key id="d22" for="edge" attr.name="value" attr.type="string"/>
+<key id="d25" for="edge" attr.name="resource type" attr.type="string"/>
+<key id="d26" for="edge" attr.name="status" attr.type="string"/>
+<key id="d27" for="edge" attr.name="response hash" attr.type="string"/>
+<key id="d28" for="edge" attr.name="request id" attr.type="string"/>
+<key id="d29" for="edge" attr.name="headers" attr.type="string"/>
+<key id="d30" for="edge" attr.name="size" attr.type="string"/>
+<key id="d31" for="edge" attr.name="key" attr.type="string"/>
+<key id="d35" for="edge" attr.name="is style" attr.type="string"/>
+"#;
+
+const ROOT_FRAME: &str = "0000000000000000000000000000000A";
+const REMOTE_FRAME: &str = "0000000000000000000000000000000B";
+
+/// A page whose script loads a remote frame, which in turn requests an image. Both the script and
+/// the image are fetched with request id 1, in their own frames.
+fn write_page(dir: &Path) -> PathBuf {
+    std::fs::create_dir_all(dir).unwrap();
+    let root = dir.join("page_graph_root.graphml");
+    std::fs::write(&root, format!("{}{}", HEADER, r#"<desc><version>0.7.0</version><about>test</about><url>https://example.com/</url><is_root>true</is_root><frame_id>0000000000000000000000000000000A</frame_id><time><start>1000</start><end>5000</end></time></desc>
+<graph id="G" edgedefault="directed">
+<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">DOM root</data><data key="d1">2</data><data key="d2">1</data><data key="d3">https://example.com/</data><data key="d4">HTML</data><data key="d5">false</data><data key="d6">1</data></node>
+<node id="n3"><data key="d0">HTML element</data><data key="d1">3</data><data key="d2">2</data><data key="d4">script</data><data key="d5">false</data><data key="d6">2</data></node>
+<node id="n4"><data key="d0">resource</data><data key="d1">4</data><data key="d2">3</data><data key="d3">https://example.com/app.js</data></node>
+<node id="n5"><data key="d0">script</data><data key="d1">5</data><data key="d2">4</data><data key="d3">https://example.com/app.js</data><data key="d8">classic</data><data key="d9">11</data><data key="d10">frame.src = 'https://frame.example/';</data></node>
+<node id="n6"><data key="d0">frame owner</data><data key="d1">6</data><data key="d2">2</data><data key="d4">iframe</data><data key="d5">false</data><data key="d6">3</data></node>
+<node id="n7"><data key="d0">remote frame</data><data key="d1">7</data><data key="d2">9</data><data key="d12">0000000000000000000000000000000B</data></node>
+<edge id="e1" source="n1" target="n3"><data key="d17">create node</data><data key="d18">1</data><data key="d19">2</data></edge>
+<edge id="e2" source="n1" target="n3"><data key="d17">insert node</data><data key="d18">2</data><data key="d19">3</data><data key="d20">1</data></edge>
+<edge id="e3" source="n1" target="n6"><data key="d17">create node</data><data key="d18">3</data><data key="d19">4</data></edge>
+<edge id="e4" source="n1" target="n6"><data key="d17">insert node</data><data key="d18">4</data><data key="d19">5</data><data key="d20">1</data></edge>
+<edge id="e5" source="n3" target="n4"><data key="d17">request start</data><data key="d18">5</data><data key="d19">6</data><data key="d25">Script</data><data key="d26">started</data><data key="d28">1</data></edge>
+<edge id="e6" source="n4" target="n3"><data key="d17">request complete</data><data key="d18">6</data><data key="d19">7</data><data key="d25">script</data><data key="d26">complete</data><data key="d27">abc</data><data key="d28">1</data><data key="d29"></data><data key="d30">100</data></edge>
+<edge id="e7" source="n3" target="n5"><data key="d17">execute</data><data key="d18">7</data><data key="d19">8</data></edge>
+<edge id="e8" source="n5" target="n6"><data key="d17">set attribute</data><data key="d18">8</data><data key="d19">9</data><data key="d31">src</data><data key="d22">https://frame.example/</data><data key="d35">false</data></edge>
+<edge id="e9" source="n6" target="n7"><data key="d17">cross DOM</data><data key="d18">9</data><data key="d19">10</data></edge>
+</graph></graphml>"#)).unwrap();
+    std::fs::write(dir.join(format!("page_graph_{}.0.graphml", REMOTE_FRAME)), format!("{}{}", HEADER, r#"<desc><version>0.7.0</version><about>test</about><url>https://frame.example/</url><is_root>false</is_root><frame_id>0000000000000000000000000000000B</frame_id><time><start>1000</start><end>5000</end></time></desc>
+<graph id="G" edgedefault="directed">
+<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">DOM root</data><data key="d1">2</data><data key="d2">1</data><data key="d3">https://frame.example/</data><data key="d4">HTML</data><data key="d5">false</data><data key="d6">1</data></node>
+<node id="n3"><data key="d0">HTML element</data><data key="d1">3</data><data key="d2">2</data><data key="d4">img</data><data key="d5">false</data><data key="d6">2</data></node>
+<node id="n4"><data key="d0">resource</data><data key="d1">4</data><data key="d2">3</data><data key="d3">https://frame.example/pixel.png</data></node>
+<edge id="e1" source="n1" target="n3"><data key="d17">create node</data><data key="d18">1</data><data key="d19">2</data></edge>
+<edge id="e2" source="n1" target="n3"><data key="d17">insert node</data><data key="d18">2</data><data key="d19">3</data><data key="d20">1</data></edge>
+<edge id="e3" source="n1" target="n3"><data key="d17">set attribute</data><data key="d18">3</data><data key="d19">4</data><data key="d31">src</data><data key="d22">pixel.png</data><data key="d35">false</data></edge>
+<edge id="e4" source="n3" target="n4"><data key="d17">request start</data><data key="d18">4</data><data key="d19">5</data><data key="d25">Image</data><data key="d26">started</data><data key="d28">1</data></edge>
+</graph></graphml>"#)).unwrap();
+    root
+}
+
+fn run(graph: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_pagegraph-cli")).arg("-f").arg(graph).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_downstream_requests_frame_keeps_whole_graph() {
+    let dir = std::env::temp_dir().join(format!("pagegraph-cli-tests-{}", std::process::id()));
+    let graph = write_page(&dir);
+
+    // The request's own frame doesn't restrict the graph, so the remote frame it loaded is followed
+    let from_root = run(&graph, &["downstream_requests", "--request-id", "1", "--request-frame", ROOT_FRAME]);
+    assert!(from_root.contains("https://example.com/app.js"));
+    assert!(from_root.contains("https://frame.example/pixel.png"));
+
+    let from_remote = run(&graph, &["downstream_requests", "--request-id", "1", "--request-frame", REMOTE_FRAME]);
+    assert!(from_remote.contains("https://frame.example/pixel.png"));
+    assert!(!from_remote.contains("https://example.com/app.js"));
+
+    // Whereas the global flag does
+    let scoped = run(&graph, &["--frame", ROOT_FRAME, "downstream_requests", "--request-id", "1"]);
+    assert!(!scoped.contains("https://frame.example/pixel.png"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_report_writes_every_report() {
+    let dir = std::env::temp_dir().join(format!("pagegraph-cli-report-tests-{}", std::process::id()));
+    let graph = write_page(&dir);
+    let filterlist = dir.join("filterlist.txt");
+    std::fs::write(&filterlist, "||frame.example/pixel.png\n").unwrap();
+    let out_dir = dir.join("report");
+
+    run(&graph, &["report", "-o", out_dir.to_str().unwrap(), "-l", filterlist.to_str().unwrap()]);
+    let expected = ["stats", "privacy", "waterfall", "scripts", "requests", "storage", "triggered_filters"];
+    expected.iter().for_each(|name| assert!(out_dir.join(format!("{}.json", name)).exists(), "{}.json was not written", name));
+
+    let index: serde_json::Value = serde_json::from_slice(&std::fs::read(out_dir.join("index.json")).unwrap()).unwrap();
+    let listed = index["reports"].as_array().unwrap().iter().map(|report| report["name"].as_str().unwrap()).collect::<Vec<_>>();
+    assert_eq!(listed, expected);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_graph_dir_reports_each_graph() {
+    let dir = std::env::temp_dir().join(format!("pagegraph-cli-batch-tests-{}", std::process::id()));
+    write_page(&dir);
+    // The URL is still reported for a graph that is cut off after its description
+    let root = std::fs::read_to_string(dir.join("page_graph_root.graphml")).unwrap();
+    let truncated = &root[..root.find("</desc>").unwrap() + "</desc>".len()];
+    std::fs::write(dir.join("truncated.graphml"), truncated).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pagegraph-cli")).arg("--graph-dir").arg(&dir).arg("list_requests").output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let mut lines = String::from_utf8(output.stdout).unwrap().lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    lines.sort_by_key(|line| line["file"].as_str().unwrap().to_string());
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line["url"] == "https://example.com/"));
+    assert!(lines[0]["file"].as_str().unwrap().ends_with("page_graph_root.graphml"));
+    assert_eq!(lines[0]["result"].as_array().unwrap().len(), 2);
+    assert!(lines[1]["error"].is_string());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
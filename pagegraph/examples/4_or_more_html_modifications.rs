@@ -6,7 +6,7 @@ use pagegraph::types::NodeType;
 
 fn main() {
     let graph_file = std::env::args().skip(1).next().expect("Provide a path to a `.graphml` file");
-    let graph = read_from_file(&graph_file);
+    let graph = read_from_file(&graph_file).expect("Could not read the graph file");
 
     let html_elements = graph.filter_nodes(|node_type| {
         match node_type {
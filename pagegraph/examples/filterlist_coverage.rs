@@ -0,0 +1,30 @@
+//! Evaluates one or more filter lists across a corpus of graphs, printing a JSON report of the
+//! requests each list would block, how much they overlap, and the third-party domains each missed
+//! most often.
+//!
+//! Usage: `filterlist_coverage -l <list>... <graph>...`
+
+use pagegraph::corpus::{evaluate_files, FilterList};
+
+fn read_filterlist(path: &str) -> FilterList {
+    let rules = std::fs::read_to_string(path).expect("Could not read the filter list");
+    let name = std::path::Path::new(path).file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_else(|| path.to_string());
+    FilterList { name, rules: rules.lines().map(str::to_string).collect() }
+}
+
+fn main() {
+    let mut lists = vec![];
+    let mut graph_files = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "-l" {
+            lists.push(read_filterlist(&args.next().expect("Provide a path to a filter list after `-l`")));
+        } else {
+            graph_files.push(arg);
+        }
+    }
+    assert!(!lists.is_empty(), "Provide at least one filter list with `-l`");
+
+    let report = evaluate_files(&graph_files, &lists, 20);
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
@@ -0,0 +1,36 @@
+//! Writes fixed-width Web API usage feature vectors for every distinct script across a corpus of
+//! graphs, as CSV, or as Parquet when built with the `parquet` feature.
+//!
+//! Usage: `script_features [--apis <file>] -o <out.csv|out.parquet> <graph>...`
+
+use pagegraph::corpus::script_features_from_files;
+
+fn main() {
+    let mut out = None;
+    let mut apis = None;
+    let mut graph_files = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "-o" {
+            out = Some(args.next().expect("Provide an output path after `-o`"));
+        } else if arg == "--apis" {
+            let apis_file = std::fs::read_to_string(args.next().expect("Provide a path to a list of APIs after `--apis`")).expect("Could not read the list of APIs");
+            apis = Some(apis_file.lines().filter(|line| !line.is_empty()).map(str::to_string).collect::<Vec<_>>());
+        } else {
+            graph_files.push(arg);
+        }
+    }
+    let out = out.expect("Provide an output path with `-o`");
+
+    let features = script_features_from_files(&graph_files, apis.as_deref());
+    features.failures.iter().for_each(|failure| eprintln!("Skipping {}: {}", failure.path.display(), failure.error));
+
+    if out.ends_with(".parquet") {
+        #[cfg(feature = "parquet")]
+        pagegraph::export::features_to_parquet(&features, &out).expect("Could not write Parquet file");
+        #[cfg(not(feature = "parquet"))]
+        panic!("Writing Parquet requires the `parquet` feature");
+    } else {
+        pagegraph::export::features_to_csv(&features, &out).expect("Could not write CSV file");
+    }
+}
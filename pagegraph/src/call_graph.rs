@@ -0,0 +1,135 @@
+//! A derived graph of the Web APIs and JS builtins that each script calls, and the values they
+//! return to each script.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::graph::{NodeId, PageGraph};
+use crate::types::{EdgeKind, EdgeType, NodeType};
+use crate::url_utils;
+
+/// At most this many distinct argument lists or return values are kept for each call graph edge.
+pub const MAX_CALL_SAMPLES: usize = 3;
+
+/// A script, Web API, or JS builtin in a [`JsCallGraph`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CallGraphNode {
+    pub node_id: String,
+    /// The type of the node in the page graph: `script`, `web API`, or `JS builtin`.
+    pub node_type: String,
+    /// The script's URL, or `script <id>` for inline scripts, or the API's method name.
+    pub label: String,
+    /// Whether a script was loaded from a different site than the document it ran in. `None` for
+    /// inline scripts and APIs.
+    pub is_third_party: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallDirection {
+    /// A script calling an API, from `JsCall` edges.
+    Call,
+    /// An API returning a value to a script, from `JsResult` edges.
+    Result,
+}
+
+/// All of the calls from a script to an API, or results from an API to a script, in a
+/// [`JsCallGraph`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CallGraphEdge {
+    pub source: String,
+    pub target: String,
+    pub direction: CallDirection,
+    pub count: usize,
+    /// The first few distinct argument lists or return values, in the order they were recorded.
+    /// Argument lists are parsed into JSON arrays, while return values are kept as strings.
+    pub samples: Vec<serde_json::Value>,
+}
+
+/// The Web API and JS builtin calls made by every script, as reported by
+/// [`PageGraph::js_call_graph`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct JsCallGraph {
+    /// Every script and API taking part in a call, sorted by node id.
+    pub nodes: Vec<CallGraphNode>,
+    /// Calls and results, sorted by source, then target, then direction.
+    pub edges: Vec<CallGraphEdge>,
+}
+
+impl PageGraph {
+    /// Builds a graph of the calls from each script to each Web API and JS builtin, and the
+    /// results returned to each script, with call counts and samples of the arguments and values.
+    pub fn js_call_graph(&self) -> JsCallGraph {
+        let mut calls = self.edges_of_kind(EdgeKind::JsCall)
+            .chain(self.edges_of_kind(EdgeKind::JsResult))
+            .collect::<Vec<_>>();
+        calls.sort_unstable_by_key(|edge| edge.id);
+
+        let mut nodes = BTreeSet::<NodeId>::new();
+        let mut edges = BTreeMap::<(NodeId, NodeId, CallDirection), (usize, Vec<serde_json::Value>)>::new();
+        calls.into_iter().for_each(|edge| {
+            let (direction, sample) = match &edge.edge_type {
+                EdgeType::JsCall { .. } => (CallDirection::Call, edge.parsed_args()),
+                EdgeType::JsResult { value } => (CallDirection::Result, value.clone().map(serde_json::Value::String)),
+                _ => return,
+            };
+            nodes.insert(edge.source);
+            nodes.insert(edge.target);
+            let (count, samples) = edges.entry((edge.source, edge.target, direction)).or_default();
+            *count += 1;
+            if let Some(sample) = sample {
+                if samples.len() < MAX_CALL_SAMPLES && !samples.contains(&sample) {
+                    samples.push(sample);
+                }
+            }
+        });
+
+        JsCallGraph {
+            nodes: nodes.into_iter().map(|node_id| {
+                let node = &self.nodes[&node_id];
+                let (label, is_third_party) = match &node.node_type {
+                    NodeType::Script { url: Some(url), .. } => (url.clone(), url_utils::is_third_party(url, &self.document_url_for(node_id))),
+                    NodeType::Script { url: None, script_id, .. } => (format!("script {}", script_id), None),
+                    NodeType::WebApi { method } |
+                    NodeType::JsBuiltin { method } => (method.clone(), None),
+                    node_type => (node_type.type_name().to_string(), None),
+                };
+                CallGraphNode {
+                    node_id: format!("{}", node_id),
+                    node_type: node.node_type.type_name().to_string(),
+                    label,
+                    is_third_party,
+                }
+            }).collect(),
+            edges: edges.into_iter().map(|((source, target, direction), (count, samples))| CallGraphEdge {
+                source: format!("{}", source),
+                target: format!("{}", target),
+                direction,
+                count,
+                samples,
+            }).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod call_graph_tests {
+    #[test]
+    fn test_js_call_graph() {
+        let calls = [(10, 1, "[1]"), (11, 2, "[2]"), (12, 3, "[1]"), (13, 4, "[3]"), (14, 5, "[4]")].iter().map(|(id, time, args)| {
+            format!(r#"<edge id="e{0}" source="n1" target="n2"><data key="d16">js call</data><data key="d17">{0}</data><data key="d18">{1}</data><data key="d25">0</data><data key="d24">{2}</data></edge>"#, id, time, args)
+        }).collect::<String>();
+        let nodes_and_edges = format!(r#"<node id="n1"><data key="d0">script</data><data key="d1">1</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n2"><data key="d0">web API</data><data key="d1">2</data><data key="d2">0</data><data key="d11">Math.random</data></node>
+{}
+<edge id="e20" source="n2" target="n1"><data key="d16">js result</data><data key="d17">20</data><data key="d18">6</data><data key="d20">0.5</data></edge>"#, calls);
+        let graph = crate::test_util::parse(&nodes_and_edges);
+
+        let call_graph = graph.js_call_graph();
+        assert_eq!(call_graph.nodes.iter().map(|node| node.label.as_str()).collect::<Vec<_>>(), ["script 11", "Math.random"]);
+        assert_eq!(call_graph.edges.len(), 2);
+        assert_eq!(call_graph.edges[0].count, 5);
+        assert_eq!(call_graph.edges[0].samples, [serde_json::json!([1]), serde_json::json!([2]), serde_json::json!([3])]);
+        assert_eq!(call_graph.edges[1].source, "n2");
+        assert_eq!(call_graph.edges[1].samples, ["0.5"]);
+    }
+}
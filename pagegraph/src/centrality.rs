@@ -0,0 +1,197 @@
+//! Centrality measures over the interactions between scripts, resources, and elements, for
+//! finding the scripts a page depends on most.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
+
+use crate::graph::{NodeId, PageGraph};
+use crate::types::{EdgeKind, NodeKind, NodeType, ScriptId};
+
+/// The damping factor used for PageRank, as in the original paper.
+pub const PAGERANK_DAMPING: f64 = 0.85;
+/// PageRank stops once no rank changes by more than this between iterations.
+const PAGERANK_TOLERANCE: f64 = 1e-9;
+const PAGERANK_MAX_ITERATIONS: usize = 100;
+
+/// How central a script is to the page, as reported by [`PageGraph::script_centrality`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScriptCentrality {
+    pub node_id: String,
+    pub script_id: ScriptId,
+    pub url: Option<String>,
+    /// The number of other scripts, resources, and elements the script interacted with directly.
+    pub degree: usize,
+    /// The fraction of shortest paths between other scripts, resources, and elements that pass
+    /// through the script.
+    pub betweenness: f64,
+    /// The script's PageRank, with each interaction counting as a vote for the actor responsible.
+    pub pagerank: f64,
+}
+
+fn is_interaction_node(node_type: &NodeType) -> bool {
+    matches!(node_type.kind(),
+        NodeKind::Script |
+        NodeKind::Resource |
+        NodeKind::HtmlElement |
+        NodeKind::FrameOwner)
+}
+
+/// Computes PageRank by power iteration. petgraph's `page_rank` is quadratic in the number of
+/// nodes, which is too slow for the interaction graphs of larger pages. The rank of nodes with
+/// no outgoing edges is spread evenly over every node.
+fn pagerank<N, E>(graph: &DiGraph<N, E>) -> Vec<f64> {
+    let count = graph.node_count();
+    if count == 0 {
+        return vec![];
+    }
+    let mut ranks = vec![1.0 / count as f64; count];
+    for _ in 0..PAGERANK_MAX_ITERATIONS {
+        let dangling = graph.node_indices()
+            .filter(|node| graph.neighbors_directed(*node, Direction::Outgoing).next().is_none())
+            .map(|node| ranks[node.index()])
+            .sum::<f64>();
+        let base = (1.0 - PAGERANK_DAMPING) / count as f64 + PAGERANK_DAMPING * dangling / count as f64;
+        let mut next = vec![base; count];
+        graph.node_indices().for_each(|node| {
+            let out_degree = graph.neighbors_directed(node, Direction::Outgoing).count();
+            graph.neighbors_directed(node, Direction::Outgoing).for_each(|target| {
+                next[target.index()] += PAGERANK_DAMPING * ranks[node.index()] / out_degree as f64;
+            });
+        });
+        let change = ranks.iter().zip(&next).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+        ranks = next;
+        if change < PAGERANK_TOLERANCE {
+            break;
+        }
+    }
+    ranks
+}
+
+/// Computes the betweenness centrality of every node with Brandes' algorithm, treating edges as
+/// unweighted and directed, normalized by the number of ordered pairs of other nodes.
+fn betweenness<N, E>(graph: &DiGraph<N, E>) -> Vec<f64> {
+    let count = graph.node_count();
+    let mut centrality = vec![0.0; count];
+    for source in graph.node_indices() {
+        let mut order = vec![];
+        let mut predecessors = vec![vec![]; count];
+        let mut paths = vec![0.0; count];
+        let mut distance = vec![usize::MAX; count];
+        paths[source.index()] = 1.0;
+        distance[source.index()] = 0;
+
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            for neighbor in graph.neighbors_directed(node, Direction::Outgoing) {
+                if distance[neighbor.index()] == usize::MAX {
+                    distance[neighbor.index()] = distance[node.index()] + 1;
+                    queue.push_back(neighbor);
+                }
+                if distance[neighbor.index()] == distance[node.index()] + 1 {
+                    paths[neighbor.index()] += paths[node.index()];
+                    predecessors[neighbor.index()].push(node);
+                }
+            }
+        }
+
+        let mut dependency = vec![0.0; count];
+        while let Some(node) = order.pop() {
+            for predecessor in &predecessors[node.index()] {
+                dependency[predecessor.index()] += paths[predecessor.index()] / paths[node.index()] * (1.0 + dependency[node.index()]);
+            }
+            if node != source {
+                centrality[node.index()] += dependency[node.index()];
+            }
+        }
+    }
+    if count > 2 {
+        let pairs = ((count - 1) * (count - 2)) as f64;
+        centrality.iter_mut().for_each(|value| *value /= pairs);
+    }
+    centrality
+}
+
+impl PageGraph {
+    /// Builds the graph of interactions between scripts, resources, HTML elements, and frame
+    /// owners, with an edge from each node to every node it acted on, not counting the structure
+    /// of the DOM.
+    fn interaction_graph(&self) -> DiGraph<NodeId, ()> {
+        let mut graph = DiGraph::new();
+        let mut indices = HashMap::<NodeId, NodeIndex>::new();
+        let mut node_ids = self.nodes.values()
+            .filter(|node| is_interaction_node(&node.node_type))
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        node_ids.sort_unstable();
+        node_ids.into_iter().for_each(|node_id| {
+            indices.insert(node_id, graph.add_node(node_id));
+        });
+
+        let mut edges = self.graph.all_edges().filter_map(|(source, target, edge_ids)| {
+            let interacts = edge_ids.iter().any(|edge_id| !matches!(self.edges[edge_id].edge_type.kind(), EdgeKind::Structure | EdgeKind::CrossDom));
+            match (indices.get(&source), indices.get(&target)) {
+                (Some(source), Some(target)) if interacts && source != target => Some((*source, *target)),
+                _ => None,
+            }
+        }).collect::<Vec<_>>();
+        edges.sort_unstable();
+        edges.into_iter().for_each(|(source, target)| {
+            graph.add_edge(source, target, ());
+        });
+        graph
+    }
+
+    /// Ranks every script by how central it is to the interactions between scripts, resources,
+    /// and elements on the page, by PageRank and then betweenness. PageRank is computed with each
+    /// interaction as a vote for the actor responsible for it, so scripts that load resources and
+    /// modify elements that are in turn depended on rank highest.
+    pub fn script_centrality(&self) -> Vec<ScriptCentrality> {
+        let graph = self.interaction_graph();
+        let mut reversed = graph.clone();
+        reversed.reverse();
+        let pageranks = pagerank(&reversed);
+        let betweenness = betweenness(&graph);
+
+        let mut scripts = graph.node_indices().filter_map(|index| {
+            let script = self.nodes[&graph[index]].as_script()?;
+            Some((graph[index], ScriptCentrality {
+                node_id: format!("{}", graph[index]),
+                script_id: script.script_id,
+                url: script.url.map(str::to_string),
+                degree: graph.neighbors_undirected(index).collect::<HashSet<_>>().len(),
+                betweenness: betweenness[index.index()],
+                pagerank: pageranks[index.index()],
+            }))
+        }).collect::<Vec<_>>();
+        scripts.sort_by(|(a_id, a), (b_id, b)| b.pagerank.total_cmp(&a.pagerank)
+            .then_with(|| b.betweenness.total_cmp(&a.betweenness))
+            .then_with(|| a_id.cmp(b_id)));
+        scripts.into_iter().map(|(_, centrality)| centrality).collect()
+    }
+}
+
+#[cfg(test)]
+mod centrality_tests {
+    use super::*;
+
+    #[test]
+    fn test_betweenness_and_pagerank() {
+        let mut graph = DiGraph::<(), ()>::new();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        let c = graph.add_node(());
+        let d = graph.add_node(());
+        graph.extend_with_edges([(a, b), (b, c), (b, d)]);
+
+        // b lies on the only paths from a to c and from a to d, out of 6 ordered pairs of others
+        assert_eq!(betweenness(&graph), [0.0, 2.0 / 6.0, 0.0, 0.0]);
+
+        let ranks = pagerank(&graph);
+        assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-6);
+        assert!(ranks[a.index()] < ranks[b.index()]);
+        assert!((ranks[c.index()] - ranks[d.index()]).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,193 @@
+//! What would disappear from a page if a single request were blocked: the requests, DOM
+//! insertions, and storage writes that it caused, and how many of those came from third parties.
+
+use crate::graph::{Edge, HasFrameId, Node, PageGraph};
+use crate::graph_algos::is_execution;
+use crate::types::{EdgeType, NodeType, RequestType};
+use crate::url_utils;
+
+/// A request that would not have been made had another request been blocked.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LostRequest {
+    pub edge_id: String,
+    pub request_id: usize,
+    pub url: String,
+    pub request_type: RequestType,
+    /// Whether the URL is of a different site than the document that requested it.
+    pub third_party: bool,
+}
+
+/// A node that would not have been inserted into a document had a request been blocked.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LostInsertion {
+    pub edge_id: String,
+    pub node_id: String,
+    pub node_type: String,
+    /// The tag name of the inserted element, if it is one.
+    pub tag_name: Option<String>,
+    pub actor_node_id: String,
+    /// Whether the insertion was made by a script loaded from a third party.
+    pub third_party: bool,
+}
+
+/// A write to storage that would not have been made had a request been blocked.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LostStorageWrite {
+    pub edge_id: String,
+    /// The type of write, i.e. `storage set`, `delete storage`, or `clear storage`.
+    pub action: String,
+    /// The type of storage written to, e.g. `local storage` or `cookie jar`.
+    pub storage: String,
+    pub key: String,
+    pub actor_node_id: String,
+    /// Whether the write was made by a script loaded from a third party.
+    pub third_party: bool,
+}
+
+/// Everything that would not have happened had a request been blocked, as reported by
+/// [`PageGraph::consequences_of_blocking`]. Each list is in timestamp order, and does not include
+/// the blocked request itself.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlockingConsequences {
+    pub request_id: usize,
+    pub url: String,
+    pub requests: Vec<LostRequest>,
+    pub dom_insertions: Vec<LostInsertion>,
+    pub storage_writes: Vec<LostStorageWrite>,
+    pub third_party_requests: usize,
+    pub third_party_dom_insertions: usize,
+    pub third_party_storage_writes: usize,
+}
+
+impl PageGraph {
+    /// Whether an action was taken by a script loaded from a different site than the document it
+    /// ran in. Actions by inline scripts and other actors are considered first-party.
+    fn is_third_party_actor(&self, actor: &Node) -> bool {
+        actor.as_script()
+            .and_then(|script| script.url)
+            .and_then(|url| url_utils::is_third_party(url, &self.document_url_for(actor.id)))
+            .unwrap_or(false)
+    }
+
+    fn lost_request(&self, edge: &Edge) -> Option<LostRequest> {
+        let (request_id, request_type) = match &edge.edge_type {
+            EdgeType::RequestStart { request_id, request_type, .. } => (*request_id, request_type.clone()),
+            _ => return None,
+        };
+        let url = self.target_node(edge).as_resource()?;
+        Some(LostRequest {
+            edge_id: format!("{}", edge.id),
+            request_id,
+            url: url.to_string(),
+            request_type,
+            third_party: url_utils::is_third_party(url, &self.document_url_for(edge.id)).unwrap_or(false),
+        })
+    }
+
+    fn lost_insertion(&self, edge: &Edge) -> Option<LostInsertion> {
+        if !matches!(edge.edge_type, EdgeType::InsertNode { .. }) {
+            return None;
+        }
+        let node = self.target_node(edge);
+        let tag_name = match &node.node_type {
+            NodeType::HtmlElement { tag_name, .. } | NodeType::FrameOwner { tag_name, .. } => Some(tag_name.clone()),
+            _ => None,
+        };
+        let actor = self.source_node(edge);
+        Some(LostInsertion {
+            edge_id: format!("{}", edge.id),
+            node_id: format!("{}", node.id),
+            node_type: node.node_type.type_name().to_string(),
+            tag_name,
+            actor_node_id: format!("{}", actor.id),
+            third_party: self.is_third_party_actor(actor),
+        })
+    }
+
+    fn lost_storage_write(&self, edge: &Edge) -> Option<LostStorageWrite> {
+        let key = match &edge.edge_type {
+            EdgeType::StorageSet { key, .. } | EdgeType::DeleteStorage { key } | EdgeType::ClearStorage { key } => key,
+            _ => return None,
+        };
+        let actor = self.source_node(edge);
+        Some(LostStorageWrite {
+            edge_id: format!("{}", edge.id),
+            action: edge.edge_type.type_name().to_string(),
+            storage: self.target_node(edge).node_type.type_name().to_string(),
+            key: key.clone(),
+            actor_node_id: format!("{}", actor.id),
+            third_party: self.is_third_party_actor(actor),
+        })
+    }
+
+    /// Reports the requests, DOM insertions, and storage writes that would not have happened had
+    /// the request with the given Blink request id been blocked, following everything downstream
+    /// of it and every action of the scripts it caused to run. The frame is as for `edges_for_request_id`. Returns `None` if there is no such
+    /// request.
+    pub fn consequences_of_blocking<C: HasFrameId>(&self, request_id: usize, frame_id: C) -> Option<BlockingConsequences> {
+        let start = self.edges_for_request_id(request_id, frame_id).into_iter().find(|edge| edge.as_request_start().is_some())?;
+        let url = self.target_node(start).as_resource()?.to_string();
+
+        // Scripts that would not have run also lose every action they took, including the DOM
+        // insertions that aren't followed as downstream effects.
+        let mut effects = self.all_downstream_effects_of(start);
+        let scripts = effects.iter()
+            .filter(|edge| is_execution(&edge.edge_type))
+            .map(|edge| self.target_node(edge))
+            .collect::<Vec<_>>();
+        effects.extend(scripts.into_iter().flat_map(|script| self.outgoing_edges(script)));
+        effects.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+        effects.dedup_by_key(|edge| edge.id);
+
+        let requests = effects.iter().filter_map(|edge| self.lost_request(edge)).collect::<Vec<_>>();
+        let dom_insertions = effects.iter().filter_map(|edge| self.lost_insertion(edge)).collect::<Vec<_>>();
+        let storage_writes = effects.iter().filter_map(|edge| self.lost_storage_write(edge)).collect::<Vec<_>>();
+
+        Some(BlockingConsequences {
+            request_id,
+            url,
+            third_party_requests: requests.iter().filter(|request| request.third_party).count(),
+            third_party_dom_insertions: dom_insertions.iter().filter(|insertion| insertion.third_party).count(),
+            third_party_storage_writes: storage_writes.iter().filter(|write| write.third_party).count(),
+            requests,
+            dom_insertions,
+            storage_writes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod consequences_tests {
+    #[test]
+    fn test_consequences_of_blocking() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">HTML element</data><data key="d1">2</data><data key="d2">0</data><data key="d4">script</data><data key="d5">false</data><data key="d6">5</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://tracker.net/t.js</data></node>
+<node id="n4"><data key="d0">script</data><data key="d1">4</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10">track()</data><data key="d3">https://tracker.net/t.js</data></node>
+<node id="n5"><data key="d0">HTML element</data><data key="d1">5</data><data key="d2">0</data><data key="d4">img</data><data key="d5">false</data><data key="d6">6</data></node>
+<node id="n6"><data key="d0">local storage</data><data key="d1">6</data><data key="d2">0</data></node>
+<node id="n7"><data key="d0">resource</data><data key="d1">7</data><data key="d2">0</data><data key="d3">https://example.com/beacon</data></node>
+<edge id="e10" source="n2" target="n3"><data key="d16">request start</data><data key="d17">10</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e11" source="n3" target="n2"><data key="d16">request complete</data><data key="d17">11</data><data key="d18">2</data><data key="d28">1</data><data key="d29">script</data><data key="d30">complete</data><data key="d33">100</data><data key="d32"></data></edge>
+<edge id="e12" source="n2" target="n4"><data key="d16">execute</data><data key="d17">12</data><data key="d18">3</data></edge>
+<edge id="e13" source="n4" target="n5"><data key="d16">insert node</data><data key="d17">13</data><data key="d18">4</data><data key="d19">1</data></edge>
+<edge id="e14" source="n4" target="n6"><data key="d16">storage set</data><data key="d17">14</data><data key="d18">5</data><data key="d21">uid</data><data key="d20">abc</data></edge>
+<edge id="e15" source="n4" target="n7"><data key="d16">request start</data><data key="d17">15</data><data key="d18">6</data><data key="d28">2</data><data key="d29">AJAX</data><data key="d30">started</data></edge>"#);
+
+        let consequences = graph.consequences_of_blocking(1, None).unwrap();
+        assert_eq!(consequences.url, "https://tracker.net/t.js");
+        assert_eq!(consequences.requests.iter().map(|request| request.request_id).collect::<Vec<_>>(), [2]);
+        assert!(!consequences.requests[0].third_party);
+        assert_eq!(consequences.dom_insertions[0].tag_name.as_deref(), Some("img"));
+        assert_eq!(consequences.storage_writes[0].storage, "local storage");
+        assert_eq!(consequences.storage_writes[0].key, "uid");
+        assert_eq!(
+            (consequences.third_party_requests, consequences.third_party_dom_insertions, consequences.third_party_storage_writes),
+            (0, 1, 1),
+        );
+
+        let beacon = graph.consequences_of_blocking(2, None).unwrap();
+        assert!(beacon.requests.is_empty() && beacon.dom_insertions.is_empty() && beacon.storage_writes.is_empty());
+        assert!(graph.consequences_of_blocking(3, None).is_none());
+    }
+}
@@ -0,0 +1,353 @@
+//! Evaluation of adblock engines across a corpus of graphs, for comparing the coverage of
+//! different filter lists, and extraction of per-script features for training classifiers.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use adblock::Engine;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::from_xml::read_with_frames;
+use crate::graph::{BlockOutcome, PageGraph};
+use crate::types::{EdgeKind, NodeKind, NodeType};
+use crate::url_utils;
+
+/// A filter list to evaluate, with a name to identify it in reports.
+pub struct FilterList {
+    pub name: String,
+    pub rules: Vec<String>,
+}
+
+impl FilterList {
+    /// Engines can't be shared between threads, so each thread builds its own.
+    fn engines(lists: &[Self]) -> Vec<Engine> {
+        lists.iter().map(|list| Engine::from_rules(&list.rules, Default::default())).collect()
+    }
+}
+
+/// The number of requests to a single site.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct DomainCount {
+    pub domain: String,
+    pub requests: usize,
+}
+
+/// How many of the requests in the corpus a single filter list would have blocked.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ListCoverage {
+    pub name: String,
+    /// Requests that would have been blocked or redirected, or never made because they were
+    /// downstream of a blocked request.
+    pub blocked: usize,
+    pub allowed: usize,
+    /// The sites most commonly requested by third-party requests that the filter list allowed,
+    /// with the most requested first.
+    pub missed_domains: Vec<DomainCount>,
+}
+
+/// A graph that could not be read, and so was left out of the report.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CorpusFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// The coverage of each filter list across a corpus of graphs.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CorpusReport {
+    /// The number of graphs evaluated.
+    pub graphs: usize,
+    /// The number of requests across all graphs.
+    pub requests: usize,
+    /// The coverage of each filter list, in the order the lists were given.
+    pub filter_lists: Vec<ListCoverage>,
+    /// `overlap[a][b]` is the number of requests blocked by both filter list `a` and filter list
+    /// `b`, so the diagonal holds the number blocked by each list.
+    pub overlap: Vec<Vec<usize>>,
+    pub failures: Vec<CorpusFailure>,
+}
+
+/// Request counts for part of the corpus, which can be combined with the counts for the rest.
+struct Tally {
+    graphs: usize,
+    requests: usize,
+    overlap: Vec<Vec<usize>>,
+    missed: Vec<HashMap<String, usize>>,
+}
+
+impl Tally {
+    fn new(lists: usize) -> Self {
+        Self {
+            graphs: 0,
+            requests: 0,
+            overlap: vec![vec![0; lists]; lists],
+            missed: vec![HashMap::new(); lists],
+        }
+    }
+
+    fn of_graph(graph: &PageGraph, engines: &[Engine]) -> Self {
+        let mut tally = Self::new(engines.len());
+        tally.graphs = 1;
+
+        let outcomes = engines.iter().map(|engine| graph.blocked_requests(engine)).collect::<Vec<_>>();
+        graph.edges_of_kind(EdgeKind::RequestStart).for_each(|edge| {
+            let url = match &graph.target_node(edge).node_type {
+                NodeType::Resource { url } => url,
+                _ => return,
+            };
+            tally.requests += 1;
+
+            let blocked = outcomes.iter()
+                .map(|outcomes| outcomes.get(&edge.id).is_some_and(BlockOutcome::is_blocked))
+                .collect::<Vec<_>>();
+            blocked.iter().enumerate().filter(|(_, blocked)| **blocked).for_each(|(a, _)| {
+                blocked.iter().enumerate().filter(|(_, blocked)| **blocked).for_each(|(b, _)| tally.overlap[a][b] += 1);
+            });
+
+            let site = match url_utils::is_third_party(url, &graph.document_url_for(edge.id)) {
+                Some(true) => url_utils::site(url),
+                _ => None,
+            };
+            if let Some(site) = site {
+                blocked.iter().enumerate().filter(|(_, blocked)| !**blocked).for_each(|(list, _)| {
+                    *tally.missed[list].entry(site.clone()).or_default() += 1;
+                });
+            }
+        });
+
+        tally
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.graphs += other.graphs;
+        self.requests += other.requests;
+        self.overlap.iter_mut().zip(other.overlap).for_each(|(row, other_row)| {
+            row.iter_mut().zip(other_row).for_each(|(count, other_count)| *count += other_count);
+        });
+        self.missed.iter_mut().zip(other.missed).for_each(|(missed, other_missed)| {
+            other_missed.into_iter().for_each(|(domain, count)| *missed.entry(domain).or_default() += count);
+        });
+        self
+    }
+
+    fn into_report(self, lists: &[FilterList], failures: Vec<CorpusFailure>, max_missed_domains: usize) -> CorpusReport {
+        let Self { graphs, requests, overlap, missed } = self;
+        let coverage = lists.iter().zip(missed).enumerate().map(|(index, (list, missed))| {
+            let mut missed_domains = missed.into_iter()
+                .map(|(domain, requests)| DomainCount { domain, requests })
+                .collect::<Vec<_>>();
+            missed_domains.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.domain.cmp(&b.domain)));
+            missed_domains.truncate(max_missed_domains);
+
+            let blocked = overlap[index][index];
+            ListCoverage {
+                name: list.name.clone(),
+                blocked,
+                allowed: requests - blocked,
+                missed_domains,
+            }
+        }).collect();
+
+        CorpusReport {
+            graphs,
+            requests,
+            filter_lists: coverage,
+            overlap,
+            failures,
+        }
+    }
+}
+
+/// Evaluates each filter list against every request in the given graphs, in parallel. At most
+/// `max_missed_domains` missed domains are reported for each list.
+pub fn evaluate_graphs(graphs: &[PageGraph], lists: &[FilterList], max_missed_domains: usize) -> CorpusReport {
+    let tally = graphs.par_iter()
+        .map_init(|| FilterList::engines(lists), |engines, graph| Tally::of_graph(graph, engines))
+        .reduce(|| Tally::new(lists.len()), Tally::merge);
+    tally.into_report(lists, vec![], max_missed_domains)
+}
+
+/// Like `evaluate_graphs`, but reading each graph, along with any remote frames recorded alongside
+/// it, from the given paths. Graphs are read and evaluated in parallel, and are dropped once
+/// evaluated. Graphs that cannot be read are reported as failures.
+pub fn evaluate_files<P: AsRef<Path> + Sync>(paths: &[P], lists: &[FilterList], max_missed_domains: usize) -> CorpusReport {
+    let (tally, failures) = paths.par_iter()
+        .map_init(|| FilterList::engines(lists), |engines, path| {
+            let path = path.as_ref();
+            match read_with_frames(&path.to_string_lossy()) {
+                Ok(graph) => (Tally::of_graph(&graph, engines), vec![]),
+                Err(e) => (Tally::new(lists.len()), vec![CorpusFailure { path: path.to_owned(), error: e.to_string() }]),
+            }
+        })
+        .reduce(|| (Tally::new(lists.len()), vec![]), |(tally, mut failures), (other_tally, other_failures)| {
+            failures.extend(other_failures);
+            (tally.merge(other_tally), failures)
+        });
+    tally.into_report(lists, failures, max_missed_domains)
+}
+
+/// Returns the hex-encoded SHA-256 hash of a script's source, which identifies the same script
+/// across graphs regardless of where it was loaded from.
+pub fn script_hash(source: &str) -> String {
+    format!("{:x}", Sha256::digest(source))
+}
+
+/// The Web API usage of every script with the same source across a corpus.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScriptFeatureRow {
+    pub script_hash: String,
+    /// The URL the script was first seen loaded from, in graph order, or `None` if it was only
+    /// seen inline.
+    pub url: Option<String>,
+    /// The number of times the script was executed across the corpus.
+    pub occurrences: usize,
+    /// The total number of calls to each API in [`ScriptFeatures::apis`], across all
+    /// occurrences.
+    pub counts: Vec<usize>,
+}
+
+/// Fixed-width feature vectors of Web API and JS builtin call counts for each distinct script in a
+/// corpus, e.g. for training tracker classification models.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScriptFeatures {
+    /// The API of each feature, in column order.
+    pub apis: Vec<String>,
+    /// One row per distinct script, sorted by hash.
+    pub scripts: Vec<ScriptFeatureRow>,
+    pub failures: Vec<CorpusFailure>,
+}
+
+#[derive(Default)]
+struct ScriptUsage {
+    url: Option<String>,
+    occurrences: usize,
+    counts: HashMap<String, usize>,
+}
+
+/// API call counts for the scripts in part of the corpus, by script hash.
+#[derive(Default)]
+struct ScriptTally(HashMap<String, ScriptUsage>);
+
+impl ScriptTally {
+    fn of_graph(graph: &PageGraph) -> Self {
+        let mut tally = Self::default();
+        graph.nodes_of_kind(NodeKind::Script).for_each(|script| {
+            let (url, source) = match &script.node_type {
+                NodeType::Script { url, source, .. } => (url, source),
+                _ => return,
+            };
+            let usage = tally.0.entry(script_hash(source)).or_default();
+            if usage.url.is_none() {
+                usage.url.clone_from(url);
+            }
+            usage.occurrences += 1;
+            graph.webapi_profile(script.id).into_iter().for_each(|(api, count)| *usage.counts.entry(api).or_default() += count);
+        });
+        tally
+    }
+
+    /// Merges in a tally of graphs later in the corpus.
+    fn merge(mut self, other: Self) -> Self {
+        other.0.into_iter().for_each(|(hash, other_usage)| {
+            let usage = self.0.entry(hash).or_default();
+            if usage.url.is_none() {
+                usage.url = other_usage.url;
+            }
+            usage.occurrences += other_usage.occurrences;
+            other_usage.counts.into_iter().for_each(|(api, count)| *usage.counts.entry(api).or_default() += count);
+        });
+        self
+    }
+
+    fn into_features(self, apis: Option<&[String]>, failures: Vec<CorpusFailure>) -> ScriptFeatures {
+        let apis = match apis {
+            Some(apis) => apis.to_vec(),
+            None => self.0.values().flat_map(|usage| usage.counts.keys().cloned()).collect::<BTreeSet<_>>().into_iter().collect(),
+        };
+        let mut scripts = self.0.into_iter().map(|(script_hash, usage)| ScriptFeatureRow {
+            script_hash,
+            counts: apis.iter().map(|api| usage.counts.get(api).copied().unwrap_or_default()).collect(),
+            url: usage.url,
+            occurrences: usage.occurrences,
+        }).collect::<Vec<_>>();
+        scripts.sort_unstable_by(|a, b| a.script_hash.cmp(&b.script_hash));
+        ScriptFeatures { apis, scripts, failures }
+    }
+}
+
+/// Counts each distinct script's calls to each Web API and JS builtin across the given graphs,
+/// in parallel. Features are given for `apis` if provided, or otherwise for every API called in
+/// the corpus, sorted.
+pub fn script_features(graphs: &[PageGraph], apis: Option<&[String]>) -> ScriptFeatures {
+    let tally = graphs.par_iter()
+        .map(ScriptTally::of_graph)
+        .reduce(ScriptTally::default, ScriptTally::merge);
+    tally.into_features(apis, vec![])
+}
+
+/// Like `script_features`, but reading each graph, along with any remote frames recorded
+/// alongside it, from the given paths. Graphs that cannot be read are reported as failures.
+pub fn script_features_from_files<P: AsRef<Path> + Sync>(paths: &[P], apis: Option<&[String]>) -> ScriptFeatures {
+    let (tally, failures) = paths.par_iter()
+        .map(|path| {
+            let path = path.as_ref();
+            match read_with_frames(&path.to_string_lossy()) {
+                Ok(graph) => (ScriptTally::of_graph(&graph), vec![]),
+                Err(e) => (ScriptTally::default(), vec![CorpusFailure { path: path.to_owned(), error: e.to_string() }]),
+            }
+        })
+        .reduce(|| (ScriptTally::default(), vec![]), |(tally, mut failures), (other_tally, other_failures)| {
+            failures.extend(other_failures);
+            (tally.merge(other_tally), failures)
+        });
+    tally.into_features(apis, failures)
+}
+
+#[cfg(test)]
+mod corpus_tests {
+    use super::*;
+    use crate::test_util::parse;
+
+    #[test]
+    fn test_evaluate_graphs() {
+        let nodes_and_edges = r#"<node id="n1"><data key="d0">DOM root</data><data key="d1">1</data><data key="d2">0</data><data key="d3">https://example.com/</data><data key="d4">HTML</data><data key="d5">false</data><data key="d6">1</data></node>
+<node id="n2"><data key="d0">HTML element</data><data key="d1">2</data><data key="d2">0</data><data key="d4">img</data><data key="d5">false</data><data key="d6">2</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://example.com/a.png</data></node>
+<node id="n4"><data key="d0">resource</data><data key="d1">4</data><data key="d2">0</data><data key="d3">https://tracker.net/p.png</data></node>
+<node id="n5"><data key="d0">resource</data><data key="d1">5</data><data key="d2">0</data><data key="d3">https://ads.org/b.png</data></node>
+<edge id="e6" source="n2" target="n3"><data key="d16">request start</data><data key="d17">6</data><data key="d18">0</data><data key="d29">Image</data><data key="d30">started</data><data key="d28">1</data></edge>
+<edge id="e7" source="n2" target="n4"><data key="d16">request start</data><data key="d17">7</data><data key="d18">0</data><data key="d29">Image</data><data key="d30">started</data><data key="d28">2</data></edge>
+<edge id="e8" source="n2" target="n5"><data key="d16">request start</data><data key="d17">8</data><data key="d18">0</data><data key="d29">Image</data><data key="d30">started</data><data key="d28">3</data></edge>"#;
+        let lists = [
+            FilterList { name: "a".to_string(), rules: vec!["||tracker.net^".to_string()] },
+            FilterList { name: "b".to_string(), rules: vec!["||tracker.net^".to_string(), "||ads.org^".to_string()] },
+        ];
+
+        let report = evaluate_graphs(&[parse(nodes_and_edges), parse(nodes_and_edges)], &lists, 10);
+        assert_eq!(report.graphs, 2);
+        assert_eq!(report.requests, 6);
+        assert_eq!(report.overlap, vec![vec![2, 2], vec![2, 4]]);
+        assert_eq!(report.filter_lists[0].allowed, 4);
+        assert_eq!(report.filter_lists[0].missed_domains, vec![DomainCount { domain: "ads.org".to_string(), requests: 2 }]);
+        assert!(report.filter_lists[1].missed_domains.is_empty());
+    }
+    #[test]
+    fn test_script_features() {
+        let nodes_and_edges = r#"<node id="n1"><data key="d0">script</data><data key="d1">1</data><data key="d2">0</data><data key="d3">https://tracker.net/t.js</data><data key="d8">classic</data><data key="d9">11</data><data key="d10">track()</data></node>
+<node id="n2"><data key="d0">web API</data><data key="d1">2</data><data key="d2">0</data><data key="d11">Navigator.userAgent</data></node>
+<edge id="e3" source="n1" target="n2"><data key="d16">js call</data><data key="d17">3</data><data key="d18">0</data><data key="d25">0</data></edge>
+<edge id="e4" source="n1" target="n2"><data key="d16">js call</data><data key="d17">4</data><data key="d18">0</data><data key="d25">0</data></edge>"#;
+        let graph = || parse(nodes_and_edges);
+
+        let features = script_features(&[graph(), graph()], None);
+        assert_eq!(features.apis, ["Navigator.userAgent"]);
+        assert_eq!(features.scripts.len(), 1);
+        assert_eq!(features.scripts[0].script_hash, script_hash("track()"));
+        assert_eq!(features.scripts[0].occurrences, 2);
+        assert_eq!(features.scripts[0].counts, [4]);
+
+        let apis = ["Screen.width".to_string(), "Navigator.userAgent".to_string()];
+        assert_eq!(script_features(&[graph()], Some(&apis)).scripts[0].counts, [0, 2]);
+    }
+}
@@ -0,0 +1,377 @@
+//! Evaluation of cosmetic (element hiding) filters against the reconstructed DOM of a page.
+//!
+//! Only a subset of CSS selectors is supported: type, universal, id, class, and attribute
+//! selectors, combined with descendant, child, and sibling combinators. Selectors using anything
+//! else, such as pseudo-classes, are reported as unsupported rather than evaluated.
+
+use std::collections::{BTreeSet, HashSet};
+
+use adblock::Engine;
+
+use crate::dom::{DomElement, DomTree};
+use crate::graph::PageGraph;
+use crate::types::NodeType;
+
+#[derive(Debug, Clone, PartialEq)]
+enum AttributeOperator {
+    Exists,
+    Equals(String),
+    /// `~=`: the value is a whitespace-separated list containing the given word.
+    Includes(String),
+    /// `|=`: the value is the given string, or starts with it followed by `-`.
+    DashMatch(String),
+    Prefix(String),
+    Suffix(String),
+    Substring(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Compound {
+    tag_name: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, AttributeOperator)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+    /// `+`: the element immediately before.
+    NextSibling,
+    /// `~`: any element before, under the same parent.
+    SubsequentSibling,
+}
+
+/// A single complex selector, stored right to left: the subject compound first, followed by
+/// each combinator and the compound to its left.
+#[derive(Debug, Clone, PartialEq)]
+struct Complex {
+    subject: Compound,
+    ancestors: Vec<(Combinator, Compound)>,
+}
+
+/// A parsed list of comma-separated CSS selectors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector(Vec<Complex>);
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+/// A minimal cursor over the characters of a selector.
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) -> bool {
+        let mut skipped = false;
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {
+            skipped = true;
+        }
+        skipped
+    }
+
+    fn name(&mut self) -> Option<String> {
+        let mut name = String::new();
+        while let Some(c) = self.chars.next_if(|c| is_name_char(*c)) {
+            name.push(c);
+        }
+        if name.is_empty() { None } else { Some(name) }
+    }
+
+    fn attribute_value(&mut self) -> Option<String> {
+        match self.chars.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                let quote = *quote;
+                self.chars.next();
+                let mut value = String::new();
+                loop {
+                    match self.chars.next()? {
+                        c if c == quote => return Some(value),
+                        '\\' => return None,
+                        c => value.push(c),
+                    }
+                }
+            }
+            _ => self.name(),
+        }
+    }
+
+    fn attribute(&mut self) -> Option<(String, AttributeOperator)> {
+        self.skip_whitespace();
+        let name = self.name()?.to_ascii_lowercase();
+        self.skip_whitespace();
+        let operator = match self.chars.next()? {
+            ']' => return Some((name, AttributeOperator::Exists)),
+            '=' => AttributeOperator::Equals as fn(String) -> AttributeOperator,
+            prefix @ ('~' | '|' | '^' | '$' | '*') => {
+                if self.chars.next()? != '=' {
+                    return None;
+                }
+                match prefix {
+                    '~' => AttributeOperator::Includes,
+                    '|' => AttributeOperator::DashMatch,
+                    '^' => AttributeOperator::Prefix,
+                    '$' => AttributeOperator::Suffix,
+                    _ => AttributeOperator::Substring,
+                }
+            }
+            _ => return None,
+        };
+        self.skip_whitespace();
+        let value = self.attribute_value()?;
+        self.skip_whitespace();
+        if self.chars.next()? != ']' {
+            return None;
+        }
+        Some((name, operator(value)))
+    }
+
+    fn compound(&mut self) -> Option<Compound> {
+        let mut compound = Compound::default();
+        let mut is_empty = true;
+        if self.chars.next_if_eq(&'*').is_some() {
+            is_empty = false;
+        } else if let Some(tag_name) = self.name() {
+            compound.tag_name = Some(tag_name.to_ascii_lowercase());
+            is_empty = false;
+        }
+        loop {
+            match self.chars.peek() {
+                Some('#') => {
+                    self.chars.next();
+                    compound.id = Some(self.name()?);
+                }
+                Some('.') => {
+                    self.chars.next();
+                    compound.classes.push(self.name()?);
+                }
+                Some('[') => {
+                    self.chars.next();
+                    compound.attributes.push(self.attribute()?);
+                }
+                _ => break,
+            }
+            is_empty = false;
+        }
+        if is_empty { None } else { Some(compound) }
+    }
+
+    fn complex(&mut self) -> Option<Complex> {
+        self.skip_whitespace();
+        let mut compounds = vec![self.compound()?];
+        let mut combinators = vec![];
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            let combinator = match self.chars.peek() {
+                None | Some(',') => break,
+                Some(c @ ('>' | '+' | '~')) => {
+                    let combinator = match c {
+                        '>' => Combinator::Child,
+                        '+' => Combinator::NextSibling,
+                        _ => Combinator::SubsequentSibling,
+                    };
+                    self.chars.next();
+                    self.skip_whitespace();
+                    combinator
+                }
+                _ if had_whitespace => Combinator::Descendant,
+                _ => return None,
+            };
+            combinators.push(combinator);
+            compounds.push(self.compound()?);
+        }
+        let subject = compounds.pop()?;
+        let ancestors = combinators.into_iter().rev().zip(compounds.into_iter().rev()).collect();
+        Some(Complex { subject, ancestors })
+    }
+}
+
+impl Selector {
+    /// Parses a selector list, returning `None` if it is malformed or uses unsupported syntax.
+    pub fn parse(selector: &str) -> Option<Self> {
+        let mut parser = Parser { chars: selector.chars().peekable() };
+        let mut complexes = vec![parser.complex()?];
+        while parser.chars.next_if_eq(&',').is_some() {
+            complexes.push(parser.complex()?);
+        }
+        Some(Self(complexes))
+    }
+
+    /// Returns whether the element matches any of the selectors in the list. Only ancestors
+    /// within the element's own document are considered.
+    pub fn matches(&self, tree: &DomTree, element: &DomElement) -> bool {
+        self.0.iter().any(|complex| complex.subject.matches(element) && complex_matches(tree, element, &complex.ancestors))
+    }
+}
+
+impl Compound {
+    fn matches(&self, element: &DomElement) -> bool {
+        self.tag_name.iter().all(|tag_name| *tag_name == element.tag_name)
+            && self.id.iter().all(|id| element.id() == Some(id.as_str()))
+            && self.classes.iter().all(|class| element.classes().any(|element_class| element_class == class))
+            && self.attributes.iter().all(|(name, operator)| element.attributes.get(name).is_some_and(|value| match operator {
+                AttributeOperator::Exists => true,
+                AttributeOperator::Equals(expected) => value == expected,
+                AttributeOperator::Includes(word) => value.split_ascii_whitespace().any(|part| part == word),
+                AttributeOperator::DashMatch(prefix) => value == prefix || value.starts_with(&format!("{}-", prefix)),
+                AttributeOperator::Prefix(prefix) => !prefix.is_empty() && value.starts_with(prefix.as_str()),
+                AttributeOperator::Suffix(suffix) => !suffix.is_empty() && value.ends_with(suffix.as_str()),
+                AttributeOperator::Substring(substring) => !substring.is_empty() && value.contains(substring.as_str()),
+            }))
+    }
+}
+
+/// Checks the remaining compounds of a complex selector against the ancestors and earlier
+/// siblings of `element`.
+fn complex_matches(tree: &DomTree, element: &DomElement, ancestors: &[(Combinator, Compound)]) -> bool {
+    let ((combinator, compound), rest) = match ancestors.split_first() {
+        Some(first) => first,
+        None => return true,
+    };
+    // The document itself is not an element, so selectors can't match it
+    let mut ancestors = tree.ancestors(element).filter(|ancestor| ancestor.parent.is_some());
+    let mut siblings = tree.previous_siblings(element);
+    match combinator {
+        Combinator::Child => ancestors.next().is_some_and(|parent| compound.matches(parent) && complex_matches(tree, parent, rest)),
+        Combinator::Descendant => ancestors.any(|ancestor| compound.matches(ancestor) && complex_matches(tree, ancestor, rest)),
+        Combinator::NextSibling => siblings.next().is_some_and(|sibling| compound.matches(sibling) && complex_matches(tree, sibling, rest)),
+        Combinator::SubsequentSibling => siblings.any(|sibling| compound.matches(sibling) && complex_matches(tree, sibling, rest)),
+    }
+}
+
+/// An element that would have been hidden or removed by a cosmetic filter.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CosmeticMatch {
+    pub node_id: String,
+    pub tag_name: String,
+    /// The selector from the filter list that matched the element.
+    pub selector: String,
+    /// Whether the filter removes the element from the document, rather than hiding it.
+    pub removed: bool,
+    /// The URL of the document containing the element, if one was recorded.
+    pub document_url: Option<String>,
+    /// The script that created the element, or `None` if it was created by the parser.
+    pub script_node_id: Option<String>,
+    pub script_url: Option<String>,
+}
+
+/// The elements matched by the cosmetic filters in an engine, as reported by
+/// [`PageGraph::hidden_elements`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct CosmeticReport {
+    /// Every element matched, sorted by node id and then selector.
+    pub matches: Vec<CosmeticMatch>,
+    /// Selectors that applied to the page but could not be evaluated, in sorted order.
+    pub unsupported_selectors: Vec<String>,
+}
+
+impl PageGraph {
+    /// Evaluates the cosmetic filters in `engine` against the final DOM of each document in the
+    /// graph, reporting which elements would have been hidden or removed and which scripts
+    /// created them. Filters are selected for each document according to its own URL.
+    pub fn hidden_elements(&self, engine: &Engine) -> CosmeticReport {
+        let tree = self.final_dom();
+        let root_url = self.root_url();
+
+        let mut matches = vec![];
+        let mut unsupported_selectors = BTreeSet::new();
+
+        tree.roots.iter().for_each(|root| {
+            let document_url = match &self.nodes[root].node_type {
+                NodeType::DomRoot { url: Some(url), .. } => Some(url.clone()),
+                _ => None,
+            };
+            let elements = tree.descendants(*root);
+
+            let resources = engine.url_cosmetic_resources(document_url.as_deref().unwrap_or(&root_url));
+            let mut hide_selectors = resources.hide_selectors;
+            if !resources.generichide {
+                let classes = elements.iter().flat_map(|element| element.classes()).collect::<HashSet<_>>();
+                let ids = elements.iter().filter_map(|element| element.id()).collect::<HashSet<_>>();
+                hide_selectors.extend(engine.hidden_class_id_selectors(classes, ids, &resources.exceptions));
+            }
+            let selectors = hide_selectors.into_iter().map(|selector| (selector, false))
+                .chain(resources.remove_selectors.into_iter().map(|selector| (selector, true)));
+
+            selectors.for_each(|(selector_str, removed)| {
+                let selector = match Selector::parse(&selector_str) {
+                    Some(selector) => selector,
+                    None => {
+                        unsupported_selectors.insert(selector_str);
+                        return;
+                    }
+                };
+                elements.iter().filter(|element| selector.matches(&tree, element)).for_each(|element| {
+                    let script = element.creator
+                        .map(|creator| &self.nodes[&creator])
+                        .filter(|creator| matches!(creator.node_type, NodeType::Script { .. }));
+                    matches.push((element.node_id, CosmeticMatch {
+                        node_id: format!("{}", element.node_id),
+                        tag_name: element.tag_name.clone(),
+                        selector: selector_str.clone(),
+                        removed,
+                        document_url: document_url.clone(),
+                        script_node_id: script.map(|script| format!("{}", script.id)),
+                        script_url: script.and_then(|script| match &script.node_type {
+                            NodeType::Script { url, .. } => url.clone(),
+                            _ => None,
+                        }),
+                    }));
+                });
+            });
+        });
+
+        matches.sort_by(|(a_id, a), (b_id, b)| (a_id, &a.selector).cmp(&(b_id, &b.selector)));
+        CosmeticReport {
+            matches: matches.into_iter().map(|(_, cosmetic_match)| cosmetic_match).collect(),
+            unsupported_selectors: unsupported_selectors.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod cosmetic_tests {
+    use super::*;
+    use crate::graph::NodeId;
+
+    fn element(id: usize, tag_name: &str, attributes: &[(&str, &str)], parent: Option<usize>, children: &[usize]) -> DomElement {
+        DomElement {
+            node_id: NodeId::from(id),
+            tag_name: tag_name.to_string(),
+            attributes: attributes.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+            parent: parent.map(NodeId::from),
+            children: children.iter().copied().map(NodeId::from).collect(),
+            creator: None,
+            inserted_by: None,
+        }
+    }
+
+    #[test]
+    fn test_selectors() {
+        let mut tree = DomTree::default();
+        tree.elements.insert(1.into(), element(1, "#document", &[], None, &[2]));
+        tree.elements.insert(2.into(), element(2, "div", &[("id", "ad-slot"), ("class", "banner wide")], Some(1), &[3]));
+        tree.elements.insert(3.into(), element(3, "a", &[("href", "https://ads.example/click"), ("lang", "en-US")], Some(2), &[]));
+        tree.roots.push(1.into());
+
+        let div = &tree.elements[&2.into()];
+        let link = &tree.elements[&3.into()];
+        let matches = |selector: &str, element: &DomElement| Selector::parse(selector).unwrap().matches(&tree, element);
+
+        assert!(matches("#ad-slot", div));
+        assert!(matches("DIV.banner.wide", div));
+        assert!(!matches("div.banner.narrow", div));
+        assert!(matches("div > a[href^=\"https://ads.\"]", link));
+        assert!(matches("#ad-slot a[lang|=en], .nothing", link));
+        assert!(matches("[class~=wide] [href*='ads']", link));
+        assert!(!matches("* > div", div));
+        assert!(!matches("a[href$=\".png\"]", link));
+
+        assert_eq!(Selector::parse("div:has(a)"), None);
+        assert_eq!(Selector::parse("div + "), None);
+        assert_eq!(Selector::parse("div,"), None);
+    }
+}
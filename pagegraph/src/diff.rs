@@ -0,0 +1,260 @@
+//! Comparison of two graphs recorded for the same page, e.g. with and without Shields enabled.
+//!
+//! Raw node and edge ids are assigned in recording order, so they rarely line up between two
+//! captures. Nodes are instead matched by what they represent: DOM nodes by their Blink node id,
+//! scripts by a hash of their source, resources by URL, and Web APIs, builtins, and filters by
+//! name. Edges are matched by their type and the keys of their endpoints.
+
+use std::collections::HashMap;
+
+use crate::graph::{Edge, Node, PageGraph};
+use crate::types::{EdgeType, NodeType};
+use crate::url_utils;
+
+/// Identifies a node in a way that is comparable between captures of the same page.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize)]
+pub struct NodeKey {
+    /// The `node type` of the node in GraphML.
+    pub node_type: String,
+    /// The Blink node id, script source hash, URL, or name identifying the node within its type.
+    /// Empty for types with no identifying information, like parsers and storage areas.
+    pub identity: String,
+    /// Distinguishes nodes with the same type and identity, counting from zero in id order.
+    pub occurrence: usize,
+}
+
+/// Identifies an edge in a way that is comparable between captures of the same page.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize)]
+pub struct EdgeKey {
+    /// The `edge type` of the edge in GraphML.
+    pub edge_type: String,
+    pub source: NodeKey,
+    pub target: NodeKey,
+    /// Distinguishes edges with the same type and endpoints, counting from zero in time order.
+    pub occurrence: usize,
+}
+
+/// A node present in only one of the graphs, or recorded differently in each.
+#[derive(Debug, serde::Serialize)]
+pub struct NodeChange<'a> {
+    pub key: NodeKey,
+    pub before: Option<&'a Node>,
+    pub after: Option<&'a Node>,
+}
+
+/// An edge present in only one of the graphs, or recorded differently in each.
+#[derive(Debug, serde::Serialize)]
+pub struct EdgeChange<'a> {
+    pub key: EdgeKey,
+    pub before: Option<&'a Edge>,
+    pub after: Option<&'a Edge>,
+}
+
+/// The changes between two graphs of the same page. Each list is sorted by key.
+#[derive(Debug, serde::Serialize)]
+pub struct GraphDiff<'a> {
+    pub added_nodes: Vec<NodeChange<'a>>,
+    pub removed_nodes: Vec<NodeChange<'a>>,
+    pub changed_nodes: Vec<NodeChange<'a>>,
+    pub added_edges: Vec<EdgeChange<'a>>,
+    pub removed_edges: Vec<EdgeChange<'a>>,
+    pub changed_edges: Vec<EdgeChange<'a>>,
+}
+
+/// Describes why two graphs could not be compared.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffError {
+    /// The graphs were recorded for different pages.
+    UrlMismatch { before: String, after: String },
+}
+
+impl std::fmt::Display for DiffError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UrlMismatch { before, after } => write!(f, "graphs were recorded for different pages ({} and {})", before, after),
+        }
+    }
+}
+
+impl std::error::Error for DiffError {}
+
+/// A 64-bit FNV-1a hash, which unlike the standard library's hashers is stable between builds.
+fn source_hash(source: &str) -> String {
+    let hash = source.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn node_identity(node_type: &NodeType) -> String {
+    match node_type {
+        NodeType::HtmlElement { node_id, .. } |
+        NodeType::TextNode { node_id, .. } |
+        NodeType::DomRoot { node_id, .. } |
+        NodeType::FrameOwner { node_id, .. } => node_id.to_string(),
+        NodeType::Script { source, .. } => source_hash(source),
+        NodeType::Resource { url } => url.clone(),
+        NodeType::WebApi { method } | NodeType::JsBuiltin { method } => method.clone(),
+        NodeType::AdFilter { rule } => rule.clone(),
+        NodeType::Binding { binding, .. } => binding.clone(),
+        NodeType::BindingEvent { binding_event } => binding_event.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Clears the ids in an edge type that are assigned afresh in each capture, so that otherwise
+/// identical edges compare equal.
+fn comparable_edge_type(edge_type: &EdgeType) -> EdgeType {
+    let mut edge_type = edge_type.clone();
+    match &mut edge_type {
+        EdgeType::RequestStart { request_id, .. } |
+        EdgeType::RequestComplete { request_id, .. } |
+        EdgeType::RequestError { request_id, .. } => *request_id = 0,
+        EdgeType::AddEventListener { event_listener_id, script_id, .. } |
+        EdgeType::RemoveEventListener { event_listener_id, script_id, .. } => {
+            *event_listener_id = 0;
+            *script_id = 0;
+        }
+        EdgeType::EventListener { event_listener_id, .. } => *event_listener_id = 0,
+        _ => (),
+    }
+    edge_type
+}
+
+fn node_keys(graph: &PageGraph) -> HashMap<NodeKey, &Node> {
+    let mut nodes = graph.nodes.values().collect::<Vec<_>>();
+    nodes.sort_unstable_by_key(|node| node.id);
+
+    let mut occurrences = HashMap::<(String, String), usize>::new();
+    nodes.into_iter().map(|node| {
+        let node_type = node.node_type.type_name().to_string();
+        let identity = node_identity(&node.node_type);
+        let occurrence = occurrences.entry((node_type.clone(), identity.clone())).or_default();
+        let key = NodeKey { node_type, identity, occurrence: *occurrence };
+        *occurrence += 1;
+        (key, node)
+    }).collect()
+}
+
+fn edge_keys<'a>(graph: &'a PageGraph, node_keys: &HashMap<NodeKey, &Node>) -> HashMap<EdgeKey, &'a Edge> {
+    let keys_by_id = node_keys.iter().map(|(key, node)| (node.id, key)).collect::<HashMap<_, _>>();
+
+    let mut edges = graph.edges.values()
+        .filter(|edge| keys_by_id.contains_key(&edge.source) && keys_by_id.contains_key(&edge.target))
+        .collect::<Vec<_>>();
+    edges.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+
+    let mut occurrences = HashMap::<(String, &NodeKey, &NodeKey), usize>::new();
+    edges.into_iter().map(|edge| {
+        let edge_type = edge.edge_type.type_name().to_string();
+        let source = keys_by_id[&edge.source];
+        let target = keys_by_id[&edge.target];
+        let occurrence = occurrences.entry((edge_type.clone(), source, target)).or_default();
+        let key = EdgeKey { edge_type, source: source.clone(), target: target.clone(), occurrence: *occurrence };
+        *occurrence += 1;
+        (key, edge)
+    }).collect()
+}
+
+/// Matched items, with their versions before and after.
+type Changes<K, T> = Vec<(K, Option<T>, Option<T>)>;
+
+/// Splits matched items into those only in `before`, only in `after`, and in both but unequal
+/// according to `same`.
+fn partition<K: Clone + Ord + std::hash::Hash, T: Copy>(
+    before: &HashMap<K, T>,
+    after: &HashMap<K, T>,
+    same: impl Fn(&T, &T) -> bool,
+) -> (Changes<K, T>, Changes<K, T>, Changes<K, T>) {
+    let mut added = after.iter()
+        .filter(|(key, _)| !before.contains_key(key))
+        .map(|(key, item)| (key.clone(), None, Some(*item)))
+        .collect::<Vec<_>>();
+    let mut removed = before.iter()
+        .filter(|(key, _)| !after.contains_key(key))
+        .map(|(key, item)| (key.clone(), Some(*item), None))
+        .collect::<Vec<_>>();
+    let mut changed = before.iter()
+        .filter_map(|(key, before_item)| after.get(key).map(|after_item| (key, before_item, after_item)))
+        .filter(|(_, before_item, after_item)| !same(before_item, after_item))
+        .map(|(key, before_item, after_item)| (key.clone(), Some(*before_item), Some(*after_item)))
+        .collect::<Vec<_>>();
+    added.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    removed.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    changed.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    (added, removed, changed)
+}
+
+/// Computes the nodes and edges added, removed, and changed between two captures of the same
+/// page. Timestamps and per-capture ids, like request ids, are not considered changes.
+///
+/// Returns an error if the graphs were recorded for different URLs, after normalization.
+pub fn diff<'a>(before: &'a PageGraph, after: &'a PageGraph) -> Result<GraphDiff<'a>, DiffError> {
+    let normalized = |url: &str| url_utils::normalize(url).unwrap_or_else(|| url.to_string());
+    if normalized(&before.desc.url) != normalized(&after.desc.url) {
+        return Err(DiffError::UrlMismatch { before: before.desc.url.clone(), after: after.desc.url.clone() });
+    }
+
+    let before_nodes = node_keys(before);
+    let after_nodes = node_keys(after);
+    let before_edges = edge_keys(before, &before_nodes);
+    let after_edges = edge_keys(after, &after_nodes);
+
+    let to_node_changes = |changes: Changes<NodeKey, &'a Node>| changes.into_iter()
+        .map(|(key, before, after)| NodeChange { key, before, after })
+        .collect::<Vec<_>>();
+    let to_edge_changes = |changes: Changes<EdgeKey, &'a Edge>| changes.into_iter()
+        .map(|(key, before, after)| EdgeChange { key, before, after })
+        .collect::<Vec<_>>();
+
+    let (added_nodes, removed_nodes, changed_nodes) = partition(&before_nodes, &after_nodes, |a, b| a.node_type == b.node_type);
+    let (added_edges, removed_edges, changed_edges) = partition(&before_edges, &after_edges, |a, b| {
+        comparable_edge_type(&a.edge_type) == comparable_edge_type(&b.edge_type)
+    });
+
+    Ok(GraphDiff {
+        added_nodes: to_node_changes(added_nodes),
+        removed_nodes: to_node_changes(removed_nodes),
+        changed_nodes: to_node_changes(changed_nodes),
+        added_edges: to_edge_changes(added_edges),
+        removed_edges: to_edge_changes(removed_edges),
+        changed_edges: to_edge_changes(changed_edges),
+    })
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+    use crate::test_util::parse;
+
+    #[test]
+    fn test_diff() {
+        let before = parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">HTML element</data><data key="d1">2</data><data key="d2">0</data><data key="d4">img</data><data key="d5">false</data><data key="d6">5</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://example.com/a.png</data></node>
+<edge id="e4" source="n1" target="n2"><data key="d16">create node</data><data key="d17">4</data><data key="d18">0</data></edge>
+<edge id="e5" source="n2" target="n3"><data key="d16">request start</data><data key="d17">5</data><data key="d18">0</data><data key="d29">Image</data><data key="d30">started</data><data key="d28">1</data></edge>
+<edge id="e6" source="n2" target="n3"><data key="d16">request start</data><data key="d17">6</data><data key="d18">0</data><data key="d29">Image</data><data key="d30">started</data><data key="d28">2</data></edge>"#);
+        let after = parse(r#"<node id="n10"><data key="d0">parser</data><data key="d1">10</data><data key="d2">0</data></node>
+<node id="n11"><data key="d0">HTML element</data><data key="d1">11</data><data key="d2">0</data><data key="d4">img</data><data key="d5">true</data><data key="d6">5</data></node>
+<node id="n12"><data key="d0">resource</data><data key="d1">12</data><data key="d2">0</data><data key="d3">https://example.com/b.png</data></node>
+<node id="n13"><data key="d0">resource</data><data key="d1">13</data><data key="d2">0</data><data key="d3">https://example.com/a.png</data></node>
+<edge id="e14" source="n10" target="n11"><data key="d16">create node</data><data key="d17">14</data><data key="d18">0</data></edge>
+<edge id="e15" source="n11" target="n13"><data key="d16">request start</data><data key="d17">15</data><data key="d18">0</data><data key="d29">Image</data><data key="d30">started</data><data key="d28">7</data></edge>"#);
+
+        let changes = diff(&before, &after).unwrap();
+        let identities = |changes: &[NodeChange]| changes.iter().map(|change| change.key.identity.clone()).collect::<Vec<_>>();
+        assert_eq!(identities(&changes.added_nodes), vec!["https://example.com/b.png"]);
+        assert!(changes.removed_nodes.is_empty());
+        assert_eq!(identities(&changes.changed_nodes), vec!["5"]);
+
+        // The request with a different request id is matched, and its repeat is removed
+        assert!(changes.added_edges.is_empty());
+        assert_eq!(changes.removed_edges.iter().map(|change| (change.key.occurrence, change.before.unwrap().id)).collect::<Vec<_>>(), vec![(1, 6.into())]);
+        assert!(changes.changed_edges.is_empty());
+
+        let mut elsewhere = parse("");
+        elsewhere.desc.url = "https://example.org/".to_string();
+        assert!(matches!(diff(&before, &elsewhere), Err(DiffError::UrlMismatch { .. })));
+    }
+}
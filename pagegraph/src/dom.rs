@@ -0,0 +1,585 @@
+//! Reconstruction of the DOM trees of a page, as they stood at the end of the recording or at any
+//! earlier point, and their serialization as HTML.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::cosmetic::Selector;
+use crate::graph::{NodeId, PageGraph, Timestamp};
+use crate::types::{EdgeType, NodeType};
+
+/// Elements that have no closing tag or contents.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose text is serialized without escaping.
+const RAW_TEXT_ELEMENTS: [&str; 7] = ["iframe", "noembed", "noframes", "plaintext", "script", "style", "xmp"];
+
+/// An element, frame owner, or document in a reconstructed DOM tree.
+#[derive(Debug, Clone)]
+pub struct DomElement {
+    pub node_id: NodeId,
+    /// The tag name, in lowercase.
+    pub tag_name: String,
+    /// Attributes as of the end of the recording, keyed by lowercase name. Inline style
+    /// properties are not included.
+    pub attributes: BTreeMap<String, String>,
+    /// The element this one was last inserted under, unless it was removed since. Documents have
+    /// no parent.
+    pub parent: Option<NodeId>,
+    /// The elements and text nodes inserted under this one, in document order.
+    pub children: Vec<NodeId>,
+    /// The script or parser node that created the element, if its creation was recorded.
+    pub creator: Option<NodeId>,
+    /// The script or parser node that last inserted the element, and so is responsible for it
+    /// being where it is, unless it was removed since.
+    pub inserted_by: Option<NodeId>,
+}
+
+impl DomElement {
+    /// Returns the value of the element's `id` attribute.
+    pub fn id(&self) -> Option<&str> {
+        self.attributes.get("id").map(String::as_str)
+    }
+
+    /// Returns each of the classes in the element's `class` attribute.
+    pub fn classes(&self) -> impl Iterator<Item=&str> {
+        self.attributes.get("class").into_iter().flat_map(|classes| classes.split_ascii_whitespace())
+    }
+}
+
+/// A text node in a reconstructed DOM tree.
+#[derive(Debug, Clone)]
+pub struct DomText {
+    pub node_id: NodeId,
+    /// The text as of the end of the recording.
+    pub text: String,
+    /// The element this text was last inserted under, unless it was removed since.
+    pub parent: Option<NodeId>,
+}
+
+/// An element or text node that was created but never became part of a document, as reported by
+/// [`PageGraph::detached_elements`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DetachedNode {
+    pub node_id: String,
+    /// The type of the node in the page graph: `HTML element`, `frame owner`, or `text node`.
+    pub node_type: String,
+    /// The tag name in lowercase, for elements.
+    pub tag_name: Option<String>,
+    /// The script or parser node that created the node.
+    pub creator_node_id: String,
+    /// The URL of the creating script, if it was fetched.
+    pub creator_script_url: Option<String>,
+}
+
+/// A recorded change to a DOM tree, with the Blink node ids it refers to resolved to nodes.
+#[derive(Debug, Clone)]
+enum DomChange {
+    Create { node_id: NodeId, actor: NodeId },
+    /// An insertion under a parent that could not be found is treated as a removal.
+    Insert { node_id: NodeId, actor: NodeId, parent: Option<NodeId>, before: Option<NodeId> },
+    Remove { node_id: NodeId },
+    SetAttribute { node_id: NodeId, key: String, value: String },
+    DeleteAttribute { node_id: NodeId, key: String },
+}
+
+/// Escapes text for use in HTML, escaping quotes as well if it is an attribute value.
+fn escape_html(value: &str, is_attribute: bool, out: &mut String) {
+    value.chars().for_each(|c| match c {
+        '&' => out.push_str("&amp;"),
+        '\u{a0}' => out.push_str("&nbsp;"),
+        '"' if is_attribute => out.push_str("&quot;"),
+        '<' if !is_attribute => out.push_str("&lt;"),
+        '>' if !is_attribute => out.push_str("&gt;"),
+        c => out.push(c),
+    });
+}
+
+/// The DOM trees of every document in a graph, including those of local and merged remote frames.
+#[derive(Debug, Clone, Default)]
+pub struct DomTree {
+    pub elements: HashMap<NodeId, DomElement>,
+    pub texts: HashMap<NodeId, DomText>,
+    /// The `DomRoot` node of each document, in id order.
+    pub roots: Vec<NodeId>,
+    /// The time each node was recorded, so that it can be left out of earlier snapshots.
+    created: HashMap<NodeId, Timestamp>,
+    /// Every recorded change to the tree, in the order they happened.
+    changes: Vec<(Option<Timestamp>, DomChange)>,
+}
+
+impl DomTree {
+    /// Returns the elements below `node_id` in document order, not including the node itself.
+    pub fn descendants(&self, node_id: NodeId) -> Vec<&DomElement> {
+        let mut descendants = vec![];
+        let mut pending = self.elements.get(&node_id)
+            .map(|element| element.children.iter().rev().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+        while let Some(node_id) = pending.pop() {
+            if let Some(element) = self.elements.get(&node_id) {
+                descendants.push(element);
+                pending.extend(element.children.iter().rev());
+            }
+        }
+        descendants
+    }
+
+    /// Iterates over the elements before this one under the same parent, from the nearest.
+    pub fn previous_siblings<'a>(&'a self, element: &'a DomElement) -> impl Iterator<Item=&'a DomElement> + 'a {
+        let siblings = element.parent
+            .and_then(|parent| self.elements.get(&parent))
+            .map(|parent| parent.children.as_slice())
+            .unwrap_or_default();
+        let position = siblings.iter().position(|sibling| *sibling == element.node_id).unwrap_or(0);
+        siblings[..position].iter().rev().filter_map(move |sibling| self.elements.get(sibling))
+    }
+
+    /// Returns the elements in any document that match a CSS selector, in document order, with
+    /// documents in id order. Returns `None` if the selector is malformed or uses syntax that
+    /// [`Selector`] does not support.
+    pub fn select(&self, selector: &str) -> Option<Vec<NodeId>> {
+        let selector = Selector::parse(selector)?;
+        Some(self.roots.iter()
+            .flat_map(|root| self.descendants(*root))
+            .filter(|element| selector.matches(self, element))
+            .map(|element| element.node_id)
+            .collect())
+    }
+
+    /// Iterates over the ancestors of the element, from its parent up to its document.
+    pub fn ancestors<'a>(&'a self, element: &'a DomElement) -> impl Iterator<Item=&'a DomElement> + 'a {
+        std::iter::successors(Some(element), move |element| element.parent.and_then(|parent| self.elements.get(&parent))).skip(1)
+    }
+
+    /// Returns the tree as it stood at the given time, replaying only the changes recorded up to
+    /// and including it. Nodes recorded after it are left out, and changes recorded without a
+    /// timestamp are assumed to have happened first.
+    ///
+    /// Each element's `inserted_by` is the script or parser responsible for it being present at
+    /// that time. Text nodes keep their final text, since changes to it are not recorded.
+    pub fn at(&self, timestamp: Timestamp) -> DomTree {
+        let mut tree = self.unassembled(|created| created <= timestamp);
+        tree.replay(Some(timestamp));
+        tree
+    }
+
+    /// Returns the nodes of the tree that were part of a document at some point during the
+    /// recording, including the documents themselves.
+    fn ever_connected(&self) -> HashSet<NodeId> {
+        let mut tree = self.unassembled(|_| true);
+        let mut connected = tree.roots.iter().copied().collect::<HashSet<_>>();
+        self.changes.iter().for_each(|(_, change)| {
+            tree.apply(change);
+            if let DomChange::Insert { node_id, .. } = change {
+                if tree.is_connected(*node_id) {
+                    connected.insert(*node_id);
+                    let descendants = tree.descendants(*node_id);
+                    let texts = tree.elements.get(node_id).into_iter()
+                        .chain(descendants.iter().copied())
+                        .flat_map(|element| element.children.iter())
+                        .filter(|child| tree.texts.contains_key(child));
+                    connected.extend(texts.chain(descendants.iter().map(|element| &element.node_id)));
+                }
+            }
+        });
+        connected
+    }
+
+    /// Whether the node's ancestors lead up to a document.
+    fn is_connected(&self, node_id: NodeId) -> bool {
+        if self.roots.contains(&node_id) {
+            return true;
+        }
+        match self.elements.get(&node_id) {
+            Some(element) => self.ancestors(element).any(|ancestor| self.roots.contains(&ancestor.node_id)),
+            None => self.texts.get(&node_id).and_then(|text| text.parent).is_some_and(|parent| self.is_connected(parent)),
+        }
+    }
+
+    /// Returns the nodes of the tree recorded at a time for which `present` holds, without any of
+    /// the recorded changes applied.
+    fn unassembled(&self, present: impl Fn(Timestamp) -> bool) -> DomTree {
+        let present = |node_id: &NodeId| match self.created.get(node_id) {
+            Some(created) => present(*created),
+            None => true,
+        };
+        DomTree {
+            elements: self.elements.values().filter(|element| present(&element.node_id)).map(|element| (element.node_id, DomElement {
+                node_id: element.node_id,
+                tag_name: element.tag_name.clone(),
+                attributes: BTreeMap::new(),
+                parent: None,
+                children: vec![],
+                creator: None,
+                inserted_by: None,
+            })).collect(),
+            texts: self.texts.values().filter(|text| present(&text.node_id)).map(|text| (text.node_id, DomText {
+                parent: None,
+                ..text.clone()
+            })).collect(),
+            roots: self.roots.iter().copied().filter(present).collect(),
+            created: self.created.clone(),
+            changes: self.changes.clone(),
+        }
+    }
+
+    /// Applies the recorded changes up to and including the given time, or all of them.
+    fn replay(&mut self, until: Option<Timestamp>) {
+        let changes = std::mem::take(&mut self.changes);
+        changes.iter()
+            .take_while(|(timestamp, _)| match (timestamp, until) {
+                (Some(timestamp), Some(until)) => *timestamp <= until,
+                _ => true,
+            })
+            .for_each(|(_, change)| self.apply(change));
+        self.changes = changes;
+    }
+
+    fn apply(&mut self, change: &DomChange) {
+        match change {
+            DomChange::Create { node_id, actor } => if let Some(element) = self.elements.get_mut(node_id) {
+                element.creator.get_or_insert(*actor);
+            },
+            DomChange::Insert { node_id, actor, parent: Some(parent), before } => {
+                self.attach(*node_id, *parent, *before);
+                if let Some(element) = self.elements.get_mut(node_id).filter(|element| element.parent == Some(*parent)) {
+                    element.inserted_by = Some(*actor);
+                }
+            }
+            DomChange::Insert { node_id, parent: None, .. } |
+            DomChange::Remove { node_id } => self.detach(*node_id),
+            DomChange::SetAttribute { node_id, key, value } => if let Some(element) = self.elements.get_mut(node_id) {
+                element.attributes.insert(key.clone(), value.clone());
+            },
+            DomChange::DeleteAttribute { node_id, key } => if let Some(element) = self.elements.get_mut(node_id) {
+                element.attributes.remove(key);
+            },
+        }
+    }
+
+    /// Serializes a node and everything below it as HTML, like `outerHTML`. Documents are
+    /// serialized as their contents. Returns `None` if the node is not in the tree.
+    pub fn to_html(&self, node_id: NodeId) -> Option<String> {
+        let mut out = String::new();
+        if let Some(text) = self.texts.get(&node_id) {
+            escape_html(&text.text, false, &mut out);
+        } else if self.roots.contains(&node_id) {
+            self.elements.get(&node_id)?.children.iter().for_each(|child| self.write_html(*child, false, &mut out));
+        } else {
+            self.elements.get(&node_id)?;
+            self.write_html(node_id, false, &mut out);
+        }
+        Some(out)
+    }
+
+    fn write_html(&self, node_id: NodeId, raw_text: bool, out: &mut String) {
+        if let Some(text) = self.texts.get(&node_id) {
+            match raw_text {
+                true => out.push_str(&text.text),
+                false => escape_html(&text.text, false, out),
+            }
+            return;
+        }
+        let element = match self.elements.get(&node_id) {
+            Some(element) => element,
+            None => return,
+        };
+        out.push('<');
+        out.push_str(&element.tag_name);
+        element.attributes.iter().for_each(|(name, value)| {
+            out.push(' ');
+            out.push_str(name);
+            out.push_str("=\"");
+            escape_html(value, true, out);
+            out.push('"');
+        });
+        out.push('>');
+        if VOID_ELEMENTS.contains(&element.tag_name.as_str()) {
+            return;
+        }
+        let raw_text = RAW_TEXT_ELEMENTS.contains(&element.tag_name.as_str());
+        element.children.iter().for_each(|child| self.write_html(*child, raw_text, out));
+        out.push_str("</");
+        out.push_str(&element.tag_name);
+        out.push('>');
+    }
+
+    fn parent_mut(&mut self, node_id: NodeId) -> Option<&mut Option<NodeId>> {
+        match self.elements.get_mut(&node_id) {
+            Some(element) => Some(&mut element.parent),
+            None => self.texts.get_mut(&node_id).map(|text| &mut text.parent),
+        }
+    }
+
+    fn detach(&mut self, node_id: NodeId) {
+        if let Some(element) = self.elements.get_mut(&node_id) {
+            element.inserted_by = None;
+        }
+        if let Some(parent) = self.parent_mut(node_id).and_then(Option::take) {
+            if let Some(parent) = self.elements.get_mut(&parent) {
+                parent.children.retain(|child| *child != node_id);
+            }
+        }
+    }
+
+    fn attach(&mut self, node_id: NodeId, parent: NodeId, before: Option<NodeId>) {
+        // An element can't be inserted below itself, however inconsistent the graph is
+        let creates_cycle = parent == node_id || self.elements.get(&parent)
+            .is_some_and(|parent| self.ancestors(parent).any(|ancestor| ancestor.node_id == node_id));
+        if creates_cycle {
+            return;
+        }
+        self.detach(node_id);
+        if let Some(parent_element) = self.elements.get_mut(&parent) {
+            let position = before
+                .and_then(|before| parent_element.children.iter().position(|child| *child == before))
+                .unwrap_or(parent_element.children.len());
+            parent_element.children.insert(position, node_id);
+            if let Some(node_parent) = self.parent_mut(node_id) {
+                *node_parent = Some(parent);
+            }
+        }
+    }
+}
+
+impl PageGraph {
+    /// Reconstructs the DOM tree of each document in the graph by replaying the creations,
+    /// insertions, removals, and attribute changes recorded for its nodes in the order they
+    /// happened. Text changes record only the final text of each text node, which is used
+    /// throughout. Use [`DomTree::at`] for the tree as it stood earlier in the recording.
+    ///
+    /// Nodes that were removed and never reinserted are kept, but detached from their document.
+    pub fn final_dom(&self) -> DomTree {
+        let mut tree = DomTree::default();
+
+        self.nodes.values().for_each(|node| {
+            match &node.node_type {
+                NodeType::HtmlElement { tag_name, .. } |
+                NodeType::FrameOwner { tag_name, .. } |
+                NodeType::DomRoot { tag_name, .. } => {
+                    tree.elements.insert(node.id, DomElement {
+                        node_id: node.id,
+                        tag_name: tag_name.to_ascii_lowercase(),
+                        attributes: BTreeMap::new(),
+                        parent: None,
+                        children: vec![],
+                        creator: None,
+                        inserted_by: None,
+                    });
+                    if matches!(node.node_type, NodeType::DomRoot { .. }) {
+                        tree.roots.push(node.id);
+                    }
+                }
+                NodeType::TextNode { text, .. } => {
+                    tree.texts.insert(node.id, DomText {
+                        node_id: node.id,
+                        text: text.clone().unwrap_or_default(),
+                        parent: None,
+                    });
+                }
+                _ => return,
+            }
+            tree.created.insert(node.id, node.node_timestamp);
+        });
+        tree.roots.sort_unstable();
+
+        let mut edges = self.edges.values()
+            .filter(|edge| tree.created.contains_key(&edge.target))
+            .collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+
+        let by_blink_id = |context: NodeId, blink_id| self.html_node_by_blink_id(context, blink_id).map(|node| node.id);
+        tree.changes = edges.into_iter().filter_map(|edge| {
+            let node_id = edge.target;
+            let change = match &edge.edge_type {
+                EdgeType::CreateNode {} => DomChange::Create { node_id, actor: edge.source },
+                EdgeType::InsertNode { parent, before } => DomChange::Insert {
+                    node_id,
+                    actor: edge.source,
+                    parent: by_blink_id(node_id, *parent),
+                    before: before.and_then(|before| by_blink_id(node_id, before)),
+                },
+                EdgeType::RemoveNode {} => DomChange::Remove { node_id },
+                EdgeType::SetAttribute { key, value, is_style: false } => DomChange::SetAttribute {
+                    node_id,
+                    key: key.to_ascii_lowercase(),
+                    value: value.clone().unwrap_or_default(),
+                },
+                EdgeType::DeleteAttribute { key, is_style: false } => DomChange::DeleteAttribute { node_id, key: key.to_ascii_lowercase() },
+                _ => return None,
+            };
+            Some((edge.edge_timestamp, change))
+        }).collect();
+        tree.replay(None);
+
+        tree
+    }
+
+    /// Returns the elements and text nodes that were created during the recording but never
+    /// inserted into a document, either directly or as part of a detached subtree, along with the
+    /// script or parser that created them. Such nodes are commonly used for fingerprinting, e.g.
+    /// canvases that are drawn to but never shown. Nodes are returned in id order.
+    pub fn detached_elements(&self) -> Vec<DetachedNode> {
+        let dom = self.final_dom();
+        let connected = dom.ever_connected();
+
+        let mut detached = self.nodes.values()
+            .filter(|node| matches!(node.node_type, NodeType::HtmlElement { .. } | NodeType::FrameOwner { .. } | NodeType::TextNode { .. }))
+            .filter(|node| !connected.contains(&node.id))
+            .filter_map(|node| {
+                let creator = self.incoming_edges(node)
+                    .filter(|edge| matches!(edge.edge_type, EdgeType::CreateNode {}))
+                    .min_by_key(|edge| (edge.edge_timestamp, edge.id))?;
+                let creator_script_url = match &self.nodes.get(&creator.source)?.node_type {
+                    NodeType::Script { url, .. } => url.clone(),
+                    _ => None,
+                };
+                Some((node.id, DetachedNode {
+                    node_id: format!("{}", node.id),
+                    node_type: node.node_type.type_name().to_string(),
+                    tag_name: dom.elements.get(&node.id).map(|element| element.tag_name.clone()),
+                    creator_node_id: format!("{}", creator.source),
+                    creator_script_url,
+                }))
+            })
+            .collect::<Vec<_>>();
+        detached.sort_unstable_by_key(|(node_id, _)| *node_id);
+        detached.into_iter().map(|(_, node)| node).collect()
+    }
+}
+
+#[cfg(test)]
+mod dom_tests {
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let mut tree = DomTree::default();
+        let mut add_element = |id: usize, tag_name: &str, attributes: &[(&str, &str)]| {
+            tree.elements.insert(id.into(), DomElement {
+                node_id: id.into(),
+                tag_name: tag_name.to_string(),
+                attributes: attributes.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+                parent: None,
+                children: vec![],
+                creator: None,
+                inserted_by: None,
+            });
+        };
+        add_element(1, "#document", &[]);
+        add_element(2, "h2", &[]);
+        add_element(3, "div", &[("class", "ad"), ("data-slot", "top")]);
+        add_element(4, "div", &[("class", "ad")]);
+        add_element(5, "div", &[("class", "ad"), ("data-slot", "detached")]);
+        tree.roots.push(1.into());
+        tree.texts.insert(6.into(), DomText { node_id: 6.into(), text: "Sponsored".to_string(), parent: None });
+        [2, 6, 3, 4].iter().for_each(|id| tree.attach((*id).into(), 1.into(), None));
+
+        let ids = |ids: &[usize]| ids.iter().map(|id| NodeId::from(*id)).collect::<Vec<_>>();
+        assert_eq!(tree.select("div.ad[data-slot]"), Some(ids(&[3])));
+        assert_eq!(tree.select("h2 + div"), Some(ids(&[3])));
+        assert_eq!(tree.select("h2 ~ .ad"), Some(ids(&[3, 4])));
+        assert_eq!(tree.select(".ad + .ad"), Some(ids(&[4])));
+        assert_eq!(tree.select("div:first-child"), None);
+    }
+
+    #[test]
+    fn test_to_html() {
+        let mut tree = DomTree::default();
+        let mut add_element = |id: usize, tag_name: &str, attributes: &[(&str, &str)]| {
+            tree.elements.insert(id.into(), DomElement {
+                node_id: id.into(),
+                tag_name: tag_name.to_string(),
+                attributes: attributes.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+                parent: None,
+                children: vec![],
+                creator: None,
+                inserted_by: None,
+            });
+        };
+        add_element(1, "#document", &[]);
+        add_element(2, "div", &[("title", "say \"hi\" & <bye>")]);
+        add_element(3, "img", &[("src", "a.png")]);
+        add_element(4, "script", &[]);
+        tree.roots.push(1.into());
+        tree.texts.insert(5.into(), DomText { node_id: 5.into(), text: "1 < 2".to_string(), parent: None });
+        tree.texts.insert(6.into(), DomText { node_id: 6.into(), text: "if (1 < 2) {}".to_string(), parent: None });
+
+        tree.attach(2.into(), 1.into(), None);
+        tree.attach(5.into(), 2.into(), None);
+        tree.attach(3.into(), 2.into(), Some(5.into()));
+        tree.attach(4.into(), 2.into(), None);
+        tree.attach(6.into(), 4.into(), None);
+
+        assert_eq!(tree.to_html(1.into()).as_deref(), Some(r#"<div title="say &quot;hi&quot; &amp; <bye>"><img src="a.png">1 &lt; 2<script>if (1 < 2) {}</script></div>"#));
+        assert_eq!(tree.to_html(5.into()).as_deref(), Some("1 &lt; 2"));
+        assert_eq!(tree.to_html(7.into()), None);
+
+        tree.detach(3.into());
+        assert_eq!(tree.to_html(2.into()).as_deref(), Some(r#"<div title="say &quot;hi&quot; &amp; <bye>">1 &lt; 2<script>if (1 < 2) {}</script></div>"#));
+    }
+}
+
+#[cfg(test)]
+mod dom_snapshot_tests {
+    use std::convert::TryFrom;
+
+    use crate::graph::{NodeId, Timestamp};
+
+    #[test]
+    fn test_dom_at() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">DOM root</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/</data><data key="d4">#document</data><data key="d5">false</data><data key="d6">1</data></node>
+<node id="n3"><data key="d0">HTML element</data><data key="d1">3</data><data key="d2">1</data><data key="d4">body</data><data key="d5">false</data><data key="d6">2</data></node>
+<node id="n4"><data key="d0">script</data><data key="d1">4</data><data key="d2">3</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n5"><data key="d0">HTML element</data><data key="d1">5</data><data key="d2">4</data><data key="d4">div</data><data key="d5">false</data><data key="d6">3</data></node>
+<edge id="e6" source="n1" target="n3"><data key="d16">create node</data><data key="d17">6</data><data key="d18">1</data></edge>
+<edge id="e7" source="n1" target="n3"><data key="d16">insert node</data><data key="d17">7</data><data key="d18">2</data><data key="d19">1</data></edge>
+<edge id="e8" source="n4" target="n5"><data key="d16">create node</data><data key="d17">8</data><data key="d18">4</data></edge>
+<edge id="e9" source="n4" target="n5"><data key="d16">set attribute</data><data key="d17">9</data><data key="d18">5</data><data key="d21">class</data><data key="d20">consent</data><data key="d22">false</data></edge>
+<edge id="e10" source="n4" target="n5"><data key="d16">insert node</data><data key="d17">10</data><data key="d18">6</data><data key="d19">2</data></edge>
+<edge id="e11" source="n4" target="n5"><data key="d16">remove node</data><data key="d17">11</data><data key="d18">8</data></edge>"#);
+        let node = |id: &str| NodeId::try_from(id).unwrap();
+        let tree = graph.final_dom();
+
+        assert_eq!(tree.to_html(node("n2")).as_deref(), Some("<body></body>"));
+        assert!(!tree.at(Timestamp::from(0)).elements.contains_key(&node("n3")));
+        assert_eq!(tree.at(Timestamp::from(1)).to_html(node("n2")).as_deref(), Some(""));
+        assert_eq!(tree.at(Timestamp::from(5)).to_html(node("n2")).as_deref(), Some("<body></body>"));
+
+        let during = tree.at(Timestamp::from(7));
+        assert_eq!(during.to_html(node("n2")).as_deref(), Some(r#"<body><div class="consent"></div></body>"#));
+        assert_eq!(during.elements[&node("n5")].inserted_by, Some(node("n4")));
+        assert_eq!(during.elements[&node("n3")].inserted_by, Some(node("n1")));
+
+        assert_eq!(tree.elements[&node("n5")].inserted_by, None);
+        assert_eq!(tree.elements[&node("n5")].creator, Some(node("n4")));
+    }
+}
+
+#[cfg(test)]
+mod detached_tests {
+    #[test]
+    fn test_detached_elements() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">DOM root</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/</data><data key="d4">#document</data><data key="d5">false</data><data key="d6">1</data></node>
+<node id="n3"><data key="d0">script</data><data key="d1">3</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data><data key="d3">https://tracker.com/fp.js</data></node>
+<node id="n4"><data key="d0">HTML element</data><data key="d1">4</data><data key="d2">1</data><data key="d4">div</data><data key="d5">false</data><data key="d6">2</data></node>
+<node id="n5"><data key="d0">HTML element</data><data key="d1">5</data><data key="d2">1</data><data key="d4">CANVAS</data><data key="d5">false</data><data key="d6">3</data></node>
+<node id="n6"><data key="d0">text node</data><data key="d1">6</data><data key="d2">1</data><data key="d5">false</data><data key="d6">4</data><data key="d7">fallback</data></node>
+<edge id="e7" source="n3" target="n4"><data key="d16">create node</data><data key="d17">7</data><data key="d18">1</data></edge>
+<edge id="e8" source="n3" target="n4"><data key="d16">insert node</data><data key="d17">8</data><data key="d18">2</data><data key="d19">1</data></edge>
+<edge id="e9" source="n3" target="n4"><data key="d16">remove node</data><data key="d17">9</data><data key="d18">3</data></edge>
+<edge id="e10" source="n3" target="n5"><data key="d16">create node</data><data key="d17">10</data><data key="d18">4</data></edge>
+<edge id="e11" source="n3" target="n6"><data key="d16">create node</data><data key="d17">11</data><data key="d18">5</data></edge>
+<edge id="e12" source="n3" target="n6"><data key="d16">insert node</data><data key="d17">12</data><data key="d18">6</data><data key="d19">3</data></edge>"#);
+
+        let detached = graph.detached_elements();
+        assert_eq!(detached.iter().map(|node| node.node_id.as_str()).collect::<Vec<_>>(), ["n5", "n6"]);
+        assert_eq!(detached[0].tag_name.as_deref(), Some("canvas"));
+        assert_eq!(detached[0].creator_node_id, "n3");
+        assert_eq!(detached[0].creator_script_url.as_deref(), Some("https://tracker.com/fp.js"));
+        assert_eq!(detached[1].node_type, "text node");
+    }
+}
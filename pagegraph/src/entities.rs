@@ -0,0 +1,225 @@
+//! Attribution of requests, scripts, and storage access to the companies that own the domains
+//! involved, using an entity map in the format published by Disconnect or DuckDuckGo's Tracker
+//! Radar.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::graph::PageGraph;
+use crate::storage::AccessCounts;
+use crate::types::{EdgeKind, NodeKind, NodeType};
+use crate::url_utils;
+
+/// An entity map could not be read.
+#[derive(Debug)]
+pub enum EntityMapError {
+    /// The map is not valid JSON.
+    Json(serde_json::Error),
+    /// The map is valid JSON, but not an object of entities.
+    NotAnObject,
+    /// The named entity is not an object listing its domains.
+    InvalidEntity(String),
+}
+
+impl std::fmt::Display for EntityMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "is not valid JSON: {}", e),
+            Self::NotAnObject => write!(f, "is not an object of entities"),
+            Self::InvalidEntity(entity) => write!(f, "has an invalid entry for entity {:?}", entity),
+        }
+    }
+}
+
+impl std::error::Error for EntityMapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for EntityMapError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// The company, or other entity, that owns each of a set of domains.
+#[derive(Debug, Clone, Default)]
+pub struct EntityMap {
+    owners: HashMap<String, String>,
+}
+
+impl EntityMap {
+    /// Reads an entity map from JSON. Both Disconnect's `entities.json`, where entities are
+    /// nested under an `entities` key, and Tracker Radar's `entity_map.json`, where they are at
+    /// the top level, are accepted. The domains of each entity are taken from its `properties`,
+    /// `resources`, and `domains` lists.
+    pub fn from_json(json: &str) -> Result<Self, EntityMapError> {
+        let value = serde_json::from_str::<serde_json::Value>(json)?;
+        let entities = match value.get("entities") {
+            Some(serde_json::Value::Object(entities)) => entities,
+            _ => value.as_object().ok_or(EntityMapError::NotAnObject)?,
+        };
+
+        let mut owners = HashMap::new();
+        for (entity, domains) in entities {
+            let domains = domains.as_object().ok_or_else(|| EntityMapError::InvalidEntity(entity.clone()))?;
+            ["properties", "resources", "domains"].iter()
+                .filter_map(|list| domains.get(*list)?.as_array())
+                .flatten()
+                .filter_map(serde_json::Value::as_str)
+                .for_each(|domain| {
+                    let domain = domain.trim_start_matches("*.").trim_matches('.').to_ascii_lowercase();
+                    owners.entry(domain).or_insert_with(|| entity.clone());
+                });
+        }
+        Ok(Self { owners })
+    }
+
+    /// Returns the entity owning a host, or the closest of its parent domains that has an owner.
+    pub fn entity_for_host(&self, host: &str) -> Option<&str> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let mut domain = host.as_str();
+        loop {
+            if let Some(entity) = self.owners.get(domain) {
+                return Some(entity);
+            }
+            domain = domain.split_once('.')?.1;
+        }
+    }
+
+    /// Returns the entity owning the host of a URL.
+    pub fn entity_for_url(&self, url: &str) -> Option<&str> {
+        self.entity_for_host(&url_utils::host(url)?)
+    }
+
+    /// Returns the entity owning the host of a URL and `true`, or the URL's site and `false` if
+    /// the map has no owner for it.
+    pub(crate) fn entity_or_site(&self, url: &str) -> Option<(String, bool)> {
+        match self.entity_for_url(url) {
+            Some(entity) => Some((entity.to_string(), true)),
+            None => Some((url_utils::site(url)?, false)),
+        }
+    }
+}
+
+/// The requests, scripts, and storage accesses attributed to a single entity, as reported by
+/// [`PageGraph::entity_report`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct EntityUsage {
+    /// The entity's name, or the site of the URLs involved if the entity map has no owner for it.
+    pub entity: String,
+    /// Whether the entity was found in the entity map.
+    pub known: bool,
+    /// The sites attributed to the entity, sorted.
+    pub sites: Vec<String>,
+    pub requests: usize,
+    /// The total size of the responses to the entity's requests, where it could be determined.
+    pub bytes: u64,
+    /// Scripts loaded from the entity's domains, or inline in its documents.
+    pub scripts: usize,
+    /// Storage accesses made by the entity's scripts.
+    pub storage: AccessCounts,
+}
+
+/// Returns the usage of the entity owning a URL, adding it to `usage` if it is new. Returns `None`
+/// if the URL has no owner in the entity map and no site.
+fn usage_for<'a>(usage: &'a mut BTreeMap<String, (EntityUsage, BTreeSet<String>)>, entities: &EntityMap, url: &str) -> Option<&'a mut EntityUsage> {
+    let (entity, known) = entities.entity_or_site(url)?;
+    let (entity_usage, sites) = usage.entry(entity.clone()).or_insert_with(|| (EntityUsage {
+        entity,
+        known,
+        ..Default::default()
+    }, BTreeSet::new()));
+    sites.extend(url_utils::site(url));
+    Some(entity_usage)
+}
+
+impl PageGraph {
+    /// Attributes every request, script, and storage access in the graph to the entity owning
+    /// the URL involved, returning the entities with the most requests first. Inline scripts and
+    /// other actors are attributed to the document they ran in. URLs whose owner is not in the
+    /// entity map are attributed to their site instead.
+    pub fn entity_report(&self, entities: &EntityMap) -> Vec<EntityUsage> {
+        let mut usage = BTreeMap::<String, (EntityUsage, BTreeSet<String>)>::new();
+        self.edges_of_kind(EdgeKind::RequestStart).for_each(|edge| {
+            if let Some(entity_usage) = self.target_node(edge).as_resource().and_then(|url| usage_for(&mut usage, entities, url)) {
+                entity_usage.requests += 1;
+            }
+        });
+        self.edges_of_kind(EdgeKind::RequestComplete).chain(self.edges_of_kind(EdgeKind::RequestError)).for_each(|edge| {
+            let size = edge.as_request_complete().map(|complete| complete.size)
+                .or_else(|| edge.as_request_error().map(|error| error.size))
+                .flatten();
+            if let (Some(size), Some(url)) = (size, self.source_node(edge).as_resource()) {
+                if let Some(entity_usage) = usage_for(&mut usage, entities, url) {
+                    entity_usage.bytes += size;
+                }
+            }
+        });
+        self.nodes_of_kind(NodeKind::Script).for_each(|script| {
+            let url = match &script.node_type {
+                NodeType::Script { url: Some(url), .. } => url.clone(),
+                _ => self.document_url_for(script.id),
+            };
+            if let Some(entity_usage) = usage_for(&mut usage, entities, &url) {
+                entity_usage.scripts += 1;
+            }
+        });
+        self.storage_accesses().into_iter().for_each(|edge| {
+            let url = match &self.source_node(edge).node_type {
+                NodeType::Script { url: Some(url), .. } => url.clone(),
+                _ => self.document_url_for(edge.source),
+            };
+            if let Some(entity_usage) = usage_for(&mut usage, entities, &url) {
+                entity_usage.storage.count(&edge.edge_type);
+            }
+        });
+
+        let mut report = usage.into_values().map(|(mut entity_usage, sites)| {
+            entity_usage.sites = sites.into_iter().collect();
+            entity_usage
+        }).collect::<Vec<_>>();
+        report.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.entity.cmp(&b.entity)));
+        report
+    }
+}
+
+#[cfg(test)]
+mod entities_tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_map_formats() {
+        let disconnect = EntityMap::from_json(r#"{"license": "", "entities": {"Google": {"properties": ["google.com"], "resources": ["doubleclick.net"]}}}"#).unwrap();
+        assert_eq!(disconnect.entity_for_url("https://ad.doubleclick.net/x"), Some("Google"));
+        let tracker_radar = EntityMap::from_json(r#"{"Alphabet": {"properties": ["google.com"], "aliases": ["Google"]}}"#).unwrap();
+        assert_eq!(tracker_radar.entity_for_host("www.Google.com."), Some("Alphabet"));
+        assert_eq!(tracker_radar.entity_for_host("notgoogle.com"), None);
+        assert!(matches!(EntityMap::from_json(r#"{"Alphabet": []}"#), Err(EntityMapError::InvalidEntity(_))));
+    }
+
+    #[test]
+    fn test_entity_report() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://www.google-analytics.com/analytics.js</data></node>
+<node id="n3"><data key="d0">script</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://www.google-analytics.com/analytics.js</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n4"><data key="d0">resource</data><data key="d1">4</data><data key="d2">0</data><data key="d3">https://cdn.example.com/a.css</data></node>
+<node id="n5"><data key="d0">local storage</data><data key="d1">5</data><data key="d2">0</data></node>
+<edge id="e6" source="n1" target="n2"><data key="d16">request start</data><data key="d17">6</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e7" source="n2" target="n1"><data key="d16">request complete</data><data key="d17">7</data><data key="d18">2</data><data key="d28">1</data><data key="d29">script</data><data key="d30">complete</data><data key="d32"></data><data key="d33">100</data></edge>
+<edge id="e8" source="n1" target="n4"><data key="d16">request start</data><data key="d17">8</data><data key="d18">3</data><data key="d28">2</data><data key="d29">CSS</data><data key="d30">started</data></edge>
+<edge id="e9" source="n3" target="n5"><data key="d16">storage set</data><data key="d17">9</data><data key="d18">4</data><data key="d21">_ga</data><data key="d20">1</data></edge>"#);
+        let entities = EntityMap::from_json(r#"{"Alphabet": {"properties": ["google-analytics.com"]}}"#).unwrap();
+
+        let report = graph.entity_report(&entities);
+        assert_eq!(report.iter().map(|usage| usage.entity.as_str()).collect::<Vec<_>>(), ["Alphabet", "example.com"]);
+        assert!(report[0].known);
+        assert_eq!(report[0].sites, ["google-analytics.com"]);
+        assert_eq!((report[0].requests, report[0].bytes, report[0].scripts, report[0].storage.writes), (1, 100, 1, 1));
+        assert!(!report[1].known);
+        assert_eq!(report[1].requests, 1);
+    }
+}
@@ -0,0 +1,357 @@
+//! Exports a [`PageGraph`] to other formats, for visualization or processing by other tools.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::call_graph::{CallDirection, JsCallGraph};
+use crate::graph::{Edge, FrameId, HasFrameId, Node, NodeId, PageGraph};
+use crate::types::{EdgeType, NodeType};
+
+mod csv;
+pub use self::csv::{centrality_to_csv, features_to_csv, to_csv, waterfall_to_csv};
+mod har;
+pub use har::to_har;
+mod trace;
+pub use trace::to_trace_events;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::to_sqlite;
+#[cfg(feature = "parquet")]
+mod parquet;
+#[cfg(feature = "parquet")]
+pub use self::parquet::{features_to_parquet, to_parquet};
+
+/// Controls which parts of the graph are exported to DOT, and how they are labelled.
+#[derive(Clone, Debug)]
+pub struct DotOptions {
+    /// Only include nodes from the given frame. Nodes from the graph's own frame can be selected by
+    /// its `desc.frame_id`.
+    pub frame_id: Option<FrameId>,
+    /// Only include the given nodes.
+    pub nodes: Option<HashSet<NodeId>>,
+    /// Labels longer than this many characters are truncated. Script sources and URLs can otherwise
+    /// make a graph unreadable.
+    pub max_label_length: usize,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self {
+            frame_id: None,
+            nodes: None,
+            max_label_length: 64,
+        }
+    }
+}
+
+/// Shape and fill color for each kind of node.
+fn node_style(node_type: &NodeType) -> (&'static str, &'static str) {
+    match node_type {
+        NodeType::Resource { .. } => ("note", "#a6cee3"),
+        NodeType::Script { .. } => ("hexagon", "#fdbf6f"),
+        NodeType::HtmlElement { .. } |
+        NodeType::TextNode { .. } |
+        NodeType::FrameOwner { .. } => ("ellipse", "#b2df8a"),
+        NodeType::DomRoot { .. } => ("house", "#33a02c"),
+        NodeType::Parser {} => ("invtriangle", "#cab2d6"),
+        NodeType::WebApi { .. } |
+        NodeType::JsBuiltin { .. } => ("diamond", "#fb9a99"),
+        NodeType::Storage {} |
+        NodeType::LocalStorage {} |
+        NodeType::SessionStorage {} |
+        NodeType::CookieJar {} => ("cylinder", "#ffff99"),
+        NodeType::RemoteFrame { .. } => ("doubleoctagon", "#e6ab02"),
+        NodeType::AdFilter { .. } |
+        NodeType::TrackerFilter |
+        NodeType::FingerprintingFilter |
+        NodeType::BraveShields {} |
+        NodeType::AdsShield {} |
+        NodeType::TrackersShield {} |
+        NodeType::JavascriptShield {} |
+        NodeType::FingerprintingShield {} |
+        NodeType::FingerprintingV2Shield {} => ("octagon", "#d9d9d9"),
+        NodeType::Binding { .. } |
+        NodeType::BindingEvent { .. } |
+        NodeType::Extensions {} |
+        NodeType::Unknown { .. } => ("ellipse", "#ffffff"),
+    }
+}
+
+/// Line style and color for each kind of edge.
+fn edge_style(edge_type: &EdgeType) -> (&'static str, &'static str) {
+    match edge_type {
+        EdgeType::RequestStart { .. } |
+        EdgeType::RequestComplete { .. } |
+        EdgeType::RequestError { .. } |
+        EdgeType::RequestResponse { .. } => ("solid", "#1f78b4"),
+        EdgeType::Execute {} |
+        EdgeType::ExecuteFromAttribute { .. } => ("bold", "#ff7f00"),
+        EdgeType::JsCall { .. } |
+        EdgeType::JsResult { .. } => ("solid", "#e31a1c"),
+        EdgeType::CrossDom {} => ("bold", "#e6ab02"),
+        EdgeType::Structure {} |
+        EdgeType::Filter {} |
+        EdgeType::Shield {} => ("dashed", "#969696"),
+        _ => ("solid", "#000000"),
+    }
+}
+
+/// The most identifying piece of data for a node, if any.
+pub(crate) fn node_detail(node_type: &NodeType) -> Option<String> {
+    match node_type {
+        NodeType::Resource { url } => Some(url.clone()),
+        NodeType::Script { url, script_id, .. } => Some(match url {
+            Some(url) => format!("{} ({})", script_id, url),
+            None => format!("{}", script_id),
+        }),
+        NodeType::HtmlElement { tag_name, node_id, .. } |
+        NodeType::DomRoot { tag_name, node_id, .. } |
+        NodeType::FrameOwner { tag_name, node_id, .. } => Some(format!("<{}> {}", tag_name, node_id)),
+        NodeType::TextNode { text, .. } => text.clone(),
+        NodeType::WebApi { method } | NodeType::JsBuiltin { method } => Some(method.clone()),
+        NodeType::RemoteFrame { frame_id } => Some(format!("{}", frame_id)),
+        NodeType::AdFilter { rule } => Some(rule.clone()),
+        NodeType::Binding { binding, .. } => Some(binding.clone()),
+        NodeType::BindingEvent { binding_event } => Some(binding_event.clone()),
+        _ => None,
+    }
+}
+
+/// The most identifying piece of data for an edge, if any.
+pub(crate) fn edge_detail(edge_type: &EdgeType) -> Option<String> {
+    match edge_type {
+        EdgeType::RequestStart { request_id, .. } |
+        EdgeType::RequestComplete { request_id, .. } |
+        EdgeType::RequestError { request_id, .. } |
+        EdgeType::RequestResponse { request_id, .. } => Some(format!("#{}", request_id)),
+        EdgeType::SetAttribute { key, .. } |
+        EdgeType::DeleteAttribute { key, .. } |
+        EdgeType::StorageSet { key, .. } |
+        EdgeType::StorageReadResult { key, .. } |
+        EdgeType::DeleteStorage { key } |
+        EdgeType::ReadStorageCall { key } |
+        EdgeType::ClearStorage { key } |
+        EdgeType::AddEventListener { key, .. } |
+        EdgeType::RemoveEventListener { key, .. } |
+        EdgeType::EventListener { key, .. } => Some(key.clone()),
+        EdgeType::ExecuteFromAttribute { attr_name } => Some(attr_name.clone()),
+        _ => None,
+    }
+}
+
+/// Quotes and escapes a string for use as a DOT identifier or attribute value.
+fn dot_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    value.chars().for_each(|c| match c {
+        '"' => escaped.push_str("\\\""),
+        '\\' => escaped.push_str("\\\\"),
+        '\n' => escaped.push_str("\\n"),
+        '\r' => (),
+        c => escaped.push(c),
+    });
+    escaped.push('"');
+    escaped
+}
+
+fn label(type_name: &str, id: &str, detail: Option<String>, max_length: usize) -> String {
+    match detail {
+        Some(detail) if detail.chars().count() > max_length => {
+            format!("{} {}\n{}…", id, type_name, detail.chars().take(max_length).collect::<String>())
+        }
+        Some(detail) => format!("{} {}\n{}", id, type_name, detail),
+        None => format!("{} {}", id, type_name),
+    }
+}
+
+fn includes_node(graph: &PageGraph, options: &DotOptions, node: &Node) -> bool {
+    let in_frame = match options.frame_id {
+        Some(frame_id) => node.id.get_frame_id().unwrap_or(graph.desc.frame_id) == frame_id,
+        None => true,
+    };
+    let in_subgraph = match &options.nodes {
+        Some(nodes) => nodes.contains(&node.id),
+        None => true,
+    };
+    in_frame && in_subgraph
+}
+
+/// Renders the graph in Graphviz DOT format, with node shapes and colors chosen by node type and
+/// edges labelled by edge type. Only edges between included nodes are rendered.
+pub fn to_dot(graph: &PageGraph, options: DotOptions) -> String {
+    let mut nodes = graph.nodes.values().filter(|node| includes_node(graph, &options, node)).collect::<Vec<_>>();
+    nodes.sort_unstable_by_key(|node| node.id);
+    let included = nodes.iter().map(|node| node.id).collect::<HashSet<_>>();
+
+    let mut edges = graph.edges.values()
+        .filter(|edge| included.contains(&edge.source) && included.contains(&edge.target))
+        .collect::<Vec<&Edge>>();
+    edges.sort_unstable_by_key(|edge| edge.id);
+
+    let mut out = String::new();
+    writeln!(out, "digraph {} {{", dot_string(&graph.desc.url)).unwrap();
+    writeln!(out, "  node [style=filled, fontname=\"Helvetica\", fontsize=10];").unwrap();
+    writeln!(out, "  edge [fontname=\"Helvetica\", fontsize=8];").unwrap();
+
+    nodes.iter().for_each(|node| {
+        let (shape, color) = node_style(&node.node_type);
+        let id = format!("{}", node.id);
+        let label = label(node.node_type.type_name(), &id, node_detail(&node.node_type), options.max_label_length);
+        writeln!(out, "  {} [label={}, shape={}, fillcolor=\"{}\"];",
+            dot_string(&id),
+            dot_string(&label),
+            shape,
+            color).unwrap();
+    });
+
+    edges.iter().for_each(|edge| {
+        let (style, color) = edge_style(&edge.edge_type);
+        let id = format!("{}", edge.id);
+        let label = label(edge.edge_type.type_name(), &id, edge_detail(&edge.edge_type), options.max_label_length);
+        writeln!(out, "  {} -> {} [label={}, style={}, color=\"{}\"];",
+            dot_string(&format!("{}", edge.source)),
+            dot_string(&format!("{}", edge.target)),
+            dot_string(&label),
+            style,
+            color).unwrap();
+    });
+
+    out.push_str("}\n");
+    out
+}
+
+/// Renders a [`JsCallGraph`] in Graphviz DOT format. Calls are drawn as solid edges and results
+/// as dashed edges, each labelled with its count. Scripts are labelled by URL, with third-party
+/// scripts outlined in red.
+pub fn call_graph_to_dot(call_graph: &JsCallGraph, options: DotOptions) -> String {
+    let mut out = String::new();
+    writeln!(out, "digraph \"js calls\" {{").unwrap();
+    writeln!(out, "  node [style=filled, fontname=\"Helvetica\", fontsize=10];").unwrap();
+    writeln!(out, "  edge [fontname=\"Helvetica\", fontsize=8];").unwrap();
+
+    call_graph.nodes.iter().for_each(|node| {
+        let (shape, color) = match node.node_type.as_str() {
+            "script" => ("hexagon", "#fdbf6f"),
+            _ => ("diamond", "#fb9a99"),
+        };
+        let label = label(&node.node_type, &node.node_id, Some(node.label.clone()), options.max_label_length);
+        let outline = if node.is_third_party == Some(true) { ", color=\"#e31a1c\", penwidth=2" } else { "" };
+        writeln!(out, "  {} [label={}, shape={}, fillcolor=\"{}\"{}];",
+            dot_string(&node.node_id),
+            dot_string(&label),
+            shape,
+            color,
+            outline).unwrap();
+    });
+
+    call_graph.edges.iter().for_each(|edge| {
+        let style = match edge.direction {
+            CallDirection::Call => "solid",
+            CallDirection::Result => "dashed",
+        };
+        writeln!(out, "  {} -> {} [label={}, style={}];",
+            dot_string(&edge.source),
+            dot_string(&edge.target),
+            dot_string(&format!("{}", edge.count)),
+            style).unwrap();
+    });
+
+    out.push_str("}\n");
+    out
+}
+
+/// Escapes a string for use in XML text or attribute values.
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    value.chars().for_each(|c| match c {
+        '&' => escaped.push_str("&amp;"),
+        '<' => escaped.push_str("&lt;"),
+        '>' => escaped.push_str("&gt;"),
+        '"' => escaped.push_str("&quot;"),
+        '\'' => escaped.push_str("&apos;"),
+        c => escaped.push(c),
+    });
+    escaped
+}
+
+/// Renders the graph in GEXF format for Gephi. The graph is dynamic: each node and edge starts
+/// existing at its timestamp, so that page activity can be replayed using Gephi's timeline.
+///
+/// Edges without a timestamp start once both of their endpoints exist.
+pub fn to_gexf(graph: &PageGraph) -> String {
+    let mut nodes = graph.nodes.values().collect::<Vec<_>>();
+    nodes.sort_unstable_by_key(|node| node.id);
+    let mut edges = graph.edges.values().collect::<Vec<_>>();
+    edges.sort_unstable_by_key(|edge| edge.id);
+
+    let mut out = String::new();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(out, r#"<gexf xmlns="http://www.gexf.net/1.2draft" version="1.2">"#).unwrap();
+    writeln!(out, r#"  <meta><description>{}</description></meta>"#, xml_escape(&graph.desc.url)).unwrap();
+    writeln!(out, r#"  <graph mode="dynamic" defaultedgetype="directed" timeformat="double">"#).unwrap();
+    writeln!(out, r#"    <attributes class="node" mode="static">"#).unwrap();
+    writeln!(out, r#"      <attribute id="type" title="node type" type="string"/>"#).unwrap();
+    writeln!(out, r#"      <attribute id="detail" title="detail" type="string"/>"#).unwrap();
+    writeln!(out, r#"    </attributes>"#).unwrap();
+    writeln!(out, r#"    <attributes class="edge" mode="static">"#).unwrap();
+    writeln!(out, r#"      <attribute id="type" title="edge type" type="string"/>"#).unwrap();
+    writeln!(out, r#"      <attribute id="detail" title="detail" type="string"/>"#).unwrap();
+    writeln!(out, r#"    </attributes>"#).unwrap();
+
+    writeln!(out, "    <nodes>").unwrap();
+    nodes.iter().for_each(|node| {
+        let id = format!("{}", node.id);
+        writeln!(out, r#"      <node id="{}" label="{}" start="{}">"#,
+            xml_escape(&id),
+            xml_escape(&format!("{} {}", id, node.node_type.type_name())),
+            node.node_timestamp).unwrap();
+        writeln!(out, "        <attvalues>").unwrap();
+        writeln!(out, r#"          <attvalue for="type" value="{}"/>"#, xml_escape(node.node_type.type_name())).unwrap();
+        if let Some(detail) = node_detail(&node.node_type) {
+            writeln!(out, r#"          <attvalue for="detail" value="{}"/>"#, xml_escape(&detail)).unwrap();
+        }
+        writeln!(out, "        </attvalues>").unwrap();
+        writeln!(out, "      </node>").unwrap();
+    });
+    writeln!(out, "    </nodes>").unwrap();
+
+    writeln!(out, "    <edges>").unwrap();
+    edges.iter().for_each(|edge| {
+        let endpoints_start = [edge.source, edge.target].iter()
+            .filter_map(|node_id| graph.nodes.get(node_id))
+            .map(|node| node.node_timestamp)
+            .max();
+        let start = edge.edge_timestamp.or(endpoints_start).unwrap_or_default();
+        writeln!(out, r#"      <edge id="{}" source="{}" target="{}" label="{}" start="{}">"#,
+            xml_escape(&format!("{}", edge.id)),
+            xml_escape(&format!("{}", edge.source)),
+            xml_escape(&format!("{}", edge.target)),
+            xml_escape(edge.edge_type.type_name()),
+            start).unwrap();
+        writeln!(out, "        <attvalues>").unwrap();
+        writeln!(out, r#"          <attvalue for="type" value="{}"/>"#, xml_escape(edge.edge_type.type_name())).unwrap();
+        if let Some(detail) = edge_detail(&edge.edge_type) {
+            writeln!(out, r#"          <attvalue for="detail" value="{}"/>"#, xml_escape(&detail)).unwrap();
+        }
+        writeln!(out, "        </attvalues>").unwrap();
+        writeln!(out, "      </edge>").unwrap();
+    });
+    writeln!(out, "    </edges>").unwrap();
+
+    writeln!(out, "  </graph>").unwrap();
+    writeln!(out, "</gexf>").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod dot_tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_escaping() {
+        assert_eq!(dot_string(r#"say "hi"\now"#), r#""say \"hi\"\\now""#);
+        assert_eq!(dot_string("a\r\nb"), r#""a\nb""#);
+        assert_eq!(label("script", "n5", Some("abcdef".to_string()), 3), "n5 script\nabc…");
+    }
+}
@@ -0,0 +1,167 @@
+//! Exports flat node and edge lists as CSV, for quick ingestion into tools like pandas or R.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::centrality::ScriptCentrality;
+use crate::corpus::ScriptFeatures;
+use crate::graph::{HasFrameId, PageGraph, WaterfallEntry};
+use crate::types::{EdgeType, NodeType};
+
+use super::{edge_detail, node_detail};
+
+/// Quotes a CSV field if it contains a delimiter, quote, or line break.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn opt<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map(|value| field(&value.to_string())).unwrap_or_default()
+}
+
+fn write_row<W: Write>(out: &mut W, fields: &[String]) -> std::io::Result<()> {
+    writeln!(out, "{}", fields.join(","))
+}
+
+/// Writes the graph into `nodes.csv` and `edges.csv` within `out_dir`, which must already exist.
+/// Common attributes are flattened into their own columns, with the most identifying data of
+/// each node or edge in a `detail` column.
+pub fn to_csv<P: AsRef<Path>>(graph: &PageGraph, out_dir: P) -> std::io::Result<()> {
+    let out_dir = out_dir.as_ref();
+
+    let mut nodes = graph.nodes.values().collect::<Vec<_>>();
+    nodes.sort_unstable_by_key(|node| node.id);
+    let mut out = BufWriter::new(File::create(out_dir.join("nodes.csv"))?);
+    writeln!(out, "id,frame_id,node_type,timestamp,url,tag_name,dom_node_id,is_deleted,script_id,detail")?;
+    for node in nodes {
+        let (url, tag_name, dom_node_id, is_deleted, script_id) = match &node.node_type {
+            NodeType::Resource { url } => (Some(url.as_str()), None, None, None, None),
+            NodeType::Script { url, script_id, .. } => (url.as_deref(), None, None, None, Some(*script_id)),
+            NodeType::DomRoot { url, tag_name, node_id, is_deleted } => (url.as_deref(), Some(tag_name.as_str()), Some(*node_id), Some(*is_deleted), None),
+            NodeType::HtmlElement { tag_name, node_id, is_deleted } |
+            NodeType::FrameOwner { tag_name, node_id, is_deleted } => (None, Some(tag_name.as_str()), Some(*node_id), Some(*is_deleted), None),
+            NodeType::TextNode { node_id, is_deleted, .. } => (None, None, Some(*node_id), Some(*is_deleted), None),
+            _ => (None, None, None, None, None),
+        };
+        write_row(&mut out, &[
+            field(&node.id.to_string()),
+            opt(node.id.get_frame_id()),
+            field(node.node_type.type_name()),
+            node.node_timestamp.to_string(),
+            opt(url),
+            opt(tag_name),
+            opt(dom_node_id),
+            opt(is_deleted),
+            opt(script_id),
+            opt(node_detail(&node.node_type)),
+        ])?;
+    }
+    out.flush()?;
+
+    let mut edges = graph.edges.values().collect::<Vec<_>>();
+    edges.sort_unstable_by_key(|edge| edge.id);
+    let mut out = BufWriter::new(File::create(out_dir.join("edges.csv"))?);
+    writeln!(out, "id,source,target,edge_type,timestamp,request_id,request_type,key,value,detail")?;
+    for edge in edges {
+        let (request_id, request_type) = match &edge.edge_type {
+            EdgeType::RequestStart { request_id, request_type, .. } => (Some(*request_id), Some(request_type.as_str())),
+            EdgeType::RequestComplete { request_id, .. } |
+            EdgeType::RequestError { request_id, .. } |
+            EdgeType::RequestResponse { request_id, .. } => (Some(*request_id), None),
+            _ => (None, None),
+        };
+        let (key, value) = match &edge.edge_type {
+            EdgeType::SetAttribute { key, value, .. } |
+            EdgeType::StorageSet { key, value } |
+            EdgeType::StorageReadResult { key, value } => (Some(key.as_str()), value.as_deref()),
+            EdgeType::DeleteAttribute { key, .. } |
+            EdgeType::DeleteStorage { key } |
+            EdgeType::ReadStorageCall { key } |
+            EdgeType::ClearStorage { key } |
+            EdgeType::AddEventListener { key, .. } |
+            EdgeType::RemoveEventListener { key, .. } |
+            EdgeType::EventListener { key, .. } => (Some(key.as_str()), None),
+            EdgeType::JsResult { value } => (None, value.as_deref()),
+            EdgeType::JsCall { args, .. } => (None, args.as_deref()),
+            _ => (None, None),
+        };
+        write_row(&mut out, &[
+            field(&edge.id.to_string()),
+            field(&edge.source.to_string()),
+            field(&edge.target.to_string()),
+            field(edge.edge_type.type_name()),
+            opt(edge.edge_timestamp),
+            opt(request_id),
+            opt(request_type),
+            opt(key),
+            opt(value),
+            opt(edge_detail(&edge.edge_type)),
+        ])?;
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+/// Writes per-script feature vectors as a single CSV file at `path`, with a column for each API
+/// after the script's hash, URL, and number of occurrences.
+pub fn features_to_csv<P: AsRef<Path>>(features: &ScriptFeatures, path: P) -> std::io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let header = ["script_hash", "url", "occurrences"].iter().map(|column| column.to_string())
+        .chain(features.apis.iter().map(|api| field(api)))
+        .collect::<Vec<_>>();
+    write_row(&mut out, &header)?;
+    for script in &features.scripts {
+        let row = vec![field(&script.script_hash), opt(script.url.as_deref()), script.occurrences.to_string()].into_iter()
+            .chain(script.counts.iter().map(|count| count.to_string()))
+            .collect::<Vec<_>>();
+        write_row(&mut out, &row)?;
+    }
+    out.flush()
+}
+
+/// Writes a request waterfall as CSV, with one row per request and its initiator chain joined by
+/// spaces.
+pub fn waterfall_to_csv<W: Write>(entries: &[WaterfallEntry], out: W) -> std::io::Result<()> {
+    let mut out = BufWriter::new(out);
+    writeln!(out, "request_id,frame_id,url,request_type,resource_type,start,complete,error,status,size,initiator,initiator_chain")?;
+    for entry in entries {
+        write_row(&mut out, &[
+            entry.request_id.to_string(),
+            opt(entry.frame_id.as_deref()),
+            field(&entry.url),
+            field(entry.request_type.as_str()),
+            opt(entry.resource_type.as_deref()),
+            opt(entry.start),
+            opt(entry.complete),
+            opt(entry.error),
+            opt(entry.status.as_deref()),
+            opt(entry.size),
+            field(&entry.initiator_node_id),
+            field(&entry.initiator_chain.join(" ")),
+        ])?;
+    }
+    out.flush()
+}
+
+/// Writes script centrality scores as CSV, with one row per script.
+pub fn centrality_to_csv<W: Write>(scripts: &[ScriptCentrality], out: W) -> std::io::Result<()> {
+    let mut out = BufWriter::new(out);
+    writeln!(out, "node_id,script_id,url,degree,betweenness,pagerank")?;
+    for script in scripts {
+        write_row(&mut out, &[
+            field(&script.node_id),
+            script.script_id.to_string(),
+            opt(script.url.as_deref()),
+            script.degree.to_string(),
+            script.betweenness.to_string(),
+            script.pagerank.to_string(),
+        ])?;
+    }
+    out.flush()
+}
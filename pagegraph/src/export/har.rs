@@ -0,0 +1,264 @@
+//! Exports the network activity recorded in a graph as a [HAR 1.2](http://www.softwareishard.com/blog/har-12-spec/)
+//! document, for consumption by existing web performance tooling.
+
+use crate::graph::{Edge, PageGraph};
+use crate::headers::ResponseHeaders;
+use crate::types::{EdgeKind, EdgeType, NodeType};
+
+#[derive(serde::Serialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(serde::Serialize)]
+struct Log {
+    version: &'static str,
+    creator: Creator,
+    pages: Vec<Page>,
+    entries: Vec<Entry>,
+}
+
+#[derive(serde::Serialize)]
+struct Creator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Page {
+    started_date_time: String,
+    id: &'static str,
+    title: String,
+    page_timings: PageTimings,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PageTimings {
+    on_content_load: i64,
+    on_load: i64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    pageref: &'static str,
+    started_date_time: String,
+    time: i64,
+    request: HarRequest,
+    response: HarResponse,
+    cache: Cache,
+    timings: Timings,
+    #[serde(rename = "_resourceType")]
+    resource_type: &'static str,
+    #[serde(rename = "_requestId")]
+    request_id: usize,
+    #[serde(rename = "_error", skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: &'static str,
+    url: String,
+    http_version: &'static str,
+    cookies: Vec<Header>,
+    headers: Vec<Header>,
+    query_string: Vec<Header>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarResponse {
+    status: u16,
+    status_text: String,
+    http_version: String,
+    cookies: Vec<Header>,
+    headers: Vec<Header>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+}
+
+/// HAR uses the same name/value structure for headers, cookies, and query parameters.
+#[derive(serde::Serialize)]
+struct Header {
+    name: String,
+    value: String,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: i64,
+    mime_type: String,
+}
+
+#[derive(serde::Serialize)]
+struct Cache {}
+
+#[derive(serde::Serialize)]
+struct Timings {
+    send: i64,
+    wait: i64,
+    receive: i64,
+}
+
+/// Formats a Unix timestamp in milliseconds as an ISO 8601 date and time in UTC.
+fn iso8601(unix_ms: i64) -> String {
+    let days = unix_ms.div_euclid(86_400_000);
+    let ms_of_day = unix_ms.rem_euclid(86_400_000);
+
+    // Converts days since the epoch to a proleptic Gregorian calendar date.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day,
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000)
+}
+
+fn query_string(url: &str) -> Vec<Header> {
+    let query = match url.split_once('?') {
+        Some((_, query)) => query.split('#').next().unwrap_or_default(),
+        None => return vec![],
+    };
+    query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) => Header { name: name.to_string(), value: value.to_string() },
+            None => Header { name: pair.to_string(), value: String::new() },
+        })
+        .collect()
+}
+
+fn to_har_headers(pairs: Vec<(&str, &str)>) -> Vec<Header> {
+    pairs.into_iter()
+        .map(|(name, value)| Header { name: name.to_string(), value: value.to_string() })
+        .collect()
+}
+
+fn entry(graph: &PageGraph, start: &Edge) -> Option<Entry> {
+    let (request_id, request_type) = match &start.edge_type {
+        EdgeType::RequestStart { request_id, request_type, .. } => (*request_id, request_type),
+        _ => return None,
+    };
+    let resource = graph.nodes.get(&start.target)?;
+    let url = match &resource.node_type {
+        NodeType::Resource { url } => url,
+        _ => return None,
+    };
+    let finish = graph.outgoing_edges(resource).find(|edge| matches!(edge.edge_type,
+        EdgeType::RequestComplete { request_id: id, .. } | EdgeType::RequestError { request_id: id, .. } if id == request_id));
+
+    let start_offset = start.edge_timestamp.unwrap_or_default().as_millis() as i64;
+    let time = finish
+        .and_then(|finish| finish.edge_timestamp)
+        .map(|end| (end.as_millis() as i64 - start_offset).max(0))
+        .unwrap_or(0);
+
+    let (raw_headers, size, mime_type, error) = match finish.map(|finish| &finish.edge_type) {
+        Some(EdgeType::RequestComplete { headers, size, resource_type, .. }) => (headers.as_str(), *size, resource_type.clone(), None),
+        Some(EdgeType::RequestError { headers, size, status, .. }) => (headers.as_str(), *size, String::new(), Some(status.clone())),
+        _ => ("", None, String::new(), Some("request did not finish".to_string())),
+    };
+    let headers = ResponseHeaders::parse(raw_headers);
+    let mut status_parts = headers.status_line.unwrap_or_default().splitn(3, ' ');
+    let http_version = status_parts.next().unwrap_or_default().to_string();
+    let status = status_parts.next().and_then(|status| status.parse::<u16>().ok()).unwrap_or(0);
+    let status_text = status_parts.next().unwrap_or_default().to_string();
+    let size = size.map(|size| size as i64).unwrap_or(-1);
+    let mime_type = headers.content_type().map(str::to_string).unwrap_or(mime_type);
+
+    Some(Entry {
+        pageref: "page_0",
+        started_date_time: iso8601(graph.desc.time.start as i64 + start_offset),
+        time,
+        request: HarRequest {
+            method: "GET",
+            url: url.clone(),
+            http_version: "",
+            cookies: vec![],
+            headers: vec![],
+            query_string: query_string(url),
+            headers_size: -1,
+            body_size: 0,
+        },
+        response: HarResponse {
+            status,
+            status_text,
+            http_version,
+            cookies: to_har_headers(headers.set_cookies()),
+            headers: to_har_headers(headers.headers),
+            content: Content { size, mime_type },
+            redirect_url: String::new(),
+            headers_size: -1,
+            body_size: size,
+        },
+        cache: Cache {},
+        timings: Timings { send: 0, wait: time, receive: 0 },
+        resource_type: request_type.as_str(),
+        request_id,
+        error,
+    })
+}
+
+/// Renders every request in the graph as an entry in a HAR 1.2 document.
+///
+/// PageGraph does not record request methods or request headers, so all requests are reported as
+/// `GET` requests without headers. Response status codes are only available when the recorded
+/// response headers include an HTTP status line; otherwise they are reported as `0`.
+pub fn to_har(graph: &PageGraph) -> String {
+    let mut starts = graph.edges_of_kind(EdgeKind::RequestStart).collect::<Vec<_>>();
+    starts.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+
+    let har = Har {
+        log: Log {
+            version: "1.2",
+            creator: Creator {
+                name: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            pages: vec![Page {
+                started_date_time: iso8601(graph.desc.time.start as i64),
+                id: "page_0",
+                title: graph.desc.url.clone(),
+                page_timings: PageTimings {
+                    on_content_load: -1,
+                    on_load: -1,
+                },
+            }],
+            entries: starts.into_iter().filter_map(|start| entry(graph, start)).collect(),
+        },
+    };
+
+    serde_json::to_string_pretty(&har).expect("HAR serialization cannot fail")
+}
+
+#[cfg(test)]
+mod har_tests {
+    use super::*;
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(951_782_400_123), "2000-02-29T00:00:00.123Z");
+        assert_eq!(iso8601(1_700_000_000_000), "2023-11-14T22:13:20.000Z");
+    }
+}
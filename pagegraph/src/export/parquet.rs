@@ -0,0 +1,138 @@
+//! Exports the node and edge tables of a graph as Parquet files, for use with columnar analytics
+//! tools like DuckDB or Spark. Each row carries the page URL and frame id of its graph, so that the
+//! files from many graphs can be queried together.
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::basic::Compression;
+use ::parquet::errors::ParquetError;
+use ::parquet::file::properties::WriterProperties;
+
+use crate::corpus::ScriptFeatures;
+use crate::graph::{HasFrameId, PageGraph};
+
+use super::{edge_detail, node_detail};
+
+fn string_column<I: IntoIterator<Item = Option<String>>>(values: I) -> ArrayRef {
+    Arc::new(values.into_iter().collect::<StringArray>())
+}
+
+fn write_batch(path: &Path, batch: RecordBatch) -> Result<(), ParquetError> {
+    let file = File::create(path)?;
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Writes the graph's nodes and edges into `nodes.parquet` and `edges.parquet` within `out_dir`,
+/// which must already exist.
+pub fn to_parquet<P: AsRef<Path>>(graph: &PageGraph, out_dir: P) -> Result<(), ParquetError> {
+    let out_dir = out_dir.as_ref();
+    let page_url = graph.desc.url.clone();
+    let graph_frame_id = format!("{}", graph.desc.frame_id);
+
+    let mut nodes = graph.nodes.values().collect::<Vec<_>>();
+    nodes.sort_unstable_by_key(|node| node.id);
+    let node_schema = Schema::new(vec![
+        Field::new("page_url", DataType::Utf8, false),
+        Field::new("graph_frame_id", DataType::Utf8, false),
+        Field::new("node_id", DataType::Utf8, false),
+        Field::new("frame_id", DataType::Utf8, true),
+        Field::new("node_type", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Int64, false),
+        Field::new("detail", DataType::Utf8, true),
+    ]);
+    let node_batch = RecordBatch::try_new(Arc::new(node_schema), vec![
+        string_column(nodes.iter().map(|_| Some(page_url.clone()))),
+        string_column(nodes.iter().map(|_| Some(graph_frame_id.clone()))),
+        string_column(nodes.iter().map(|node| Some(format!("{}", node.id)))),
+        string_column(nodes.iter().map(|node| node.id.get_frame_id().map(|frame_id| format!("{}", frame_id)))),
+        string_column(nodes.iter().map(|node| Some(node.node_type.type_name().to_string()))),
+        Arc::new(nodes.iter().map(|node| node.node_timestamp.as_millis() as i64).collect::<Int64Array>()),
+        string_column(nodes.iter().map(|node| node_detail(&node.node_type))),
+    ])?;
+    write_batch(&out_dir.join("nodes.parquet"), node_batch)?;
+
+    let mut edges = graph.edges.values().collect::<Vec<_>>();
+    edges.sort_unstable_by_key(|edge| edge.id);
+    let edge_schema = Schema::new(vec![
+        Field::new("page_url", DataType::Utf8, false),
+        Field::new("graph_frame_id", DataType::Utf8, false),
+        Field::new("edge_id", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+        Field::new("target", DataType::Utf8, false),
+        Field::new("edge_type", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Int64, true),
+        Field::new("detail", DataType::Utf8, true),
+    ]);
+    let edge_batch = RecordBatch::try_new(Arc::new(edge_schema), vec![
+        string_column(edges.iter().map(|_| Some(page_url.clone()))),
+        string_column(edges.iter().map(|_| Some(graph_frame_id.clone()))),
+        string_column(edges.iter().map(|edge| Some(format!("{}", edge.id)))),
+        string_column(edges.iter().map(|edge| Some(format!("{}", edge.source)))),
+        string_column(edges.iter().map(|edge| Some(format!("{}", edge.target)))),
+        string_column(edges.iter().map(|edge| Some(edge.edge_type.type_name().to_string()))),
+        Arc::new(edges.iter().map(|edge| edge.edge_timestamp.map(|timestamp| timestamp.as_millis() as i64)).collect::<Int64Array>()),
+        string_column(edges.iter().map(|edge| edge_detail(&edge.edge_type))),
+    ])?;
+    write_batch(&out_dir.join("edges.parquet"), edge_batch)?;
+
+    Ok(())
+}
+
+/// Writes per-script feature vectors as a single Parquet file at `path`, with a column for each
+/// API after the script's hash, URL, and number of occurrences.
+pub fn features_to_parquet<P: AsRef<Path>>(features: &ScriptFeatures, path: P) -> Result<(), ParquetError> {
+    let scripts = &features.scripts;
+    let fields = vec![
+        Field::new("script_hash", DataType::Utf8, false),
+        Field::new("url", DataType::Utf8, true),
+        Field::new("occurrences", DataType::Int64, false),
+    ].into_iter()
+        .chain(features.apis.iter().map(|api| Field::new(api.as_str(), DataType::Int64, false)))
+        .collect::<Vec<_>>();
+    let columns = vec![
+        string_column(scripts.iter().map(|script| Some(script.script_hash.clone()))),
+        string_column(scripts.iter().map(|script| script.url.clone())),
+        Arc::new(scripts.iter().map(|script| script.occurrences as i64).collect::<Int64Array>()) as ArrayRef,
+    ].into_iter()
+        .chain((0..features.apis.len()).map(|column| {
+            Arc::new(scripts.iter().map(|script| script.counts[column] as i64).collect::<Int64Array>()) as ArrayRef
+        }))
+        .collect::<Vec<_>>();
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+    write_batch(path.as_ref(), batch)
+}
+
+#[cfg(test)]
+mod parquet_tests {
+    use ::parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use super::*;
+
+    #[test]
+    fn test_tables_round_trip() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/a.js</data></node>
+<edge id="e3" source="n1" target="n2"><data key="d16">request start</data><data key="d17">3</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>"#);
+        let out_dir = std::env::temp_dir().join(format!("pagegraph-parquet-test-{}", std::process::id()));
+        std::fs::create_dir_all(&out_dir).unwrap();
+
+        to_parquet(&graph, &out_dir).unwrap();
+
+        let rows = |file: &str| {
+            let reader = SerializedFileReader::new(File::open(out_dir.join(file)).unwrap()).unwrap();
+            reader.metadata().file_metadata().num_rows()
+        };
+        assert_eq!(rows("nodes.parquet"), 2);
+        assert_eq!(rows("edges.parquet"), 1);
+        std::fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+//! Exports graphs into a normalized SQLite database. Many graphs can be exported into the same
+//! database file, so that a whole crawl can be queried at once.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::graph::{HasFrameId, PageGraph};
+use crate::types::{EdgeType, NodeType};
+
+use super::{edge_detail, node_detail};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS graphs (
+    graph_id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL,
+    version TEXT NOT NULL,
+    frame_id TEXT NOT NULL,
+    is_root INTEGER NOT NULL,
+    start_time INTEGER NOT NULL,
+    end_time INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS nodes (
+    graph_id INTEGER NOT NULL REFERENCES graphs(graph_id),
+    node_id TEXT NOT NULL,
+    frame_id TEXT,
+    node_type TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    detail TEXT,
+    PRIMARY KEY (graph_id, node_id)
+);
+CREATE TABLE IF NOT EXISTS edges (
+    graph_id INTEGER NOT NULL REFERENCES graphs(graph_id),
+    edge_id TEXT NOT NULL,
+    source TEXT NOT NULL,
+    target TEXT NOT NULL,
+    edge_type TEXT NOT NULL,
+    timestamp INTEGER,
+    detail TEXT,
+    PRIMARY KEY (graph_id, edge_id)
+);
+CREATE TABLE IF NOT EXISTS requests (
+    graph_id INTEGER NOT NULL REFERENCES graphs(graph_id),
+    request_id INTEGER NOT NULL,
+    start_edge_id TEXT NOT NULL,
+    initiator_id TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    request_type TEXT NOT NULL,
+    state TEXT NOT NULL,
+    status TEXT,
+    size INTEGER,
+    response_hash TEXT,
+    PRIMARY KEY (graph_id, start_edge_id)
+);
+CREATE TABLE IF NOT EXISTS scripts (
+    graph_id INTEGER NOT NULL REFERENCES graphs(graph_id),
+    node_id TEXT NOT NULL,
+    script_id INTEGER NOT NULL,
+    script_type TEXT NOT NULL,
+    url TEXT,
+    source TEXT NOT NULL,
+    PRIMARY KEY (graph_id, node_id)
+);
+";
+
+/// Writes the graph into the SQLite database at `path`, creating it and its tables if necessary.
+/// Each graph is assigned a new `graph_id`, which is returned and used to key its rows in the
+/// `nodes`, `edges`, `requests`, and `scripts` tables.
+pub fn to_sqlite<P: AsRef<Path>>(graph: &PageGraph, path: P) -> Result<i64, rusqlite::Error> {
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO graphs (url, version, frame_id, is_root, start_time, end_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            graph.desc.url,
+            graph.desc.version,
+            format!("{}", graph.desc.frame_id),
+            graph.desc.is_root,
+            graph.desc.time.start as i64,
+            graph.desc.time.end as i64,
+        ],
+    )?;
+    let graph_id = tx.last_insert_rowid();
+
+    {
+        let mut insert_node = tx.prepare("INSERT INTO nodes VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+        let mut insert_script = tx.prepare("INSERT INTO scripts VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+        for node in graph.nodes.values() {
+            let node_id = format!("{}", node.id);
+            insert_node.execute(params![
+                graph_id,
+                node_id,
+                node.id.get_frame_id().map(|frame_id| format!("{}", frame_id)),
+                node.node_type.type_name(),
+                node.node_timestamp.as_millis() as i64,
+                node_detail(&node.node_type),
+            ])?;
+            if let NodeType::Script { url, script_type, script_id, source } = &node.node_type {
+                insert_script.execute(params![graph_id, node_id, *script_id as i64, script_type, url, source])?;
+            }
+        }
+
+        let mut insert_edge = tx.prepare("INSERT INTO edges VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+        let mut insert_request = tx.prepare("INSERT INTO requests VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)")?;
+        for edge in graph.edges.values() {
+            insert_edge.execute(params![
+                graph_id,
+                format!("{}", edge.id),
+                format!("{}", edge.source),
+                format!("{}", edge.target),
+                edge.edge_type.type_name(),
+                edge.edge_timestamp.map(|timestamp| timestamp.as_millis() as i64),
+                edge_detail(&edge.edge_type),
+            ])?;
+
+            let (request_id, request_type) = match &edge.edge_type {
+                EdgeType::RequestStart { request_id, request_type, .. } => (*request_id, request_type),
+                _ => continue,
+            };
+            let resource = match graph.nodes.get(&edge.target) {
+                Some(resource) => resource,
+                None => continue,
+            };
+            let url = match &resource.node_type {
+                NodeType::Resource { url } => url,
+                _ => continue,
+            };
+            let (state, status, size, response_hash) = graph.outgoing_edges(resource)
+                .find_map(|finish| match &finish.edge_type {
+                    EdgeType::RequestComplete { request_id: id, status, size, response_hash, .. } if *id == request_id => {
+                        Some(("complete", Some(status), *size, response_hash.as_ref()))
+                    }
+                    EdgeType::RequestError { request_id: id, status, size, .. } if *id == request_id => {
+                        Some(("error", Some(status), *size, None))
+                    }
+                    _ => None,
+                })
+                .unwrap_or(("pending", None, None, None));
+            insert_request.execute(params![
+                graph_id,
+                request_id as i64,
+                format!("{}", edge.id),
+                format!("{}", edge.source),
+                format!("{}", resource.id),
+                url,
+                request_type.as_str(),
+                state,
+                status,
+                size.map(|size| size as i64),
+                response_hash,
+            ])?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(graph_id)
+}
+
+#[cfg(test)]
+mod sqlite_tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_round_trip() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/a.js</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://example.com/b.png</data></node>
+<edge id="e4" source="n1" target="n2"><data key="d16">request start</data><data key="d17">4</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e5" source="n2" target="n1"><data key="d16">request complete</data><data key="d17">5</data><data key="d18">1</data><data key="d28">1</data><data key="d29">script</data><data key="d30">complete</data><data key="d32"></data><data key="d33">10</data></edge>
+<edge id="e6" source="n1" target="n3"><data key="d16">request start</data><data key="d17">6</data><data key="d18">1</data><data key="d28">2</data><data key="d29">Image</data><data key="d30">started</data></edge>"#);
+        let path = std::env::temp_dir().join(format!("pagegraph-sqlite-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // Both graphs are kept in the same database under their own ids
+        let first = to_sqlite(&graph, &path).unwrap();
+        let second = to_sqlite(&graph, &path).unwrap();
+        assert_ne!(first, second);
+
+        let conn = Connection::open(&path).unwrap();
+        let count = |sql: &str| conn.query_row(sql, params![first], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM requests WHERE graph_id = ?1"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM requests WHERE graph_id = ?1 AND state = 'complete' AND size = 10"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM requests WHERE graph_id = ?1 AND state = 'pending'"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM nodes WHERE graph_id = ?1"), 3);
+        assert_eq!(count("SELECT COUNT(*) FROM edges WHERE graph_id = ?1"), 3);
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+//! Exports the page timeline in the Chrome trace event format, which can be viewed in Perfetto or
+//! `about:tracing`.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::graph::{Edge, FrameId, HasFrameId, PageGraph, Timestamp};
+use crate::types::{EdgeKind, EdgeType, NodeType};
+
+const REQUESTS_TID: u64 = 1;
+const SCRIPTS_TID: u64 = 2;
+const DOM_TID: u64 = 3;
+
+/// Converts a timestamp offset in milliseconds to trace event microseconds.
+fn micros(timestamp: Timestamp) -> i64 {
+    timestamp.as_millis() as i64 * 1000
+}
+
+/// Assigns each frame context in the graph its own trace process.
+struct Processes {
+    pids: HashMap<Option<FrameId>, u64>,
+}
+
+impl Processes {
+    fn pid(&mut self, frame_id: Option<FrameId>) -> u64 {
+        let next = self.pids.len() as u64 + 1;
+        *self.pids.entry(frame_id).or_insert(next)
+    }
+}
+
+fn is_dom_mutation(edge_type: &EdgeType) -> bool {
+    matches!(edge_type.kind(),
+        EdgeKind::CreateNode |
+        EdgeKind::InsertNode |
+        EdgeKind::RemoveNode |
+        EdgeKind::DeleteNode |
+        EdgeKind::TextChange |
+        EdgeKind::SetAttribute |
+        EdgeKind::DeleteAttribute)
+}
+
+fn request_events(graph: &PageGraph, processes: &mut Processes, start: &Edge) -> Option<[Value; 2]> {
+    let (request_id, request_type) = match &start.edge_type {
+        EdgeType::RequestStart { request_id, request_type, .. } => (*request_id, request_type),
+        _ => return None,
+    };
+    let start_ts = start.edge_timestamp?;
+    let resource = graph.nodes.get(&start.target)?;
+    let url = match &resource.node_type {
+        NodeType::Resource { url } => url,
+        _ => return None,
+    };
+    let finish = graph.outgoing_edges(resource).find(|edge| matches!(edge.edge_type,
+        EdgeType::RequestComplete { request_id: id, .. } | EdgeType::RequestError { request_id: id, .. } if id == request_id));
+    let (end_ts, outcome) = match finish {
+        Some(finish) => (finish.edge_timestamp.unwrap_or(start_ts), finish.edge_type.type_name()),
+        None => (start_ts, "pending"),
+    };
+
+    let pid = processes.pid(start.id.get_frame_id());
+    let id = format!("{}", start.id);
+    let category = format!("request.{}", request_type.as_str());
+    let args = json!({ "url": url, "request_id": request_id, "initiator": format!("{}", start.source), "outcome": outcome });
+    Some([
+        json!({ "name": url, "cat": category, "ph": "b", "id": id, "pid": pid, "tid": REQUESTS_TID, "ts": micros(start_ts), "args": args }),
+        json!({ "name": url, "cat": category, "ph": "e", "id": id, "pid": pid, "tid": REQUESTS_TID, "ts": micros(end_ts.max(start_ts)) }),
+    ])
+}
+
+/// Script activity spans from the script's execution until the last action it was recorded taking.
+fn script_events(graph: &PageGraph, processes: &mut Processes, execute: &Edge) -> Option<[Value; 2]> {
+    let start_ts = execute.edge_timestamp?;
+    let script = graph.nodes.get(&execute.target)?;
+    let (script_id, url) = match &script.node_type {
+        NodeType::Script { script_id, url, .. } => (*script_id, url),
+        _ => return None,
+    };
+    let end_ts = graph.outgoing_edges(script)
+        .filter_map(|edge| edge.edge_timestamp)
+        .max()
+        .unwrap_or(start_ts)
+        .max(start_ts);
+
+    let pid = processes.pid(script.id.get_frame_id());
+    let id = format!("{}", execute.id);
+    let name = match url {
+        Some(url) => format!("script {} ({})", script_id, url),
+        None => format!("script {}", script_id),
+    };
+    let args = json!({ "script_id": script_id, "node_id": format!("{}", script.id), "url": url });
+    Some([
+        json!({ "name": name, "cat": "script", "ph": "b", "id": id, "pid": pid, "tid": SCRIPTS_TID, "ts": micros(start_ts), "args": args }),
+        json!({ "name": name, "cat": "script", "ph": "e", "id": id, "pid": pid, "tid": SCRIPTS_TID, "ts": micros(end_ts) }),
+    ])
+}
+
+/// Renders script executions, requests, and DOM mutations as a trace in the Chrome trace event
+/// format. Each frame context is represented as a separate process, with one thread each for
+/// requests, scripts, and DOM mutations.
+pub fn to_trace_events(graph: &PageGraph) -> String {
+    let mut processes = Processes { pids: HashMap::new() };
+    processes.pid(None);
+
+    let mut edges = graph.edges.values().collect::<Vec<_>>();
+    edges.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+
+    let mut events = vec![];
+    for edge in edges {
+        match &edge.edge_type {
+            EdgeType::RequestStart { .. } => events.extend(request_events(graph, &mut processes, edge).into_iter().flatten()),
+            EdgeType::Execute {} | EdgeType::ExecuteFromAttribute { .. } => events.extend(script_events(graph, &mut processes, edge).into_iter().flatten()),
+            edge_type if is_dom_mutation(edge_type) => if let Some(timestamp) = edge.edge_timestamp {
+                let pid = processes.pid(edge.id.get_frame_id());
+                events.push(json!({
+                    "name": edge_type.type_name(),
+                    "cat": "dom",
+                    "ph": "i",
+                    "s": "t",
+                    "pid": pid,
+                    "tid": DOM_TID,
+                    "ts": micros(timestamp),
+                    "args": { "edge_id": format!("{}", edge.id), "actor": format!("{}", edge.source), "target": format!("{}", edge.target) },
+                }));
+            },
+            _ => (),
+        }
+    }
+
+    let mut pids = processes.pids.into_iter().collect::<Vec<_>>();
+    pids.sort_unstable_by_key(|(_, pid)| *pid);
+    for (frame_id, pid) in pids {
+        let process_name = match frame_id {
+            Some(frame_id) => format!("frame {}", frame_id),
+            None => format!("{} (frame {})", graph.desc.url, graph.desc.frame_id),
+        };
+        events.push(json!({ "name": "process_name", "ph": "M", "pid": pid, "args": { "name": process_name } }));
+        for (tid, thread_name) in [(REQUESTS_TID, "requests"), (SCRIPTS_TID, "scripts"), (DOM_TID, "DOM mutations")].iter() {
+            events.push(json!({ "name": "thread_name", "ph": "M", "pid": pid, "tid": tid, "args": { "name": thread_name } }));
+        }
+    }
+
+    let trace = json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+        "metadata": {
+            "url": graph.desc.url,
+            "recording_start_ms": graph.desc.time.start,
+            "recording_end_ms": graph.desc.time.end,
+        },
+    });
+    serde_json::to_string(&trace).expect("trace serialization cannot fail")
+}
@@ -0,0 +1,447 @@
+//! Detection of browser fingerprinting by the Web APIs that scripts call.
+//!
+//! Web API calls are only recorded by builds of Brave with the relevant APIs instrumented, so
+//! graphs recorded without them will never show fingerprinting.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::graph::{Edge, Node, NodeId, PageGraph};
+use crate::graph_algos::happened_before;
+use crate::types::{EdgeType, NodeKind, NodeType};
+
+/// Canvas methods that draw content which can then be read back to fingerprint the renderer.
+pub const CANVAS_DRAW_METHODS: [&str; 12] = [
+    "fillText", "strokeText", "fillRect", "strokeRect", "rect", "arc", "fill", "stroke",
+    "lineTo", "bezierCurveTo", "quadraticCurveTo", "drawImage",
+];
+
+/// Methods that read back the pixels drawn to a canvas.
+pub const CANVAS_READBACK_METHODS: [&str; 4] = [
+    "HTMLCanvasElement.toDataURL",
+    "HTMLCanvasElement.toBlob",
+    "CanvasRenderingContext2D.getImageData",
+    "OffscreenCanvas.convertToBlob",
+];
+
+/// Canvases narrower or shorter than this, in pixels, are too small to fingerprint with.
+pub const MIN_CANVAS_SIZE: u64 = 16;
+
+/// Scripts drawing text with fewer distinct characters than this, in fewer colors than
+/// [`MIN_CANVAS_TEXT_COLORS`], are not fingerprinting with it.
+pub const MIN_CANVAS_TEXT_CHARACTERS: usize = 10;
+
+pub const MIN_CANVAS_TEXT_COLORS: usize = 2;
+
+/// Scripts reading at least this many distinct `Navigator` or `Screen` properties are sweeping
+/// them for fingerprinting.
+pub const NAVIGATOR_SWEEP_THRESHOLD: usize = 8;
+
+/// Scripts measuring text or element sizes at least this many times are probing for installed
+/// fonts.
+pub const FONT_PROBE_THRESHOLD: usize = 20;
+
+/// Scripts querying WebGL parameters at least this many times are enumerating the GPU.
+pub const WEBGL_PARAMETER_THRESHOLD: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FingerprintingTechnique {
+    /// Drawing to a canvas and reading the result back.
+    Canvas,
+    /// Reading many properties of `navigator` and `screen`.
+    NavigatorSweep,
+    /// Rendering audio with an oscillator and reading the result back.
+    Audio,
+    /// Measuring many text runs or element sizes, to detect which fonts are installed.
+    FontProbing,
+    /// Querying many WebGL parameters, or the unmasked GPU vendor and renderer.
+    WebGl,
+}
+
+impl FingerprintingTechnique {
+    /// The contribution of the technique to a script's score. The weights of all techniques sum
+    /// to 1, with the techniques least likely to have a benign explanation weighted highest.
+    pub fn weight(&self) -> f64 {
+        match self {
+            Self::Canvas => 0.3,
+            Self::Audio => 0.2,
+            Self::WebGl => 0.2,
+            Self::FontProbing => 0.15,
+            Self::NavigatorSweep => 0.15,
+        }
+    }
+}
+
+/// The calls that show a script using a fingerprinting technique.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TechniqueEvidence {
+    pub technique: FingerprintingTechnique,
+    /// The distinct APIs called, sorted.
+    pub apis: Vec<String>,
+    /// The `JsCall` edges of the calls, in id order.
+    pub edge_ids: Vec<String>,
+    /// The parsed arguments of each call, in the same order.
+    pub args: Vec<serde_json::Value>,
+}
+
+/// A script using at least one fingerprinting technique, as reported by
+/// [`PageGraph::fingerprinting_scores`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FingerprintingScore {
+    pub node_id: String,
+    pub url: Option<String>,
+    /// The sum of the weights of the techniques used, between 0 and 1.
+    pub score: f64,
+    pub techniques: Vec<TechniqueEvidence>,
+}
+
+/// A script that appears to fingerprint the browser with a canvas, as reported by
+/// [`PageGraph::canvas_fingerprinting_scripts`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CanvasFingerprint {
+    pub node_id: String,
+    pub url: Option<String>,
+    /// The distinct text drawn, in the order it was first drawn.
+    pub text: Vec<String>,
+    pub distinct_characters: usize,
+    /// The distinct fill and stroke styles set, in the order they were first set.
+    pub colors: Vec<String>,
+    /// The `JsCall` edges that drew text or set styles.
+    pub draw_edge_ids: Vec<String>,
+    /// The `JsCall` edges that read the canvas back, after text was drawn.
+    pub readback_edge_ids: Vec<String>,
+    /// The `JsResult` edges returning the read back image to the script, where recorded.
+    pub result_edge_ids: Vec<String>,
+}
+
+/// Returns the arguments recorded for a call, as parsed by [`Edge::parsed_args`].
+fn call_args(edge: &Edge) -> Vec<serde_json::Value> {
+    match edge.parsed_args() {
+        Some(serde_json::Value::Array(args)) => args,
+        _ => vec![],
+    }
+}
+
+/// Returns an argument as a string, without quotes if it is a JSON string.
+fn arg_string(arg: &serde_json::Value) -> String {
+    match arg {
+        serde_json::Value::String(arg) => arg.clone(),
+        arg => arg.to_string(),
+    }
+}
+
+/// Whether a readback covers a large enough area of the canvas. The size read by
+/// `getImageData` is given by its arguments, while the other methods read the whole canvas.
+fn reads_enough(call: &ApiCall) -> bool {
+    if call.member != "getImageData" {
+        return true;
+    }
+    let args = call_args(call.edge);
+    let dimension = |index: usize| args.get(index).and_then(serde_json::Value::as_f64).map(f64::abs);
+    match (dimension(2), dimension(3)) {
+        (Some(width), Some(height)) => width >= MIN_CANVAS_SIZE as f64 && height >= MIN_CANVAS_SIZE as f64,
+        _ => true,
+    }
+}
+
+/// A call from a script to a Web API or JS builtin, with the method split into the interface and
+/// member names, e.g. `Navigator` and `userAgent`.
+struct ApiCall<'a> {
+    method: &'a str,
+    interface: &'a str,
+    member: &'a str,
+    edge: &'a Edge,
+}
+
+fn canvas<'a>(calls: &[ApiCall<'a>]) -> Vec<&'a Edge> {
+    let draws = calls.iter()
+        .filter(|call| call.interface == "CanvasRenderingContext2D" && CANVAS_DRAW_METHODS.contains(&call.member))
+        .collect::<Vec<_>>();
+    let readbacks = calls.iter()
+        .filter(|call| CANVAS_READBACK_METHODS.contains(&call.method))
+        .filter(|readback| draws.iter().any(|draw| happened_before(draw.edge, readback.edge)))
+        .collect::<Vec<_>>();
+    if readbacks.is_empty() {
+        return vec![];
+    }
+    draws.into_iter().chain(readbacks).map(|call| call.edge).collect()
+}
+
+fn navigator_sweep<'a>(calls: &[ApiCall<'a>]) -> Vec<&'a Edge> {
+    let reads = calls.iter()
+        .filter(|call| call.interface == "Navigator" || call.interface == "Screen")
+        .collect::<Vec<_>>();
+    let properties = reads.iter().map(|call| call.method).collect::<BTreeSet<_>>();
+    if properties.len() < NAVIGATOR_SWEEP_THRESHOLD {
+        return vec![];
+    }
+    reads.into_iter().map(|call| call.edge).collect()
+}
+
+fn audio<'a>(calls: &[ApiCall<'a>]) -> Vec<&'a Edge> {
+    let oscillators = calls.iter().filter(|call| call.member == "createOscillator").collect::<Vec<_>>();
+    let readbacks = calls.iter()
+        .filter(|call| matches!(call.member, "startRendering" | "getChannelData" | "getFloatFrequencyData"))
+        .collect::<Vec<_>>();
+    if oscillators.is_empty() || readbacks.is_empty() {
+        return vec![];
+    }
+    oscillators.into_iter().chain(readbacks).map(|call| call.edge).collect()
+}
+
+fn font_probing<'a>(calls: &[ApiCall<'a>]) -> Vec<&'a Edge> {
+    let measurements = calls.iter()
+        .filter(|call| call.member == "measureText" || call.member == "offsetWidth" || call.member == "offsetHeight")
+        .collect::<Vec<_>>();
+    if measurements.len() < FONT_PROBE_THRESHOLD {
+        return vec![];
+    }
+    measurements.into_iter().map(|call| call.edge).collect()
+}
+
+fn webgl<'a>(calls: &[ApiCall<'a>]) -> Vec<&'a Edge> {
+    let queries = calls.iter()
+        .filter(|call| call.interface.starts_with("WebGL"))
+        .filter(|call| matches!(call.member, "getParameter" | "getShaderPrecisionFormat" | "getSupportedExtensions" | "getExtension"))
+        .collect::<Vec<_>>();
+    let unmasks_renderer = queries.iter().any(|call| call.member == "getExtension" &&
+        call_args(call.edge).iter().any(|arg| arg == "WEBGL_debug_renderer_info"));
+    if !unmasks_renderer && queries.len() < WEBGL_PARAMETER_THRESHOLD {
+        return vec![];
+    }
+    queries.into_iter().map(|call| call.edge).collect()
+}
+
+/// Returns the calls showing that a script used a technique, or nothing if it didn't.
+type Detector = for<'a> fn(&[ApiCall<'a>]) -> Vec<&'a Edge>;
+
+const DETECTORS: [(FingerprintingTechnique, Detector); 5] = [
+    (FingerprintingTechnique::Canvas, canvas),
+    (FingerprintingTechnique::NavigatorSweep, navigator_sweep),
+    (FingerprintingTechnique::Audio, audio),
+    (FingerprintingTechnique::FontProbing, font_probing),
+    (FingerprintingTechnique::WebGl, webgl),
+];
+
+impl PageGraph {
+    /// Returns the script's calls to Web APIs and JS builtins, in id order.
+    fn api_calls<'a>(&'a self, script: &'a Node) -> Vec<ApiCall<'a>> {
+        let mut calls = self.outgoing_edges(script)
+            .filter(|edge| matches!(edge.edge_type, EdgeType::JsCall { .. }))
+            .filter_map(|edge| {
+                let method = match &self.target_node(edge).node_type {
+                    NodeType::WebApi { method } | NodeType::JsBuiltin { method } => method.as_str(),
+                    _ => return None,
+                };
+                let (interface, member) = method.rsplit_once('.').unwrap_or(("", method));
+                Some(ApiCall { method, interface, member, edge })
+            })
+            .collect::<Vec<_>>();
+        calls.sort_unstable_by_key(|call| call.edge.id);
+        calls
+    }
+
+    /// Counts the script's calls to each Web API and JS builtin, by method name. The profile is
+    /// empty if the node is not a script, or made no calls.
+    pub fn webapi_profile(&self, script: NodeId) -> HashMap<String, usize> {
+        let mut profile = HashMap::new();
+        if let Some(script) = self.nodes.get(&script).filter(|node| matches!(node.node_type, NodeType::Script { .. })) {
+            self.api_calls(script).into_iter().for_each(|call| *profile.entry(call.method.to_string()).or_default() += 1);
+        }
+        profile
+    }
+
+    /// Scores each script by the fingerprinting techniques its Web API and JS builtin calls
+    /// show it using, with the calls supporting each. Only scripts using at least one technique
+    /// are returned, with the highest scoring first.
+    pub fn fingerprinting_scores(&self) -> Vec<FingerprintingScore> {
+        let mut scripts = self.nodes_of_kind(NodeKind::Script).collect::<Vec<_>>();
+        scripts.sort_unstable_by_key(|script| script.id);
+        let mut scores = scripts.into_iter().filter_map(|script| {
+            let calls = self.api_calls(script);
+            let techniques = DETECTORS.iter().filter_map(|(technique, detector)| {
+                let mut edges = detector(&calls);
+                if edges.is_empty() {
+                    return None;
+                }
+                edges.sort_unstable_by_key(|edge| edge.id);
+                edges.dedup_by_key(|edge| edge.id);
+                let apis = calls.iter()
+                    .filter(|call| edges.iter().any(|edge| edge.id == call.edge.id))
+                    .map(|call| call.method.to_string())
+                    .collect::<BTreeSet<_>>();
+                Some(TechniqueEvidence {
+                    technique: *technique,
+                    apis: apis.into_iter().collect(),
+                    edge_ids: edges.iter().map(|edge| format!("{}", edge.id)).collect(),
+                    args: edges.iter().map(|edge| edge.parsed_args().unwrap_or_else(|| serde_json::Value::Array(vec![]))).collect(),
+                })
+            }).collect::<Vec<_>>();
+            if techniques.is_empty() {
+                return None;
+            }
+            Some(FingerprintingScore {
+                node_id: format!("{}", script.id),
+                url: match &script.node_type {
+                    NodeType::Script { url, .. } => url.clone(),
+                    _ => None,
+                },
+                score: techniques.iter().map(|evidence| evidence.technique.weight()).sum(),
+                techniques,
+            })
+        }).collect::<Vec<_>>();
+        scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scores
+    }
+
+    /// Finds scripts fingerprinting the browser with a canvas, by the heuristic used in prior
+    /// measurement studies. A script is reported if it:
+    ///
+    /// - draws text with at least [`MIN_CANVAS_TEXT_CHARACTERS`] distinct characters, or in at
+    ///   least [`MIN_CANVAS_TEXT_COLORS`] colors;
+    /// - then reads the canvas back with `toDataURL` or `toBlob`, or with `getImageData` over an
+    ///   area at least [`MIN_CANVAS_SIZE`] pixels on each side;
+    /// - never calls `save` or `restore` on a canvas, or listens for events on a canvas it
+    ///   created, as interactive drawing does;
+    /// - never inserts a canvas it created into the document, or gives one a `width` or `height`
+    ///   below [`MIN_CANVAS_SIZE`].
+    ///
+    /// Canvases can't be told apart through Web API calls, so all of a script's canvas calls
+    /// are considered together. Results are in script id order.
+    pub fn canvas_fingerprinting_scripts(&self) -> Vec<CanvasFingerprint> {
+        let mut scripts = self.nodes_of_kind(NodeKind::Script).collect::<Vec<_>>();
+        scripts.sort_unstable_by_key(|script| script.id);
+        scripts.into_iter().filter_map(|script| {
+            let calls = self.api_calls(script);
+            let is_canvas_call = |call: &&ApiCall| call.interface == "CanvasRenderingContext2D";
+
+            if calls.iter().filter(is_canvas_call).any(|call| call.member == "save" || call.member == "restore") {
+                return None;
+            }
+
+            let canvases = self.outgoing_edges(script)
+                .filter(|edge| matches!(edge.edge_type, EdgeType::CreateNode {}))
+                .map(|edge| self.target_node(edge))
+                .filter(|node| matches!(&node.node_type, NodeType::HtmlElement { tag_name, .. } if tag_name.eq_ignore_ascii_case("canvas")))
+                .collect::<Vec<_>>();
+            let disqualifies = |edge: &Edge| match &edge.edge_type {
+                EdgeType::InsertNode { .. } | EdgeType::AddEventListener { .. } => edge.source == script.id,
+                EdgeType::SetAttribute { key, value: Some(value), .. } if key == "width" || key == "height" => {
+                    value.trim().parse::<u64>().is_ok_and(|size| size < MIN_CANVAS_SIZE)
+                }
+                _ => false,
+            };
+            if canvases.iter().any(|canvas| self.incoming_edges(canvas).any(disqualifies)) {
+                return None;
+            }
+
+            let mut text = Vec::<String>::new();
+            let mut colors = Vec::<String>::new();
+            let mut draws = vec![];
+            calls.iter().filter(is_canvas_call).for_each(|call| {
+                let (values, arg) = match call.member {
+                    "fillText" | "strokeText" => (&mut text, call_args(call.edge).first().map(arg_string)),
+                    "fillStyle" | "strokeStyle" => (&mut colors, call_args(call.edge).first().map(arg_string)),
+                    _ => return,
+                };
+                draws.push(call);
+                if let Some(arg) = arg {
+                    if !values.contains(&arg) {
+                        values.push(arg);
+                    }
+                }
+            });
+            let distinct_characters = text.iter().flat_map(|text| text.chars()).collect::<BTreeSet<_>>().len();
+            if distinct_characters < MIN_CANVAS_TEXT_CHARACTERS && colors.len() < MIN_CANVAS_TEXT_COLORS {
+                return None;
+            }
+
+            let text_draws = draws.iter()
+                .filter(|call| call.member == "fillText" || call.member == "strokeText")
+                .collect::<Vec<_>>();
+            let readbacks = calls.iter()
+                .filter(|call| CANVAS_READBACK_METHODS.contains(&call.method) && reads_enough(call))
+                .filter(|readback| text_draws.iter().any(|draw| happened_before(draw.edge, readback.edge)))
+                .collect::<Vec<_>>();
+            if readbacks.is_empty() {
+                return None;
+            }
+
+            Some(CanvasFingerprint {
+                node_id: format!("{}", script.id),
+                url: match &script.node_type {
+                    NodeType::Script { url, .. } => url.clone(),
+                    _ => None,
+                },
+                text,
+                distinct_characters,
+                colors,
+                draw_edge_ids: draws.iter().map(|call| format!("{}", call.edge.id)).collect(),
+                readback_edge_ids: readbacks.iter().map(|call| format!("{}", call.edge.id)).collect(),
+                result_edge_ids: readbacks.iter()
+                    .filter_map(|call| self.js_call_result(call.edge))
+                    .map(|result| format!("{}", result.id))
+                    .collect(),
+            })
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod fingerprinting_tests {
+    use super::*;
+
+    /// Builds a graph with a single script node, `n1`, calling the given Web APIs. Calls are given
+    /// as `(edge id, API node id, timestamp, args)`.
+    fn graph_with_calls(apis: &[(usize, &str)], calls: &[(usize, usize, usize, &str)], other: &str) -> PageGraph {
+        let apis = apis.iter().map(|(id, method)| {
+            format!(r#"<node id="n{0}"><data key="d0">web API</data><data key="d1">{0}</data><data key="d2">0</data><data key="d11">{1}</data></node>"#, id, method)
+        }).collect::<String>();
+        let calls = calls.iter().map(|(id, target, time, args)| {
+            format!(r#"<edge id="e{0}" source="n1" target="n{1}"><data key="d16">js call</data><data key="d17">{0}</data><data key="d18">{2}</data><data key="d25">0</data><data key="d24">{3}</data></edge>"#, id, target, time, args)
+        }).collect::<String>();
+        let nodes_and_edges = format!(r#"<node id="n1"><data key="d0">script</data><data key="d1">1</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+{}
+{}
+{}"#, apis, calls, other);
+        crate::test_util::parse(&nodes_and_edges)
+    }
+
+    #[test]
+    fn test_fingerprinting_scores() {
+        let graph = graph_with_calls(
+            &[(2, "CanvasRenderingContext2D.fillText"), (3, "HTMLCanvasElement.toDataURL"), (4, "Navigator.userAgent")],
+            &[(10, 3, 1, ""), (11, 2, 2, ""), (12, 3, 3, ""), (13, 4, 4, "")],
+            "");
+
+        let scores = graph.fingerprinting_scores();
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].score, FingerprintingTechnique::Canvas.weight());
+        let canvas = &scores[0].techniques[0];
+        assert_eq!(canvas.technique, FingerprintingTechnique::Canvas);
+        // The readback before anything was drawn isn't evidence
+        assert_eq!(canvas.edge_ids, ["e11", "e12"]);
+        assert_eq!(canvas.apis, ["CanvasRenderingContext2D.fillText", "HTMLCanvasElement.toDataURL"]);
+    }
+
+    #[test]
+    fn test_canvas_fingerprinting_scripts() {
+        let apis = [(2, "CanvasRenderingContext2D.fillText"), (3, "HTMLCanvasElement.toDataURL"), (4, "CanvasRenderingContext2D.getImageData")];
+        let result = r#"<edge id="e20" source="n3" target="n1"><data key="d16">js result</data><data key="d17">20</data><data key="d18">4</data><data key="d20">data:image/png;base64,AAAA</data></edge>"#;
+
+        let graph = graph_with_calls(&apis, &[(10, 2, 1, r#"["Cwm fjordbank glyphs vext quiz", 2, 15]"#), (11, 3, 2, "[]")], result);
+        let fingerprints = graph.canvas_fingerprinting_scripts();
+        assert_eq!(fingerprints.len(), 1);
+        assert_eq!(fingerprints[0].text, ["Cwm fjordbank glyphs vext quiz"]);
+        assert_eq!(fingerprints[0].readback_edge_ids, ["e11"]);
+        assert_eq!(fingerprints[0].result_edge_ids, ["e20"]);
+
+        // Too little text
+        let graph = graph_with_calls(&apis, &[(10, 2, 1, r#"["OK", 2, 15]"#), (11, 3, 2, "[]")], "");
+        assert!(graph.canvas_fingerprinting_scripts().is_empty());
+
+        // Too small a readback
+        let graph = graph_with_calls(&apis, &[(10, 2, 1, r#"["Cwm fjordbank glyphs vext quiz", 2, 15]"#), (11, 4, 2, "[0, 0, 8, 8]")], "");
+        assert!(graph.canvas_fingerprinting_scripts().is_empty());
+    }
+}
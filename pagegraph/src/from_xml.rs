@@ -1,217 +1,311 @@
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::BufReader;
-use std::collections::HashMap;
+use std::io::Read;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::convert::TryFrom;
 
-use xml::reader::{ EventReader, XmlEvent };
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
 use petgraph::graphmap::DiGraphMap;
 
 use crate::{ graph, types };
 
-/// Reads a PageGraph from a GraphML-formatted file.
-pub fn read_from_file(file: &str) -> graph::PageGraph {
-    let file = File::open(file).unwrap();
-    let file = BufReader::new(file);
-
-    let mut parser = EventReader::new(file);
+/// Describes why a GraphML document could not be read as a PageGraph.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The file could not be opened or read.
+    Io(std::io::Error),
+    /// The document is not well-formed XML.
+    Xml(quick_xml::Error),
+    /// The document ended before the named element was closed.
+    UnexpectedEof(&'static str),
+    /// An element or XML event appeared somewhere it was not expected.
+    UnexpectedContent { context: &'static str, found: String },
+    /// A required element was not present in the document.
+    MissingElement(&'static str),
+    /// A required XML attribute or data item was not present on an element.
+    MissingAttribute { context: &'static str, attribute: String },
+    /// A data item referenced a key id that was not declared by any `key` element.
+    UndeclaredKey(String),
+    /// An attribute or data item could not be parsed into the expected type.
+    InvalidValue { attribute: String, value: String },
+    /// A node's `node type` is not known to this version of the crate.
+    UnknownNodeType(String),
+    /// An edge's `edge type` is not known to this version of the crate.
+    UnknownEdgeType(String),
+    /// A node or edge had data items that are not part of its type.
+    ExtraData { item: String, keys: Vec<String> },
+    /// The `id` data item of a node or edge did not match its `id` XML attribute.
+    IdMismatch { expected: String, found: String },
+}
 
-    if let Ok(XmlEvent::StartDocument { .. }) = parser.next() {
-        return parse_xml_document(&mut parser);
-    } else {
-        panic!("couldn't find start of document");
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read graph: {}", e),
+            Self::Xml(e) => write!(f, "malformed XML: {}", e),
+            Self::UnexpectedEof(context) => write!(f, "document ended inside `{}`", context),
+            Self::UnexpectedContent { context, found } => write!(f, "unexpected {} in `{}`", found, context),
+            Self::MissingElement(name) => write!(f, "could not find `{}` element", name),
+            Self::MissingAttribute { context, attribute } => write!(f, "couldn't find `{}` value on {}", attribute, context),
+            Self::UndeclaredKey(key) => write!(f, "data item references undeclared key `{}`", key),
+            Self::InvalidValue { attribute, value } => write!(f, "could not parse attribute `{}`: `{}`", attribute, value),
+            Self::UnknownNodeType(node_type) => write!(f, "unknown node type `{}`", node_type),
+            Self::UnknownEdgeType(edge_type) => write!(f, "unknown edge type `{}`", edge_type),
+            Self::ExtraData { item, keys } => write!(f, "extra data on {}: {:?}", item, keys),
+            Self::IdMismatch { expected, found } => write!(f, "wrong id: expected {}, found {}", expected, found),
+        }
     }
 }
 
-fn parse_xml_document<R: std::io::Read>(parser: &mut EventReader<R>) -> graph::PageGraph {
-    if let Ok(XmlEvent::StartElement { name, .. }) = parser.next() {
-        if name.local_name == "graphml" {
-            return parse_graphml(parser);
-        } else {
-            panic!("expected graphml element");
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Xml(e) => Some(e),
+            _ => None,
         }
-    } else {
-        panic!("could not find graphml element");
     }
 }
 
-/// For simple data items of the form `<local_name>This is the return value</local_name>`
-fn parse_str_data<R: std::io::Read>(
-    parser: &mut EventReader<R>,
-    _attributes: Vec<xml::attribute::OwnedAttribute>,
-    local_name: &str,
-) -> String {
-    let mut result = None;
+impl From<std::io::Error> for ParseError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
 
-    while let Ok(e) = parser.next() {
-        match e {
-            XmlEvent::EndElement { name } => {
-                if name.local_name == local_name {
-                    break
-                }
-            }
-            XmlEvent::Characters(chars) => result = Some(chars),
-            XmlEvent::Whitespace(_) => (),
-            o => {panic!("Unexpected {:?} in `{}`", o, local_name)}
-        }
+impl From<quick_xml::Error> for ParseError {
+    fn from(e: quick_xml::Error) -> Self {
+        Self::Xml(e)
     }
+}
 
-    return result.unwrap();
+impl From<quick_xml::events::attributes::AttrError> for ParseError {
+    fn from(e: quick_xml::events::attributes::AttrError) -> Self {
+        Self::Xml(quick_xml::Error::InvalidAttr(e))
+    }
 }
 
-fn build_desc<R: std::io::Read>(
-    parser: &mut EventReader<R>,
-    _attributes: Vec<xml::attribute::OwnedAttribute>
-) -> graph::PageGraphDescriptor {
-    const STR_REP: &'static str = "desc";
+fn invalid_value(attribute: &str, value: &str) -> ParseError {
+    ParseError::InvalidValue { attribute: attribute.to_string(), value: value.to_string() }
+}
 
-    let mut version = None;
-    let mut about = None;
-    let mut url = None;
-    let mut is_root = None;
-    let mut frame_id = None;
-    let mut time = None;
+fn unexpected(context: &'static str, found: &[u8]) -> ParseError {
+    ParseError::UnexpectedContent { context, found: String::from_utf8_lossy(found).into_owned() }
+}
 
-    while let Ok(e) = parser.next() {
-        match e {
-            XmlEvent::EndElement { name } => {
-                if name.local_name == STR_REP {
-                    break
-                }
-            }
-            XmlEvent::StartElement { name, attributes, namespace: _ } => {
-                let local_name = &name.local_name[..];
-                match local_name {
-                    "version" => version = Some(parse_str_data(parser, attributes, local_name)),
-                    "about" => about = Some(parse_str_data(parser, attributes, local_name)),
-                    "url" => url = Some(parse_str_data(parser, attributes, local_name)),
-                    "is_root" => is_root = Some(parse_str_data(parser, attributes, local_name)),
-                    "frame_id" => frame_id = Some(parse_str_data(parser, attributes, local_name)),
-                    "time" => time = Some(build_time(parser, attributes)),
-                    o => panic!("unexpected {:?} in `{}`", o, STR_REP),
-                }
-            }
-            XmlEvent::Whitespace(_) => (),
-            o => {panic!("Unexpected {:?} in `{}`", o, STR_REP)}
+/// Options controlling how a GraphML document is read.
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// If set, nodes and edges of a type unknown to this version of the crate cause parsing to fail
+    /// with [`ParseError::UnknownNodeType`] or [`ParseError::UnknownEdgeType`]. Otherwise, they are
+    /// retained as [`NodeType::Unknown`](types::NodeType::Unknown) and
+    /// [`EdgeType::Unknown`](types::EdgeType::Unknown).
+    pub strict: bool,
+}
+
+/// Selects which nodes and edges are loaded from a graph, by the value of their `node type` or
+/// `edge type`. Data items following the type of a rejected node or edge are skipped without being
+/// stored, and edges are only loaded if both of their endpoints were loaded.
+pub struct LoadFilter<'f> {
+    node_filter: Box<dyn Fn(&str) -> bool + 'f>,
+    edge_filter: Box<dyn Fn(&str) -> bool + 'f>,
+}
+
+impl<'f> LoadFilter<'f> {
+    pub fn new(node_filter: impl Fn(&str) -> bool + 'f, edge_filter: impl Fn(&str) -> bool + 'f) -> Self {
+        Self {
+            node_filter: Box::new(node_filter),
+            edge_filter: Box::new(edge_filter),
         }
     }
+}
+
+/// Reads a PageGraph from a GraphML-formatted file, using the default (tolerant) options.
+pub fn read_from_file(file: &str) -> Result<graph::PageGraph, ParseError> {
+    read_from_file_with_options(file, &ParseOptions::default())
+}
+
+/// Reads a PageGraph from a GraphML-formatted file.
+pub fn read_from_file_with_options(file: &str, options: &ParseOptions) -> Result<graph::PageGraph, ParseError> {
+    let document = std::fs::read(file)?;
+    GraphmlParser::new(&document, options, None).parse_document()
+}
+
+/// Reads a PageGraph from any source of GraphML-formatted data, using the default (tolerant)
+/// options.
+pub fn read_from_reader<R: std::io::Read>(reader: R) -> Result<graph::PageGraph, ParseError> {
+    read_from_reader_with_options(reader, &ParseOptions::default())
+}
 
-    graph::PageGraphDescriptor {
-        version: version.unwrap(),
-        about: about.unwrap(),
-        url: url.unwrap(),
-        is_root: is_root.unwrap().parse::<bool>().unwrap(),
-        frame_id: graph::FrameId::try_from(frame_id.unwrap().as_str()).unwrap(),
-        time: time.unwrap(),
+/// Reads a PageGraph from any source of GraphML-formatted data.
+pub fn read_from_reader_with_options<R: std::io::Read>(reader: R, options: &ParseOptions) -> Result<graph::PageGraph, ParseError> {
+    let document = read_document(reader)?;
+    GraphmlParser::new(&document, options, None).parse_document()
+}
+
+/// Reads only the nodes and edges selected by `filter` from a GraphML-formatted file.
+pub fn read_filtered(file: &str, options: &ParseOptions, filter: &LoadFilter) -> Result<graph::PageGraph, ParseError> {
+    let document = std::fs::read(file)?;
+    GraphmlParser::new(&document, options, Some(filter)).parse_document()
+}
+
+/// Reads only the nodes and edges selected by `filter` from any source of GraphML-formatted data.
+pub fn read_filtered_from_reader<R: std::io::Read>(reader: R, options: &ParseOptions, filter: &LoadFilter) -> Result<graph::PageGraph, ParseError> {
+    let document = read_document(reader)?;
+    GraphmlParser::new(&document, options, Some(filter)).parse_document()
+}
+
+/// Reads only the descriptor of a GraphML-formatted file, such as the page's URL and the time
+/// span of the recording, without reading any further than the end of its `desc` element.
+pub fn read_descriptor(file: &str) -> Result<graph::PageGraphDescriptor, ParseError> {
+    const DESC_END: &[u8] = b"</desc>";
+
+    let mut file = File::open(file)?;
+    let mut document = vec![];
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut chunk)?;
+        let search_from = document.len().saturating_sub(DESC_END.len() - 1);
+        document.extend_from_slice(&chunk[..read]);
+        if read == 0 || document[search_from..].windows(DESC_END.len()).any(|window| window == DESC_END) {
+            break;
+        }
     }
+    GraphmlParser::new(&document, &ParseOptions::default(), None).parse_descriptor()
 }
 
-/// For the `time` element within `desc`.
-fn build_time<R: std::io::Read>(
-    parser: &mut EventReader<R>,
-    _attributes: Vec<xml::attribute::OwnedAttribute>
-) -> graph::PageGraphTime {
-    const STR_REP: &str = "time";
+fn read_document<R: std::io::Read>(mut reader: R) -> Result<Vec<u8>, ParseError> {
+    let mut document = vec![];
+    reader.read_to_end(&mut document)?;
+    Ok(document)
+}
 
-    let mut start = None;
-    let mut end = None;
+/// Describes why a remote frame's graph could not be merged into the graph that embedded it.
+#[derive(Debug)]
+pub enum FrameError {
+    /// The frame's graph could not be read.
+    Parse(ParseError),
+    /// The frame's graph was read, but could not be merged.
+    Merge(graph::MergeError),
+}
 
-    while let Ok(e) = parser.next() {
-        match e {
-            XmlEvent::EndElement { name } => {
-                if name.local_name == STR_REP {
-                    break
-                }
-            }
-            XmlEvent::StartElement { name, attributes, namespace: _ } => {
-                let local_name = &name.local_name[..];
-                match local_name {
-                    "start" => start = Some(parse_str_data(parser, attributes, local_name)),
-                    "end" => end = Some(parse_str_data(parser, attributes, local_name)),
-                    o => panic!("unexpected {:?} in `{}`", o, STR_REP),
-                }
-            }
-            XmlEvent::Whitespace(_) => (),
-            o => {panic!("Unexpected {:?} in `{}`", o, STR_REP)}
+impl std::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "could not be read: {}", e),
+            Self::Merge(e) => write!(f, "could not be merged: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(e) => Some(e),
+            Self::Merge(e) => Some(e),
         }
     }
+}
 
-    graph::PageGraphTime {
-        start: start.unwrap().parse::<u64>().unwrap(),
-        end: end.unwrap().parse::<u64>().unwrap(),
+impl From<ParseError> for FrameError {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
     }
 }
 
-fn parse_graphml<R: std::io::Read>(parser: &mut EventReader<R>) -> graph::PageGraph {
-    let mut desc = None;
-    let mut node_items = HashMap::new();
-    let mut edge_items = HashMap::new();
-    while let Ok(e) = parser.next() {
-        match e {
-            XmlEvent::StartElement { name, attributes, namespace: _ } => {
-                match &name.local_name[..] {
-                    "key" => {
-                        let (for_type, id, key) = build_key(parser, attributes);
-                        match for_type {
-                            KeyItemFor::Node => node_items.insert(id, key),
-                            KeyItemFor::Edge => edge_items.insert(id, key),
-                        };
-                    }
-                    "desc" => desc = Some(build_desc(parser, attributes)),
-                    "graph" => {
-                        break;
-                    }
-                    _ => println!("Unhandled local name: {}", name.local_name),
-                }
-            }
-            XmlEvent::EndElement { name } => {
-                if name.local_name == "graphml" {
-                    panic!("graphml ended without graph definition");
-                } else {
-                    panic!("unexpected end of element {}", name);
-                }
-            }
-            XmlEvent::Whitespace(_) => (),
-            o => {panic!("unexpected {:?} in `graphml`", o)}
+impl From<graph::MergeError> for FrameError {
+    fn from(e: graph::MergeError) -> Self {
+        Self::Merge(e)
+    }
+}
+
+/// Provides the separately-recorded graphs of remote frames, so that they can be merged into the
+/// graph of the page that embedded them.
+pub trait FrameLoader {
+    /// Loads the `document`th graph recorded for the given frame, counting from zero in navigation
+    /// order, or returns `None` if no such graph was recorded.
+    fn load_frame(&self, frame_id: &graph::FrameId, document: usize) -> Option<Result<graph::PageGraph, ParseError>>;
+}
+
+/// Loads frame graphs from the `page_graph_{frame_id}.{document}.graphml` files that are written
+/// alongside the root graph. A frame that navigates produces one file per document.
+pub struct FilesystemFrameLoader {
+    dir: PathBuf,
+    options: ParseOptions,
+}
+
+impl FilesystemFrameLoader {
+    /// Creates a loader for the frames of the graph in the given file.
+    pub fn for_graph(file: &str, options: ParseOptions) -> Self {
+        let dir = Path::new(file).parent().map(Path::to_path_buf).unwrap_or_default();
+        Self { dir, options }
+    }
+}
+
+impl FrameLoader for FilesystemFrameLoader {
+    fn load_frame(&self, frame_id: &graph::FrameId, document: usize) -> Option<Result<graph::PageGraph, ParseError>> {
+        let frame_path = self.dir.join(format!("page_graph_{}.{}.graphml", frame_id, document));
+        if !frame_path.exists() {
+            return None;
         }
+        let document = match std::fs::read(frame_path) {
+            Ok(document) => document,
+            Err(e) => return Some(Err(e.into())),
+        };
+        Some(GraphmlParser::new(&document, &self.options, None).parse_document())
     }
+}
 
-    let key = KeyModel { node_items, edge_items };
-    let graph = Some(build_graph(parser, &key, desc.expect("could not find desc")));
+/// Merges the graphs of every remote frame in `graph` that `loader` can provide, including frames
+/// nested within other remote frames. Each document recorded for a frame is merged in navigation
+/// order, until the loader has no further documents. Frames without a recorded graph are left
+/// unmerged.
+///
+/// Documents whose graphs could not be read or merged are skipped, and returned along with the
+/// error.
+pub fn merge_frames<L: FrameLoader>(graph: &mut graph::PageGraph, loader: &L) -> Vec<(graph::FrameId, FrameError)> {
+    let mut failures = vec![];
+    let mut seen = HashSet::new();
+    let mut pending = graph.all_remote_frame_ids();
 
-    while let Ok(e) = parser.next() {
-        match e {
-            XmlEvent::StartElement { name, attributes: _, namespace: _ } => {
-                match &name.local_name[..] {
-                    "key" => {
-                        panic!("key item located after graph");
-                    }
-                    "graph" => {
-                        panic!("more than one graph item not supported");
+    while let Some(frame_id) = pending.pop() {
+        if !seen.insert(frame_id) {
+            continue;
+        }
+        let mut document = 0;
+        while let Some(loaded) = loader.load_frame(&frame_id, document) {
+            match loaded {
+                Ok(frame_graph) => {
+                    // Remote frames within this frame are only discoverable once it has been merged.
+                    let nested_frame_ids = frame_graph.all_remote_frame_ids();
+                    match graph.merge_frame_document(frame_graph, &frame_id, document) {
+                        Ok(()) => pending.extend(nested_frame_ids),
+                        Err(e) => failures.push((frame_id, e.into())),
                     }
-                    _ => println!("Unhandled local name: {}", name.local_name),
                 }
+                Err(e) => failures.push((frame_id, e.into())),
             }
-            XmlEvent::EndElement { name } => {
-                if name.local_name == "graphml" {
-                    break
-                }
-            }
-            XmlEvent::Whitespace(_) => (),
-            o => {panic!("Unexpected {:?} in `graphml`", o)}
+            document += 1;
         }
     }
 
-    graph.expect("could not find graph")
+    failures
 }
 
-struct KeyModel {
-    node_items: HashMap<String, KeyItem>,
-    edge_items: HashMap<String, KeyItem>,
+/// Reads a PageGraph from a GraphML-formatted file, and merges in the graphs of any remote frames
+/// recorded alongside it. Frames whose graphs could not be read or merged are omitted.
+pub fn read_with_frames(file: &str) -> Result<graph::PageGraph, ParseError> {
+    let mut graph = read_from_file(file)?;
+    merge_frames(&mut graph, &FilesystemFrameLoader::for_graph(file, ParseOptions::default()));
+    Ok(graph)
 }
 
-struct KeyItem {
-    id: String,
-    _attr_type: String,
+/// Maps the `id` of each declared GraphML `key` to its intelligible `attr.name`.
+struct KeyModel {
+    node_items: HashMap<Vec<u8>, String>,
+    edge_items: HashMap<Vec<u8>, String>,
 }
 
 enum KeyItemFor {
@@ -219,398 +313,706 @@ enum KeyItemFor {
     Edge,
 }
 
-impl TryFrom<&str> for KeyItemFor {
+impl TryFrom<&[u8]> for KeyItemFor {
     type Error = ();
 
-    fn try_from(v: &str) -> Result<Self, ()> {
+    fn try_from(v: &[u8]) -> Result<Self, ()> {
         match v {
-            "node" => Ok(Self::Node),
-            "edge" => Ok(Self::Edge),
+            b"node" => Ok(Self::Node),
+            b"edge" => Ok(Self::Edge),
             _ => Err(())
         }
     }
 }
 
-fn build_key<R: std::io::Read>(
-    parser: &mut EventReader<R>,
-    attributes: Vec<xml::attribute::OwnedAttribute>
-) -> (KeyItemFor, String, KeyItem) {
-    let mut id = None;
-    let mut for_type = None;
-    let mut attr_name = None;
-    let mut attr_type = None;
-    for attribute in attributes {
-        let name = attribute.name.local_name;
-        match &name[..] {
-            "id" => id = Some(attribute.value),
-            "for" => for_type = Some(attribute.value),
-            "attr.name" => attr_name = Some(attribute.value),
-            "attr.type" => attr_type = Some(attribute.value),
-            _ => panic!("Unexpected value in key: {}", &name),
-        }
-    }
-    let key_item = KeyItem {
-        id: id.expect("couldn't find `id` value on key"),
-        _attr_type: attr_type.expect("couldn't find `attr.type` value on key"),
+/// Parses an unsigned integer directly from raw attribute or text bytes.
+fn parse_usize(attribute: &str, value: &[u8]) -> Result<usize, ParseError> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .ok_or_else(|| invalid_value(attribute, &String::from_utf8_lossy(value)))
+}
+
+/// Parses a GraphML item id of the form `n123` or `e123`.
+fn parse_item_id(attribute: &str, value: &[u8], prefix: u8) -> Result<usize, ParseError> {
+    let trimmed = value.iter().position(|b| *b != prefix).map(|start| &value[start..]).unwrap_or(&[]);
+    parse_usize(attribute, trimmed).map_err(|_| invalid_value(attribute, &String::from_utf8_lossy(value)))
+}
+
+/// Parses a timestamp data item, which may be formatted with a fractional part.
+fn parse_timestamp(contained: &str) -> Result<graph::Timestamp, ParseError> {
+    let millis = if contained.contains('.') {
+        contained.trim_end_matches('0')
+            .trim_end_matches('.')
+            .parse::<isize>()
+            .map_err(|_| invalid_value("timestamp", contained))?
+    } else {
+        contained.parse::<isize>().unwrap_or_default()
     };
+    Ok(graph::Timestamp::from(millis))
+}
+
+fn is_whitespace(text: &[u8]) -> bool {
+    text.iter().all(u8::is_ascii_whitespace)
+}
 
-    if let Ok(XmlEvent::EndElement { name }) = parser.next() {
-        if &name.local_name != "key" {
-            panic!("expected end of key element");
+/// The PageGraph schema version recorded in a graph's `desc`, used to select the legacy attribute
+/// layouts that need to be mapped onto the current [`types::NodeType`] and [`types::EdgeType`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct SchemaVersion(u32, u32, u32);
+
+impl SchemaVersion {
+    /// Parses a dotted version string, treating any missing components as `0`. Unparseable versions
+    /// are assumed to be current, so that no legacy mappings are applied to them.
+    fn parse(version: &str) -> Self {
+        let mut parts = version.trim().trim_start_matches('v').split('.').map(|part| part.parse::<u32>());
+        let mut next = || parts.next().unwrap_or(Ok(0));
+        match (next(), next(), next()) {
+            (Ok(major), Ok(minor), Ok(patch)) => Self(major, minor, patch),
+            _ => Self(u32::MAX, u32::MAX, u32::MAX),
         }
-    } else {
-        panic!("could not find end of key element");
     }
+}
 
-    (
-        KeyItemFor::try_from(&for_type.expect("couldn't find `for` value on key")[..])
-            .expect("unexpected `for` value on key"),
-        attr_name.expect("couldn't find `attr.name` value on key"),
-        key_item,
-    )
+/// A single change in how a node or edge type's data items are laid out.
+enum LayoutChange {
+    /// A data item was renamed from the first name to the second.
+    Rename(&'static str, &'static str),
+    /// A data item was removed.
+    Remove(&'static str),
+    /// A data item was added; older graphs are given the provided value.
+    Add(&'static str, &'static str),
 }
 
-fn build_graph<R: std::io::Read>(parser: &mut EventReader<R>, key: &KeyModel, desc: graph::PageGraphDescriptor) -> graph::PageGraph {
-    const STR_REP: &'static str = "graph";
+/// Changes to the GraphML layout of node and edge types. Each entry applies to graphs recorded
+/// with a schema version older than the one given.
+const EDGE_LAYOUT_CHANGES: &[(SchemaVersion, &str, LayoutChange)] = &[
+    (SchemaVersion(0, 7, 0), "request start", LayoutChange::Rename("request type", "resource type")),
+    (SchemaVersion(0, 7, 0), "request start", LayoutChange::Remove("value")),
+    (SchemaVersion(0, 7, 0), "request complete", LayoutChange::Add("headers", "")),
+    (SchemaVersion(0, 7, 0), "request complete", LayoutChange::Add("size", "")),
+    (SchemaVersion(0, 7, 0), "request error", LayoutChange::Add("headers", "")),
+    (SchemaVersion(0, 7, 0), "request error", LayoutChange::Add("size", "")),
+];
+const NODE_LAYOUT_CHANGES: &[(SchemaVersion, &str, LayoutChange)] = &[];
 
-    let mut edges = HashMap::new();
-    let mut nodes = HashMap::new();
-    let mut graph = DiGraphMap::<graph::NodeId, Vec<graph::EdgeId>>::new();
+/// Rewrites the data items of a node or edge recorded with an older schema version into the
+/// current layout for its type.
+fn upgrade_layout(
+    changes: &[(SchemaVersion, &str, LayoutChange)],
+    version: SchemaVersion,
+    type_str: &str,
+    attrs: &mut HashMap<&str, Cow<str>>,
+) {
+    changes.iter()
+        .filter(|(before, item_type, _)| version < *before && *item_type == type_str)
+        .for_each(|(_, _, change)| match change {
+            LayoutChange::Rename(from, to) => if let Some(value) = attrs.remove(from) {
+                attrs.insert(to, value);
+            },
+            LayoutChange::Remove(attr) => {
+                attrs.remove(attr);
+            }
+            LayoutChange::Add(attr, default) => {
+                attrs.entry(attr).or_insert(Cow::Borrowed(default));
+            }
+        });
+}
 
-    while let Ok(e) = parser.next() {
-        match e {
-            XmlEvent::StartElement { name, attributes, namespace: _ } => {
-                match &name.local_name[..] {
-                    "node" => {
-                        let node = build_node(parser, attributes, &key.node_items);
-                        graph.add_node(node.id);
-                        nodes.insert(node.id, node);
-                    }
-                    "edge" => {
-                        let edge = build_edge(parser, attributes, &key.edge_items);
-                        if let Some(concurrent_edges) = graph.edge_weight_mut(edge.source, edge.target) {
-                            concurrent_edges.push(edge.id);
-                        } else {
-                            graph.add_edge(edge.source, edge.target, vec![edge.id]);
-                        }
-                        edges.insert(edge.id, edge);
-                    }
-                    _ => println!("Unhandled local name in {}: {}", STR_REP, name.local_name),
-                }
+/// Pull parser over a GraphML document held in memory. Events borrow from the document, and
+/// attribute keys are compared as raw bytes, so that text is only copied out of the document for
+/// values the graph retains, or that had to be unescaped.
+struct GraphmlParser<'i, 'o> {
+    reader: Reader<&'i [u8]>,
+    options: &'o ParseOptions,
+    filter: Option<&'o LoadFilter<'o>>,
+}
+
+impl<'i, 'o> GraphmlParser<'i, 'o> {
+    fn new(document: &'i [u8], options: &'o ParseOptions, filter: Option<&'o LoadFilter<'o>>) -> Self {
+        Self {
+            reader: Reader::from_reader(document),
+            options,
+            filter,
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Event<'i>, ParseError> {
+        Ok(self.reader.read_event()?)
+    }
+
+    /// Skips over the remainder of an element whose start tag has just been read.
+    fn skip_element(&mut self, name: &[u8]) -> Result<(), ParseError> {
+        self.reader.read_to_end(quick_xml::name::QName(name))?;
+        Ok(())
+    }
+
+    /// Reads the text content of an element whose start tag has just been read, up to and
+    /// including its end tag. Content made of a single unescaped run of text is borrowed from the
+    /// document.
+    fn text_content(&mut self, context: &'static str) -> Result<Cow<'i, str>, ParseError> {
+        let mut result = Cow::Borrowed("");
+
+        loop {
+            let content = match self.next_event()? {
+                Event::End(_) => break,
+                Event::Text(text) => text.unescape()?,
+                Event::CData(cdata) => cdata.decode().map_err(quick_xml::Error::from)?,
+                Event::Comment(_) => continue,
+                Event::Eof => return Err(ParseError::UnexpectedEof(context)),
+                Event::Start(e) | Event::Empty(e) => return Err(unexpected(context, e.name().as_ref())),
+                _ => continue,
+            };
+            if result.is_empty() {
+                result = content;
+            } else {
+                result.to_mut().push_str(&content);
             }
-            XmlEvent::EndElement { name } => {
-                if name.local_name == STR_REP {
-                    break
-                }
+        }
+
+        Ok(result)
+    }
+
+    fn parse_document(&mut self) -> Result<graph::PageGraph, ParseError> {
+        loop {
+            match self.next_event()? {
+                Event::Start(e) if e.local_name().as_ref() == b"graphml" => break,
+                Event::Start(e) | Event::Empty(e) => return Err(unexpected("document", e.name().as_ref())),
+                Event::Text(text) if !is_whitespace(&text) => return Err(unexpected("document", &text)),
+                Event::Eof => return Err(ParseError::MissingElement("graphml")),
+                _ => (),
             }
-            XmlEvent::Whitespace(_) => (),
-            o => {panic!("Unexpected {:?} in `{}`", o, STR_REP)}
         }
+
+        self.parse_graphml()
     }
 
-    graph::PageGraph::new(desc, edges, nodes, graph)
-}
+    /// Reads the document up to and including its `desc` element, ignoring anything before it.
+    fn parse_descriptor(&mut self) -> Result<graph::PageGraphDescriptor, ParseError> {
+        loop {
+            match self.next_event()? {
+                Event::Start(e) if e.local_name().as_ref() == b"desc" => return self.build_desc(),
+                Event::Eof => return Err(ParseError::MissingElement("desc")),
+                _ => (),
+            }
+        }
+    }
 
-fn build_edge<R: std::io::Read>(
-    parser: &mut EventReader<R>,
-    attributes: Vec<xml::attribute::OwnedAttribute>,
-    key: &HashMap<String, KeyItem>
-) -> graph::Edge {
-    const STR_REP: &'static str = "edge";
+    fn build_desc(&mut self) -> Result<graph::PageGraphDescriptor, ParseError> {
+        const STR_REP: &str = "desc";
 
-    let mut id_value = None;
-    let mut source_value = None;
-    let mut target_value = None;
-    let mut edge_type = None;
-    let mut edge_timestamp = None;
-    let mut data = HashMap::new();
-    for attribute in attributes {
-        let name = attribute.name.local_name;
-        match &name[..] {
-            "id" => id_value = Some(attribute.value
-                    .trim_start_matches('e')
-                    .parse::<usize>()
-                    .expect("Parse edge id as usize")
-                    .into()
-                ),
-            "source" => source_value = Some(attribute.value
-                    .trim_start_matches('n')
-                    .parse::<usize>()
-                    .expect("Parse source node id as usize")
-                    .into()
-                ),
-            "target" => target_value = Some(attribute.value
-                    .trim_start_matches('n')
-                    .parse::<usize>()
-                    .expect("Parse target node id as usize")
-                    .into()
-                ),
-            _ => panic!("Unexpected attribute in {}: {}", STR_REP, name),
-        }
-    }
-
-    while let Ok(e) = parser.next() {
-        match e {
-            XmlEvent::StartElement { name, attributes, namespace: _ } => {
-                match &name.local_name[..] {
-                    DataItem::STR_REP => {
-                        let data_item = DataItem::build_data(parser, attributes);
-                        let contained = data_item.contained;
-                        if key.get("edge type").unwrap().id == data_item.key {
-                            edge_type = Some(contained.to_string());
-                        } else if key.get("id").unwrap().id == data_item.key {
-                            let edge_id: graph::EdgeId = contained.parse::<usize>()
-                                .expect("parse edge id as usize")
-                                .into();
-                            if edge_id != id_value.unwrap() {
-                                panic!("wrong edge id");
-                            }
-                        } else if key.get("timestamp").unwrap().id == data_item.key {
-                            edge_timestamp = Some(if contained.contains('.') {
-                                contained.trim_end_matches('0')
-                                    .trim_end_matches('.')
-                                    .parse::<isize>()
-                                    .unwrap()
-                                } else {
-                                    contained.parse::<isize>()
-                                        .unwrap_or_default()
-                                });
-                        } else {
-                            data.insert(data_item.key, contained);
-                        }
+        let mut version = None;
+        let mut about = None;
+        let mut url = None;
+        let mut is_root = None;
+        let mut frame_id = None;
+        let mut time = None;
+
+        loop {
+            match self.next_event()? {
+                Event::End(_) => break,
+                Event::Start(e) => {
+                    match e.local_name().as_ref() {
+                        b"version" => version = Some(self.text_content("version")?.into_owned()),
+                        b"about" => about = Some(self.text_content("about")?.into_owned()),
+                        b"url" => url = Some(self.text_content("url")?.into_owned()),
+                        b"is_root" => is_root = Some(self.text_content("is_root")?),
+                        b"frame_id" => frame_id = Some(self.text_content("frame_id")?),
+                        b"time" => time = Some(self.build_time()?),
+                        o => return Err(unexpected(STR_REP, o)),
                     }
-                    _ => println!("Unhandled local name in {}: {}", STR_REP, name.local_name),
                 }
+                Event::Text(text) if !is_whitespace(&text) => return Err(unexpected(STR_REP, &text)),
+                Event::Empty(e) => return Err(unexpected(STR_REP, e.name().as_ref())),
+                Event::Eof => return Err(ParseError::UnexpectedEof(STR_REP)),
+                _ => (),
             }
-            XmlEvent::EndElement { name } => {
-                if name.local_name == STR_REP {
-                    break
+        }
+
+        let missing = |attribute: &str| ParseError::MissingAttribute { context: STR_REP, attribute: attribute.to_string() };
+
+        let is_root = is_root.ok_or_else(|| missing("is_root"))?;
+        let frame_id = frame_id.ok_or_else(|| missing("frame_id"))?;
+
+        Ok(graph::PageGraphDescriptor {
+            version: version.ok_or_else(|| missing("version"))?,
+            about: about.ok_or_else(|| missing("about"))?,
+            url: url.ok_or_else(|| missing("url"))?,
+            is_root: is_root.parse::<bool>().map_err(|_| invalid_value("is_root", &is_root))?,
+            frame_id: graph::FrameId::try_from(&*frame_id).map_err(|_| invalid_value("frame_id", &frame_id))?,
+            time: time.ok_or_else(|| missing("time"))?,
+        })
+    }
+
+    /// For the `time` element within `desc`.
+    fn build_time(&mut self) -> Result<graph::PageGraphTime, ParseError> {
+        const STR_REP: &str = "time";
+
+        let mut start = None;
+        let mut end = None;
+
+        loop {
+            match self.next_event()? {
+                Event::End(_) => break,
+                Event::Start(e) => {
+                    match e.local_name().as_ref() {
+                        b"start" => start = Some(self.text_content("start")?),
+                        b"end" => end = Some(self.text_content("end")?),
+                        o => return Err(unexpected(STR_REP, o)),
+                    }
                 }
+                Event::Text(text) if !is_whitespace(&text) => return Err(unexpected(STR_REP, &text)),
+                Event::Empty(e) => return Err(unexpected(STR_REP, e.name().as_ref())),
+                Event::Eof => return Err(ParseError::UnexpectedEof(STR_REP)),
+                _ => (),
             }
-            XmlEvent::Whitespace(_) => (),
-            o => {panic!("Unexpected {:?} in `{}`", o, STR_REP)}
         }
+
+        let parse_time = |attribute: &str, value: Option<Cow<str>>| {
+            let value = value.ok_or_else(|| ParseError::MissingAttribute { context: STR_REP, attribute: attribute.to_string() })?;
+            value.parse::<u64>().map_err(|_| invalid_value(attribute, &value))
+        };
+
+        Ok(graph::PageGraphTime {
+            start: parse_time("start", start)?,
+            end: parse_time("end", end)?,
+        })
     }
 
-    let edge_type_attr = &edge_type.as_ref().expect("couldn't find `edge type` attr on node")[..];
+    fn parse_graphml(&mut self) -> Result<graph::PageGraph, ParseError> {
+        let mut desc = None;
+        let mut key = KeyModel { node_items: HashMap::new(), edge_items: HashMap::new() };
+        loop {
+            match self.next_event()? {
+                Event::Start(e) => {
+                    match e.local_name().as_ref() {
+                        b"key" => {
+                            let (for_type, id, attr_name) = build_key(&e)?;
+                            self.skip_element(b"key")?;
+                            match for_type {
+                                KeyItemFor::Node => key.node_items.insert(id, attr_name),
+                                KeyItemFor::Edge => key.edge_items.insert(id, attr_name),
+                            };
+                        }
+                        b"desc" => desc = Some(self.build_desc()?),
+                        b"graph" => {
+                            break;
+                        }
+                        o => {
+                            let o = o.to_vec();
+                            println!("Unhandled local name: {}", String::from_utf8_lossy(&o));
+                            self.skip_element(&o)?;
+                        }
+                    }
+                }
+                Event::Empty(e) => {
+                    match e.local_name().as_ref() {
+                        b"key" => {
+                            let (for_type, id, attr_name) = build_key(&e)?;
+                            match for_type {
+                                KeyItemFor::Node => key.node_items.insert(id, attr_name),
+                                KeyItemFor::Edge => key.edge_items.insert(id, attr_name),
+                            };
+                        }
+                        b"graph" => return Err(ParseError::MissingElement("graph")),
+                        o => println!("Unhandled local name: {}", String::from_utf8_lossy(o)),
+                    }
+                }
+                Event::End(_) => return Err(ParseError::MissingElement("graph")),
+                Event::Text(text) if !is_whitespace(&text) => return Err(unexpected("graphml", &text)),
+                Event::Eof => return Err(ParseError::UnexpectedEof("graphml")),
+                _ => (),
+            }
+        }
 
-    let edge_type = types::EdgeType::construct(edge_type_attr, &mut data, key);
-    assert!(data.is_empty(), "extra data on edge {:?}: {:?}", edge_type, data);
+        let graph = self.build_graph(&key, desc.ok_or(ParseError::MissingElement("desc"))?)?;
 
-    let id = id_value.expect("couldn't find `id` value on edge");
-    let source = source_value.expect("couldn't find `source` value on edge");
-    let target = target_value.expect("couldn't find `target` value on edge");
+        loop {
+            match self.next_event()? {
+                Event::Start(e) | Event::Empty(e) => {
+                    match e.local_name().as_ref() {
+                        b"key" => return Err(unexpected("graphml", b"key item located after graph")),
+                        b"graph" => return Err(unexpected("graphml", b"more than one graph item")),
+                        o => {
+                            let o = o.to_vec();
+                            println!("Unhandled local name: {}", String::from_utf8_lossy(&o));
+                            self.skip_element(&o)?;
+                        }
+                    }
+                }
+                Event::End(_) => break,
+                Event::Text(text) if !is_whitespace(&text) => return Err(unexpected("graphml", &text)),
+                Event::Eof => return Err(ParseError::UnexpectedEof("graphml")),
+                _ => (),
+            }
+        }
 
-    graph::Edge {
-        id,
-        edge_type,
-        edge_timestamp,
-        source,
-        target,
+        Ok(graph)
     }
-}
 
-fn build_node<R: std::io::Read>(
-    parser: &mut EventReader<R>,
-    attributes: Vec<xml::attribute::OwnedAttribute>,
-    key: &HashMap<String, KeyItem>
-) -> graph::Node {
-    const STR_REP: &'static str = "node";
+    fn build_graph(&mut self, key: &KeyModel, desc: graph::PageGraphDescriptor) -> Result<graph::PageGraph, ParseError> {
+        const STR_REP: &str = "graph";
 
-    let mut id_value = None;
-    let mut node_type = None;
-    let mut node_timestamp = None;
-    let mut data = HashMap::new();
-    for attribute in attributes {
-        let name = attribute.name.local_name;
-        match &name[..] {
-            "id" => id_value = Some(attribute.value
-                    .trim_start_matches('n')
-                    .parse::<usize>()
-                    .expect("Parse node id as usize")
-                    .into()
-                ),
-            _ => panic!("Unexpected attribute in {}: {}", STR_REP, name),
-        }
-    }
-
-    while let Ok(e) = parser.next() {
-        match e {
-            XmlEvent::StartElement { name, attributes, namespace: _ } => {
-                match &name.local_name[..] {
-                    DataItem::STR_REP => {
-                        let data_item = DataItem::build_data(parser, attributes);
-                        let contained = data_item.contained;
-                        if key.get("node type").unwrap().id == data_item.key {
-                            node_type = Some(contained.to_string());
-                        } else if key.get("id").unwrap().id == data_item.key {
-                            let node_id: graph::NodeId = contained.parse::<usize>()
-                                .expect("parse node id as usize")
-                                .into();
-                            if node_id != id_value.unwrap() {
-                                panic!("wrong node id");
+        let version = SchemaVersion::parse(&desc.version);
+        let mut edges = vec![];
+        let mut nodes = HashMap::new();
+        let mut graph = DiGraphMap::<graph::NodeId, Vec<graph::EdgeId>>::new();
+
+        loop {
+            match self.next_event()? {
+                Event::Start(e) => {
+                    match e.local_name().as_ref() {
+                        b"node" => {
+                            let id = node_attributes(&e)?;
+                            if let Some(node) = self.build_node(id, &key.node_items, version)? {
+                                graph.add_node(node.id);
+                                nodes.insert(node.id, node);
+                            }
+                        }
+                        b"edge" => {
+                            let (id, source, target) = edge_attributes(&e)?;
+                            if let Some(edge) = self.build_edge(id, source, target, &key.edge_items, version)? {
+                                edges.push(edge);
                             }
-                        } else if key.get("timestamp").unwrap().id == data_item.key {
-                            node_timestamp = Some(if contained.contains('.') {
-                                contained.trim_end_matches('0')
-                                    .trim_end_matches('.')
-                                    .parse::<isize>()
-                                    .unwrap()
-                                } else {
-                                    contained.parse::<isize>()
-                                        .unwrap_or_default()
-                                });
-                        } else {
-                            data.insert(data_item.key, contained);
+                        }
+                        o => {
+                            let o = o.to_vec();
+                            println!("Unhandled local name in {}: {}", STR_REP, String::from_utf8_lossy(&o));
+                            self.skip_element(&o)?;
                         }
                     }
-                    _ => println!("Unhandled local name in {}: {}", STR_REP, name.local_name),
                 }
+                Event::Empty(e) => match e.local_name().as_ref() {
+                    b"node" => return Err(ParseError::MissingAttribute { context: "node", attribute: "node type".to_string() }),
+                    b"edge" => return Err(ParseError::MissingAttribute { context: "edge", attribute: "edge type".to_string() }),
+                    o => println!("Unhandled local name in {}: {}", STR_REP, String::from_utf8_lossy(o)),
+                },
+                Event::End(_) => break,
+                Event::Text(text) if !is_whitespace(&text) => return Err(unexpected(STR_REP, &text)),
+                Event::Eof => return Err(ParseError::UnexpectedEof(STR_REP)),
+                _ => (),
             }
-            XmlEvent::EndElement { name } => {
-                if name.local_name == STR_REP {
-                    break
+        }
+
+        // Edges are only added once all nodes are known, so that a filtered load can drop edges
+        // whose endpoints were not loaded.
+        let edges = edges.into_iter()
+            .filter(|edge| self.filter.is_none() || (nodes.contains_key(&edge.source) && nodes.contains_key(&edge.target)))
+            .map(|edge| {
+                if let Some(concurrent_edges) = graph.edge_weight_mut(edge.source, edge.target) {
+                    concurrent_edges.push(edge.id);
+                } else {
+                    graph.add_edge(edge.source, edge.target, vec![edge.id]);
+                }
+                (edge.id, edge)
+            })
+            .collect();
+
+        Ok(graph::PageGraph::new(desc, edges, nodes, graph))
+    }
+
+    /// Reads every `data` child of a node or edge, up to and including the closing tag. Data items
+    /// are keyed by their declared attribute name.
+    ///
+    /// If `accept` rejects the value of the `type_attr` data item, the rest of the element is
+    /// skipped and `None` is returned.
+    fn build_data<'k>(
+        &mut self,
+        context: &'static str,
+        key: &'k HashMap<Vec<u8>, String>,
+        type_attr: &str,
+        accept: Option<&dyn Fn(&str) -> bool>,
+    ) -> Result<Option<HashMap<&'k str, Cow<'i, str>>>, ParseError> {
+        let mut data = HashMap::new();
+
+        loop {
+            let (attr_name, is_empty) = match self.next_event()? {
+                Event::Start(e) if e.local_name().as_ref() == b"data" => (data_key(&e, key)?, false),
+                Event::Empty(e) if e.local_name().as_ref() == b"data" => (data_key(&e, key)?, true),
+                Event::Start(e) => {
+                    let o = e.name().as_ref().to_vec();
+                    println!("Unhandled local name in {}: {}", context, String::from_utf8_lossy(&o));
+                    self.skip_element(&o)?;
+                    continue;
                 }
+                Event::Empty(e) => {
+                    println!("Unhandled local name in {}: {}", context, String::from_utf8_lossy(e.name().as_ref()));
+                    continue;
+                }
+                Event::End(_) => break,
+                Event::Text(text) if !is_whitespace(&text) => return Err(unexpected(context, &text)),
+                Event::Eof => return Err(ParseError::UnexpectedEof(context)),
+                _ => continue,
+            };
+            let contained = if is_empty {
+                Cow::Borrowed("")
+            } else {
+                self.text_content("data")?
+            };
+            if attr_name == type_attr && accept.map(|accept| !accept(&contained)).unwrap_or(false) {
+                self.skip_element(context.as_bytes())?;
+                return Ok(None);
             }
-            XmlEvent::Whitespace(_) => (),
-            o => {panic!("Unexpected {:?} in `{}`", o, STR_REP)}
+            data.insert(attr_name, contained);
         }
+
+        Ok(Some(data))
     }
 
-    let node_type_attr = &node_type.as_ref().expect("couldn't find `node type` attr on node")[..];
+    fn build_edge(
+        &mut self,
+        id: graph::EdgeId,
+        source: graph::NodeId,
+        target: graph::NodeId,
+        key: &HashMap<Vec<u8>, String>,
+        version: SchemaVersion,
+    ) -> Result<Option<graph::Edge>, ParseError> {
+        const STR_REP: &str = "edge";
 
-    let node_type = types::NodeType::construct(node_type_attr, &mut data, key);
-    assert!(data.is_empty(), "extra data on node {:?}: {:?}", node_type, data);
+        let accept = self.filter.map(|filter| &filter.edge_filter as &dyn Fn(&str) -> bool);
+        let mut data = match self.build_data(STR_REP, key, "edge type", accept)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let missing = |attribute: &str| ParseError::MissingAttribute { context: STR_REP, attribute: attribute.to_string() };
 
-    let id = id_value.expect("couldn't find `id` value on node");
-    let node_timestamp = node_timestamp.expect("couldn't find `timestamp` attr on node");
+        if let Some(contained) = data.remove("id") {
+            let edge_id: graph::EdgeId = parse_item_id("id", contained.as_bytes(), b'e')?.into();
+            if edge_id != id {
+                return Err(ParseError::IdMismatch { expected: format!("{}", id), found: format!("{}", edge_id) });
+            }
+        }
+        let edge_timestamp = data.remove("timestamp").map(|contained| parse_timestamp(&contained)).transpose()?;
+        let edge_type_attr = data.remove("edge type").ok_or_else(|| missing("edge type"))?;
+        upgrade_layout(EDGE_LAYOUT_CHANGES, version, &edge_type_attr, &mut data);
+
+        let edge_type = types::EdgeType::construct(edge_type_attr, &mut data, self.options.strict)?;
+        if !data.is_empty() {
+            return Err(ParseError::ExtraData { item: format!("{}", id), keys: data.into_keys().map(str::to_string).collect() });
+        }
 
-    graph::Node {
-        id,
-        node_type,
-        node_timestamp,
+        Ok(Some(graph::Edge {
+            id,
+            edge_type,
+            edge_timestamp,
+            source,
+            target,
+        }))
     }
-}
 
-/// Represents a `data` GraphML node, which provides attributes associated with a particular node
-/// or edge.
-#[derive(Debug, PartialEq)]
-struct DataItem {
-    key: String,
-    contained: String,
+    fn build_node(&mut self, id: graph::NodeId, key: &HashMap<Vec<u8>, String>, version: SchemaVersion) -> Result<Option<graph::Node>, ParseError> {
+        const STR_REP: &str = "node";
+
+        let accept = self.filter.map(|filter| &filter.node_filter as &dyn Fn(&str) -> bool);
+        let mut data = match self.build_data(STR_REP, key, "node type", accept)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let missing = |attribute: &str| ParseError::MissingAttribute { context: STR_REP, attribute: attribute.to_string() };
+
+        if let Some(contained) = data.remove("id") {
+            let node_id: graph::NodeId = parse_item_id("id", contained.as_bytes(), b'n')?.into();
+            if node_id != id {
+                return Err(ParseError::IdMismatch { expected: format!("{}", id), found: format!("{}", node_id) });
+            }
+        }
+        let node_timestamp = parse_timestamp(&data.remove("timestamp").ok_or_else(|| missing("timestamp"))?)?;
+        let node_type_attr = data.remove("node type").ok_or_else(|| missing("node type"))?;
+        upgrade_layout(NODE_LAYOUT_CHANGES, version, &node_type_attr, &mut data);
+
+        let node_type = types::NodeType::construct(node_type_attr, &mut data, self.options.strict)?;
+        if !data.is_empty() {
+            return Err(ParseError::ExtraData { item: format!("{}", id), keys: data.into_keys().map(str::to_string).collect() });
+        }
+
+        Ok(Some(graph::Node {
+            id,
+            node_type,
+            node_timestamp,
+        }))
+    }
 }
 
-impl DataItem {
-    const STR_REP: &'static str = "data";
+fn build_key(e: &BytesStart) -> Result<(KeyItemFor, Vec<u8>, String), ParseError> {
+    const STR_REP: &str = "key";
+
+    let mut id = None;
+    let mut for_type = None;
+    let mut attr_name = None;
+    let mut attr_type = None;
+    for attribute in e.attributes() {
+        let attribute = attribute?;
+        match attribute.key.local_name().as_ref() {
+            b"id" => id = Some(attribute.value.into_owned()),
+            b"for" => for_type = Some(KeyItemFor::try_from(attribute.value.as_ref())
+                .map_err(|_| invalid_value("for", &String::from_utf8_lossy(&attribute.value)))?),
+            b"attr.name" => attr_name = Some(attribute.unescape_value()?.into_owned()),
+            b"attr.type" => attr_type = Some(()),
+            o => return Err(unexpected(STR_REP, o)),
+        }
+    }
+    let missing = |attribute: &str| ParseError::MissingAttribute { context: STR_REP, attribute: attribute.to_string() };
+    attr_type.ok_or_else(|| missing("attr.type"))?;
 
-    fn build_data<R: std::io::Read>(
-        parser: &mut EventReader<R>,
-        attributes: Vec<xml::attribute::OwnedAttribute>
-    ) -> Self {
-        let mut key_value = None;
-        let mut contained_value = None;
+    Ok((
+        for_type.ok_or_else(|| missing("for"))?,
+        id.ok_or_else(|| missing("id"))?,
+        attr_name.ok_or_else(|| missing("attr.name"))?,
+    ))
+}
 
-        for attribute in attributes {
-            let name = attribute.name.local_name;
-            match &name[..] {
-                "key" => key_value = Some(attribute.value),
-                _ => panic!("Unexpected attribute in {}: {}", Self::STR_REP, name),
-            }
+fn node_attributes(e: &BytesStart) -> Result<graph::NodeId, ParseError> {
+    let mut id_value = None;
+    for attribute in e.attributes() {
+        let attribute = attribute?;
+        match attribute.key.local_name().as_ref() {
+            b"id" => id_value = Some(parse_item_id("id", &attribute.value, b'n')?.into()),
+            o => return Err(unexpected("node", o)),
         }
+    }
+    id_value.ok_or_else(|| ParseError::MissingAttribute { context: "node", attribute: "id".to_string() })
+}
 
-        while let Ok(e) = parser.next() {
-            match e {
-                XmlEvent::EndElement { name } => {
-                    if name.local_name == Self::STR_REP {
-                        break
-                    }
-                }
-                XmlEvent::Characters(c) => {
-                    contained_value = Some(c);
-                }
-                XmlEvent::Whitespace(_) => (),
-                o => {panic!("Unexpected {:?} in `{}`", o, Self::STR_REP)}
-            }
+fn edge_attributes(e: &BytesStart) -> Result<(graph::EdgeId, graph::NodeId, graph::NodeId), ParseError> {
+    const STR_REP: &str = "edge";
+
+    let mut id_value = None;
+    let mut source_value = None;
+    let mut target_value = None;
+    for attribute in e.attributes() {
+        let attribute = attribute?;
+        match attribute.key.local_name().as_ref() {
+            b"id" => id_value = Some(parse_item_id("id", &attribute.value, b'e')?.into()),
+            b"source" => source_value = Some(parse_item_id("source", &attribute.value, b'n')?.into()),
+            b"target" => target_value = Some(parse_item_id("target", &attribute.value, b'n')?.into()),
+            o => return Err(unexpected(STR_REP, o)),
         }
+    }
+    let missing = |attribute: &str| ParseError::MissingAttribute { context: STR_REP, attribute: attribute.to_string() };
+    Ok((
+        id_value.ok_or_else(|| missing("id"))?,
+        source_value.ok_or_else(|| missing("source"))?,
+        target_value.ok_or_else(|| missing("target"))?,
+    ))
+}
 
-        Self {
-            key: key_value.expect("couldn't find `key` value on data"),
-            contained: contained_value.unwrap_or_default(),
+/// Resolves the `key` attribute of a `data` element to the attribute name it was declared with.
+fn data_key<'k>(e: &BytesStart, key: &'k HashMap<Vec<u8>, String>) -> Result<&'k str, ParseError> {
+    let mut key_value = None;
+    for attribute in e.attributes() {
+        let attribute = attribute?;
+        match attribute.key.local_name().as_ref() {
+            b"key" => key_value = Some(attribute.value.into_owned()),
+            o => return Err(unexpected("data", o)),
         }
     }
+    let key_value = key_value.ok_or_else(|| ParseError::MissingAttribute { context: "data", attribute: "key".to_string() })?;
+    key.get(&key_value)
+        .map(|attr_name| &attr_name[..])
+        .ok_or_else(|| ParseError::UndeclaredKey(String::from_utf8_lossy(&key_value).into_owned()))
 }
 
-/// Remove and return an attribute from an attribute map according to the key, if present
+/// Remove and return an attribute's value from an attribute map by name, if present
+macro_rules! drain_opt_value_from {
+    ( $attrs:ident, $attr:expr ) => {
+        $attrs.remove($attr)
+    };
+}
+/// Fail if the attribute's value does not exist in the map
+macro_rules! drain_value_from {
+    ( $attrs:ident, $attr:expr ) => {
+        drain_opt_value_from!($attrs, $attr)
+            .ok_or_else(|| ParseError::MissingAttribute { context: "data", attribute: $attr.to_string() })?
+    };
+}
+/// Remove and return an attribute from an attribute map by name as an owned string, if present
 macro_rules! drain_opt_string_from {
-    ( $attrs:ident, $key:ident, $attr:expr ) => {
-        $attrs.remove(&$key.get($attr).expect(&format!("could not find `{}` in key", $attr)).id)
+    ( $attrs:ident, $attr:expr ) => {
+        drain_opt_value_from!($attrs, $attr).map(Cow::into_owned)
     };
 }
-/// Panic if the attribute string does not exist in the map
+/// Fail if the attribute string does not exist in the map
 macro_rules! drain_string_from {
-    ( $attrs:ident, $key:ident, $attr:expr ) => {
-        drain_opt_string_from!($attrs, $key, $attr)
-            .expect(&format!("attribute `{}` was not present", $attr))
+    ( $attrs:ident, $attr:expr ) => {
+        drain_value_from!($attrs, $attr).into_owned()
     };
 }
-/// Panic if the attribute string cannot be parsed as a boolean value
+/// Fail if the attribute string cannot be parsed as a boolean value
 macro_rules! drain_bool_from {
-    ( $attrs:ident, $key:ident, $attr:expr ) => {
-        drain_string_from!($attrs, $key, $attr)
-            .to_ascii_lowercase()
-            .parse::<bool>()
-            .expect(&format!("could not parse attribute `{}` as bool", $attr))
+    ( $attrs:ident, $attr:expr ) => {
+        {
+            let value = drain_value_from!($attrs, $attr);
+            value
+                .to_ascii_lowercase()
+                .parse::<bool>()
+                .map_err(|_| invalid_value($attr, &value))?
+        }
     };
 }
-/// Panic if the optional attribute string cannot be parsed as an unsigned numeric value
+/// Fail if the optional attribute string cannot be parsed as an unsigned numeric value
 macro_rules! drain_opt_usize_from {
-    ( $attrs:ident, $key:ident, $attr:expr ) => {
-        drain_opt_string_from!($attrs, $key, $attr)
+    ( $attrs:ident, $attr:expr ) => {
+        drain_opt_value_from!($attrs, $attr)
             .map(|inner_data| inner_data
                 .parse::<usize>()
-                .expect(&format!("could not parse attribute `{}` as usize", $attr))
+                .map_err(|_| invalid_value($attr, &inner_data))
             )
+            .transpose()?
     };
 }
-/// Panic if the attribute string cannot be parsed as an unsigned numeric value
+/// Fail if the attribute string cannot be parsed as an unsigned numeric value
 macro_rules! drain_usize_from {
-    ( $attrs:ident, $key:ident, $attr:expr ) => {
+    ( $attrs:ident, $attr:expr ) => {
         {
-            let value = drain_string_from!($attrs, $key, $attr);
+            let value = drain_value_from!($attrs, $attr);
             value
                 .parse::<usize>()
-                .expect(&format!("could not parse attribute `{}` as usize: `{}`", $attr, value))
+                .map_err(|_| invalid_value($attr, &value))?
         }
     };
 }
 
-/// Allows building this type from a type string and a set of associated attributes, each of which
-/// correspond to intelligible string representations through a key.
+/// Sizes are recorded as empty or negative for responses that could not be sized, such as streamed
+/// fetches, video, or audio, so any value that isn't a byte count is treated as unknown.
+macro_rules! drain_size_from {
+    ( $attrs:ident ) => {
+        drain_value_from!($attrs, "size").parse::<u64>().ok()
+    };
+}
+
+/// Allows building this type from a type string and a set of associated attributes, keyed by their
+/// intelligible `attr.name` from the document's `key` declarations.
 ///
 /// Any attributes used will be drained from `attrs`.
-trait KeyedAttrs {
-    fn construct(type_str: &str, attrs: &mut HashMap<String, String>, key: &HashMap<String, KeyItem>) -> Self;
+trait KeyedAttrs: Sized {
+    fn construct(type_str: Cow<str>, attrs: &mut HashMap<&str, Cow<str>>, strict: bool) -> Result<Self, ParseError>;
 }
 
 impl KeyedAttrs for types::NodeType {
-    fn construct(type_str: &str, attrs: &mut HashMap<String, String>, key: &HashMap<String, KeyItem>) -> Self {
+    fn construct(type_str: Cow<str>, attrs: &mut HashMap<&str, Cow<str>>, strict: bool) -> Result<Self, ParseError> {
+        macro_rules! drain_value {
+            ( $attr:expr ) => { drain_value_from!(attrs, $attr) }
+        }
         macro_rules! drain_opt_string {
-            ( $attr:expr ) => { drain_opt_string_from!(attrs, key, $attr) }
+            ( $attr:expr ) => { drain_opt_string_from!(attrs, $attr) }
         }
         macro_rules! drain_string {
-            ( $attr:expr ) => { drain_string_from!(attrs, key, $attr) }
+            ( $attr:expr ) => { drain_string_from!(attrs, $attr) }
         }
         macro_rules! drain_bool {
-            ( $attr:expr ) => { drain_bool_from!(attrs, key, $attr) }
+            ( $attr:expr ) => { drain_bool_from!(attrs, $attr) }
         }
         macro_rules! drain_usize {
-            ( $attr:expr ) => { drain_usize_from!(attrs, key, $attr) }
+            ( $attr:expr ) => { drain_usize_from!(attrs, $attr) }
         }
 
-        match type_str {
+        Ok(match &*type_str {
             "extensions" => Self::Extensions {},
             "remote frame" => Self::RemoteFrame {
-                frame_id: graph::FrameId::try_from(&drain_string!("frame id") as &str).unwrap()
+                frame_id: {
+                    let frame_id = drain_value!("frame id");
+                    graph::FrameId::try_from(&*frame_id).map_err(|_| invalid_value("frame id", &frame_id))?
+                }
             },
             "resource" => Self::Resource {
                 url: drain_string!("url")
@@ -671,30 +1073,43 @@ impl KeyedAttrs for types::NodeType {
             "binding event" => Self::BindingEvent {
                 binding_event: drain_string!("binding event"),
             },
-            _ => panic!("Unknown node type `{}`", type_str),
-        }
+            _ if strict => return Err(ParseError::UnknownNodeType(type_str.into_owned())),
+            _ => Self::Unknown {
+                attrs: attrs.drain().map(|(k, v)| (k.to_string(), v.into_owned())).collect(),
+                type_name: type_str.into_owned(),
+            },
+        })
     }
 }
 
 impl KeyedAttrs for types::EdgeType {
-    fn construct(type_str: &str, attrs: &mut HashMap<String, String>, key: &HashMap<String, KeyItem>) -> Self {
+    fn construct(type_str: Cow<str>, attrs: &mut HashMap<&str, Cow<str>>, strict: bool) -> Result<Self, ParseError> {
+        macro_rules! drain_value {
+            ( $attr:expr ) => { drain_value_from!(attrs, $attr) }
+        }
+        macro_rules! drain_opt_value {
+            ( $attr:expr ) => { drain_opt_value_from!(attrs, $attr) }
+        }
         macro_rules! drain_opt_string {
-            ( $attr:expr ) => { drain_opt_string_from!(attrs, key, $attr) }
+            ( $attr:expr ) => { drain_opt_string_from!(attrs, $attr) }
         }
         macro_rules! drain_string {
-            ( $attr:expr ) => { drain_string_from!(attrs, key, $attr) }
+            ( $attr:expr ) => { drain_string_from!(attrs, $attr) }
         }
         macro_rules! drain_bool {
-            ( $attr:expr ) => { drain_bool_from!(attrs, key, $attr) }
+            ( $attr:expr ) => { drain_bool_from!(attrs, $attr) }
         }
         macro_rules! drain_opt_usize {
-            ( $attr:expr ) => { drain_opt_usize_from!(attrs, key, $attr) }
+            ( $attr:expr ) => { drain_opt_usize_from!(attrs, $attr) }
         }
         macro_rules! drain_usize {
-            ( $attr:expr ) => { drain_usize_from!(attrs, key, $attr) }
+            ( $attr:expr ) => { drain_usize_from!(attrs, $attr) }
+        }
+        macro_rules! drain_size {
+            () => { drain_size_from!(attrs) }
         }
 
-        match type_str {
+        Ok(match &*type_str {
             "filter" => Self::Filter {},
             "structure" => Self::Structure {},
             "cross DOM" => Self::CrossDom {},
@@ -722,21 +1137,27 @@ impl KeyedAttrs for types::EdgeType {
                 response_hash: drain_opt_string!("response hash"),
                 request_id: drain_usize!("request id"),
                 headers: drain_string!("headers"),
-                size: drain_string!("size"),
+                size: drain_size!(),
             },
             "request error" => Self::RequestError {
                 status: drain_string!("status"),
                 request_id: drain_usize!("request id"),
                 value: drain_opt_string!("value"),
                 headers: drain_string!("headers"),
-                size: drain_string!("size"),
+                size: drain_size!(),
             },
             "request start" => Self::RequestStart {
-                request_type: crate::types::RequestType::from(&drain_string!("resource type")[..]),
+                request_type: crate::types::RequestType::from(&*drain_value!("resource type")),
                 status: drain_string!("status"),
                 request_id: drain_usize!("request id"),
             },
-            "request response" => Self::RequestResponse,
+            "request response" => Self::RequestResponse {
+                request_id: drain_usize!("request id"),
+                status: drain_string!("status"),
+                // Redirect responses may be recorded without headers or a body
+                headers: drain_opt_string!("headers").unwrap_or_default(),
+                size: drain_opt_value!("size").and_then(|size| size.parse::<u64>().ok()),
+            },
             "add event listener" => Self::AddEventListener {
                 key: drain_string!("key"),
                 event_listener_id: drain_usize!("event listener id"),
@@ -786,7 +1207,134 @@ impl KeyedAttrs for types::EdgeType {
             "binding event" => Self::BindingEvent {
                 script_position: drain_usize!("script position"),
             },
-            _ => panic!("Unknown edge type `{}`", type_str),
+            _ if strict => return Err(ParseError::UnknownEdgeType(type_str.into_owned())),
+            _ => Self::Unknown {
+                attrs: attrs.drain().map(|(k, v)| (k.to_string(), v.into_owned())).collect(),
+                type_name: type_str.into_owned(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod parse_error_tests {
+    use super::*;
+    use crate::test_util::HEADER;
+
+    fn parse(body: &str) -> Result<graph::PageGraph, ParseError> {
+        read_from_reader(format!("{}{}", HEADER, body).as_bytes())
+    }
+
+    #[test]
+    fn test_valid_graph() {
+        let graph = parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node></graph></graphml>"#).unwrap();
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.desc.url, "https://example.com/");
+    }
+
+    #[test]
+    fn test_truncated_graph() {
+        assert!(matches!(parse(r#"<node id="n1"><data key="d0">parser</data>"#), Err(ParseError::UnexpectedEof("node"))));
+    }
+
+    #[test]
+    fn test_unknown_node_type() {
+        let body = r#"<node id="n1"><data key="d0">teleporter</data><data key="d1">1</data><data key="d2">0</data></node></graph></graphml>"#;
+        let graph = parse(body).unwrap();
+        assert!(matches!(&graph.nodes[&1.into()].node_type, types::NodeType::Unknown { type_name, .. } if type_name == "teleporter"));
+
+        let result = read_from_reader_with_options(format!("{}{}", HEADER, body).as_bytes(), &ParseOptions { strict: true });
+        assert!(matches!(result, Err(ParseError::UnknownNodeType(t)) if t == "teleporter"));
+    }
+
+    #[test]
+    fn test_legacy_request_layout() {
+        let legacy_header = HEADER
+            .replace("<version>0.7.0</version>", "<version>0.6.3</version>")
+            .replace("<desc>", r#"<key id="d35" for="edge" attr.name="request type" attr.type="string"/>
+<desc>"#);
+        let body = r#"<edge id="e3" source="n1" target="n2"><data key="d16">request start</data><data key="d17">3</data><data key="d18">0</data><data key="d35">Script</data><data key="d30">started</data><data key="d28">7</data><data key="d20">ignored</data></edge></graph></graphml>"#;
+        let graph = read_from_reader(format!("{}{}", legacy_header, body).as_bytes()).unwrap();
+        assert!(matches!(&graph.edges[&3.into()].edge_type, types::EdgeType::RequestStart { request_type: types::RequestType::Script, request_id: 7, .. }));
+
+        // The same layout is not accepted from a current graph
+        let current_header = legacy_header.replace("<version>0.6.3</version>", "<version>0.7.0</version>");
+        let result = read_from_reader(format!("{}{}", current_header, body).as_bytes());
+        assert!(matches!(result, Err(ParseError::MissingAttribute { .. })));
+    }
+
+    #[test]
+    fn test_filtered_load() {
+        let body = r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">teleporter</data><data key="d1">2</data><data key="d2">0</data></node>
+<node id="n3"><data key="d0">parser</data><data key="d1">3</data><data key="d2">0</data></node>
+<edge id="e4" source="n1" target="n2"><data key="d16">create node</data><data key="d17">4</data><data key="d18">0</data></edge>
+<edge id="e5" source="n1" target="n3"><data key="d16">create node</data><data key="d17">5</data><data key="d18">0</data></edge>
+</graph></graphml>"#;
+        let filter = LoadFilter::new(|node_type| node_type == "parser", |_| true);
+        let graph = read_filtered_from_reader(format!("{}{}", HEADER, body).as_bytes(), &ParseOptions::default(), &filter).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+        assert!(graph.edges.contains_key(&5.into()));
+    }
+
+    struct DocumentLoader(Vec<String>);
+
+    impl FrameLoader for DocumentLoader {
+        fn load_frame(&self, _frame_id: &graph::FrameId, document: usize) -> Option<Result<graph::PageGraph, ParseError>> {
+            self.0.get(document).map(|body| read_from_reader(body.as_bytes()))
         }
     }
+
+    #[test]
+    fn test_merge_frame_documents() {
+        let mut graph = read_from_reader(format!("{}{}", HEADER, r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">remote frame</data><data key="d1">2</data><data key="d2">0</data><data key="d34">0000000000000000000000000000000B</data></node>
+</graph></graphml>"#).as_bytes()).unwrap();
+
+        let frame_document = HEADER
+            .replace("<is_root>true</is_root>", "<is_root>false</is_root>")
+            .replace("0000000000000000000000000000000A", "0000000000000000000000000000000B")
+            + r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">DOM root</data><data key="d1">2</data><data key="d2">0</data><data key="d4">HTML</data><data key="d5">false</data><data key="d6">1</data></node>
+</graph></graphml>"#;
+        let failures = merge_frames(&mut graph, &DocumentLoader(vec![frame_document.clone(), frame_document]));
+        assert!(failures.is_empty());
+        assert_eq!(graph.nodes.len(), 6);
+
+        let remote_frame = &graph.nodes[&2.into()];
+        let mut attached = graph.outgoing_neighbors(remote_frame).map(|node| format!("{}", node.id)).collect::<Vec<_>>();
+        attached.sort_unstable();
+        assert_eq!(attached, [
+            "n1:0000000000000000000000000000000B",
+            "n1:0000000000000000000000000000000B.1",
+            "n2:0000000000000000000000000000000B",
+            "n2:0000000000000000000000000000000B.1",
+        ]);
+        assert!(graph.validate().is_empty());
+    }
+
+    #[test]
+    fn test_mismatched_id() {
+        let result = parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">2</data><data key="d2">0</data></node></graph></graphml>"#);
+        assert!(matches!(result, Err(ParseError::IdMismatch { .. })));
+    }
+
+    #[test]
+    fn test_read_descriptor() {
+        let path = std::env::temp_dir().join(format!("pagegraph-descriptor-test-{}.graphml", std::process::id()));
+        // Nothing after the descriptor is read, so the rest of the document may be incomplete
+        std::fs::write(&path, format!("{}<node id=", HEADER)).unwrap();
+        let desc = read_descriptor(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(desc.url, "https://example.com/");
+        assert_eq!((desc.time.start, desc.time.end), (1, 2));
+    }
+
+    #[test]
+    fn test_escaped_text() {
+        let graph = parse(r#"<node id="n1"><data key="d0">script</data><data key="d1">1</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10">if (a &lt; b) <![CDATA[x && y]]>;</data></node></graph></graphml>"#).unwrap();
+        assert!(matches!(&graph.nodes[&1.into()].node_type, types::NodeType::Script { source, .. } if source == "if (a < b) x && y;"));
+    }
 }
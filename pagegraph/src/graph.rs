@@ -1,11 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
+use std::sync::{OnceLock, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use petgraph::graphmap::DiGraphMap;
 
-use crate::types::{NodeType, EdgeType, RequestType};
+use crate::headers::ResponseHeaders;
+use crate::types::{NodeType, NodeKind, EdgeType, EdgeKind, RequestType, HtmlElementId, ScriptId};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PageGraphDescriptor {
     pub version: String,
     pub about: String,
@@ -15,23 +19,104 @@ pub struct PageGraphDescriptor {
     pub time: PageGraphTime,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PageGraphTime {
     pub start: u64,
     pub end: u64,
 }
 
+/// The time at which a node or edge was recorded, in milliseconds since navigation start.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, serde::Serialize)]
+#[serde(transparent)]
+pub struct Timestamp(isize);
+
+impl Timestamp {
+    /// Returns the number of milliseconds since navigation start, as recorded in the graph.
+    pub fn as_millis(&self) -> isize {
+        self.0
+    }
+
+    /// Returns the time elapsed since navigation start. Timestamps from before navigation start
+    /// are reported as zero.
+    pub fn as_duration_since_navigation(&self) -> Duration {
+        Duration::from_millis(self.0.max(0) as u64)
+    }
+
+    /// Returns the wall-clock time at which the item was recorded, given the recording times of
+    /// the graph it belongs to.
+    pub fn as_wall_clock(&self, time: &PageGraphTime) -> SystemTime {
+        let navigation_start = UNIX_EPOCH + Duration::from_millis(time.start);
+        let offset = Duration::from_millis(self.0.unsigned_abs() as u64);
+        if self.0 < 0 {
+            navigation_start - offset
+        } else {
+            navigation_start + offset
+        }
+    }
+}
+
+impl From<isize> for Timestamp {
+    fn from(millis: isize) -> Self {
+        Self(millis)
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::fmt::Debug for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Identifies a request by the frame context that made it and its Blink request id.
+type RequestKey = (Option<FrameId>, usize, usize);
+
+/// A request id as listed by [`PageGraph::all_request_ids`]: `(request_id, frame, document,
+/// resource_url, request_type, status)`.
+pub type RequestIdSummary = (usize, Option<FrameId>, usize, String, RequestType, Option<String>);
+
+/// Identifies a DOM node by its frame context and its Blink node id.
+type DomNodeKey = (Option<FrameId>, usize, HtmlElementId);
+
 /// The main PageGraph data structure.
 #[derive(Debug)]
 pub struct PageGraph {
     pub desc: PageGraphDescriptor,
+    /// Every edge in the graph. Lookups are indexed, so `invalidate_indexes` must be called after
+    /// modifying it.
     pub edges: HashMap<EdgeId, Edge>,
+    /// Every node in the graph. Lookups are indexed, so `invalidate_indexes` must be called after
+    /// modifying it.
     pub nodes: HashMap<NodeId, Node>,
     pub graph: DiGraphMap<NodeId, Vec<EdgeId>>,
 
-    next_edge_id: std::cell::RefCell<usize>,
+    next_edge_id: AtomicUsize,
+    /// Edges for each request, keyed by frame, document, and Blink request id. Built on first use by
+    /// `edges_for_request_id`, so it must be reset whenever `edges` is modified.
+    request_id_index: OnceLock<HashMap<RequestKey, Vec<EdgeId>>>,
+    /// DOM nodes for each Blink node id within a frame context. Built on first use by
+    /// `html_node_by_blink_id`, so it must be reset whenever `nodes` is modified.
+    dom_node_index: OnceLock<HashMap<DomNodeKey, Vec<NodeId>>>,
+    /// Nodes and edges of each kind, built on first use by `nodes_of_kind` and `edges_of_kind`.
+    node_kind_index: OnceLock<HashMap<NodeKind, Vec<NodeId>>>,
+    edge_kind_index: OnceLock<HashMap<EdgeKind, Vec<EdgeId>>>,
+    /// DOM roots already attributed to HTML nodes and scripts, filled in as they are resolved by
+    /// the `dom_root_for_*` lookups, so it must be reset by `invalidate_indexes` whenever `nodes`
+    /// or `edges` is modified.
+    dom_root_cache: RwLock<HashMap<NodeId, Option<NodeId>>>,
 }
 
+// A parsed graph can be queried concurrently from multiple threads.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<PageGraph>();
+};
+
 impl PageGraph {
     pub fn new(desc: PageGraphDescriptor, edges: HashMap<EdgeId, Edge>, nodes: HashMap<NodeId, Node>, graph: DiGraphMap<NodeId, Vec<EdgeId>>) -> Self {
         Self {
@@ -39,17 +124,174 @@ impl PageGraph {
             edges,
             nodes,
             graph,
-            next_edge_id: std::cell::RefCell::new(usize::MAX),
+            next_edge_id: AtomicUsize::new(usize::MAX),
+            request_id_index: OnceLock::new(),
+            dom_node_index: OnceLock::new(),
+            node_kind_index: OnceLock::new(),
+            edge_kind_index: OnceLock::new(),
+            dom_root_cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns every `RequestStart`, `RequestComplete`, and `RequestError` edge for the given Blink
+    /// request id within the frame context of `context`. A frame id on its own refers to the
+    /// frame's first document, where `None` refers to the root frame. Edges are returned in id
+    /// order.
+    ///
+    /// The lookup is indexed, with the index built on the first call.
+    pub fn edges_for_request_id<C: HasFrameId>(&self, request_id: usize, context: C) -> Vec<&Edge> {
+        self.request_id_index().get(&(context.get_frame_id(), context.get_document(), request_id))
+            .map(|edge_ids| edge_ids.iter().map(|edge_id| &self.edges[edge_id]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the request edges for the given Blink request id in every frame context it was used
+    /// in, as with `edges_for_request_id`, tagged with the frame and document. Request ids are
+    /// only unique within a renderer process, so the same id may refer to unrelated requests in
+    /// different frames, or in different documents of a frame that navigated. Frame contexts are
+    /// returned in id order, starting with the root frame.
+    pub fn requests_by_id_any_frame(&self, request_id: usize) -> Vec<(Option<FrameId>, usize, Vec<&Edge>)> {
+        let mut requests = self.request_id_index().iter()
+            .filter(|((_, _, id), _)| *id == request_id)
+            .map(|((frame_id, document, _), edge_ids)| (*frame_id, *document, edge_ids.iter().map(|edge_id| &self.edges[edge_id]).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        requests.sort_unstable_by_key(|(frame_id, document, _)| (*frame_id, *document));
+        requests
+    }
+
+    /// Lists every request id in the graph, once per frame context it was used in, as
+    /// `(request_id, frame, document, resource_url, request_type, status)`. The URL and type are
+    /// taken from the first `RequestStart` edge, so a redirected request is listed under its
+    /// original URL. The status is that of the `RequestComplete` or `RequestError` edge, or `None`
+    /// if the request never finished. Requests are sorted by frame, starting with the root frame,
+    /// then by document and request id.
+    pub fn all_request_ids(&self) -> Vec<RequestIdSummary> {
+        let mut requests = self.request_id_index().iter().filter_map(|((frame_id, document, request_id), edge_ids)| {
+            let edges = edge_ids.iter().map(|edge_id| &self.edges[edge_id]).collect::<Vec<_>>();
+            let (start, request) = edges.iter().find_map(|edge| Some((*edge, edge.as_request_start()?)))?;
+            let url = self.target_node(start).as_resource()?.to_string();
+            let status = edges.iter().find_map(|edge| edge.as_request_complete().map(|complete| complete.status.to_string()))
+                .or_else(|| edges.iter().find_map(|edge| edge.as_request_error().map(|error| error.status.to_string())));
+            Some((*request_id, *frame_id, *document, url, request.request_type.clone(), status))
+        }).collect::<Vec<_>>();
+        requests.sort_unstable_by_key(|(request_id, frame_id, document, ..)| (*frame_id, *document, *request_id));
+        requests
+    }
+
+    fn request_id_index(&self) -> &HashMap<RequestKey, Vec<EdgeId>> {
+        self.request_id_index.get_or_init(|| {
+            let mut index = HashMap::<_, Vec<EdgeId>>::new();
+            self.edges.values().for_each(|edge| if let Some(request_id) = edge.request_id() {
+                index.entry((edge.id.get_frame_id(), edge.id.get_document(), request_id)).or_default().push(edge.id);
+            });
+            index.values_mut().for_each(|edge_ids| edge_ids.sort_unstable());
+            index
+        })
+    }
+
+    /// Returns the DOM root, HTML element, frame owner, or text node with the given Blink node id,
+    /// from the same frame context as `context`. Returns `None` if there is no such node, or if
+    /// the id is ambiguous within the frame context.
+    ///
+    /// The lookup is indexed, with the index built on the first call.
+    pub fn html_node_by_blink_id<C: HasFrameId>(&self, context: C, blink_id: HtmlElementId) -> Option<&Node> {
+        let index = self.dom_node_index.get_or_init(|| {
+            let mut index = HashMap::<_, Vec<NodeId>>::new();
+            self.nodes.values().for_each(|node| if let Some(node_id) = node.blink_node_id() {
+                index.entry((node.id.get_frame_id(), node.id.get_document(), node_id)).or_default().push(node.id);
+            });
+            index
+        });
+        match index.get(&(context.get_frame_id(), context.get_document(), blink_id)).map(Vec::as_slice) {
+            Some([node_id]) => self.nodes.get(node_id),
+            _ => None,
         }
     }
 
+    /// Returns every node of the given kind, in id order.
+    ///
+    /// The lookup is indexed, with the index built on the first call.
+    pub fn nodes_of_kind(&self, kind: NodeKind) -> impl Iterator<Item=&Node> {
+        let index = self.node_kind_index.get_or_init(|| {
+            let mut index = HashMap::<_, Vec<NodeId>>::new();
+            self.nodes.values().for_each(|node| index.entry(node.node_type.kind()).or_default().push(node.id));
+            index.values_mut().for_each(|node_ids| node_ids.sort_unstable());
+            index
+        });
+        index.get(&kind).into_iter().flatten().map(move |node_id| &self.nodes[node_id])
+    }
+
+    /// Returns every edge of the given kind, in id order.
+    ///
+    /// The lookup is indexed, with the index built on the first call.
+    pub fn edges_of_kind(&self, kind: EdgeKind) -> impl Iterator<Item=&Edge> {
+        let index = self.edge_kind_index.get_or_init(|| {
+            let mut index = HashMap::<_, Vec<EdgeId>>::new();
+            self.edges.values().for_each(|edge| index.entry(edge.edge_type.kind()).or_default().push(edge.id));
+            index.values_mut().for_each(|edge_ids| edge_ids.sort_unstable());
+            index
+        });
+        index.get(&kind).into_iter().flatten().map(move |edge_id| &self.edges[edge_id])
+    }
+
+    /// Discards indexes derived from the graph's nodes and edges, so that they are rebuilt on the
+    /// next lookup. This must be called after modifying `nodes` or `edges` directly, otherwise
+    /// lookups may return stale results or panic on missing items.
+    pub fn invalidate_indexes(&mut self) {
+        self.request_id_index.take();
+        self.dom_node_index.take();
+        self.node_kind_index.take();
+        self.edge_kind_index.take();
+        self.dom_root_cache.get_mut().unwrap().clear();
+    }
+
+    /// Returns the DOM root previously attributed to the given HTML node or script, if any has
+    /// been resolved since `invalidate_indexes` was last called.
+    pub(crate) fn cached_dom_root(&self, node_id: NodeId) -> Option<Option<&Node>> {
+        let cache = self.dom_root_cache.read().unwrap();
+        cache.get(&node_id).map(|dom_root| dom_root.map(|dom_root| &self.nodes[&dom_root]))
+    }
+
+    /// Records the DOM root attributed to the given HTML node or script.
+    pub(crate) fn cache_dom_root(&self, node_id: NodeId, dom_root: Option<&Node>) {
+        self.dom_root_cache.write().unwrap().insert(node_id, dom_root.map(|dom_root| dom_root.id));
+    }
+
     /// Returns a new edge id that is guaranteed not to collide with an existing id in the graph.
+    /// Ids are allocated atomically, so that a graph can be shared between threads.
     pub(crate) fn new_edge_id(&self) -> EdgeId {
-        let new_id = EdgeId::from(self.next_edge_id.replace_with(|id| *id - 1));
+        let new_id = EdgeId::from(self.next_edge_id.fetch_sub(1, Ordering::Relaxed));
         assert!(!self.edges.contains_key(&new_id));
         new_id
     }
 
+    /// Copies the nodes matching `keep_node` and the edges matching `keep_edge` into a new graph
+    /// with the same descriptor. Edges are only kept if both of their endpoints are. New edge ids
+    /// allocated in the copy will not collide with ids already allocated in this graph.
+    pub(crate) fn subgraph<N: Fn(&Node) -> bool, E: Fn(&Edge) -> bool>(&self, keep_node: N, keep_edge: E) -> PageGraph {
+        let nodes = self.nodes.values()
+            .filter(|node| keep_node(node))
+            .map(|node| (node.id, node.clone()))
+            .collect::<HashMap<_, _>>();
+        let edges = self.edges.values()
+            .filter(|edge| nodes.contains_key(&edge.source) && nodes.contains_key(&edge.target) && keep_edge(edge))
+            .map(|edge| (edge.id, edge.clone()))
+            .collect::<HashMap<_, _>>();
+
+        let mut graph = DiGraphMap::<NodeId, Vec<EdgeId>>::new();
+        nodes.keys().for_each(|node_id| { graph.add_node(*node_id); });
+        let mut sorted_edges = edges.values().collect::<Vec<_>>();
+        sorted_edges.sort_unstable_by_key(|edge| edge.id);
+        sorted_edges.into_iter().for_each(|edge| match graph.edge_weight_mut(edge.source, edge.target) {
+            Some(concurrent_edges) => concurrent_edges.push(edge.id),
+            None => { graph.add_edge(edge.source, edge.target, vec![edge.id]); },
+        });
+
+        let subgraph = PageGraph::new(self.desc.clone(), edges, nodes, graph);
+        subgraph.next_edge_id.store(self.next_edge_id.load(Ordering::Relaxed), Ordering::Relaxed);
+        subgraph
+    }
+
     pub fn source_node<'a>(&'a self, edge: &Edge) -> &'a Node {
         self.nodes.get(&edge.source).unwrap_or_else(|| panic!("Source node for edge {:?} could not be found in the graph", edge))
     }
@@ -91,17 +333,26 @@ impl PageGraph {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde::Serialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
 struct GraphItemId {
     id: usize,
     frame_id: Option<FrameId>,
+    /// Index of the serialized document within the frame, in navigation order. Only non-zero for
+    /// items merged from a frame that navigated after its first document.
+    #[serde(default, skip_serializing_if = "is_first_document")]
+    document: usize,
+}
+
+fn is_first_document(document: &usize) -> bool {
+    *document == 0
 }
 
 impl From<usize> for GraphItemId {
     fn from(v: usize) -> Self {
         Self {
             id: v,
-            frame_id: None
+            frame_id: None,
+            document: 0,
         }
     }
 }
@@ -112,9 +363,14 @@ impl TryFrom<&str> for GraphItemId {
     fn try_from(v: &str) -> Result<Self, Self::Error> {
         if let Some((id, frame_id)) = v.split_once(':') {
             let id = id.parse::<usize>()?;
+            let (frame_id, document) = match frame_id.split_once('.') {
+                Some((frame_id, document)) => (frame_id, document.parse::<usize>()?),
+                None => (frame_id, 0),
+            };
             Ok(GraphItemId {
                 id,
                 frame_id: Some(FrameId::try_from(frame_id)?),
+                document,
             })
         } else {
             let id = v.parse::<usize>()?;
@@ -124,24 +380,47 @@ impl TryFrom<&str> for GraphItemId {
 }
 
 impl GraphItemId {
-    fn copy_for_frame_id(&self, frame_id: &FrameId) -> Self {
+    fn copy_for_frame_document(&self, frame_id: &FrameId, document: usize) -> Self {
         Self {
             id: self.id,
             frame_id: Some(frame_id.clone()),
+            document,
+        }
+    }
+
+    fn fmt_with_prefix(&self, f: &mut std::fmt::Formatter<'_>, prefix: char) -> std::fmt::Result {
+        match self.frame_id {
+            Some(frame_id) if self.document != 0 => write!(f, "{}{}:{}.{}", prefix, self.id, frame_id, self.document),
+            Some(frame_id) => write!(f, "{}{}:{}", prefix, self.id, frame_id),
+            None => write!(f, "{}{}", prefix, self.id),
         }
     }
 }
 
 pub trait HasFrameId {
     fn get_frame_id(&self) -> Option<FrameId>;
+
+    /// Returns the index of the frame document this item belongs to, in navigation order.
+    fn get_document(&self) -> usize {
+        0
+    }
 }
 
+/// A frame id on its own refers to the first document of the frame, where `None` refers to the
+/// root frame.
+impl HasFrameId for Option<FrameId> {
+    fn get_frame_id(&self) -> Option<FrameId> {
+        *self
+    }
+}
+
+/// Items are in the same frame context if they belong to the same document of the same frame.
 pub fn is_same_frame_context<A: HasFrameId, B: HasFrameId>(a: A, b: B) -> bool {
-    a.get_frame_id() == b.get_frame_id()
+    a.get_frame_id() == b.get_frame_id() && a.get_document() == b.get_document()
 }
 
 /// An identifier used to reference a node.
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde::Serialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
 pub struct NodeId(GraphItemId);
 
 impl From<usize> for NodeId {
@@ -152,7 +431,11 @@ impl From<usize> for NodeId {
 
 impl NodeId {
     pub fn copy_for_frame_id(&self, frame_id: &FrameId) -> Self {
-        Self(self.0.copy_for_frame_id(frame_id))
+        Self(self.0.copy_for_frame_document(frame_id, 0))
+    }
+
+    pub fn copy_for_frame_document(&self, frame_id: &FrameId, document: usize) -> Self {
+        Self(self.0.copy_for_frame_document(frame_id, document))
     }
 }
 
@@ -160,15 +443,15 @@ impl HasFrameId for NodeId {
     fn get_frame_id(&self) -> Option<FrameId> {
         self.0.frame_id
     }
+
+    fn get_document(&self) -> usize {
+        self.0.document
+    }
 }
 
 impl std::fmt::Display for NodeId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(frame_id) = self.get_frame_id() {
-            write!(f, "n{}:{}", self.0.id, frame_id)
-        } else {
-            write!(f, "n{}", self.0.id)
-        }
+        self.0.fmt_with_prefix(f, 'n')
     }
 }
 
@@ -185,7 +468,7 @@ impl TryFrom<&str> for NodeId {
 }
 
 /// Downstream requests tree
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct DownstreamRequests {
     pub request_id: usize,
     pub url: String,
@@ -194,14 +477,751 @@ pub struct DownstreamRequests {
     pub children: Vec<DownstreamRequests>,
 }
 
+impl DownstreamRequests {
+    /// Returns this request and every request below it in depth-first order, keeping only the
+    /// first occurrence of any request that appears via several causal paths.
+    pub fn flatten_unique(&self) -> Vec<&DownstreamRequests> {
+        let mut seen = HashSet::new();
+        let mut requests = vec![];
+        let mut to_visit = vec![self];
+        while let Some(request) = to_visit.pop() {
+            if seen.insert((request.node_id, request.request_id)) {
+                requests.push(request);
+                to_visit.extend(request.children.iter().rev());
+            }
+        }
+        requests
+    }
+
+    /// Returns the number of requests in the tree, including this one. Requests appearing more
+    /// than once are counted each time.
+    pub fn count(&self) -> usize {
+        1 + self.children.iter().map(Self::count).sum::<usize>()
+    }
+
+    /// Returns the distinct URLs requested in the tree, in the order returned by
+    /// `flatten_unique`.
+    pub fn urls(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.flatten_unique().into_iter()
+            .map(|request| request.url.as_str())
+            .filter(|url| seen.insert(*url))
+            .collect()
+    }
+}
+
+/// Describes why the tree of downstream requests of an edge could not be built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownstreamError {
+    /// The request with the given edge id caused further requests, but was already nested at
+    /// the maximum depth.
+    MaxDepthExceeded { edge_id: EdgeId, max_depth: usize },
+}
+
+impl std::fmt::Display for DownstreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MaxDepthExceeded { edge_id, max_depth } => write!(f, "downstream requests of {} are nested more than {} levels deep", edge_id, max_depth),
+        }
+    }
+}
+
+impl std::error::Error for DownstreamError {}
+
+/// Explains why a request was made, as the chain of actions that led up to it, similar to the
+/// "Initiator" column of browser developer tools.
+#[derive(serde::Serialize)]
+pub struct Explanation {
+    pub request_id: usize,
+    pub url: String,
+    pub request_type: RequestType,
+    /// Actions from the earliest known cause through to the request itself.
+    pub chain: Vec<InitiatorStep>,
+}
+
+/// How a script came to run, as part of a [`ProvenanceStep`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptOrigin {
+    /// Fetched from the `src` of a script element.
+    Fetched,
+    /// Inline in a script element parsed from the page's HTML.
+    InlineHtml,
+    /// Inline in a script element whose text, or the element itself, was inserted by a script.
+    InjectedText,
+    /// Compiled from a string by another script, e.g. through `eval`, `new Function`, or
+    /// `setTimeout`.
+    Eval,
+    /// Run from an inline event handler attribute, such as `onclick`.
+    InlineHandler,
+    /// Executed in some other way, or never recorded as executed.
+    Unknown,
+}
+
+impl ScriptOrigin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fetched => "fetched",
+            Self::InlineHtml => "inline HTML",
+            Self::InjectedText => "injected text",
+            Self::Eval => "eval",
+            Self::InlineHandler => "inline handler",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// One script in a chain of scripts that led to another script running, as reported by
+/// [`PageGraph::script_provenance`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProvenanceStep {
+    pub script_node_id: String,
+    pub script_url: Option<String>,
+    pub origin: ScriptOrigin,
+    /// The `Execute` or `ExecuteFromAttribute` edge that first ran the script.
+    pub execute_edge_id: Option<String>,
+    /// The edge that supplied the script's code: the `InsertNode` of its text or element, the
+    /// `SetAttribute` of its element's `src` or handler attribute, or, for scripts compiled from
+    /// strings, the `Execute` edge itself.
+    pub cause_edge_id: Option<String>,
+}
+
+/// Describes whether a request was first- or third-party, relative to the document that made it.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PartyInfo {
+    /// Origin of the document that made the request, if it could be determined.
+    pub initiator_origin: Option<String>,
+    /// Origin of the requested URL.
+    pub request_origin: Option<String>,
+    /// Whether the requested URL belongs to a different site (eTLD+1) from the document that made
+    /// it, as determined by [`url_utils::is_third_party`](crate::url_utils::is_third_party).
+    /// Requests whose initiating document is unknown are considered third-party.
+    pub is_third_party: bool,
+    /// URL of the document that made the request, if it could be determined.
+    pub frame_url: Option<String>,
+}
+
+/// A request in the waterfall reported by [`PageGraph::request_waterfall`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WaterfallEntry {
+    pub request_id: usize,
+    /// The merged remote frame the request was made in, or `None` for the root frame.
+    pub frame_id: Option<String>,
+    pub url: String,
+    pub request_type: RequestType,
+    /// The type of resource reported when the request completed.
+    pub resource_type: Option<String>,
+    pub start: Option<Timestamp>,
+    pub complete: Option<Timestamp>,
+    pub error: Option<Timestamp>,
+    /// The status the request completed or failed with.
+    pub status: Option<String>,
+    pub size: Option<u64>,
+    /// The element, script, or parser that started the request.
+    pub initiator_node_id: String,
+    /// The edges from the earliest known cause of the request through to its `RequestStart`
+    /// edge, as in [`Explanation::chain`].
+    pub initiator_chain: Vec<String>,
+}
+
+/// A URL requested while following a request's redirects, as reported by
+/// [`PageGraph::redirect_chain`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RedirectHop {
+    pub url: String,
+    /// The `RequestStart` edge for the URL, or `None` if the hop is only known from the
+    /// `Location` header of the previous response.
+    pub start_edge_id: Option<String>,
+    /// The `RequestResponse`, `RequestComplete`, or `RequestError` edge answering the request.
+    pub response_edge_id: Option<String>,
+    /// The HTTP status code of the response, from its status line.
+    pub status_code: Option<u16>,
+    /// The URL the response redirected to, resolved against this hop's URL.
+    pub location: Option<String>,
+}
+
+/// A single request to a resource, as reported in [`ResourceInfo`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResourceRequest {
+    pub request_id: usize,
+    /// The merged remote frame the request was made in, or `None` for the root frame.
+    pub frame_id: Option<String>,
+    pub request_type: RequestType,
+    pub start_edge_id: String,
+    pub start: Option<Timestamp>,
+    /// The element, script, or parser that started the request.
+    pub initiator_node_id: String,
+    /// The `RequestComplete` or `RequestError` edge that finished the request, if any.
+    pub finish_edge_id: Option<String>,
+    pub finish: Option<Timestamp>,
+    /// The status the request completed or failed with.
+    pub status: Option<String>,
+    /// The type of resource reported when the request completed.
+    pub resource_type: Option<String>,
+    pub response_hash: Option<String>,
+    pub size: Option<u64>,
+}
+
+/// A `ResourceBlock` edge recording that a resource was blocked, as reported in [`ResourceInfo`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResourceBlocker {
+    pub edge_id: String,
+    /// The shield or filter node that blocked the resource.
+    pub node_id: String,
+    pub node_type: String,
+    /// The filter rule responsible, for ad filter nodes.
+    pub rule: Option<String>,
+}
+
+/// Everything recorded about the requests to a single resource, as reported by
+/// [`PageGraph::resource_info`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResourceInfo {
+    pub node_id: String,
+    pub url: String,
+    /// Every request made to the resource, in the order they started.
+    pub requests: Vec<ResourceRequest>,
+    pub blocked_by: Vec<ResourceBlocker>,
+}
+
+impl ResourceInfo {
+    /// Whether any shield or filter recorded blocking the resource.
+    pub fn is_blocked(&self) -> bool {
+        !self.blocked_by.is_empty()
+    }
+}
+
+/// A single action in the chain of actions that led to a request.
+#[derive(serde::Serialize)]
+pub struct InitiatorStep {
+    pub edge_id: String,
+    pub action: String,
+    pub timestamp: Option<Timestamp>,
+    pub actor: NodeSummary,
+    pub target: NodeSummary,
+}
+
+/// Briefly identifies a node for display.
+#[derive(serde::Serialize)]
+pub struct NodeSummary {
+    pub node_id: String,
+    pub node_type: String,
+    pub detail: Option<String>,
+}
+
+impl NodeSummary {
+    pub fn new(node: &Node) -> Self {
+        Self {
+            node_id: format!("{}", node.id),
+            node_type: node.node_type.type_name().to_string(),
+            detail: crate::export::node_detail(&node.node_type),
+        }
+    }
+}
+
+/// An estimate of the user-visible functionality that depends on a resource, to help judge whether
+/// blocking it is safe, as reported by [`PageGraph::breakage_risk`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BreakageReport {
+    pub node_id: String,
+    pub url: String,
+    pub risk: BreakageRisk,
+    /// The scripts that would not have run had the resource been blocked, in id order.
+    pub scripts: Vec<String>,
+    /// The number of requests that would not have been made had the resource been blocked.
+    pub downstream_requests: usize,
+    /// Event listeners registered by the scripts, in edge id order.
+    pub event_listeners: Vec<ListenerRegistration>,
+    /// Elements created by the scripts, in node id order.
+    pub created_elements: Vec<CreatedElement>,
+    /// Actions taken by the scripts on forms and form controls, in edge id order.
+    pub form_interactions: Vec<FormInteraction>,
+}
+
+/// How likely blocking a resource is to break the page, from the functionality that depends on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakageRisk {
+    /// Nothing the user can see or interact with depends on the resource.
+    Low,
+    /// The resource's scripts add elements to a document, or listen for events other than user
+    /// input.
+    Medium,
+    /// The resource's scripts handle user input, or interact with forms.
+    High,
+}
+
+/// An event listener registered by a script.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ListenerRegistration {
+    pub edge_id: String,
+    pub script_node_id: String,
+    /// The node that the listener was registered on.
+    pub target_node_id: String,
+    pub event: String,
+    /// The number of times the listener was dispatched during the recording.
+    pub dispatches: usize,
+}
+
+/// An element created by a script.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CreatedElement {
+    pub node_id: String,
+    pub tag_name: String,
+    pub script_node_id: String,
+    /// Whether the element was part of a document at the end of the recording.
+    pub attached: bool,
+}
+
+/// An action taken by a script on a form, a form control, or an element inside a form.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FormInteraction {
+    pub edge_id: String,
+    pub action: String,
+    pub script_node_id: String,
+    pub node_id: String,
+    pub tag_name: String,
+}
+
+/// Everything a script did during the recording, as reported by [`PageGraph::script_activity`].
+/// Each list is ordered by the id of its first edge.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScriptActivity {
+    pub node_id: String,
+    pub script_id: ScriptId,
+    pub url: Option<String>,
+    pub requests: Vec<ScriptRequest>,
+    /// The DOM nodes created by the script.
+    pub created_nodes: Vec<String>,
+    /// The DOM nodes inserted by the script or whose attributes it set or deleted, by node id.
+    pub modified_nodes: Vec<ActionGroup>,
+    /// The DOM nodes removed or deleted by the script.
+    pub removed_nodes: Vec<String>,
+    pub storage_reads: Vec<StorageAccess>,
+    /// Values set, deleted, or cleared by the script.
+    pub storage_writes: Vec<StorageAccess>,
+    pub event_listeners: Vec<ListenerRegistration>,
+    /// Calls to Web APIs, by method.
+    pub web_api_calls: Vec<ActionGroup>,
+    /// Calls to JavaScript builtins, by method.
+    pub js_builtin_calls: Vec<ActionGroup>,
+    /// The scripts executed by the script.
+    pub child_scripts: Vec<String>,
+}
+
+/// A request started by a script.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScriptRequest {
+    pub request_id: usize,
+    pub edge_id: String,
+    pub url: String,
+    pub request_type: RequestType,
+}
+
+/// Actions that a script took on a single node or method.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ActionGroup {
+    /// The node id or method the actions were taken on.
+    pub target: String,
+    pub count: usize,
+    pub edge_ids: Vec<String>,
+}
+
+/// Accesses that a script made to a single key of a storage area.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StorageAccess {
+    /// The type of storage area, e.g. `local storage` or `cookie jar`.
+    pub storage: String,
+    pub key: String,
+    pub count: usize,
+    pub edge_ids: Vec<String>,
+}
+
+/// An event listener registration, with what became of it, as reported by
+/// [`PageGraph::event_listeners`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EventListenerInfo {
+    /// The `AddEventListener` edge registering the listener.
+    pub edge_id: String,
+    pub event: String,
+    pub event_listener_id: usize,
+    /// The node the listener was registered on, and its tag name if it is an element.
+    pub target_node_id: String,
+    pub target_type: String,
+    pub target_tag_name: Option<String>,
+    /// The node that registered the listener.
+    pub registering_node_id: String,
+    /// The script containing the listener's handler, and its node and URL if it could be found.
+    pub script_id: ScriptId,
+    pub script_node_id: Option<String>,
+    pub script_url: Option<String>,
+    /// The `RemoveEventListener` edge that removed the listener, if it was removed before being
+    /// registered again.
+    pub removal_edge_id: Option<String>,
+    /// The `EventListener` edges of each time the listener fired, in id order.
+    pub dispatch_edge_ids: Vec<String>,
+}
+
+/// A document, frame owner, or remote frame in the tree reported by [`PageGraph::frame_tree`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FrameTreeNode {
+    pub node_id: String,
+    /// The type of the node in the page graph: `DOM root`, `frame owner`, or `remote frame`.
+    pub node_type: String,
+    /// The URL of a document, or the final `src` attribute of a frame owner.
+    pub url: Option<String>,
+    /// The tag name of a frame owner, in lowercase.
+    pub tag_name: Option<String>,
+    /// The id of a remote frame, or of the merged remote frame the node was recorded in.
+    pub frame_id: Option<String>,
+    /// Whether the graph of a remote frame has been merged in. `None` for other nodes.
+    pub merged: Option<bool>,
+    /// The frame owners in a document, or the documents loaded in a frame, in id order.
+    pub children: Vec<FrameTreeNode>,
+}
+
+/// A script run from an inline event handler attribute, as reported by
+/// [`PageGraph::inline_handler_scripts`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InlineHandlerScript {
+    pub script_node_id: String,
+    pub script_id: ScriptId,
+    /// The element owning the handler attribute, and its tag name.
+    pub element_node_id: String,
+    pub element_tag_name: Option<String>,
+    /// The handler attribute, e.g. `onclick`.
+    pub attr_name: String,
+    /// The `ExecuteFromAttribute` edges of each time the handler ran, in id order.
+    pub execution_edge_ids: Vec<String>,
+    /// The `SetAttribute` edges that set the handler before each run, in id order.
+    pub set_attribute_edge_ids: Vec<String>,
+}
+
+/// A binding used by a binding event.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BindingUse {
+    /// The `Binding` edge from the binding event to the binding.
+    pub edge_id: String,
+    pub binding_node_id: String,
+    pub binding: String,
+    pub binding_type: String,
+}
+
+/// A binding event triggered by a script, with the position in the script that triggered it, as
+/// reported by [`PageGraph::binding_events`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BindingEventInfo {
+    /// The `BindingEvent` edge from the script to the binding event.
+    pub edge_id: String,
+    pub binding_event_node_id: String,
+    pub binding_event: String,
+    pub script_node_id: String,
+    pub script_id: Option<ScriptId>,
+    pub script_url: Option<String>,
+    /// The offset in the script's source at which the event was triggered.
+    pub script_position: usize,
+    /// The bindings used by the event, in id order.
+    pub bindings: Vec<BindingUse>,
+}
+
+/// Requests of one type to a resource that matched an adblock filter or exception, as reported by
+/// [`PageGraph::resources_matching_filters_detailed`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FilterMatch {
+    pub url: String,
+    pub node_id: String,
+    pub request_type: RequestType,
+    /// The `RequestStart` edges of every request of this type to the resource, in id order.
+    pub edge_ids: Vec<String>,
+    pub blocking_filter: Option<String>,
+    pub exception_filter: Option<String>,
+    /// Whether the requests would be blocked, because a blocking filter matched and either no
+    /// exception applied or the blocking filter was `$important`.
+    pub blocked: bool,
+}
+
+/// A document that matched an adblock filter or exception, as reported by
+/// [`PageGraph::frames_matching_filters`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FrameMatch {
+    /// The frame owner element embedding the document, or `None` for the page itself.
+    pub frame_owner_node_id: Option<String>,
+    /// The `DomRoot` node of the document, or `None` if the document's URL was taken from the
+    /// frame owner's `src` attribute or the document is the page itself.
+    pub document_node_id: Option<String>,
+    pub url: String,
+    /// The URL of the document embedding the frame, which determines its partiness. For the
+    /// page itself, this is the page's own URL.
+    pub parent_url: String,
+    pub blocking_filter: Option<String>,
+    pub exception_filter: Option<String>,
+    /// Whether the document would be blocked, because a blocking filter matched and either no
+    /// exception applied or the blocking filter was `$important`.
+    pub blocked: bool,
+}
+
+/// Describes whether, and why, an adblock engine would have prevented a particular request.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum BlockOutcome {
+    /// No blocking filter matched the request, or an exception applied to it.
+    Allowed,
+    /// A blocking filter matched the request directly.
+    Blocked {
+        filter: Option<String>,
+    },
+    /// A blocking filter matched the request, but a `$redirect` rule replaced the response with
+    /// one of the engine's resources rather than cancelling it.
+    Redirected {
+        filter: Option<String>,
+        /// The replacement resource, as a `data:` URL.
+        redirect: String,
+    },
+    /// The request would never have been made, because it is downstream of a blocked or
+    /// redirected request.
+    Cascaded {
+        upstream_edge_id: String,
+    },
+}
+
+impl BlockOutcome {
+    /// Whether the resource that was originally requested would not have been loaded. Redirected
+    /// requests are still made, but count as blocked.
+    pub fn is_blocked(&self) -> bool {
+        !matches!(self, Self::Allowed)
+    }
+}
+
+/// A request that a `$redirect` rule would have answered with a replacement resource, as reported
+/// by [`PageGraph::redirected_requests`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RedirectedRequest {
+    pub request_id: usize,
+    pub edge_id: String,
+    pub url: String,
+    pub request_type: RequestType,
+    pub filter: Option<String>,
+    /// The replacement resource, as a `data:` URL.
+    pub redirect: String,
+    /// The edges that still would have occurred, i.e. the completion or error of the request.
+    pub retained_edges: Vec<String>,
+    /// The edges downstream of the request that would not have occurred, because the replacement
+    /// resource has no effects of its own. For instance, a replaced script executes nothing.
+    pub suppressed_edges: Vec<String>,
+    /// The request start edges among `suppressed_edges`.
+    pub suppressed_requests: Vec<String>,
+}
+
+/// A/B classification of a single request against two adblock engines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineAgreement {
+    BlockedByBoth,
+    BlockedOnlyByA,
+    BlockedOnlyByB,
+    BlockedByNeither,
+}
+
+/// The outcome of a single request from the graph, as evaluated by two different engines.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EngineComparison {
+    pub request_id: usize,
+    pub edge_id: String,
+    pub url: String,
+    pub request_type: RequestType,
+    pub classification: EngineAgreement,
+    pub engine_a: BlockOutcome,
+    pub engine_b: BlockOutcome,
+}
+
+/// A violation of one of the structural invariants that the graph algorithms rely on, as reported
+/// by [`PageGraph::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// An edge refers to a node that is not present in the graph.
+    DanglingEdge { edge_id: EdgeId, missing_node_id: NodeId },
+    /// A frame context has no parser, or more than one.
+    ParserCount { frame_id: Option<FrameId>, document: usize, count: usize },
+    /// An `InsertNode` edge names a parent DOM node id that does not exist in its frame context.
+    MissingInsertParent { edge_id: EdgeId, parent: HtmlElementId },
+    /// A request was started, but never completed or errored.
+    UnfinishedRequest { edge_id: EdgeId, request_id: usize },
+    /// A request was completed or errored, but never started.
+    UnstartedRequest { edge_id: EdgeId, request_id: usize },
+    /// A node's timestamp falls outside of the graph's recorded time span.
+    NodeTimestampOutOfRange { node_id: NodeId, timestamp: Timestamp },
+    /// An edge's timestamp falls outside of the graph's recorded time span.
+    EdgeTimestampOutOfRange { edge_id: EdgeId, timestamp: Timestamp },
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DanglingEdge { edge_id, missing_node_id } => write!(f, "edge {} refers to missing node {}", edge_id, missing_node_id),
+            Self::ParserCount { frame_id: Some(frame_id), document: 0, count } => write!(f, "frame {} has {} parsers", frame_id, count),
+            Self::ParserCount { frame_id: Some(frame_id), document, count } => write!(f, "document {} of frame {} has {} parsers", document, frame_id, count),
+            Self::ParserCount { frame_id: None, count, .. } => write!(f, "root frame has {} parsers", count),
+            Self::MissingInsertParent { edge_id, parent } => write!(f, "insertion {} has nonexistent parent {}", edge_id, parent),
+            Self::UnfinishedRequest { edge_id, request_id } => write!(f, "request {} started by {} never finished", request_id, edge_id),
+            Self::UnstartedRequest { edge_id, request_id } => write!(f, "request {} finished by {} was never started", request_id, edge_id),
+            Self::NodeTimestampOutOfRange { node_id, timestamp } => write!(f, "node {} has out-of-range timestamp {}", node_id, timestamp),
+            Self::EdgeTimestampOutOfRange { edge_id, timestamp } => write!(f, "edge {} has out-of-range timestamp {}", edge_id, timestamp),
+        }
+    }
+}
+
+/// Describes why a frame's graph could not be merged into a root graph.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeError {
+    /// Frames can only be merged into the graph of a top-level page.
+    NotRootGraph,
+    /// The graph being merged in is the graph of a top-level page, rather than a frame.
+    NotFrameGraph,
+    /// The root graph has no `RemoteFrame` node for the frame.
+    MissingRemoteFrame(FrameId),
+    /// The frame's graph has no top-level node of the given kind.
+    MissingRoot { kind: &'static str },
+    /// The frame's graph has more than one top-level node of the given kind.
+    MultipleRoots { kind: &'static str, count: usize },
+}
+
+impl std::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotRootGraph => write!(f, "frames can only be merged into a root graph"),
+            Self::NotFrameGraph => write!(f, "the graph being merged is a root graph, not a frame"),
+            Self::MissingRemoteFrame(frame_id) => write!(f, "no remote frame node for frame {}", frame_id),
+            Self::MissingRoot { kind } => write!(f, "frame has no top-level {}", kind),
+            Self::MultipleRoots { kind, count } => write!(f, "frame has {} top-level {} nodes", count, kind),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Describes why the DOM root responsible for a node or edge could not be determined.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributionError {
+    /// The node is not a DOM root, HTML element, text node, or frame owner.
+    NotHtmlNode(NodeId),
+    /// The node was inserted under a parent HTML node id that matches no node, or several.
+    MissingParent { node_id: NodeId, parent: HtmlElementId },
+    /// The node was never inserted into the document, and was not created exactly once.
+    CreationCount { node_id: NodeId, count: usize },
+    /// The edge was taken by a kind of node that cannot be attributed to a DOM root.
+    UnexpectedActor { edge_id: EdgeId, actor: NodeId },
+    /// None of the script's executions could be attributed to a DOM root with a URL.
+    UnattributedScript(NodeId),
+    /// The remote frame was not attached to exactly one frame owner.
+    RemoteFrameAttachments { node_id: NodeId, count: usize },
+    /// DOM roots cannot be determined for edges of this type.
+    UnsupportedEdge(EdgeId),
+    /// The local frame context does not have exactly one top-level DOM root.
+    LocalContextRoots { frame_id: Option<FrameId>, document: usize, count: usize },
+}
+
+impl std::fmt::Display for AttributionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotHtmlNode(node_id) => write!(f, "{} is not an HTML node", node_id),
+            Self::MissingParent { node_id, parent } => write!(f, "no unique HTML parent node with id {} found for {}", parent, node_id),
+            Self::CreationCount { node_id, count } => write!(f, "{} was never inserted, and was created {} times", node_id, count),
+            Self::UnexpectedActor { edge_id, actor } => write!(f, "{} was taken by {}, which cannot be attributed to a DOM root", edge_id, actor),
+            Self::UnattributedScript(node_id) => write!(f, "script {} has no execution attributable to a DOM root", node_id),
+            Self::RemoteFrameAttachments { node_id, count } => write!(f, "remote frame {} has {} incoming cross DOM edges", node_id, count),
+            Self::UnsupportedEdge(edge_id) => write!(f, "DOM roots cannot be determined for {}", edge_id),
+            Self::LocalContextRoots { frame_id, document, count } => {
+                match frame_id {
+                    Some(frame_id) => write!(f, "frame {} document {} has {} local context DOM roots", frame_id, document, count),
+                    None => write!(f, "root frame document {} has {} local context DOM roots", document, count),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttributionError {}
+
 /// A node, representing a side effect of a page load.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct Node {
     pub id: NodeId,
-    pub node_timestamp: isize,
+    pub node_timestamp: Timestamp,
     pub node_type: NodeType,
 }
 
+/// The data recorded for a `Script` node, borrowed from the node.
+#[derive(Debug, Clone, Copy)]
+pub struct ScriptData<'a> {
+    pub script_id: ScriptId,
+    pub script_type: &'a str,
+    pub url: Option<&'a str>,
+    pub source: &'a str,
+}
+
+impl Node {
+    /// Returns the URL of a `Resource` node.
+    pub fn as_resource(&self) -> Option<&str> {
+        match &self.node_type {
+            NodeType::Resource { url } => Some(url),
+            _ => None,
+        }
+    }
+
+    /// Returns the data recorded for a `Script` node.
+    pub fn as_script(&self) -> Option<ScriptData<'_>> {
+        match &self.node_type {
+            NodeType::Script { url, script_type, script_id, source } => Some(ScriptData {
+                script_id: *script_id,
+                script_type,
+                url: url.as_deref(),
+                source,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the name and type of a `Binding` node.
+    pub fn as_binding(&self) -> Option<(&str, &str)> {
+        match &self.node_type {
+            NodeType::Binding { binding, binding_type } => Some((binding, binding_type)),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of a `BindingEvent` node.
+    pub fn as_binding_event(&self) -> Option<&str> {
+        match &self.node_type {
+            NodeType::BindingEvent { binding_event } => Some(binding_event),
+            _ => None,
+        }
+    }
+
+    /// Returns the Blink node id of a `DomRoot`, `HtmlElement`, `FrameOwner`, or `TextNode` node.
+    pub fn blink_node_id(&self) -> Option<HtmlElementId> {
+        match self.node_type {
+            NodeType::DomRoot { node_id, .. } |
+            NodeType::HtmlElement { node_id, .. } |
+            NodeType::FrameOwner { node_id, .. } |
+            NodeType::TextNode { node_id, .. } => Some(node_id),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a serialized argument list, as recorded for `JsCall` edges, into a JSON array.
+fn parse_js_args(args: &str) -> serde_json::Value {
+    let parsed = serde_json::from_str(args).or_else(|_| {
+        let unescaped = html_escape::decode_html_entities(args);
+        serde_json::from_str(&unescaped).map_err(|_| unescaped.into_owned())
+    });
+    match parsed {
+        Ok(serde_json::Value::Array(args)) => serde_json::Value::Array(args),
+        Ok(arg) => serde_json::Value::Array(vec![arg]),
+        Err(args) => serde_json::Value::Array(vec![serde_json::Value::String(args)]),
+    }
+}
+
 /// An identifier used to reference an edge.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde::Serialize)]
 pub struct EdgeId(GraphItemId);
@@ -239,7 +1259,11 @@ impl TryFrom<&str> for EdgeId {
 
 impl EdgeId {
     pub fn copy_for_frame_id(&self, frame_id: &FrameId) -> Self {
-        Self(self.0.copy_for_frame_id(frame_id))
+        Self(self.0.copy_for_frame_document(frame_id, 0))
+    }
+
+    pub fn copy_for_frame_document(&self, frame_id: &FrameId, document: usize) -> Self {
+        Self(self.0.copy_for_frame_document(frame_id, document))
     }
 }
 
@@ -247,15 +1271,15 @@ impl HasFrameId for EdgeId {
     fn get_frame_id(&self) -> Option<FrameId> {
         self.0.frame_id
     }
+
+    fn get_document(&self) -> usize {
+        self.0.document
+    }
 }
 
 impl std::fmt::Display for EdgeId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(frame_id) = self.get_frame_id() {
-            write!(f, "e{}:{}", self.0.id, frame_id)
-        } else {
-            write!(f, "e{}", self.0.id)
-        }
+        self.0.fmt_with_prefix(f, 'e')
     }
 }
 
@@ -263,7 +1287,7 @@ impl std::fmt::Display for EdgeId {
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct Edge {
     pub id: EdgeId,
-    pub edge_timestamp: Option<isize>,
+    pub edge_timestamp: Option<Timestamp>,
     pub edge_type: EdgeType,
     pub source: NodeId,
     pub target: NodeId,
@@ -275,7 +1299,121 @@ impl PartialEq for Edge {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde::Serialize)]
+/// The data recorded for a `RequestStart` edge, borrowed from the edge.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestStartData<'a> {
+    pub request_id: usize,
+    pub request_type: &'a RequestType,
+    pub status: &'a str,
+}
+
+/// The data recorded for a `RequestComplete` edge, borrowed from the edge.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestCompleteData<'a> {
+    pub request_id: usize,
+    pub resource_type: &'a str,
+    pub status: &'a str,
+    pub response_hash: Option<&'a str>,
+    pub headers: &'a str,
+    pub size: Option<u64>,
+}
+
+/// The data recorded for a `RequestError` edge, borrowed from the edge.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestErrorData<'a> {
+    pub request_id: usize,
+    pub status: &'a str,
+    pub headers: &'a str,
+    pub size: Option<u64>,
+}
+
+impl Edge {
+    /// Returns the data recorded for a `RequestStart` edge.
+    pub fn as_request_start(&self) -> Option<RequestStartData<'_>> {
+        match &self.edge_type {
+            EdgeType::RequestStart { request_id, request_type, status } => Some(RequestStartData {
+                request_id: *request_id,
+                request_type,
+                status,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the data recorded for a `RequestComplete` edge.
+    pub fn as_request_complete(&self) -> Option<RequestCompleteData<'_>> {
+        match &self.edge_type {
+            EdgeType::RequestComplete { request_id, resource_type, status, response_hash, headers, size, .. } => Some(RequestCompleteData {
+                request_id: *request_id,
+                resource_type,
+                status,
+                response_hash: response_hash.as_deref(),
+                headers,
+                size: *size,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the data recorded for a `RequestError` edge.
+    pub fn as_request_error(&self) -> Option<RequestErrorData<'_>> {
+        match &self.edge_type {
+            EdgeType::RequestError { request_id, status, headers, size, .. } => Some(RequestErrorData {
+                request_id: *request_id,
+                status,
+                headers,
+                size: *size,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the script position recorded for a `BindingEvent` edge.
+    pub fn as_binding_event(&self) -> Option<usize> {
+        match self.edge_type {
+            EdgeType::BindingEvent { script_position } => Some(script_position),
+            _ => None,
+        }
+    }
+
+    /// Parses the arguments recorded for a `JsCall` edge into a JSON array, with one element per
+    /// argument. Returns `None` for other edges, and for calls recorded without arguments.
+    ///
+    /// Arguments are usually recorded as a JSON array, but may have had their quotes and other
+    /// special characters escaped as HTML entities. Arguments that still can't be parsed are
+    /// returned as a single string.
+    pub fn parsed_args(&self) -> Option<serde_json::Value> {
+        match &self.edge_type {
+            EdgeType::JsCall { args: Some(args), .. } => Some(parse_js_args(args)),
+            _ => None,
+        }
+    }
+
+    /// Parses the response headers recorded for a `RequestResponse`, `RequestComplete`, or
+    /// `RequestError` edge.
+    pub fn parsed_headers(&self) -> Option<ResponseHeaders<'_>> {
+        match &self.edge_type {
+            EdgeType::RequestResponse { headers, .. } |
+            EdgeType::RequestComplete { headers, .. } |
+            EdgeType::RequestError { headers, .. } => Some(ResponseHeaders::parse(headers)),
+            _ => None,
+        }
+    }
+
+    /// Returns the Blink request id of a `RequestStart`, `RequestResponse`, `RequestComplete`, or
+    /// `RequestError` edge.
+    pub fn request_id(&self) -> Option<usize> {
+        match self.edge_type {
+            EdgeType::RequestStart { request_id, .. } |
+            EdgeType::RequestResponse { request_id, .. } |
+            EdgeType::RequestComplete { request_id, .. } |
+            EdgeType::RequestError { request_id, .. } => Some(request_id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
 pub struct FrameId(u128);
 
 impl TryFrom<&str> for FrameId {
@@ -301,6 +1439,19 @@ impl std::fmt::Debug for FrameId {
     }
 }
 
+#[cfg(test)]
+mod js_args_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_js_args() {
+        assert_eq!(parse_js_args(r#"["2d",{"alpha":false}]"#), serde_json::json!(["2d", {"alpha": false}]));
+        assert_eq!(parse_js_args("[&quot;uid&quot;,16]"), serde_json::json!(["uid", 16]));
+        assert_eq!(parse_js_args("42"), serde_json::json!([42]));
+        assert_eq!(parse_js_args("Cwm fjordbank &amp; glyphs"), serde_json::json!(["Cwm fjordbank & glyphs"]));
+    }
+}
+
 #[cfg(test)]
 mod id_parsing_tests {
     use super::*;
@@ -320,32 +1471,34 @@ mod id_parsing_tests {
 
     #[test]
     fn test_graph_item_id_parsing() {
-        assert_eq!(GraphItemId::try_from("0"), Ok(GraphItemId{ id: 0, frame_id: None }));
-        assert_eq!(GraphItemId::try_from("8"), Ok(GraphItemId{ id: 8, frame_id: None }));
-        assert_eq!(GraphItemId::try_from("200"), Ok(GraphItemId{ id: 200, frame_id: None }));
-        assert_eq!(GraphItemId::try_from("103810150"), Ok(GraphItemId{ id: 103810150, frame_id: None }));
-        assert_eq!(GraphItemId::try_from("0:00000000000000000000000000000000"), Ok(GraphItemId{ id: 0, frame_id: Some(FrameId(0)) }));
-        assert_eq!(GraphItemId::try_from("8:00000000000000000000000000000001"), Ok(GraphItemId{ id: 8, frame_id: Some(FrameId(1)) }));
-        assert_eq!(GraphItemId::try_from("200:0000000000000000000000000000000f"), Ok(GraphItemId{ id: 200, frame_id: Some(FrameId(15)) }));
-        assert_eq!(GraphItemId::try_from("103810150:FfFFFFFfFffFFFfFFFFfffFFFfFFFfff"), Ok(GraphItemId{ id: 103810150, frame_id: Some(FrameId(u128::MAX)) }));
+        assert_eq!(GraphItemId::try_from("0"), Ok(GraphItemId{ id: 0, frame_id: None, document: 0 }));
+        assert_eq!(GraphItemId::try_from("8"), Ok(GraphItemId{ id: 8, frame_id: None, document: 0 }));
+        assert_eq!(GraphItemId::try_from("200"), Ok(GraphItemId{ id: 200, frame_id: None, document: 0 }));
+        assert_eq!(GraphItemId::try_from("103810150"), Ok(GraphItemId{ id: 103810150, frame_id: None, document: 0 }));
+        assert_eq!(GraphItemId::try_from("0:00000000000000000000000000000000"), Ok(GraphItemId{ id: 0, frame_id: Some(FrameId(0)), document: 0 }));
+        assert_eq!(GraphItemId::try_from("8:00000000000000000000000000000001"), Ok(GraphItemId{ id: 8, frame_id: Some(FrameId(1)), document: 0 }));
+        assert_eq!(GraphItemId::try_from("200:0000000000000000000000000000000f"), Ok(GraphItemId{ id: 200, frame_id: Some(FrameId(15)), document: 0 }));
+        assert_eq!(GraphItemId::try_from("103810150:FfFFFFFfFffFFFfFFFFfffFFFfFFFfff"), Ok(GraphItemId{ id: 103810150, frame_id: Some(FrameId(u128::MAX)), document: 0 }));
+        assert_eq!(GraphItemId::try_from("8:00000000000000000000000000000001.2"), Ok(GraphItemId{ id: 8, frame_id: Some(FrameId(1)), document: 2 }));
 
         assert_eq!(GraphItemId::try_from("38 : 00000000000000000000000000000000"), Err(ParseIdError::ParseIntError));
         assert_eq!(GraphItemId::try_from("f8:00000000000000000000000000000000"), Err(ParseIdError::ParseIntError));
         assert_eq!(GraphItemId::try_from(":00000000000000000000000000000000"), Err(ParseIdError::ParseIntError));
         assert_eq!(GraphItemId::try_from("0:0000000000000000000000000000000"), Err(ParseIdError::FrameIdLength));
         assert_eq!(GraphItemId::try_from("0:000000000000000000000000000000000"), Err(ParseIdError::FrameIdLength));
+        assert_eq!(GraphItemId::try_from("0:00000000000000000000000000000000."), Err(ParseIdError::ParseIntError));
     }
 
     #[test]
     fn test_edge_id_parsing() {
-        assert_eq!(EdgeId::try_from("e0"), Ok(EdgeId(GraphItemId{ id: 0, frame_id: None })));
-        assert_eq!(EdgeId::try_from("e8"), Ok(EdgeId(GraphItemId{ id: 8, frame_id: None })));
-        assert_eq!(EdgeId::try_from("e200"), Ok(EdgeId(GraphItemId{ id: 200, frame_id: None })));
-        assert_eq!(EdgeId::try_from("e103810150"), Ok(EdgeId(GraphItemId{ id: 103810150, frame_id: None })));
-        assert_eq!(EdgeId::try_from("e0:00000000000000000000000000000000"), Ok(EdgeId(GraphItemId{ id: 0, frame_id: Some(FrameId(0)) })));
-        assert_eq!(EdgeId::try_from("e8:00000000000000000000000000000001"), Ok(EdgeId(GraphItemId{ id: 8, frame_id: Some(FrameId(1)) })));
-        assert_eq!(EdgeId::try_from("e200:0000000000000000000000000000000f"), Ok(EdgeId(GraphItemId{ id: 200, frame_id: Some(FrameId(15)) })));
-        assert_eq!(EdgeId::try_from("e103810150:FfFFFFFfFffFFFfFFFFfffFFFfFFFfff"), Ok(EdgeId(GraphItemId{ id: 103810150, frame_id: Some(FrameId(u128::MAX)) })));
+        assert_eq!(EdgeId::try_from("e0"), Ok(EdgeId(GraphItemId{ id: 0, frame_id: None, document: 0 })));
+        assert_eq!(EdgeId::try_from("e8"), Ok(EdgeId(GraphItemId{ id: 8, frame_id: None, document: 0 })));
+        assert_eq!(EdgeId::try_from("e200"), Ok(EdgeId(GraphItemId{ id: 200, frame_id: None, document: 0 })));
+        assert_eq!(EdgeId::try_from("e103810150"), Ok(EdgeId(GraphItemId{ id: 103810150, frame_id: None, document: 0 })));
+        assert_eq!(EdgeId::try_from("e0:00000000000000000000000000000000"), Ok(EdgeId(GraphItemId{ id: 0, frame_id: Some(FrameId(0)), document: 0 })));
+        assert_eq!(EdgeId::try_from("e8:00000000000000000000000000000001"), Ok(EdgeId(GraphItemId{ id: 8, frame_id: Some(FrameId(1)), document: 0 })));
+        assert_eq!(EdgeId::try_from("e200:0000000000000000000000000000000f"), Ok(EdgeId(GraphItemId{ id: 200, frame_id: Some(FrameId(15)), document: 0 })));
+        assert_eq!(EdgeId::try_from("e103810150:FfFFFFFfFffFFFfFFFFfffFFFfFFFfff"), Ok(EdgeId(GraphItemId{ id: 103810150, frame_id: Some(FrameId(u128::MAX)), document: 0 })));
 
         assert_eq!(EdgeId::try_from("n0"), Err(ParseIdError::MissingPrefix));
         assert_eq!(EdgeId::try_from("8"), Err(ParseIdError::MissingPrefix));
@@ -365,14 +1518,14 @@ mod id_parsing_tests {
 
     #[test]
     fn test_node_id_parsing() {
-        assert_eq!(NodeId::try_from("n0"), Ok(NodeId(GraphItemId{ id: 0, frame_id: None })));
-        assert_eq!(NodeId::try_from("n8"), Ok(NodeId(GraphItemId{ id: 8, frame_id: None })));
-        assert_eq!(NodeId::try_from("n200"), Ok(NodeId(GraphItemId{ id: 200, frame_id: None })));
-        assert_eq!(NodeId::try_from("n103810150"), Ok(NodeId(GraphItemId{ id: 103810150, frame_id: None })));
-        assert_eq!(NodeId::try_from("n0:00000000000000000000000000000000"), Ok(NodeId(GraphItemId{ id: 0, frame_id: Some(FrameId(0)) })));
-        assert_eq!(NodeId::try_from("n8:00000000000000000000000000000001"), Ok(NodeId(GraphItemId{ id: 8, frame_id: Some(FrameId(1)) })));
-        assert_eq!(NodeId::try_from("n200:0000000000000000000000000000000f"), Ok(NodeId(GraphItemId{ id: 200, frame_id: Some(FrameId(15)) })));
-        assert_eq!(NodeId::try_from("n103810150:FfFFFFFfFffFFFfFFFFfffFFFfFFFfff"), Ok(NodeId(GraphItemId{ id: 103810150, frame_id: Some(FrameId(u128::MAX)) })));
+        assert_eq!(NodeId::try_from("n0"), Ok(NodeId(GraphItemId{ id: 0, frame_id: None, document: 0 })));
+        assert_eq!(NodeId::try_from("n8"), Ok(NodeId(GraphItemId{ id: 8, frame_id: None, document: 0 })));
+        assert_eq!(NodeId::try_from("n200"), Ok(NodeId(GraphItemId{ id: 200, frame_id: None, document: 0 })));
+        assert_eq!(NodeId::try_from("n103810150"), Ok(NodeId(GraphItemId{ id: 103810150, frame_id: None, document: 0 })));
+        assert_eq!(NodeId::try_from("n0:00000000000000000000000000000000"), Ok(NodeId(GraphItemId{ id: 0, frame_id: Some(FrameId(0)), document: 0 })));
+        assert_eq!(NodeId::try_from("n8:00000000000000000000000000000001"), Ok(NodeId(GraphItemId{ id: 8, frame_id: Some(FrameId(1)), document: 0 })));
+        assert_eq!(NodeId::try_from("n200:0000000000000000000000000000000f"), Ok(NodeId(GraphItemId{ id: 200, frame_id: Some(FrameId(15)), document: 0 })));
+        assert_eq!(NodeId::try_from("n103810150:FfFFFFFfFffFFFfFFFFfffFFFfFFFfff"), Ok(NodeId(GraphItemId{ id: 103810150, frame_id: Some(FrameId(u128::MAX)), document: 0 })));
 
         assert_eq!(NodeId::try_from("e0"), Err(ParseIdError::MissingPrefix));
         assert_eq!(NodeId::try_from("8"), Err(ParseIdError::MissingPrefix));
@@ -409,5 +1562,83 @@ mod id_parsing_tests {
         test_str("n200:0000000000000000000000000000000F");
         test_str("n103810150:FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF");
         test_str("n99999:0123456789ABCDEF0123456789ABCDEF");
+        test_str("n8:00000000000000000000000000000001.1");
+    }
+}
+
+#[cfg(test)]
+mod index_tests {
+    use std::convert::TryFrom;
+
+    use crate::graph::{Edge, FrameId, HasFrameId};
+    use crate::types::EdgeKind;
+
+    #[test]
+    fn indexes_follow_modifications() {
+        let mut graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/a.js</data></node>
+<edge id="e3" source="n1" target="n2"><data key="d16">request start</data><data key="d17">3</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e4" source="n2" target="n1"><data key="d16">request complete</data><data key="d17">4</data><data key="d18">2</data><data key="d28">1</data><data key="d29">script</data><data key="d30">complete</data><data key="d31"></data><data key="d32"></data><data key="d33">10</data></edge>"#);
+        assert_eq!(graph.edges_for_request_id(1, None).len(), 2);
+        assert_eq!(graph.edges_of_kind(EdgeKind::RequestComplete).count(), 1);
+
+        let complete = graph.edges_of_kind(EdgeKind::RequestComplete).next().unwrap().id;
+        let complete = graph.edges.remove(&complete).unwrap();
+        graph.graph.edge_weight_mut(complete.source, complete.target).unwrap().retain(|edge_id| *edge_id != complete.id);
+        graph.invalidate_indexes();
+
+        assert_eq!(graph.edges_for_request_id(1, None).len(), 1);
+        assert_eq!(graph.edges_of_kind(EdgeKind::RequestComplete).count(), 0);
+    }
+
+    #[test]
+    fn requests_are_indexed_per_document() {
+        let mut graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">remote frame</data><data key="d1">2</data><data key="d2">0</data><data key="d34">0000000000000000000000000000000B</data></node>"#);
+        let frame_document = |url: &str| crate::test_util::parse_with_header(
+            &crate::test_util::HEADER.replace("<is_root>true</is_root>", "<is_root>false</is_root>"),
+            &format!(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">DOM root</data><data key="d1">2</data><data key="d2">0</data><data key="d4">HTML</data><data key="d5">false</data><data key="d6">1</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">{}</data></node>
+<edge id="e4" source="n1" target="n3"><data key="d16">request start</data><data key="d17">4</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>"#, url));
+        let frame_id = FrameId::try_from("0000000000000000000000000000000B").unwrap();
+        graph.merge_frame_document(frame_document("https://a.com/a.js"), &frame_id, 0).unwrap();
+        graph.merge_frame_document(frame_document("https://b.com/b.js"), &frame_id, 1).unwrap();
+
+        let url = |edges: Vec<&Edge>| edges.iter().map(|edge| graph.target_node(edge).as_resource().unwrap().to_string()).collect::<Vec<_>>();
+        assert_eq!(url(graph.edges_for_request_id(1, Some(frame_id))), ["https://a.com/a.js"]);
+        let second = graph.edges.keys().find(|edge_id| edge_id.get_document() == 1).copied().unwrap();
+        assert_eq!(url(graph.edges_for_request_id(1, second)), ["https://b.com/b.js"]);
+
+        let documents = graph.requests_by_id_any_frame(1).into_iter().map(|(frame_id, document, _)| (frame_id, document)).collect::<Vec<_>>();
+        assert_eq!(documents, [(Some(frame_id), 0), (Some(frame_id), 1)]);
+        let requests = graph.all_request_ids().into_iter().map(|(_, _, document, url, ..)| (document, url)).collect::<Vec<_>>();
+        assert_eq!(requests, [(0, "https://a.com/a.js".to_string()), (1, "https://b.com/b.js".to_string())]);
+    }
+}
+
+#[cfg(test)]
+mod downstream_requests_tests {
+    use super::*;
+
+    #[test]
+    fn test_downstream_requests_helpers() {
+        let json = r#"{"request_id":1,"url":"https://a.com/a.js","request_type":"Script","node_id":{"id":4,"frame_id":null},"children":[
+            {"request_id":2,"url":"https://b.com/b.js","request_type":"Script","node_id":{"id":6,"frame_id":null},"children":[
+                {"request_id":3,"url":"https://c.com/p.gif","request_type":"Image","node_id":{"id":8,"frame_id":null},"children":[]}
+            ]},
+            {"request_id":3,"url":"https://c.com/p.gif","request_type":"Image","node_id":{"id":8,"frame_id":null},"children":[]},
+            {"request_id":3,"url":"https://c.com/p.gif","request_type":"Image","node_id":{"id":9,"frame_id":12,"document":1},"children":[]}
+        ]}"#;
+        let tree: DownstreamRequests = serde_json::from_str(json).unwrap();
+
+        assert_eq!(tree.count(), 5);
+        let flattened = tree.flatten_unique().iter().map(|request| format!("{}", request.node_id)).collect::<Vec<_>>();
+        assert_eq!(flattened, ["n4", "n6", "n8", "n9:0000000000000000000000000000000C.1"]);
+        assert_eq!(tree.urls(), ["https://a.com/a.js", "https://b.com/b.js", "https://c.com/p.gif"]);
+        assert_eq!(tree.children[0].request_type, RequestType::Script);
+
+        let round_trip: DownstreamRequests = serde_json::from_str(&serde_json::to_string(&tree).unwrap()).unwrap();
+        assert_eq!(round_trip.flatten_unique().len(), 4);
     }
 }
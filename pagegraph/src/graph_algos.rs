@@ -1,11 +1,113 @@
-use crate::graph::{PageGraph, Edge, EdgeId, Node, NodeId, FrameId, DownstreamRequests};
-use crate::types::{EdgeType, NodeType};
+use crate::graph::{PageGraph, Edge, EdgeId, Node, NodeId, FrameId, HasFrameId, Timestamp, DownstreamRequests, DownstreamError, BlockOutcome, EngineAgreement, EngineComparison, MergeError, AttributionError, ValidationIssue, PartyInfo, FilterMatch, FrameMatch, FrameTreeNode, RedirectedRequest, Explanation, InitiatorStep, ProvenanceStep, ScriptOrigin, WaterfallEntry, RedirectHop, ResourceInfo, ResourceRequest, ResourceBlocker, NodeSummary, BreakageReport, BreakageRisk, ListenerRegistration, EventListenerInfo, BindingEventInfo, BindingUse, InlineHandlerScript, CreatedElement, FormInteraction, ScriptActivity, ScriptRequest, ActionGroup, StorageAccess};
+use crate::types::{EdgeType, EdgeKind, NodeType, NodeKind, RequestType, ScriptId};
+use crate::url_utils;
+use crate::dom::DomTree;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use petgraph::Direction;
 use adblock::{Engine, request::Request};
+use adblock::resources::{MimeType, Resource, ResourceType};
 
 const CAN_HAVE_SRC: [&str; 9] = ["audio", "embed", "iframe", "img", "input", "script", "source", "track", "video"];
 
+const FORM_TAGS: [&str; 5] = ["button", "form", "input", "select", "textarea"];
+
+/// Events that are only dispatched in response to user input.
+const INPUT_EVENTS: [&str; 16] = [
+    "change", "click", "contextmenu", "dblclick", "input", "keydown", "keypress", "keyup",
+    "mousedown", "mouseup", "paste", "pointerdown", "pointerup", "submit", "touchend", "touchstart",
+];
+
+/// Shield nodes are recorded once per graph, and are shared between frames once merged.
+fn is_shield_singleton(node_type: &NodeType) -> bool {
+    matches!(node_type.kind(),
+        NodeKind::BraveShields |
+        NodeKind::AdsShield |
+        NodeKind::TrackersShield |
+        NodeKind::JavascriptShield |
+        NodeKind::FingerprintingShield |
+        NodeKind::FingerprintingV2Shield)
+}
+
+/// Whether `cause` could have happened before `effect`, assuming so if either is untimed.
+pub(crate) fn happened_before(cause: &Edge, effect: &Edge) -> bool {
+    match (cause.edge_timestamp, effect.edge_timestamp) {
+        (Some(cause_time), Some(effect_time)) => cause_time <= effect_time,
+        _ => true,
+    }
+}
+
+/// Whether an edge of this type from a script may have downstream effects of its own.
+fn is_script_action(edge_type: &EdgeType) -> bool {
+    match edge_type {
+        // A script execution can cause a network request
+        EdgeType::RequestStart { .. } => true,
+        // A script execution can cause another script to be executed
+        EdgeType::Execute {} => true,
+        // A script execution can set attributes on other HTML elements, causing them
+        // to initiate a network request
+        EdgeType::SetAttribute { .. } => true,
+        // A script execution can read and write storage and cookies, affecting itself
+        // and other scripts
+        EdgeType::StorageSet { .. } |
+        EdgeType::DeleteStorage { .. } |
+        EdgeType::ClearStorage { .. } |
+        EdgeType::ReadStorageCall { .. } => true,
+        // A script execution can call Web APIs and JS builtins, and use their results
+        EdgeType::JsCall { .. } => true,
+        // A script execution can register event listeners, causing handlers to run later
+        EdgeType::AddEventListener { .. } => true,
+        // A script execution can trigger binding events, using native or extension bindings
+        EdgeType::BindingEvent { .. } => true,
+        // TODO scripts can create/insert DOM elements, build 3rd party frames...
+        _ => false,
+    }
+}
+
+/// Whether an edge executes a script, either from a script element or from an inline event handler
+/// attribute.
+pub(crate) fn is_execution(edge_type: &EdgeType) -> bool {
+    matches!(edge_type, EdgeType::Execute {} | EdgeType::ExecuteFromAttribute { .. })
+}
+
+/// Whether `middle` happened strictly after `first`, but no later than `last`. Untimed edges are
+/// assumed not to have.
+fn happened_between(middle: &Edge, first: &Edge, last: &Edge) -> bool {
+    match (first.edge_timestamp, middle.edge_timestamp, last.edge_timestamp) {
+        (Some(first_time), Some(middle_time), Some(last_time)) => first_time < middle_time && middle_time <= last_time,
+        _ => false,
+    }
+}
+
+/// Returns the most recent of `causes` that could have happened before `effect`.
+fn latest_before<'a, I: Iterator<Item=&'a Edge>>(causes: I, effect: &Edge) -> Option<&'a Edge> {
+    causes.filter(|cause| happened_before(cause, effect)).max_by_key(|cause| (cause.edge_timestamp, cause.id))
+}
+
+/// Makes an empty stand-in for each resource named by a `$redirect` or `$redirect-rule` option in
+/// the given rules, so that they can be evaluated without the real resources.
+fn placeholder_redirect_resources(rules: &[String]) -> Vec<Resource> {
+    let names = rules.iter()
+        .filter_map(|rule| rule.rsplit_once('$').map(|(_, options)| options))
+        .flat_map(|options| options.split(','))
+        .filter_map(|option| option.strip_prefix("redirect=").or_else(|| option.strip_prefix("redirect-rule=")))
+        // Strip any priority suffix, as in `noop.js:10`
+        .map(|name| match name.rsplit_once(':') {
+            Some((name, priority)) if priority.parse::<i32>().is_ok() => name,
+            _ => name,
+        })
+        .collect::<BTreeSet<_>>();
+    names.into_iter().map(|name| Resource {
+        name: name.to_owned(),
+        aliases: vec![],
+        kind: ResourceType::Mime(MimeType::from_extension(name)),
+        content: String::new(),
+        dependencies: vec![],
+        permission: Default::default(),
+    }).collect()
+}
+
 #[derive(serde::Serialize)]
 pub struct MatchedResource {
     url: String,
@@ -33,60 +135,156 @@ impl PageGraph {
         ).collect()
     }
 
-    /// Inserts the graph for a given frame into this graph, namespacing ids to avoid conflicts.
-    /// The matching `remote frame` node will gain two new outgoing `cross DOM` edges to the `DOM
-    /// root` and `parser` nodes from the frame.
-    pub fn merge_frame(&mut self, frame_graph: PageGraph, frame_id: &FrameId) {
-        assert!(self.desc.is_root);
-        assert!(!frame_graph.desc.is_root);
+    /// Returns the page's frames as a tree, from each top-level document down through the frame
+    /// owners embedded in it to the documents they loaded. Local frames lead straight to their
+    /// documents, while remote frames lead to the documents of their graphs once merged.
+    ///
+    /// Documents without an embedding frame, and frame owners that could not be placed in any
+    /// document, are returned as the roots of the tree, in id order.
+    pub fn frame_tree(&self) -> Vec<FrameTreeNode> {
+        let mut frame_owners = HashMap::<Option<NodeId>, Vec<NodeId>>::new();
+        self.nodes_of_kind(NodeKind::FrameOwner).for_each(|frame_owner| {
+            let dom_root = self.try_dom_root_for_html_node(frame_owner).ok().flatten().map(|dom_root| dom_root.id);
+            frame_owners.entry(dom_root).or_default().push(frame_owner.id);
+        });
 
-        // Find the single `remote frame` node with the specified `frame_id`
-        let matching_remote_frames = self.filter_nodes(|n| matches!(n, NodeType::RemoteFrame { frame_id: node_frame_id } if node_frame_id == frame_id));
-        assert!(matching_remote_frames.len() == 1);
-        let remote_frame = matching_remote_frames[0].id.clone();
+        let dom = self.final_dom();
+        let mut visited = HashSet::new();
+        let mut roots = self.nodes_of_kind(NodeKind::DomRoot)
+            .filter(|dom_root| !self.incoming_edges(dom_root).any(|edge| matches!(edge.edge_type, EdgeType::CrossDom {})))
+            .map(|dom_root| dom_root.id)
+            .chain(frame_owners.get(&None).into_iter().flatten().copied())
+            .collect::<Vec<_>>();
+        roots.sort_unstable();
+        roots.into_iter()
+            .filter_map(|root| match visited.contains(&root) {
+                true => None,
+                false => Some(self.frame_tree_node(root, &frame_owners, &dom, &mut visited)),
+            })
+            .collect()
+    }
 
-        // Find the frame's single "DOM root" node with no incoming "cross DOM" edges
-        let matching_dom_roots: Vec<_> = frame_graph.nodes.values().filter(|node| {
-            if let NodeType::DomRoot { .. } = node.node_type {
-                frame_graph.incoming_edges(node).filter(|edge| {
-                    matches!(edge.edge_type, EdgeType::CrossDom {})
-                }).next().is_none()
-            } else {
-                false
+    fn frame_tree_node(&self, node_id: NodeId, frame_owners: &HashMap<Option<NodeId>, Vec<NodeId>>, dom: &DomTree, visited: &mut HashSet<NodeId>) -> FrameTreeNode {
+        visited.insert(node_id);
+        let node = &self.nodes[&node_id];
+        let cross_dom_children = || self.outgoing_edges(node)
+            .filter(|edge| matches!(edge.edge_type, EdgeType::CrossDom {}))
+            .map(|edge| self.target_node(edge))
+            .filter(|child| matches!(child.node_type, NodeType::DomRoot { .. } | NodeType::RemoteFrame { .. }))
+            .map(|child| child.id)
+            .collect::<Vec<_>>();
+        let (url, tag_name, frame_id, merged, mut children) = match &node.node_type {
+            NodeType::DomRoot { url, .. } => (url.clone(), None, node_id.get_frame_id(), None, frame_owners.get(&Some(node_id)).cloned().unwrap_or_default()),
+            NodeType::FrameOwner { tag_name, .. } => (
+                dom.elements.get(&node_id).and_then(|element| element.attributes.get("src")).cloned(),
+                Some(tag_name.to_ascii_lowercase()),
+                node_id.get_frame_id(),
+                None,
+                cross_dom_children(),
+            ),
+            NodeType::RemoteFrame { frame_id } => {
+                let children = cross_dom_children();
+                (None, None, Some(*frame_id), Some(!children.is_empty()), children)
             }
-        }).collect();
-        assert_eq!(matching_dom_roots.len(), 1, "Wrong number of top-level DOM roots");
-        let dom_root = matching_dom_roots[0].id.clone();
+            _ => (None, None, node_id.get_frame_id(), None, vec![]),
+        };
+        children.sort_unstable();
+        children.retain(|child| !visited.contains(child));
+        FrameTreeNode {
+            node_id: format!("{}", node_id),
+            node_type: node.node_type.type_name().to_string(),
+            url,
+            tag_name,
+            frame_id: frame_id.map(|frame_id| format!("{}", frame_id)),
+            merged,
+            children: children.into_iter().map(|child| self.frame_tree_node(child, frame_owners, dom, visited)).collect(),
+        }
+    }
 
-        // Find the frame's single "parser" node with no incoming "cross DOM" edges
-        let matching_parsers: Vec<_> = frame_graph.nodes.values().filter(|node| {
-            if let NodeType::Parser { .. } = node.node_type {
-                frame_graph.incoming_edges(node).filter(|edge| {
+    /// Merges the separately-recorded graph of a remote frame into this root graph. The frame's
+    /// top-level DOM root and parser are connected to the frame's `RemoteFrame` node with new
+    /// `CrossDom` edges. If several `RemoteFrame` nodes were recorded for the same frame, each of
+    /// them is connected.
+    ///
+    /// This merges the frame's first document. Use `merge_frame_document` for the documents
+    /// recorded after the frame navigated.
+    ///
+    /// No changes are made to the graph if an error is returned.
+    pub fn merge_frame(&mut self, frame_graph: PageGraph, frame_id: &FrameId) -> Result<(), MergeError> {
+        self.merge_frame_document(frame_graph, frame_id, 0)
+    }
+
+    /// Like `merge_frame`, but for the `document`th graph recorded for the frame, counting from
+    /// zero in navigation order. Ids from each document are namespaced separately, so that every
+    /// document of a frame can be merged and attached to the same `RemoteFrame` node.
+    pub fn merge_frame_document(&mut self, frame_graph: PageGraph, frame_id: &FrameId, document: usize) -> Result<(), MergeError> {
+        if !self.desc.is_root {
+            return Err(MergeError::NotRootGraph);
+        }
+        if frame_graph.desc.is_root {
+            return Err(MergeError::NotFrameGraph);
+        }
+
+        // Find every `remote frame` node with the specified `frame_id`
+        let remote_frames = self.nodes_of_kind(NodeKind::RemoteFrame)
+            .filter(|node| matches!(node.node_type, NodeType::RemoteFrame { frame_id: node_frame_id } if node_frame_id == *frame_id))
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        if remote_frames.is_empty() {
+            return Err(MergeError::MissingRemoteFrame(*frame_id));
+        }
+
+        // Find the frame's single top-level node of the given type, with no incoming "cross DOM" edges
+        let top_level_node = |kind: &'static str, is_kind: fn(&NodeType) -> bool| {
+            let matching: Vec<_> = frame_graph.nodes.values().filter(|node| {
+                is_kind(&node.node_type) && !frame_graph.incoming_edges(node).any(|edge| {
                     matches!(edge.edge_type, EdgeType::CrossDom {})
-                }).next().is_none()
-            } else {
-                false
+                })
+            }).collect();
+            match matching.len() {
+                0 => Err(MergeError::MissingRoot { kind }),
+                1 => Ok(matching[0].id),
+                count => Err(MergeError::MultipleRoots { kind, count }),
             }
-        }).collect();
-        assert_eq!(matching_parsers.len(), 1, "Wrong number of top-level parsers");
-        let parser = matching_parsers[0].id.clone();
+        };
+        let dom_root = top_level_node("DOM root", |node_type| matches!(node_type, NodeType::DomRoot { .. }))?;
+        let parser = top_level_node("parser", |node_type| matches!(node_type, NodeType::Parser {}))?;
+
+        // Each graph records its own singleton "Brave Shields" and per-shield nodes. Map the
+        // frame's singletons onto the merged graph's, so that shield-related edges from every
+        // frame remain attached to the same nodes. Singletons not yet present are copied as usual,
+        // and will be shared by subsequently merged frames.
+        let unified_nodes = frame_graph.nodes.values()
+            .filter(|node| is_shield_singleton(&node.node_type))
+            .filter_map(|frame_node| {
+                let mut root_nodes = self.nodes.values()
+                    .filter(|root_node| root_node.node_type == frame_node.node_type);
+                match (root_nodes.next(), root_nodes.next()) {
+                    (Some(root_node), None) => Some((frame_node.id, root_node.id)),
+                    _ => None,
+                }
+            })
+            .collect::<HashMap<_, _>>();
+        let new_id_for = |node_id: NodeId| unified_nodes.get(&node_id).copied().unwrap_or_else(|| node_id.copy_for_frame_document(frame_id, document));
 
-        // TODO "Brave Shields" node should be merged as well
+        // The frame graph is consumed, so that its nodes and edges can be moved into this graph
+        // rather than cloned
+        let PageGraph { nodes: frame_nodes, edges: mut frame_edges, graph: frame_structure, .. } = frame_graph;
 
         // For each node in the frame graph
-        frame_graph.graph.nodes().for_each(|node_id| {
-            // create a new id for the node by prepending the frame id
-            let new_node_id = node_id.copy_for_frame_id(frame_id);
-            let mut new_node = frame_graph.nodes.get(&node_id).unwrap().clone();
+        frame_nodes.into_iter().filter(|(node_id, _)| !unified_nodes.contains_key(node_id)).for_each(|(node_id, mut new_node)| {
+            // re-key the node by prepending the frame id
+            let new_node_id = node_id.copy_for_frame_document(frame_id, document);
             new_node.id = new_node_id;
 
-            // insert a copy of the node, with the new id, into the root graph
+            // move the node, with the new id, into the root graph
             self.graph.add_node(new_node.id);
             self.nodes.insert(new_node.id, new_node);
+        });
 
-            // if the original node has the previously discovered "DOM root" or "parser" id:
-            if node_id == dom_root || node_id == parser {
-                // insert a new edge from "remote frame" to the new node
+        // insert a new edge from each "remote frame" to the frame's "parser" and "DOM root"
+        for new_node_id in [parser, dom_root].iter().map(|node_id| node_id.copy_for_frame_document(frame_id, document)) {
+            for remote_frame in remote_frames.iter().copied() {
                 let new_edge = Edge {
                     id: self.new_edge_id(),
                     edge_timestamp: None,
@@ -100,207 +298,285 @@ impl PageGraph {
                 }
                 self.edges.insert(new_edge.id, new_edge);
             }
-        });
+        }
 
         // For each edge in the frame graph
-        frame_graph.graph.all_edges().for_each(|(from_node_id, to_node_id, edge_ids)| {
-            // create a new id for the source, target, and edge by prepending the frame id
-            let new_from_node_id = from_node_id.copy_for_frame_id(frame_id);
-            let new_to_node_id = to_node_id.copy_for_frame_id(frame_id);
+        frame_structure.all_edges().for_each(|(from_node_id, to_node_id, edge_ids)| {
+            // edges between unified shield nodes are already present in the root graph
+            if unified_nodes.contains_key(&from_node_id) && unified_nodes.contains_key(&to_node_id) {
+                return;
+            }
+
+            // re-key the source, target, and edge by prepending the frame id
+            let new_from_node_id = new_id_for(from_node_id);
+            let new_to_node_id = new_id_for(to_node_id);
 
-            // insert a copy of the edge, with the new ids, into the root graph
+            // move the edge, with the new ids, into the root graph
             let new_edge_ids = edge_ids.iter().map(|edge_id| {
-                let mut new_edge = frame_graph.edges.get(edge_id).unwrap().clone();
-                let new_edge_id = edge_id.copy_for_frame_id(frame_id);
+                let mut new_edge = frame_edges.remove(edge_id).unwrap();
+                let new_edge_id = edge_id.copy_for_frame_document(frame_id, document);
                 new_edge.id = new_edge_id;
                 new_edge.source = new_from_node_id;
                 new_edge.target = new_to_node_id;
                 self.edges.insert(new_edge.id, new_edge);
                 new_edge_id
             }).collect::<Vec<_>>();
-            self.graph.add_edge(new_from_node_id, new_to_node_id, new_edge_ids);
+            match self.graph.edge_weight_mut(new_from_node_id, new_to_node_id) {
+                Some(edges) => edges.extend(new_edge_ids),
+                None => { self.graph.add_edge(new_from_node_id, new_to_node_id, new_edge_ids); },
+            }
         });
+        self.invalidate_indexes();
+
+        Ok(())
     }
 
     pub fn filter_edges<F: Fn(&EdgeType) -> bool>(&self, f: F) -> Vec<&Edge> {
-        self.edges.values().filter(|edge| {
-            f(&edge.edge_type)
-        }).collect()
+        self.iter_edges_where(f).collect()
     }
 
     pub fn filter_nodes<F: Fn(&NodeType) -> bool>(&self, f: F) -> Vec<&Node> {
-        self.nodes.values().filter(|node| {
+        self.iter_nodes_where(f).collect()
+    }
+
+    /// Lazily iterates over the edges whose type matches `f`, in arbitrary order.
+    pub fn iter_edges_where<F: Fn(&EdgeType) -> bool>(&self, f: F) -> impl Iterator<Item=&Edge> {
+        self.edges.values().filter(move |edge| {
+            f(&edge.edge_type)
+        })
+    }
+
+    /// Lazily iterates over the nodes whose type matches `f`, in arbitrary order.
+    pub fn iter_nodes_where<F: Fn(&NodeType) -> bool>(&self, f: F) -> impl Iterator<Item=&Node> {
+        self.nodes.values().filter(move |node| {
             f(&node.node_type)
-        }).collect()
+        })
+    }
+
+    /// Returns a copy of the graph as it stood at `timestamp`, containing only the nodes and edges
+    /// recorded at or before then. Untimed edges are kept if both of their endpoints are.
+    pub fn slice_until(&self, timestamp: Timestamp) -> PageGraph {
+        self.subgraph(
+            |node| node.node_timestamp <= timestamp,
+            |edge| edge.edge_timestamp.into_iter().all(|edge_timestamp| edge_timestamp <= timestamp))
+    }
+
+    /// Returns a copy of the graph containing only the nodes and edges recorded between `start`
+    /// and `end`, inclusive. Untimed edges are kept if both of their endpoints are.
+    pub fn slice_between(&self, start: Timestamp, end: Timestamp) -> PageGraph {
+        let in_range = |timestamp: Timestamp| (start..=end).contains(&timestamp);
+        self.subgraph(
+            |node| in_range(node.node_timestamp),
+            |edge| edge.edge_timestamp.into_iter().all(in_range))
+    }
+
+    /// Returns a copy of the nodes and edges recorded by a single frame, including its local
+    /// parser and DOM roots, for every document the frame navigated to. `None` refers to the root
+    /// frame. Shield nodes shared with other frames are included if the frame's edges reach them,
+    /// but the `CrossDom` edges linking frames together are not.
+    ///
+    /// Ids are left as they were in the merged graph. For a remote frame, the descriptor is updated
+    /// to describe the frame and the URL of its first document, if one was recorded.
+    pub fn frame_subgraph(&self, frame: Option<FrameId>) -> PageGraph {
+        // The `CrossDom` edges added by merging are given ids in the root frame
+        let in_frame = |edge: &Edge| edge.id.get_frame_id() == frame && !(
+            matches!(edge.edge_type, EdgeType::CrossDom {}) && edge.target.get_frame_id() != frame);
+        // Shield nodes are shared between frames once merged, and keep the id of the frame that
+        // first recorded them
+        let shared_nodes = self.edges.values()
+            .filter(|edge| in_frame(edge))
+            .flat_map(|edge| [edge.source, edge.target])
+            .filter(|node_id| node_id.get_frame_id() != frame)
+            .collect::<HashSet<_>>();
+        let mut subgraph = self.subgraph(
+            |node| node.id.get_frame_id() == frame || shared_nodes.contains(&node.id),
+            in_frame);
+
+        if let Some(frame_id) = frame {
+            let url = subgraph.nodes.values()
+                .find(|node| node.id.get_frame_id() == frame && node.id.get_document() == 0)
+                .and_then(|node| subgraph.try_local_context_root_for_id(node.id).ok())
+                .and_then(|dom_root| match &dom_root.node_type {
+                    NodeType::DomRoot { url, .. } => url.clone(),
+                    _ => None,
+                });
+            subgraph.desc.frame_id = frame_id;
+            subgraph.desc.is_root = false;
+            subgraph.desc.url = url.unwrap_or_default();
+        }
+
+        subgraph
     }
 
     pub fn dom_root_for_html_node<'a>(&'a self, node: &'a Node) -> Option<&'a Node> {
+        self.try_dom_root_for_html_node(node).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Returns the DOM root that the given HTML node belongs to, or an error describing the graph
+    /// irregularity that prevented it from being found.
+    ///
+    /// Results are memoized per node until `invalidate_indexes` is called.
+    pub fn try_dom_root_for_html_node<'a>(&'a self, node: &'a Node) -> Result<Option<&'a Node>, AttributionError> {
+        if let Some(dom_root) = self.cached_dom_root(node.id) {
+            return Ok(dom_root);
+        }
+        let dom_root = self.resolve_dom_root_for_html_node(node)?;
+        self.cache_dom_root(node.id, dom_root);
+        Ok(dom_root)
+    }
+
+    fn resolve_dom_root_for_html_node<'a>(&'a self, node: &'a Node) -> Result<Option<&'a Node>, AttributionError> {
         match node.node_type {
-            NodeType::DomRoot { .. } => return Some(node),
+            NodeType::DomRoot { .. } => Ok(Some(node)),
             NodeType::HtmlElement { .. } | NodeType::TextNode { .. } | NodeType::FrameOwner { .. } => {
-                let mut parent_ids = self.incoming_edges(node).filter_map(|edge| if let EdgeType::InsertNode { parent, .. } = edge.edge_type { Some(parent) } else { None });
+                let parent_ids = self.incoming_edges(node).filter_map(|edge| if let EdgeType::InsertNode { parent, .. } = edge.edge_type { Some(parent) } else { None });
                 // Look for all parent elements, as per parent id from InsertNode
-                while let Some(parent_id) = parent_ids.next() {
-                    let parent_node = {
-                        let mut parent_nodes = self.nodes.values().filter(|parent_node|
-                            crate::graph::is_same_frame_context(node.id, parent_node.id) &&
-                            matches!(parent_node.node_type, NodeType::HtmlElement { node_id, .. } | NodeType::DomRoot { node_id, .. } | NodeType::FrameOwner { node_id, .. } if node_id == parent_id)
-                        );
-                        let parent_node = parent_nodes.next().expect(&format!("No HTML parent node with id {} found for {:?}", parent_id, node));
-                        assert!(parent_nodes.next().is_none(), "Multiple HTML parent nodes with id {} found", parent_id);
-                        parent_node
-                    };
-
-                    if let Some(dom_root) = self.dom_root_for_html_node(parent_node) {
-                        return Some(dom_root);
+                for parent_id in parent_ids {
+                    let parent_node = self.html_node_by_blink_id(node.id, parent_id)
+                        .ok_or(AttributionError::MissingParent { node_id: node.id, parent: parent_id })?;
+
+                    if let Some(dom_root) = self.try_dom_root_for_html_node(parent_node)? {
+                        return Ok(Some(dom_root));
                     }
                 }
 
                 // If the element was never inserted, it may have been created by a script.
-                let creator = {
-                    let mut creators = self.incoming_edges(node).filter_map(|edge| if let EdgeType::CreateNode { .. } = edge.edge_type { Some(self.source_node(edge)) } else { None });
-                    let creator = creators.next().unwrap();
-                    assert!(creators.next().is_none(), "HTML element was created multiple times");
-                    creator
+                let creations = self.incoming_edges(node)
+                    .filter(|edge| matches!(edge.edge_type, EdgeType::CreateNode { .. }))
+                    .collect::<Vec<_>>();
+                let creation = match creations[..] {
+                    [creation] => creation,
+                    _ => return Err(AttributionError::CreationCount { node_id: node.id, count: creations.len() }),
                 };
+                let creator = self.source_node(creation);
 
                 match creator.node_type {
-                    NodeType::Script { .. } => {
-                        let dom_roots = self.incoming_edges(creator)
-                            .filter(|edge| matches!(edge.edge_type, EdgeType::Execute {}))
-                            .map(|edge| self.dom_root_for_edge(edge).unwrap())
-                            .collect::<Vec<_>>();
-
-                        if dom_roots.len() == 0 {
-                            // There's some complicated cases with module scripts that can sometimes
-                            // lead to infinite loops, so just find the local context URL and use that.
-                            // TODO improve
-                            return Some(self.local_context_root_for_id(creator.id));
-                        }
-                        assert!(dom_roots.len() >= 1, "Script had no executor (a) {:?}", creator);
-                        // Sometimes the same script src is executed from multiple DOM roots in the
-                        // same local frame context
-                        // In practice, we only use the URL here to check the partiness of a
-                        // request, which will be the same for all roots. So we just take the first
-                        // one alphabetically for the sake of determinism.
-                        // TODO improve
-                        let mut all_dom_roots = dom_roots.iter().filter(|root| matches!(&root.node_type, NodeType::DomRoot { url, .. } if url.is_some())).collect::<Vec<_>>();
-                        all_dom_roots.sort_unstable_by_key(|root| match &root.node_type { NodeType::DomRoot { url, .. } => url.to_owned(), _ => unreachable!() });
-
-                        Some(all_dom_roots[0])
-                    }
-                    _ => panic!("HTML element never inserted, but created by {:?}, which is not a script", creator),
+                    NodeType::Script { .. } => self.try_dom_root_for_script(creator, Some(creator.id)),
+                    _ => Err(AttributionError::UnexpectedActor { edge_id: creation.id, actor: creator.id }),
                 }
             }
-            _ => panic!("Supplied node was not an HTML element"),
+            _ => Err(AttributionError::NotHtmlNode(node.id)),
         }
     }
 
     /// Returns the DOM root node(s) according to the frame that the given edge originated from.
     pub fn dom_root_for_edge(&self, edge: &Edge) -> Option<&Node> {
-        match &edge.edge_type {
-            EdgeType::RequestComplete { .. } => {
-                let target = self.target_node(edge);
-                match &target.node_type {
-                    NodeType::HtmlElement { .. } | NodeType::FrameOwner { .. } => Some(self.dom_root_for_html_node(target).unwrap_or_else(|| panic!("could not find DOM root for request initiator element {:?}", target))),
-                    NodeType::Script { .. } => {
-                        // Scripts generally are pointed to by a single Execute edge, but there can
-                        // be more than one for multiple script elements with the same source.
-                        let dom_roots = self.incoming_edges(self.target_node(edge))
-                            .filter(|edge| matches!(edge.edge_type, EdgeType::Execute {}))
-                            .map(|edge| self.dom_root_for_edge(edge).unwrap())
-                            .collect::<Vec<_>>();
-
-                        if dom_roots.len() == 0 {
-                            // There's some complicated cases with module scripts that can sometimes
-                            // lead to infinite loops, so just find the local context URL and use that.
-                            // TODO improve
-                            return Some(self.local_context_root_for_id(edge.id));
-                        }
-                        assert!(dom_roots.len() >= 1, "Script had no executor (b) {:?}", self.target_node(edge));
-                        // Sometimes the same script src is executed from multiple DOM roots in the
-                        // same local frame context
-                        // In practice, we only use the URL here to check the partiness of a
-                        // request, which will be the same for all roots. So we just take the first
-                        // one alphabetically for the sake of determinism.
-                        // TODO improve
-                        let mut all_dom_roots = dom_roots.iter().filter(|root| matches!(&root.node_type, NodeType::DomRoot { url, .. } if url.is_some())).collect::<Vec<_>>();
-                        all_dom_roots.sort_unstable_by_key(|root| match &root.node_type { NodeType::DomRoot { url, .. } => url.to_owned(), _ => unreachable!() });
+        self.try_dom_root_for_edge(edge).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-                        Some(all_dom_roots[0])
-                    }
+    /// Returns the DOM root node according to the frame that the given edge originated from, or
+    /// an error describing the graph irregularity that prevented it from being found.
+    pub fn try_dom_root_for_edge(&self, edge: &Edge) -> Result<Option<&Node>, AttributionError> {
+        match &edge.edge_type {
+            EdgeType::RequestStart { .. } | EdgeType::RequestComplete { .. } | EdgeType::RequestError { .. } => {
+                // Requests are attributed to the node that initiated them.
+                let initiator = match edge.edge_type {
+                    EdgeType::RequestStart { .. } => self.source_node(edge),
+                    _ => self.target_node(edge),
+                };
+                match &initiator.node_type {
+                    NodeType::HtmlElement { .. } | NodeType::FrameOwner { .. } => self.try_dom_root_for_html_node(initiator),
+                    NodeType::Script { .. } => self.try_dom_root_for_script(initiator, Some(edge.id)),
                     // Prefetches and requests from CSS are initiated by the parser. We have no way
                     // to attribute those to particular DOM roots, so we ignore them for now.
-                    NodeType::Parser { .. } => None,
-                    _ => panic!("Request initiated by {:?} (something other than a script or HTML element)", &target),
+                    NodeType::Parser { .. } => Ok(None),
+                    _ => Err(AttributionError::UnexpectedActor { edge_id: edge.id, actor: initiator.id }),
                 }
             }
             EdgeType::Execute { .. } => {
                 let source = self.source_node(edge);
                 match &source.node_type {
-                    NodeType::HtmlElement { tag_name, .. } if tag_name == "script" => Some(self.dom_root_for_html_node(source).expect("could not find DOM root for script executor element")),
+                    NodeType::HtmlElement { tag_name, .. } if tag_name == "script" => self.try_dom_root_for_html_node(source),
                     NodeType::Script { script_type, .. } if script_type == "module" => {
                         // There's some complicated cases with module scripts that can sometimes
                         // lead to infinite loops, so just find the local context URL and use that.
                         // TODO improve
-                        Some(self.local_context_root_for_id(edge.id))
-                    }
-                    NodeType::Script { .. } => {
-                        // Scripts generally are pointed to by a single Execute edge, but there can
-                        // be more than one for multiple script elements with the same source.
-                        let dom_roots = self.incoming_edges(source)
-                            .filter(|edge| matches!(edge.edge_type, EdgeType::Execute {}))
-                            .map(|edge| self.dom_root_for_edge(edge).unwrap())
-                            .collect::<Vec<_>>();
-
-                        assert!(dom_roots.len() >= 1, "Script had no executor (b) {:?}", self.target_node(edge));
-                        // Sometimes the same script src is executed from multiple DOM roots in the
-                        // same local frame context
-                        // In practice, we only use the URL here to check the partiness of a
-                        // request, which will be the same for all roots. So we just take the first
-                        // one alphabetically for the sake of determinism.
-                        // TODO improve
-                        let mut all_dom_roots = dom_roots.iter().filter(|root| matches!(&root.node_type, NodeType::DomRoot { url, .. } if url.is_some())).collect::<Vec<_>>();
-                        all_dom_roots.sort_unstable_by_key(|root| match &root.node_type { NodeType::DomRoot { url, .. } => url.to_owned(), _ => unreachable!() });
-
-                        Some(all_dom_roots[0])
+                        self.try_local_context_root_for_id(edge.id).map(Some)
                     }
+                    NodeType::Script { .. } => self.try_dom_root_for_script(source, None::<EdgeId>),
                     // Unclear why, but DOM roots sometimes execute scripts as well.
-                    NodeType::DomRoot { .. } => Some(source),
-                    _ => panic!("Script was executed by {:?} (something other than a script HTML element or another script)", &source.node_type),
+                    NodeType::DomRoot { .. } => Ok(Some(source)),
+                    _ => Err(AttributionError::UnexpectedActor { edge_id: edge.id, actor: source.id }),
                 }
             }
+            // Inline handlers run in the document of the element that owns the attribute.
+            EdgeType::ExecuteFromAttribute { .. } => self.try_dom_root_for_html_node(self.source_node(edge)),
             EdgeType::CrossDom {} => {
                 let source = self.source_node(edge);
                 match &source.node_type {
                     NodeType::RemoteFrame { .. } => {
-                        let previous_edge = {
-                            let mut previous_edges = self.incoming_edges(source).filter(|edge| matches!(edge.edge_type, EdgeType::CrossDom {}));
-                            let previous_edge = previous_edges.next().expect("remote frame had no incoming cross DOM edge");
-                            assert!(previous_edges.next().is_none());
-                            previous_edge
-                        };
-
-                        self.dom_root_for_edge(previous_edge)
-                    }
-                    NodeType::FrameOwner { .. } => {
-                        Some(self.dom_root_for_html_node(source).expect("could not find DOM root for frame owner element"))
+                        let previous_edges = self.incoming_edges(source)
+                            .filter(|edge| matches!(edge.edge_type, EdgeType::CrossDom {}))
+                            .collect::<Vec<_>>();
+                        match previous_edges[..] {
+                            [previous_edge] => self.try_dom_root_for_edge(previous_edge),
+                            _ => Err(AttributionError::RemoteFrameAttachments { node_id: source.id, count: previous_edges.len() }),
+                        }
                     }
+                    NodeType::FrameOwner { .. } => self.try_dom_root_for_html_node(source),
                     // When a script creates a DOM root, it can be attached directly to another
                     // root.
-                    NodeType::DomRoot { .. } => {
-                        Some(source)
-                    }
-                    _ => panic!("Cross DOM edge {:?} has a {:?} source (not a remote frame, frame owner, or another DOM root)", &edge, &source),
+                    NodeType::DomRoot { .. } => Ok(Some(source)),
+                    _ => Err(AttributionError::UnexpectedActor { edge_id: edge.id, actor: source.id }),
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(AttributionError::UnsupportedEdge(edge.id)),
         }
     }
 
+    /// Attributes a script to the DOM root of one of its executions. Scripts that were never
+    /// executed are attributed to the top-level DOM root of the local context of `fallback`, if
+    /// provided.
+    ///
+    /// Attributions made from executions are memoized per script until the graph is modified.
+    fn try_dom_root_for_script<'a, I: HasFrameId + Copy>(&'a self, script: &'a Node, fallback: Option<I>) -> Result<Option<&'a Node>, AttributionError> {
+        if let Some(dom_root) = self.cached_dom_root(script.id) {
+            return Ok(dom_root);
+        }
+
+        // Scripts generally are pointed to by a single Execute edge, but there can be more than
+        // one for multiple script elements with the same source.
+        let dom_roots = self.incoming_edges(script)
+            .filter(|edge| is_execution(&edge.edge_type))
+            .map(|edge| self.try_dom_root_for_edge(edge))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let (true, Some(fallback)) = (dom_roots.is_empty(), fallback) {
+            // There's some complicated cases with module scripts that can sometimes lead to
+            // infinite loops, so just find the local context URL and use that.
+            // TODO improve
+            return self.try_local_context_root_for_id(fallback).map(Some);
+        }
+
+        // Sometimes the same script src is executed from multiple DOM roots in the same local
+        // frame context.
+        // In practice, we only use the URL here to check the partiness of a request, which will
+        // be the same for all roots. So we just take the first one alphabetically for the sake
+        // of determinism.
+        // TODO improve
+        dom_roots.into_iter()
+            .flatten()
+            .filter_map(|root| match &root.node_type {
+                NodeType::DomRoot { url: Some(url), .. } => Some((url, root)),
+                _ => None,
+            })
+            .min_by_key(|(url, _)| *url)
+            .map(|(_, root)| {
+                self.cache_dom_root(script.id, Some(root));
+                Some(root)
+            })
+            .ok_or(AttributionError::UnattributedScript(script.id))
+    }
+
     /// Returns the top-level DOM root node for a particular local context - not necessarily the
     /// root of a given frame, but at least still first-party to that frame.
     pub fn local_context_root_for_id<I: crate::graph::HasFrameId + Copy>(&self, item: I) -> &Node {
+        self.try_local_context_root_for_id(item).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Returns the top-level DOM root node for a particular local context, or an error if there
+    /// is not exactly one.
+    pub fn try_local_context_root_for_id<I: crate::graph::HasFrameId + Copy>(&self, item: I) -> Result<&Node, AttributionError> {
         let matching_dom_roots: Vec<_> = self.nodes.values()
             // Only consider nodes in the same local context
             .filter(|node| crate::graph::is_same_frame_context(item, node.id))
@@ -314,8 +590,47 @@ impl PageGraph {
                 }).next().is_none()
             })
             .collect();
-        assert_eq!(matching_dom_roots.len(), 1, "Wrong number of local context DOM roots");
-        matching_dom_roots[0]
+        match matching_dom_roots[..] {
+            [root] => Ok(root),
+            _ => Err(AttributionError::LocalContextRoots {
+                frame_id: item.get_frame_id(),
+                document: item.get_document(),
+                count: matching_dom_roots.len(),
+            }),
+        }
+    }
+
+    /// Classifies the request with the given Blink request id as first- or third-party, relative
+    /// to the document that made it. Frame ids are interpreted as for `edges_for_request_id`.
+    ///
+    /// Requests that cannot be attributed to a particular document, such as those initiated by the
+    /// parser, are considered relative to the top-level document of their local frame context.
+    /// Returns `None` if there is no `RequestStart` edge for the request.
+    pub fn request_party<C: HasFrameId>(&self, request_id: usize, frame_id: C) -> Option<PartyInfo> {
+        let start = self.edges_for_request_id(request_id, frame_id)
+            .into_iter()
+            .find(|edge| matches!(edge.edge_type, EdgeType::RequestStart { .. }))?;
+        let url = self.target_node(start).as_resource()?;
+
+        let dom_root = match self.try_dom_root_for_edge(start) {
+            Ok(Some(dom_root)) => Some(dom_root),
+            _ => self.try_local_context_root_for_id(start.id).ok(),
+        };
+        let frame_url = dom_root.and_then(|dom_root| match &dom_root.node_type {
+            NodeType::DomRoot { url, .. } => url.clone(),
+            _ => None,
+        });
+
+        let is_third_party = frame_url.as_deref()
+            .and_then(|frame_url| url_utils::is_third_party(url, frame_url))
+            .unwrap_or(true);
+
+        Some(PartyInfo {
+            initiator_origin: frame_url.as_deref().and_then(url_utils::origin),
+            request_origin: url_utils::origin(url),
+            is_third_party,
+            frame_url,
+        })
     }
 
     /// Returns a sorted Vec including 1 edge representing every time the given HtmlElement node was
@@ -346,6 +661,56 @@ impl PageGraph {
         }
     }
 
+    /// Returns a sorted Vec including 1 edge representing every time the given node, or any
+    /// element or text node inside it, was modified in the page. The subtree is taken from the
+    /// final reconstructed DOM, so nodes that were moved out of it or removed are not included.
+    pub fn all_subtree_modifications(&self, node_id: NodeId) -> Vec<(&EdgeId, &Edge)> {
+        let dom = self.final_dom();
+        let mut subtree = vec![node_id];
+        dom.elements.get(&node_id).into_iter()
+            .chain(dom.descendants(node_id))
+            .flat_map(|element| element.children.iter())
+            .filter(|child| dom.texts.contains_key(child))
+            .for_each(|text| subtree.push(*text));
+        subtree.extend(dom.descendants(node_id).into_iter().map(|element| element.node_id));
+
+        let mut modifications: Vec<_> = subtree.into_iter()
+            .filter(|node_id| self.nodes.contains_key(node_id))
+            .flat_map(|node_id| self.graph.edges_directed(node_id, Direction::Incoming))
+            .flat_map(|(_source, _target, edge_ids)| edge_ids)
+            .map(|edge_id| (edge_id, self.edges.get(edge_id).unwrap()))
+            .filter(|(_id, edge)| !matches!(edge.edge_type, EdgeType::Structure { .. }))
+            .collect();
+
+        modifications.sort_by_key(|(edge_id, edge)| (edge.edge_timestamp, **edge_id));
+
+        modifications
+    }
+
+    /// Reconstructs the values an attribute of an element held over time, in the order they were
+    /// set, along with the script or parser node that set each one. Deletions of the attribute
+    /// are included with a value of `None`. Attribute names are compared case-insensitively, and
+    /// changes to inline style properties are not included.
+    pub fn attribute_history(&self, node_id: NodeId, attr: &str) -> Vec<(Timestamp, Option<String>, NodeId)> {
+        let element = match self.nodes.get(&node_id) {
+            Some(element) => element,
+            None => return vec![],
+        };
+        let mut changes = self.incoming_edges(element).filter(|edge| match &edge.edge_type {
+            EdgeType::SetAttribute { key, is_style: false, .. } |
+            EdgeType::DeleteAttribute { key, is_style: false } => key.eq_ignore_ascii_case(attr),
+            _ => false,
+        }).collect::<Vec<_>>();
+        changes.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+        changes.into_iter().map(|edge| {
+            let value = match &edge.edge_type {
+                EdgeType::SetAttribute { value, .. } => Some(value.clone().unwrap_or_default()),
+                _ => None,
+            };
+            (edge.edge_timestamp.unwrap_or_default(), value, edge.source)
+        }).collect()
+    }
+
     /// Get a collection of any Script nodes responsible for fetching the given Resource node.
     pub fn scripts_that_caused_resource(&self, node_id: NodeId) -> Vec<(NodeId, &Node)> {
         let element = self.nodes.get(&node_id).unwrap();
@@ -400,11 +765,98 @@ impl PageGraph {
         return self.desc.url.to_string();
     }
 
+    /// Returns the URL of the document that requests from the given item are made relative to, for
+    /// the purposes of adblock matching. Items in merged remote frames use the URL of their own
+    /// frame's top-level document, if it was recorded, and all others use the page's URL.
+    pub(crate) fn document_url_for<I: HasFrameId + Copy>(&self, item: I) -> String {
+        if item.get_frame_id().is_none() {
+            return self.root_url();
+        }
+        self.try_local_context_root_for_id(item).ok()
+            .and_then(|dom_root| match &dom_root.node_type {
+                NodeType::DomRoot { url, .. } => url.clone(),
+                _ => None,
+            })
+            .unwrap_or_else(|| self.root_url())
+    }
+
+    /// Evaluates the page itself against `$document` filters, and every frame embedded in it
+    /// against `$subdocument` filters, relative to the document embedding the frame. Only frames
+    /// and documents that matched a filter or exception are reported, in frame owner id order.
+    pub fn frames_matching_filters(&self, patterns: Vec<String>) -> Vec<FrameMatch> {
+        let blocker = Engine::from_rules_debug(&patterns, Default::default());
+        self.frames_matching_engine(&blocker)
+    }
+
+    /// Like `frames_matching_filters`, but using an engine that has already been built.
+    ///
+    /// The documents loaded in each frame are found through its `CrossDom` edges, including
+    /// those of merged remote frames. If none were recorded, the frame owner's final `src`
+    /// attribute is used instead.
+    pub fn frames_matching_engine(&self, blocker: &Engine) -> Vec<FrameMatch> {
+        let url_of = |node: &Node| match &node.node_type {
+            NodeType::DomRoot { url, .. } => url.clone(),
+            _ => None,
+        };
+
+        let root_url = self.root_url();
+        let mut frames = vec![(None, None, root_url.clone(), root_url, "document")];
+
+        let dom = self.final_dom();
+        self.nodes_of_kind(NodeKind::FrameOwner).for_each(|frame_owner| {
+            let parent_url = self.try_dom_root_for_html_node(frame_owner).ok().flatten()
+                .and_then(url_of)
+                .unwrap_or_else(|| self.document_url_for(frame_owner.id));
+
+            let frame_children = self.outgoing_edges(frame_owner)
+                .filter(|edge| matches!(edge.edge_type, EdgeType::CrossDom {}))
+                .map(|edge| self.target_node(edge));
+            let documents = frame_children.flat_map(|child| match child.node_type {
+                NodeType::RemoteFrame { .. } => self.outgoing_edges(child)
+                    .filter(|edge| matches!(edge.edge_type, EdgeType::CrossDom {}))
+                    .map(|edge| self.target_node(edge))
+                    .collect::<Vec<_>>(),
+                _ => vec![child],
+            }).filter(|document| matches!(document.node_type, NodeType::DomRoot { .. })).collect::<Vec<_>>();
+
+            if documents.is_empty() {
+                let src = dom.elements.get(&frame_owner.id).and_then(|element| element.attributes.get("src"));
+                let src_url = src.and_then(|src| url::Url::parse(&parent_url).and_then(|base| base.join(src)).ok());
+                if let Some(src_url) = src_url {
+                    frames.push((Some(frame_owner.id), None, src_url.into(), parent_url, "subdocument"));
+                }
+            } else {
+                documents.into_iter().filter_map(|document| Some((document.id, url_of(document)?))).for_each(|(document_id, url)| {
+                    frames.push((Some(frame_owner.id), Some(document_id), url, parent_url.clone(), "subdocument"));
+                });
+            }
+        });
+
+        // Document URLs are recorded as the page reported them, so may need normalizing first
+        let normalize = |url: &str| url::Url::parse(url).map(String::from).unwrap_or_else(|_| url.to_owned());
+        frames.into_iter().filter_map(|(frame_owner_id, document_id, url, parent_url, request_type)| {
+            let adblock_request = Request::new(&normalize(&url), &normalize(&parent_url), request_type).ok()?;
+            let blocker_result = blocker.check_network_request_subset(&adblock_request, false, true);
+            if !blocker_result.matched && blocker_result.exception.is_none() {
+                return None;
+            }
+            Some(FrameMatch {
+                frame_owner_node_id: frame_owner_id.map(|node_id| format!("{}", node_id)),
+                document_node_id: document_id.map(|node_id| format!("{}", node_id)),
+                url,
+                parent_url,
+                blocked: blocker_result.matched && (blocker_result.exception.is_none() || blocker_result.important),
+                blocking_filter: blocker_result.filter,
+                exception_filter: blocker_result.exception,
+            })
+        }).collect()
+    }
+
     /// Get every request type and associated resource size for a given resource.
     ///
     /// Some requests, like streamed fetches, video, or audio cannot be properly sized, so their
     /// sizes will be None.
-    pub fn resource_request_types(&self, resource_node: &NodeId) -> Vec<(String, Option<usize>)> {
+    pub fn resource_request_types(&self, resource_node: &NodeId) -> Vec<(String, Option<u64>)> {
         if let NodeType::Resource { .. } = self.nodes.get(resource_node).unwrap().node_type {
             let request_start_edges = self.graph
                 .edges_directed(resource_node.to_owned(), Direction::Incoming)
@@ -418,14 +870,10 @@ impl PageGraph {
                     if let Some(Edge { edge_type: EdgeType::RequestStart { request_type, request_id, .. }, .. }) = self.edges.get(edge_id) {
                         let request_type = request_type.as_str().to_owned();
 
-                        let mut matching_request_sizes = self.edges
-                            .iter()
-                            .filter_map(|(_, Edge { edge_type, .. })| if let EdgeType::RequestComplete { size, request_id: id, .. } = edge_type {
-                                    if id == request_id {
-                                        Some(size.parse::<usize>().ok())
-                                    } else {
-                                        None
-                                    }
+                        let mut matching_request_sizes = self.edges_for_request_id(*request_id, *edge_id)
+                            .into_iter()
+                            .filter_map(|Edge { edge_type, .. }| if let EdgeType::RequestComplete { size, .. } = edge_type {
+                                    Some(*size)
                                 } else {
                                     None
                                 });
@@ -450,15 +898,27 @@ impl PageGraph {
 
     /// Get a collection of all Resource nodes whose requests match a set of adblock filter patterns.
     pub fn resources_matching_filters(&self, graph: &PageGraph, patterns: Vec<String>) -> Vec<MatchedResource> {
-        let source_url = self.root_url();
+        let blocker = Engine::from_rules_debug(&patterns, Default::default());
+        self.resources_matching(graph, &blocker, false)
+    }
 
-        let mut matching_resources : Vec<MatchedResource> = vec![];
+    /// Like `resources_matching_filters`, but using an engine that has already been built, so
+    /// that the same filters can be evaluated over many graphs without rebuilding it each time.
+    /// The engine should be built in debug mode in order to report the filters that matched.
+    ///
+    /// If `only_exceptions` is set, only resources whose requests matched an exception filter are
+    /// reported.
+    pub fn resources_matching_engine(&self, engine: &Engine, only_exceptions: bool) -> Vec<MatchedResource> {
+        self.resources_matching(self, engine, only_exceptions)
+    }
 
-        let blocker = Engine::from_rules_debug(&patterns, Default::default());
+    fn resources_matching(&self, graph: &PageGraph, blocker: &Engine, only_exceptions: bool) -> Vec<MatchedResource> {
+        let mut matching_resources : Vec<MatchedResource> = vec![];
 
         for (id, node) in self.nodes.iter() {
             match &node.node_type {
                 NodeType::Resource { url } => {
+                    let source_url = self.document_url_for(*id);
                     let request_types = self.resource_request_types(&id);
                     for (request_type, _size) in request_types.into_iter() {
                         let adblock_request = match Request::new(&url, &source_url, &request_type) {
@@ -467,7 +927,12 @@ impl PageGraph {
                         };
                         let blocker_result = blocker
                             .check_network_request_subset(&adblock_request, false, true);
-                        if blocker_result.matched || blocker_result.exception.is_some() {
+                        let is_match = if only_exceptions {
+                            blocker_result.exception.is_some()
+                        } else {
+                            blocker_result.matched || blocker_result.exception.is_some()
+                        };
+                        if is_match {
                             let matching_request_types = graph.resource_request_types(&id).into_iter().map(|(ty, _)| ty).collect();
                             let requests = graph.incoming_edges(&node)
                                 .filter_map(|edge| {
@@ -499,9 +964,194 @@ impl PageGraph {
         matching_resources
     }
 
+    /// Like `resources_matching_filters`, but reports the requests of each type to each resource
+    /// separately, along with the filters that matched them and their `RequestStart` edges.
+    /// Resources that were never requested are not considered. Results are sorted by their first
+    /// edge id.
+    pub fn resources_matching_filters_detailed(&self, patterns: Vec<String>) -> Vec<FilterMatch> {
+        let blocker = Engine::from_rules_debug(&patterns, Default::default());
+        self.resources_matching_engine_detailed(&blocker)
+    }
+
+    /// Like `resources_matching_filters_detailed`, but using an engine that has already been
+    /// built.
+    pub fn resources_matching_engine_detailed(&self, blocker: &Engine) -> Vec<FilterMatch> {
+        let mut requests = HashMap::<(NodeId, &str), (&RequestType, Vec<EdgeId>)>::new();
+        self.edges_of_kind(EdgeKind::RequestStart).for_each(|edge| if let EdgeType::RequestStart { request_type, .. } = &edge.edge_type {
+            requests.entry((edge.target, request_type.as_str())).or_insert_with(|| (request_type, vec![])).1.push(edge.id);
+        });
+
+        let mut matches = requests.into_iter().filter_map(|((node_id, _), (request_type, edge_ids))| {
+            let url = self.nodes.get(&node_id)?.as_resource()?;
+            let adblock_request = Request::new(url, &self.document_url_for(node_id), request_type.as_str()).ok()?;
+            let blocker_result = blocker.check_network_request_subset(&adblock_request, false, true);
+            if !blocker_result.matched && blocker_result.exception.is_none() {
+                return None;
+            }
+            let blocked = blocker_result.matched && (blocker_result.exception.is_none() || blocker_result.important);
+            Some((edge_ids[0], FilterMatch {
+                url: url.to_string(),
+                node_id: format!("{}", node_id),
+                request_type: request_type.clone(),
+                edge_ids: edge_ids.iter().map(|edge_id| format!("{}", edge_id)).collect(),
+                blocking_filter: blocker_result.filter,
+                exception_filter: blocker_result.exception,
+                blocked,
+            }))
+        }).collect::<Vec<_>>();
+        matches.sort_unstable_by_key(|(first_edge_id, _)| *first_edge_id);
+
+        matches.into_iter().map(|(_, filter_match)| filter_match).collect()
+    }
+
+    /// Evaluates every request in the graph against the given adblock engine.
+    ///
+    /// Requests that are matched by a blocking filter (without an exception, or with an
+    /// `$important` match) are considered blocked, unless a `$redirect` rule supplied one of the
+    /// engine's resources in place of the response, in which case they are considered redirected.
+    /// Any requests downstream of a blocked or redirected request are considered blocked as well,
+    /// since they would never have been made.
+    pub fn blocked_requests(&self, engine: &Engine) -> HashMap<EdgeId, BlockOutcome> {
+        let request_starts = self.edges_of_kind(EdgeKind::RequestStart).collect::<Vec<_>>();
+
+        let mut outcomes = request_starts.iter().map(|edge| {
+            let outcome = match (&self.target_node(edge).node_type, &edge.edge_type) {
+                (NodeType::Resource { url }, EdgeType::RequestStart { request_type, .. }) => {
+                    match Request::new(url, &self.document_url_for(edge.id), request_type.as_str()) {
+                        Ok(adblock_request) => {
+                            let blocker_result = engine.check_network_request(&adblock_request);
+                            if blocker_result.matched && (blocker_result.exception.is_none() || blocker_result.important) {
+                                match blocker_result.redirect {
+                                    Some(redirect) => BlockOutcome::Redirected { filter: blocker_result.filter, redirect },
+                                    None => BlockOutcome::Blocked { filter: blocker_result.filter },
+                                }
+                            } else {
+                                BlockOutcome::Allowed
+                            }
+                        }
+                        Err(_) => BlockOutcome::Allowed,
+                    }
+                }
+                _ => BlockOutcome::Allowed,
+            };
+            (edge.id, outcome)
+        }).collect::<HashMap<_, _>>();
+
+        // Cascade each direct block to everything downstream of it. Iterating in id order keeps
+        // the reported upstream edge stable when a request is downstream of several blocks.
+        let mut directly_blocked = request_starts.iter()
+            .filter(|edge| matches!(outcomes.get(&edge.id), Some(BlockOutcome::Blocked { .. } | BlockOutcome::Redirected { .. })))
+            .collect::<Vec<_>>();
+        directly_blocked.sort_unstable_by_key(|edge| edge.id);
+
+        for blocked_edge in directly_blocked {
+            for downstream_edge in self.all_downstream_effects_of(blocked_edge) {
+                if let Some(outcome @ BlockOutcome::Allowed) = outcomes.get_mut(&downstream_edge.id) {
+                    *outcome = BlockOutcome::Cascaded { upstream_edge_id: format!("{}", blocked_edge.id) };
+                }
+            }
+        }
+
+        outcomes
+    }
+
+    /// Evaluates every request in the graph against the given adblock engine, and reports those
+    /// that would have been answered with one of the engine's redirect resources, along with the
+    /// recorded activity that would no longer have occurred as a result. Results are sorted by
+    /// request start edge id.
+    ///
+    /// `$redirect` rules naming a resource that the engine doesn't have are ignored, so its
+    /// resources should be loaded first.
+    pub fn redirected_requests(&self, engine: &Engine) -> Vec<RedirectedRequest> {
+        let outcomes = self.blocked_requests(engine);
+
+        self.edges_of_kind(EdgeKind::RequestStart).filter_map(|edge| {
+            let (filter, redirect) = match outcomes.get(&edge.id) {
+                Some(BlockOutcome::Redirected { filter, redirect }) => (filter.clone(), redirect.clone()),
+                _ => return None,
+            };
+            let (request_id, request_type) = match &edge.edge_type {
+                EdgeType::RequestStart { request_id, request_type, .. } => (*request_id, request_type.clone()),
+                _ => unreachable!(),
+            };
+            let url = match &self.target_node(edge).node_type {
+                NodeType::Resource { url } => url.clone(),
+                _ => return None,
+            };
+
+            // The request still completes, but with a response that does nothing
+            let retained = self.direct_downstream_effects_of(edge).into_iter().map(|edge| edge.id).collect::<HashSet<_>>();
+            let suppressed = self.all_downstream_effects_of(edge).into_iter()
+                .filter(|effect| !retained.contains(&effect.id))
+                .collect::<Vec<_>>();
+            let mut retained_edges = retained.into_iter().collect::<Vec<_>>();
+            retained_edges.sort_unstable();
+
+            Some(RedirectedRequest {
+                request_id,
+                edge_id: format!("{}", edge.id),
+                url,
+                request_type,
+                filter,
+                redirect,
+                retained_edges: retained_edges.into_iter().map(|edge_id| format!("{}", edge_id)).collect(),
+                suppressed_requests: suppressed.iter()
+                    .filter(|effect| matches!(effect.edge_type, EdgeType::RequestStart { .. }))
+                    .map(|effect| format!("{}", effect.id))
+                    .collect(),
+                suppressed_edges: suppressed.iter().map(|effect| format!("{}", effect.id)).collect(),
+            })
+        }).collect()
+    }
+
+    /// Like `redirected_requests`, but using an engine built from the given filter rules. Each
+    /// resource named by their `$redirect` or `$redirect-rule` options is stood in for by an empty
+    /// resource of the same type.
+    pub fn redirects_matching_filters(&self, patterns: Vec<String>) -> Vec<RedirectedRequest> {
+        let mut engine = Engine::from_rules_debug(&patterns, Default::default());
+        engine.use_resources(placeholder_redirect_resources(&patterns));
+        self.redirected_requests(&engine)
+    }
+
+    /// Compares the requests blocked by two different adblock engines, including any downstream
+    /// requests that would be prevented as a result. Results are sorted by request start edge id.
+    pub fn compare_engines(&self, engine_a: &Engine, engine_b: &Engine) -> Vec<EngineComparison> {
+        let mut outcomes_a = self.blocked_requests(engine_a);
+        let mut outcomes_b = self.blocked_requests(engine_b);
+
+        self.edges_of_kind(EdgeKind::RequestStart).filter_map(|edge| {
+            let (request_id, request_type) = match &edge.edge_type {
+                EdgeType::RequestStart { request_id, request_type, .. } => (*request_id, request_type.clone()),
+                _ => unreachable!(),
+            };
+            let url = match &self.target_node(edge).node_type {
+                NodeType::Resource { url } => url.clone(),
+                _ => return None,
+            };
+            let engine_a = outcomes_a.remove(&edge.id).unwrap_or(BlockOutcome::Allowed);
+            let engine_b = outcomes_b.remove(&edge.id).unwrap_or(BlockOutcome::Allowed);
+            let classification = match (engine_a.is_blocked(), engine_b.is_blocked()) {
+                (true, true) => EngineAgreement::BlockedByBoth,
+                (true, false) => EngineAgreement::BlockedOnlyByA,
+                (false, true) => EngineAgreement::BlockedOnlyByB,
+                (false, false) => EngineAgreement::BlockedByNeither,
+            };
+            Some(EngineComparison {
+                request_id,
+                edge_id: format!("{}", edge.id),
+                url,
+                request_type,
+                classification,
+                engine_a,
+                engine_b,
+            })
+        }).collect()
+    }
+
     pub fn direct_downstream_effects_of(&self, edge: &Edge) -> Vec<&Edge>{
         match &edge.edge_type {
-            EdgeType::Filter {} => unimplemented!(),
+            // Filters record which rule matched a request, rather than causing anything
+            EdgeType::Filter {} => vec![],
             EdgeType::Structure {} => panic!("Structure edges should not be examined for downstream effects"),
             EdgeType::CrossDom {} => {
                 // Cross DOM edges can point to frame roots, including remote frames
@@ -512,9 +1162,7 @@ impl PageGraph {
 
                         // Find the single Parser node that belongs to the same local frame context
                         // as this DOM root
-                        let parsers = self.filter_nodes(|node_type| matches!(node_type, NodeType::Parser {}));
-                        let mut same_context_parsers = parsers
-                            .iter()
+                        let mut same_context_parsers = self.nodes_of_kind(NodeKind::Parser)
                             .filter(|parser| {
                                 crate::graph::is_same_frame_context(edge.target, parser.id)
                             });
@@ -640,11 +1288,29 @@ impl PageGraph {
                     _ => panic!("Cross DOM edges should only point to DOM roots, parsers, and remote frames, {:?}", self.target_node(edge)),
                 }
             }
-            EdgeType::ResourceBlock {} => unimplemented!(),
-            EdgeType::Shield {} => unimplemented!(),
-            EdgeType::TextChange {} => unimplemented!(),
-            EdgeType::RemoveNode {} => unimplemented!(),
-            EdgeType::DeleteNode {} => unimplemented!(),
+            // Blocks and shields record a blocking decision, rather than causing anything
+            EdgeType::ResourceBlock {} | EdgeType::Shield {} => vec![],
+            EdgeType::TextChange {} => {
+                // Changing the text of an inline script before it runs determines what is
+                // executed. The text node's parent is taken from its latest insertion.
+                let target = self.target_node(edge);
+                self.incoming_edges(target)
+                    .filter_map(|insertion| match insertion.edge_type {
+                        EdgeType::InsertNode { parent, .. } if happened_before(insertion, edge) => Some((insertion, parent)),
+                        _ => None,
+                    })
+                    .max_by_key(|(insertion, _)| (insertion.edge_timestamp, insertion.id))
+                    .and_then(|(_, parent_id)| self.html_node_by_blink_id(edge.id, parent_id))
+                    .and_then(|parent_node| self.next_inline_execution(parent_node, edge))
+                    .into_iter()
+                    .collect()
+            }
+            EdgeType::RemoveNode {} | EdgeType::DeleteNode {} => {
+                // Removing or deleting a node doesn't cause anything to happen. In particular,
+                // removing a script element doesn't stop a script that has already started
+                // executing.
+                vec![]
+            }
             EdgeType::InsertNode { parent: parent_id, .. } => {
                 // Inserting a node can cause certain elements with `src` attributes to trigger a
                 // network request, however we use `SetAttribute` instead as a rough approximation
@@ -655,32 +1321,10 @@ impl PageGraph {
                 // chronologically next outgoing execute edge from the parent script as an effect
                 // of this insertion.
                 if let NodeType::TextNode { .. } = self.target_node(edge).node_type {
-                    let parent_node = {
-                        let mut parent_nodes = self.nodes.values().filter(|parent_node|
-                            crate::graph::is_same_frame_context(edge.id, parent_node.id) &&
-                            matches!(parent_node.node_type, NodeType::HtmlElement { node_id, .. } | NodeType::DomRoot { node_id, .. } | NodeType::FrameOwner { node_id, .. } if node_id == *parent_id)
-                        );
-                        let parent_node = parent_nodes.next().expect(&format!("No HTML parent node with id {} found for insertion {:?}", parent_id, edge));
-                        assert!(parent_nodes.next().is_none(), "Multiple HTML parent nodes with id {} found", parent_id);
-                        parent_node
-                    };
-
-                    match &parent_node.node_type {
-                        NodeType::HtmlElement { tag_name, .. } if tag_name == "script" => {
-                            let insertion_time = edge.edge_timestamp;
-                            let next_execution = self.outgoing_edges(parent_node)
-                                .filter(|edge| matches!(edge.edge_type, EdgeType::Execute {}) && edge.edge_timestamp >= insertion_time)
-                                .min_by_key(|edge| edge.edge_timestamp);
-                            // Some script elements are not executed, e.g.
-                            // `<script type="application/json">`.
-                            // This is fine.
-                            return match next_execution {
-                                Some(next_execution) => vec![next_execution],
-                                None => vec![],
-                            };
-                        },
-                        _ => (),
-                    }
+                    let parent_node = self.html_node_by_blink_id(edge.id, *parent_id)
+                        .unwrap_or_else(|| panic!("No unique HTML parent node with id {} found for insertion {:?}", parent_id, edge));
+
+                    return self.next_inline_execution(parent_node, edge).into_iter().collect();
                 }
 
                 vec![]
@@ -689,8 +1333,15 @@ impl PageGraph {
                 // Creating a node generally doesn't cause anything to happen.
                 vec![]
             }
-            EdgeType::JsResult { .. } => unimplemented!(),
-            EdgeType::JsCall { .. } => unimplemented!(),
+            EdgeType::JsResult { .. } => {
+                // The script continues executing with the value that was returned to it.
+                self.script_activity_after(self.target_node(edge), edge)
+            }
+            EdgeType::JsCall { .. } => {
+                // Calling a Web API or JS builtin causes the next corresponding result to be
+                // returned to the calling script. Some calls never return a recorded value.
+                self.js_call_result(edge).into_iter().collect()
+            }
             EdgeType::RequestComplete { resource_type, .. } => {
                 // If RequestComplete has a "script" resource type, and points to an HTML script
                 // element, then attribute any Executions from that element to this edge.
@@ -706,38 +1357,84 @@ impl PageGraph {
                 vec![]
             },
             EdgeType::RequestStart { request_id, .. } => {
-                // Request starts cause request completions or errors.
+                // Request starts cause responses, such as redirects, and request completions or
+                // errors.
                 self.outgoing_edges(self.target_node(edge)).filter(|edge| match edge.edge_type {
+                    EdgeType::RequestResponse { request_id: response_id, .. } if *request_id == response_id => true,
                     EdgeType::RequestComplete { request_id: complete_id, .. } if *request_id == complete_id => true,
                     EdgeType::RequestError { request_id: error_id, .. } if *request_id == error_id => true,
                     _ => false,
                 }).collect()
             }
-            EdgeType::RequestResponse => unimplemented!(),
-            EdgeType::AddEventListener { .. } => unimplemented!(),
-            EdgeType::RemoveEventListener { .. } => unimplemented!(),
-            EdgeType::EventListener { .. } => unimplemented!(),
-            EdgeType::StorageSet { .. } => unimplemented!(),
-            EdgeType::StorageReadResult { .. } => unimplemented!(),
-            EdgeType::DeleteStorage { .. } => unimplemented!(),
-            EdgeType::ReadStorageCall { .. } => unimplemented!(),
-            EdgeType::ClearStorage { .. } => unimplemented!(),
-            EdgeType::StorageBucket {} => unimplemented!(),
-            EdgeType::ExecuteFromAttribute { .. } => unimplemented!(),
-            EdgeType::Execute {} => {
-                self.outgoing_edges(self.target_node(edge)).filter(|edge| match edge.edge_type {
-                    // A script execution can cause a network request
-                    EdgeType::RequestStart { .. } => true,
-                    // A script execution can cause another script to be executed
-                    EdgeType::Execute {} => true,
-                    // A script execution can set attributes on other HTML elements, causing them
-                    // to initiate a network request
-                    EdgeType::SetAttribute { .. } => true,
-                    // TODO scripts can create/insert DOM elements, execute web APIs and JS builtins,
-                    // build 3rd party frames, access storage, access cookies...
+            EdgeType::RequestResponse { request_id, .. } => {
+                // A redirect causes the request to be started again for the new URL. Responses
+                // that aren't followed by another start lead to the request's completion or error.
+                let later = self.edges_for_request_id(*request_id, edge.id).into_iter()
+                    .filter(|other| (other.edge_timestamp, other.id) > (edge.edge_timestamp, edge.id))
+                    .collect::<Vec<_>>();
+                let next_start = later.iter()
+                    .filter(|other| matches!(other.edge_type, EdgeType::RequestStart { .. }))
+                    .min_by_key(|other| (other.edge_timestamp, other.id));
+                match next_start {
+                    Some(next_start) => vec![*next_start],
+                    None => later.into_iter()
+                        .filter(|other| other.source == edge.source && matches!(other.edge_type, EdgeType::RequestComplete { .. } | EdgeType::RequestError { .. }))
+                        .collect(),
+                }
+            }
+            EdgeType::AddEventListener { key, event_listener_id, .. } => {
+                // Registering a listener causes it to be dispatched for later events, until it is
+                // removed or registered again.
+                let target = self.target_node(edge);
+                let is_same_listener = |other: &Edge| other.id != edge.id && match &other.edge_type {
+                    EdgeType::AddEventListener { key: other_key, event_listener_id: other_id, .. } |
+                    EdgeType::RemoveEventListener { key: other_key, event_listener_id: other_id, .. } => other_key == key && other_id == event_listener_id,
+                    _ => false,
+                };
+                self.outgoing_edges(target).filter(|dispatch| match &dispatch.edge_type {
+                    EdgeType::EventListener { key: dispatch_key, event_listener_id: dispatch_id } => {
+                        dispatch_key == key && dispatch_id == event_listener_id &&
+                            happened_before(edge, dispatch) &&
+                            !self.incoming_edges(target).any(|other| is_same_listener(other) && happened_between(other, edge, dispatch))
+                    }
                     _ => false,
                 }).collect()
             }
+            EdgeType::RemoveEventListener { .. } => {
+                // Removing a listener only prevents later dispatches.
+                vec![]
+            }
+            EdgeType::EventListener { .. } => {
+                // Dispatching an event runs the listener's handler script.
+                self.script_activity_after(self.target_node(edge), edge)
+            }
+            EdgeType::StorageSet { key, .. } |
+            EdgeType::DeleteStorage { key } => {
+                // Writing a value determines the results of later reads of the same key, until it
+                // is written again.
+                self.storage_reads_observing(edge, Some(key))
+            }
+            EdgeType::ClearStorage { .. } => {
+                // Clearing storage determines the results of later reads of every key.
+                self.storage_reads_observing(edge, None)
+            }
+            EdgeType::ReadStorageCall { .. } => {
+                // Reading storage causes the next corresponding result to be returned to the
+                // calling script.
+                self.storage_read_result(edge).into_iter().collect()
+            }
+            EdgeType::StorageReadResult { .. } => {
+                // The script continues executing with the value it read.
+                self.script_activity_after(self.target_node(edge), edge)
+            }
+            // Storage buckets only structure the storage nodes of the graph
+            EdgeType::StorageBucket {} => vec![],
+            // Nothing is known about the effects of edge types from newer graph versions
+            EdgeType::Unknown { .. } => vec![],
+            EdgeType::Execute {} |
+            EdgeType::ExecuteFromAttribute { .. } => {
+                self.outgoing_edges(self.target_node(edge)).filter(|edge| is_script_action(&edge.edge_type)).collect()
+            }
             EdgeType::SetAttribute { key, .. } => {
                 let target = self.target_node(edge);
                 match &target.node_type {
@@ -787,74 +1484,1365 @@ impl PageGraph {
                                 next_timestamp.map(|t| edge.edge_timestamp.unwrap() < *t).unwrap_or(true)
                             ).collect()
                     }
-                    _ => vec![],
+                    // Setting an inline event handler attribute causes the handler to run each
+                    // time it is triggered, until the attribute is set again
+                    _ => self.outgoing_edges(target)
+                        .filter(|execution| matches!(&execution.edge_type, EdgeType::ExecuteFromAttribute { attr_name } if attr_name == key))
+                        .filter(|execution| self.direct_upstream_causes_of(execution).contains(&edge))
+                        .collect(),
                 }
             }
-            EdgeType::DeleteAttribute { .. } => unimplemented!(),
-            EdgeType::Binding { .. } => unimplemented!(),
-            EdgeType::BindingEvent { .. } => unimplemented!(),
+            EdgeType::DeleteAttribute { .. } => {
+                // Deleting an attribute doesn't cause anything to happen; e.g. removing `src`
+                // doesn't cancel a request that has already started.
+                vec![]
+            }
+            // Using a binding doesn't cause anything further that the graph records
+            EdgeType::Binding {} => vec![],
+            // A binding event uses the bindings attached to it
+            EdgeType::BindingEvent { .. } => {
+                self.outgoing_edges(self.target_node(edge)).filter(|edge| matches!(edge.edge_type, EdgeType::Binding {})).collect()
+            }
         }
     }
 
+    /// If `parent_node` is a script element, returns its chronologically next execution at or after
+    /// the given edge. Some script elements are not executed, e.g.
+    /// `<script type="application/json">`, which is fine.
+    fn next_inline_execution<'a>(&'a self, parent_node: &'a Node, edge: &Edge) -> Option<&'a Edge> {
+        match &parent_node.node_type {
+            NodeType::HtmlElement { tag_name, .. } if tag_name == "script" => {
+                let change_time = edge.edge_timestamp;
+                self.outgoing_edges(parent_node)
+                    .filter(|edge| matches!(edge.edge_type, EdgeType::Execute {}) && edge.edge_timestamp >= change_time)
+                    .min_by_key(|edge| edge.edge_timestamp)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `JsResult` edge returning the value of the given `JsCall` edge, i.e. the next
+    /// result from the same Web API or JS builtin returned to the calling script.
+    pub(crate) fn js_call_result(&self, call: &Edge) -> Option<&Edge> {
+        self.outgoing_edges(self.target_node(call))
+            .filter(|result| matches!(result.edge_type, EdgeType::JsResult { .. }) &&
+                result.target == call.source &&
+                happened_before(call, result))
+            .min_by_key(|result| (result.edge_timestamp, result.id))
+    }
+
+    /// Returns the `StorageReadResult` edge returning the value requested by the given
+    /// `ReadStorageCall` edge, i.e. the next result for the same key returned to the calling script.
+    pub(crate) fn storage_read_result(&self, call: &Edge) -> Option<&Edge> {
+        let key = match &call.edge_type {
+            EdgeType::ReadStorageCall { key } => key,
+            _ => return None,
+        };
+        self.outgoing_edges(self.target_node(call))
+            .filter(|result| matches!(&result.edge_type, EdgeType::StorageReadResult { key: result_key, .. } if result_key == key) &&
+                result.target == call.source &&
+                happened_before(call, result))
+            .min_by_key(|result| (result.edge_timestamp, result.id))
+    }
+
+    /// Returns the `StorageReadResult` edges whose values were determined by the given write to a
+    /// storage area, i.e. later reads of `key` (or of any key, if `None`) that happened before
+    /// the key was written again.
+    fn storage_reads_observing<'a>(&'a self, write: &Edge, key: Option<&str>) -> Vec<&'a Edge> {
+        let storage = self.target_node(write);
+        let overwrites = |other: &Edge, read_key: &str| other.id != write.id && match &other.edge_type {
+            EdgeType::StorageSet { key, .. } | EdgeType::DeleteStorage { key } => key == read_key,
+            EdgeType::ClearStorage { .. } => true,
+            _ => false,
+        };
+        self.outgoing_edges(storage).filter(|read| match &read.edge_type {
+            EdgeType::StorageReadResult { key: read_key, .. } => {
+                key.map(|key| key == read_key).unwrap_or(true) &&
+                    happened_before(write, read) &&
+                    !self.incoming_edges(storage).any(|other| {
+                        overwrites(other, read_key) && happened_between(other, write, read)
+                    })
+            }
+            _ => false,
+        }).collect()
+    }
+
+    /// Returns the actions a script took after the given edge, which may have been influenced by
+    /// it.
+    fn script_activity_after<'a>(&'a self, script: &'a Node, edge: &Edge) -> Vec<&'a Edge> {
+        if !matches!(script.node_type, NodeType::Script { .. }) {
+            return vec![];
+        }
+        self.outgoing_edges(script)
+            .filter(|action| is_script_action(&action.edge_type) && happened_before(edge, action))
+            .collect()
+    }
+
     /// Returns all actions that would not have occurred had the given action been omitted from the
-    /// original graph.
+    /// original graph, ordered by timestamp.
     pub fn all_downstream_effects_of<'a>(&'a self, edge: &'a Edge) -> Vec<&'a Edge> {
-        let mut edges_to_check = vec![edge];
-        let mut already_checked = vec![];
+        self.all_downstream_effects_from(vec![edge])
+    }
 
-        let original_edge = edge;
+    /// Returns all actions taken by the given node, along with everything that would not have
+    /// occurred had they been omitted from the original graph, ordered by timestamp.
+    pub fn all_downstream_effects_of_node<'a>(&'a self, node: &Node) -> Vec<&'a Edge> {
+        let actions = self.actions_of(node);
+        let mut effects = self.all_downstream_effects_from(actions.clone());
+        effects.extend(actions);
+        effects.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+        effects
+    }
 
-        while let Some(edge) = edges_to_check.pop() {
-            let direct_effects = self.direct_downstream_effects_of(edge);
-            if edge != original_edge {
-                already_checked.push(edge);
-            }
+    /// Returns the outgoing edges of a node that can have downstream effects.
+    fn actions_of<'a>(&'a self, node: &Node) -> Vec<&'a Edge> {
+        self.outgoing_edges(node)
+            .filter(|edge| !matches!(edge.edge_type,
+                EdgeType::Structure {} |
+                EdgeType::Filter {} |
+                EdgeType::ResourceBlock {} |
+                EdgeType::Shield {} |
+                EdgeType::StorageBucket {}))
+            .collect()
+    }
+
+    /// Returns all actions caused by any of the given actions, not including the actions
+    /// themselves, ordered by timestamp.
+    fn all_downstream_effects_from<'a>(&'a self, causes: Vec<&'a Edge>) -> Vec<&'a Edge> {
+        let mut already_checked = causes.iter().map(|edge| edge.id).collect::<HashSet<_>>();
+        let mut edges_to_check = causes;
+        let mut effects = vec![];
 
-            direct_effects.into_iter().for_each(|edge|
-                if !already_checked.contains(&edge) && edge != original_edge {
+        while let Some(edge) = edges_to_check.pop() {
+            self.direct_downstream_effects_of(edge).into_iter().for_each(|edge|
+                if already_checked.insert(edge.id) {
+                    effects.push(edge);
                     edges_to_check.push(edge);
                 }
             );
         }
 
-        already_checked
+        effects.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+        effects
     }
 
     /// Returns all requests that would not have occurred had the given Request Start edge been
-    /// omitted
-    pub fn all_downstream_requests_nested<'a>(&'a self, edge: &'a Edge) -> Vec<DownstreamRequests> {
-        let mut edges_to_check = vec![edge];
-        let mut already_checked = vec![];
-        let mut answer = vec![];
+    /// omitted, nested under the request that caused them.
+    ///
+    /// Each request is reported at most once, under the first request found to cause it, so
+    /// cycles in the graph are not followed. Requests nested more than `max_depth` levels deep
+    /// result in an error.
+    pub fn all_downstream_requests_nested<'a>(&'a self, edge: &'a Edge, max_depth: usize) -> Result<Vec<DownstreamRequests>, DownstreamError> {
+        let mut already_reported = HashSet::new();
+        already_reported.insert(edge.id);
+        self.downstream_requests_below(edge, &mut already_reported, 0, max_depth)
+    }
 
-        let original_edge = edge;
+    /// Returns all requests that would not have occurred had the given node taken none of its
+    /// actions, with the requests started directly by the node or nearest to its actions at the
+    /// top level.
+    ///
+    /// Requests are reported at most once and nested up to `max_depth` levels deep, as in
+    /// `all_downstream_requests_nested`.
+    pub fn all_downstream_requests_of_node_nested(&self, node: &Node, max_depth: usize) -> Result<Vec<DownstreamRequests>, DownstreamError> {
+        let (mut request_starts, causes): (Vec<_>, Vec<_>) = self.actions_of(node).into_iter()
+            .partition(|edge| matches!(edge.edge_type, EdgeType::RequestStart { .. }));
+        let mut already_reported = request_starts.iter().map(|edge| edge.id).collect::<HashSet<_>>();
+        request_starts.extend(self.nearest_downstream_requests(causes, &mut already_reported));
+        self.nest_downstream_requests(request_starts, &mut already_reported, 0, max_depth)
+    }
 
+    fn downstream_requests_below<'a>(&'a self, request_edge: &'a Edge, already_reported: &mut HashSet<EdgeId>, depth: usize, max_depth: usize) -> Result<Vec<DownstreamRequests>, DownstreamError> {
+        let request_starts = self.nearest_downstream_requests(vec![request_edge], already_reported);
+
+        if request_starts.is_empty() {
+            return Ok(vec![]);
+        }
+        if depth >= max_depth {
+            return Err(DownstreamError::MaxDepthExceeded { edge_id: request_edge.id, max_depth });
+        }
+
+        self.nest_downstream_requests(request_starts, already_reported, depth, max_depth)
+    }
+
+    /// Returns the requests caused by the given actions that aren't caused by another request in
+    /// turn, skipping any that were already reported.
+    fn nearest_downstream_requests<'a>(&'a self, causes: Vec<&'a Edge>, already_reported: &mut HashSet<EdgeId>) -> Vec<&'a Edge> {
+        let mut already_checked = causes.iter().map(|edge| edge.id).collect::<HashSet<_>>();
+        let mut edges_to_check = causes;
+        let mut request_starts = vec![];
+
+        // Find the nearest downstream requests first, so that each is nested under its closest
+        // cause.
         while let Some(edge) = edges_to_check.pop() {
-            let direct_effects = self.direct_downstream_effects_of(edge);
-            if edge != original_edge {
-                already_checked.push(edge);
-            }
-
-            direct_effects.into_iter().for_each(|edge|
-                if let EdgeType::RequestStart { request_id, request_type, .. } = &edge.edge_type {
-                    let node = self.target_node(edge);
-                    let url = match &node.node_type {
-                        NodeType::Resource { url } => url,
-                        _ => unreachable!()
-                    };
-                    let downstream_req = DownstreamRequests {
-                        request_id: request_id.clone(),
-                        request_type: request_type.clone(),
-                        node_id: node.id,
-                        url: url.to_string(),
-                        children: self.all_downstream_requests_nested(edge)
-                    };
-                    answer.push(downstream_req)
-                } else if !already_checked.contains(&edge) && edge != original_edge {
+            self.direct_downstream_effects_of(edge).into_iter().for_each(|edge| match &edge.edge_type {
+                // A request may be reachable along several paths, e.g. both directly from a
+                // script and via a value it read back from storage.
+                EdgeType::RequestStart { .. } => if already_reported.insert(edge.id) {
+                    request_starts.push(edge);
+                }
+                _ => if already_checked.insert(edge.id) {
+                    edges_to_check.push(edge);
+                }
+            });
+        }
+
+        request_starts
+    }
+
+    fn nest_downstream_requests<'a>(&'a self, mut request_starts: Vec<&'a Edge>, already_reported: &mut HashSet<EdgeId>, depth: usize, max_depth: usize) -> Result<Vec<DownstreamRequests>, DownstreamError> {
+        request_starts.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+        request_starts.into_iter().map(|edge| {
+            let (request_id, request_type) = match &edge.edge_type {
+                EdgeType::RequestStart { request_id, request_type, .. } => (*request_id, request_type.clone()),
+                _ => unreachable!(),
+            };
+            let node = self.target_node(edge);
+            let url = match &node.node_type {
+                NodeType::Resource { url } => url,
+                _ => unreachable!()
+            };
+            Ok(DownstreamRequests {
+                request_id,
+                request_type,
+                node_id: node.id,
+                url: url.to_string(),
+                children: self.downstream_requests_below(edge, already_reported, depth + 1, max_depth)?,
+            })
+        }).collect()
+    }
+
+    /// Returns all actions that directly caused the given action to occur. This is the inverse of
+    /// `direct_downstream_effects_of`, following `Execute`, `RequestStart`, `RequestResponse`, `RequestComplete`,
+    /// `SetAttribute`, `InsertNode`, and `CrossDom` edges backwards. Actions whose causes are
+    /// not understood, or which were taken by the root frame's parser, have no causes.
+    pub fn direct_upstream_causes_of(&self, edge: &Edge) -> Vec<&Edge> {
+        let actor = self.source_node(edge);
+        let mut causes = match &edge.edge_type {
+            // Request responses, completions, and errors are caused by the start of the same request
+            EdgeType::RequestResponse { request_id, .. } |
+            EdgeType::RequestComplete { request_id, .. } |
+            EdgeType::RequestError { request_id, .. } => {
+                self.incoming_edges(actor).filter(|cause| cause.as_request_start().map(|start| start.request_id) == Some(*request_id)).collect()
+            }
+            // Elements with a `src` load it because it was most recently set to that value
+            EdgeType::RequestStart { .. } |
+            EdgeType::CrossDom {} if matches!(&actor.node_type, NodeType::HtmlElement { tag_name, .. } | NodeType::FrameOwner { tag_name, .. } if CAN_HAVE_SRC.contains(&tag_name.as_str())) => {
+                let src_changes = self.incoming_edges(actor)
+                    .filter(|cause| matches!(&cause.edge_type, EdgeType::SetAttribute { key, .. } if key == "src"));
+                latest_before(src_changes, edge).into_iter().collect()
+            }
+            // Remote frames are attached to the frame owner that embeds them
+            EdgeType::CrossDom {} if matches!(actor.node_type, NodeType::RemoteFrame { .. }) => {
+                self.incoming_edges(actor).filter(|cause| matches!(cause.edge_type, EdgeType::CrossDom {})).collect()
+            }
+            // Script elements execute once their source has been fetched, or inserted inline
+            EdgeType::Execute {} if matches!(&actor.node_type, NodeType::HtmlElement { tag_name, .. } if tag_name == "script") => {
+                let fetches = self.incoming_edges(actor)
+                    .filter(|cause| matches!(&cause.edge_type, EdgeType::RequestComplete { resource_type, .. } if resource_type == "script"));
+                let inline_insertions = self.edges_of_kind(EdgeKind::InsertNode).filter(|cause| {
+                    matches!(cause.edge_type, EdgeType::InsertNode { parent, .. } if Some(parent) == actor.blink_node_id()) &&
+                        crate::graph::is_same_frame_context(cause.id, actor.id) &&
+                        matches!(self.target_node(cause).node_type, NodeType::TextNode { .. })
+                });
+                fetches.chain(latest_before(inline_insertions, edge)).collect()
+            }
+            // Inline event handlers run because their attribute was most recently set to that value
+            EdgeType::ExecuteFromAttribute { attr_name } => {
+                let handler_changes = self.incoming_edges(actor)
+                    .filter(|cause| matches!(&cause.edge_type, EdgeType::SetAttribute { key, .. } if key == attr_name));
+                latest_before(handler_changes, edge).into_iter().collect()
+            }
+            // Bindings are used because of the binding events they are attached to
+            EdgeType::Binding {} => self.incoming_edges(actor)
+                .filter(|cause| matches!(cause.edge_type, EdgeType::BindingEvent { .. }))
+                .collect(),
+            EdgeType::Execute {} |
+            EdgeType::RequestStart { .. } |
+            EdgeType::SetAttribute { .. } |
+            EdgeType::InsertNode { .. } |
+            EdgeType::BindingEvent { .. } |
+            EdgeType::CreateNode {} => match actor.node_type {
+                // Scripts act because they were executed
+                NodeType::Script { .. } => self.incoming_edges(actor)
+                    .filter(|cause| is_execution(&cause.edge_type) && happened_before(cause, edge))
+                    .collect(),
+                // Parsers act because their frame was loaded
+                NodeType::Parser {} => self.incoming_edges(actor)
+                    .filter(|cause| matches!(cause.edge_type, EdgeType::CrossDom {}))
+                    .collect(),
+                _ => vec![],
+            },
+            _ => vec![],
+        };
+        // Redirected requests are started again because of the redirect response
+        if let EdgeType::RequestStart { request_id, .. } = edge.edge_type {
+            let responses = self.edges_for_request_id(request_id, edge.id).into_iter()
+                .filter(|cause| matches!(cause.edge_type, EdgeType::RequestResponse { .. }) && (cause.edge_timestamp, cause.id) < (edge.edge_timestamp, edge.id));
+            causes.extend(latest_before(responses, edge));
+        }
+        causes.sort_unstable_by_key(|cause| cause.id);
+        causes.dedup();
+        causes
+    }
+
+    /// Returns all actions that led to the given action occurring, back to the initial parsing
+    /// of the page, in timestamp order.
+    pub fn all_upstream_causes_of<'a>(&'a self, edge: &'a Edge) -> Vec<&'a Edge> {
+        let mut already_checked = HashSet::new();
+        already_checked.insert(edge.id);
+        let mut edges_to_check = vec![edge];
+        let mut causes = vec![];
+
+        while let Some(edge) = edges_to_check.pop() {
+            self.direct_upstream_causes_of(edge).into_iter().for_each(|edge|
+                if already_checked.insert(edge.id) {
+                    causes.push(edge);
                     edges_to_check.push(edge);
                 }
             );
         }
-        answer
+
+        causes.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+        causes
+    }
+
+    /// Explains why the request with the given Blink request id, made within the given frame (or
+    /// the root frame, if `None`), was made. Starting from the request, the most recent direct
+    /// cause of each action is followed back to the earliest known cause.
+    ///
+    /// Returns `None` if no such request was started.
+    pub fn explain_request<C: HasFrameId>(&self, request_id: usize, frame_id: C) -> Option<Explanation> {
+        let start = self.edges_for_request_id(request_id, frame_id).into_iter().find(|edge| edge.as_request_start().is_some())?;
+        let request_type = start.as_request_start()?.request_type.clone();
+        let url = self.target_node(start).as_resource()?.to_string();
+
+        let chain = self.initiator_chain(start);
+
+        Some(Explanation {
+            request_id,
+            url,
+            request_type,
+            chain: chain.into_iter().map(|edge| InitiatorStep {
+                edge_id: format!("{}", edge.id),
+                action: edge.edge_type.type_name().to_string(),
+                timestamp: edge.edge_timestamp,
+                actor: NodeSummary::new(self.source_node(edge)),
+                target: NodeSummary::new(self.target_node(edge)),
+            }).collect(),
+        })
+    }
+
+    /// Follows the request with the given Blink request id, made within the given frame (or the
+    /// root frame, if `None`), through each of its redirects, returning every URL requested in
+    /// order. Redirects are taken from `RequestResponse` edges, and from the `Location` header of
+    /// each response where the following `RequestStart` edge wasn't recorded.
+    ///
+    /// Returns an empty list if no such request was recorded.
+    pub fn redirect_chain<C: HasFrameId>(&self, request_id: usize, frame_id: C) -> Vec<RedirectHop> {
+        let mut edges = self.edges_for_request_id(request_id, frame_id);
+        edges.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+
+        let mut hops = Vec::<RedirectHop>::new();
+        edges.into_iter().for_each(|edge| {
+            if edge.as_request_start().is_some() {
+                let url = self.target_node(edge).as_resource().unwrap_or_default().to_string();
+                hops.push(RedirectHop {
+                    url,
+                    start_edge_id: Some(format!("{}", edge.id)),
+                    response_edge_id: None,
+                    status_code: None,
+                    location: None,
+                });
+                return;
+            }
+            let headers = match edge.parsed_headers() {
+                Some(headers) => headers,
+                None => return,
+            };
+            // A response to a hop that was already answered is for the URL it redirected to
+            if !matches!(hops.last(), Some(hop) if hop.response_edge_id.is_none()) {
+                let url = hops.last()
+                    .and_then(|hop| hop.location.clone())
+                    .unwrap_or_else(|| self.source_node(edge).as_resource().unwrap_or_default().to_string());
+                hops.push(RedirectHop { url, start_edge_id: None, response_edge_id: None, status_code: None, location: None });
+            }
+            let hop = hops.last_mut().unwrap();
+            hop.response_edge_id = Some(format!("{}", edge.id));
+            hop.status_code = headers.status_code();
+            hop.location = headers.get("location").map(|location| {
+                url::Url::parse(&hop.url).and_then(|base| base.join(location))
+                    .map(String::from)
+                    .unwrap_or_else(|_| location.to_string())
+            });
+        });
+        hops
+    }
+
+    /// Collects every request made to the given Resource node, with the edges that started and
+    /// finished each one, and any shields or filters that recorded blocking it.
+    ///
+    /// Returns `None` if the node is not a Resource.
+    pub fn resource_info(&self, node_id: NodeId) -> Option<ResourceInfo> {
+        let node = self.nodes.get(&node_id)?;
+        let url = node.as_resource()?.to_string();
+
+        let mut starts = self.incoming_edges(node).filter(|edge| edge.as_request_start().is_some()).collect::<Vec<_>>();
+        starts.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+        let requests = starts.into_iter().map(|start| {
+            let request = start.as_request_start().unwrap();
+            let frame_id = start.id.get_frame_id();
+            // Requests for the same id to other resources belong to other hops of a redirect
+            let finish = self.edges_for_request_id(request.request_id, start.id).into_iter()
+                .filter(|edge| edge.source == node_id)
+                .find(|edge| edge.as_request_complete().is_some() || edge.as_request_error().is_some());
+            let complete = finish.and_then(Edge::as_request_complete);
+            let error = finish.and_then(Edge::as_request_error);
+            ResourceRequest {
+                request_id: request.request_id,
+                frame_id: frame_id.map(|frame_id| format!("{}", frame_id)),
+                request_type: request.request_type.clone(),
+                start_edge_id: format!("{}", start.id),
+                start: start.edge_timestamp,
+                initiator_node_id: format!("{}", start.source),
+                finish_edge_id: finish.map(|edge| format!("{}", edge.id)),
+                finish: finish.and_then(|edge| edge.edge_timestamp),
+                status: complete.as_ref().map(|complete| complete.status.to_string())
+                    .or_else(|| error.as_ref().map(|error| error.status.to_string())),
+                resource_type: complete.as_ref().map(|complete| complete.resource_type.to_string()),
+                response_hash: complete.as_ref().and_then(|complete| complete.response_hash.map(str::to_string)),
+                size: complete.as_ref().map(|complete| complete.size)
+                    .or_else(|| error.as_ref().map(|error| error.size))
+                    .flatten(),
+            }
+        }).collect();
+
+        let mut blocks = self.incoming_edges(node).map(|edge| (edge, edge.source))
+            .chain(self.outgoing_edges(node).map(|edge| (edge, edge.target)))
+            .filter(|(edge, _)| matches!(edge.edge_type, EdgeType::ResourceBlock {}))
+            .collect::<Vec<_>>();
+        blocks.sort_unstable_by_key(|(edge, _)| edge.id);
+        let blocked_by = blocks.into_iter().map(|(edge, blocker)| {
+            let blocker = &self.nodes[&blocker];
+            ResourceBlocker {
+                edge_id: format!("{}", edge.id),
+                node_id: format!("{}", blocker.id),
+                node_type: blocker.node_type.type_name().to_string(),
+                rule: match &blocker.node_type {
+                    NodeType::AdFilter { rule } => Some(rule.clone()),
+                    _ => None,
+                },
+            }
+        }).collect();
+
+        Some(ResourceInfo { node_id: format!("{}", node_id), url, requests, blocked_by })
+    }
+
+    /// Follows the latest upstream cause of each edge back from a request, returning the edges
+    /// from the earliest known cause through to the request itself.
+    pub(crate) fn initiator_chain<'a>(&'a self, start: &'a Edge) -> Vec<&'a Edge> {
+        let mut chain = vec![start];
+        loop {
+            let latest_cause = self.direct_upstream_causes_of(chain[chain.len() - 1])
+                .into_iter()
+                .filter(|cause| !chain.contains(cause))
+                .max_by_key(|cause| (cause.edge_timestamp, cause.id));
+            match latest_cause {
+                Some(cause) => chain.push(cause),
+                None => break,
+            }
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Lists every request made by the page and its merged frames, in the order they started,
+    /// with when each completed or failed, its size, and the chain of actions that led to it, like
+    /// the network panel of a browser's developer tools.
+    pub fn request_waterfall(&self) -> Vec<WaterfallEntry> {
+        let mut starts = self.edges_of_kind(EdgeKind::RequestStart).collect::<Vec<_>>();
+        starts.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+        starts.into_iter().filter_map(|start| {
+            let request = start.as_request_start()?;
+            let url = self.target_node(start).as_resource()?.to_string();
+            let frame_id = start.id.get_frame_id();
+            let edges = self.edges_for_request_id(request.request_id, start.id);
+            let complete = edges.iter().find_map(|edge| Some((*edge, edge.as_request_complete()?)));
+            let error = edges.iter().find_map(|edge| Some((*edge, edge.as_request_error()?)));
+            let (status, size) = match (complete, error) {
+                (Some((_, complete)), _) => (Some(complete.status.to_string()), complete.size),
+                (None, Some((_, error))) => (Some(error.status.to_string()), error.size),
+                (None, None) => (None, None),
+            };
+            Some(WaterfallEntry {
+                request_id: request.request_id,
+                frame_id: frame_id.map(|frame_id| format!("{}", frame_id)),
+                url,
+                request_type: request.request_type.clone(),
+                resource_type: complete.map(|(_, complete)| complete.resource_type.to_string()),
+                start: start.edge_timestamp,
+                complete: complete.and_then(|(edge, _)| edge.edge_timestamp),
+                error: error.and_then(|(edge, _)| edge.edge_timestamp),
+                status,
+                size,
+                initiator_node_id: format!("{}", start.source),
+                initiator_chain: self.initiator_chain(start).into_iter().map(|edge| format!("{}", edge.id)).collect(),
+            })
+        }).collect()
+    }
+
+    /// Determines how a script came to run: the edge that first executed it, how its code got into
+    /// the page, the edge that supplied that code, and the script responsible, if any.
+    fn script_origin(&self, script: &Node) -> (ScriptOrigin, Option<&Edge>, Option<&Edge>, Option<&Node>) {
+        let execution = match self.incoming_edges(script).filter(|edge| is_execution(&edge.edge_type)).min_by_key(|edge| (edge.edge_timestamp, edge.id)) {
+            Some(execution) => execution,
+            None => return (ScriptOrigin::Unknown, None, None, None),
+        };
+        let executor = self.source_node(execution);
+        let script_actor = |cause: Option<&'_ Edge>| cause
+            .map(|cause| self.source_node(cause))
+            .filter(|actor| matches!(actor.node_type, NodeType::Script { .. }));
+
+        let (origin, cause) = match (&execution.edge_type, &executor.node_type) {
+            (EdgeType::Execute {}, NodeType::Script { .. }) => return (ScriptOrigin::Eval, Some(execution), Some(execution), Some(executor)),
+            (EdgeType::ExecuteFromAttribute { .. }, _) => (ScriptOrigin::InlineHandler, self.direct_upstream_causes_of(execution).pop()),
+            (EdgeType::Execute {}, NodeType::HtmlElement { tag_name, .. }) if tag_name == "script" => {
+                let fetched = self.incoming_edges(executor)
+                    .any(|edge| matches!(&edge.edge_type, EdgeType::RequestComplete { resource_type, .. } if resource_type == "script"));
+                // The element's code is supplied by its `src`, or by inserting text into it
+                let code_changes = if fetched {
+                    self.incoming_edges(executor)
+                        .filter(|cause| matches!(&cause.edge_type, EdgeType::SetAttribute { key, .. } if key == "src"))
+                        .collect::<Vec<_>>()
+                } else {
+                    self.edges_of_kind(EdgeKind::InsertNode).filter(|cause| {
+                        matches!(cause.edge_type, EdgeType::InsertNode { parent, .. } if Some(parent) == executor.blink_node_id()) &&
+                            crate::graph::is_same_frame_context(cause.id, executor.id) &&
+                            matches!(self.target_node(cause).node_type, NodeType::TextNode { .. })
+                    }).collect::<Vec<_>>()
+                };
+                let insertions = self.incoming_edges(executor).filter(|cause| matches!(cause.edge_type, EdgeType::InsertNode { .. }));
+                let code_change = latest_before(code_changes.into_iter(), execution);
+                // Scripts can also insert elements whose code was already supplied
+                let cause = match (script_actor(code_change), latest_before(insertions, execution)) {
+                    (None, Some(insertion)) if script_actor(Some(insertion)).is_some() => Some(insertion),
+                    _ => code_change,
+                };
+                match (fetched, script_actor(cause)) {
+                    (true, _) => (ScriptOrigin::Fetched, cause),
+                    (false, Some(_)) => (ScriptOrigin::InjectedText, cause),
+                    (false, None) => (ScriptOrigin::InlineHtml, cause),
+                }
+            }
+            _ => (ScriptOrigin::Unknown, None),
+        };
+        (origin, Some(execution), cause, script_actor(cause))
+    }
+
+    /// Traces how a script came to run, back through the scripts that compiled it from a string,
+    /// inserted it into the page, or set its handler attribute, to the original fetched script or
+    /// inline HTML. The first step is the given script itself, and each later step is the script
+    /// responsible for the one before. Returns an empty chain if the node is not a script.
+    pub fn script_provenance(&self, script_node: NodeId) -> Vec<ProvenanceStep> {
+        let mut chain = vec![];
+        let mut visited = HashSet::new();
+        let mut next = self.nodes.get(&script_node).filter(|node| matches!(node.node_type, NodeType::Script { .. }));
+        while let Some(script) = next.filter(|script| visited.insert(script.id)) {
+            let (origin, execution, cause, parent) = self.script_origin(script);
+            chain.push(ProvenanceStep {
+                script_node_id: format!("{}", script.id),
+                script_url: script.as_script().and_then(|data| data.url.map(str::to_string)),
+                origin,
+                execute_edge_id: execution.map(|edge| format!("{}", edge.id)),
+                cause_edge_id: cause.map(|edge| format!("{}", edge.id)),
+            });
+            next = parent;
+        }
+        chain
+    }
+
+    /// Estimates what user-visible functionality depends on a resource, from what the scripts that
+    /// would not have run without it did: the event listeners they registered, the elements they
+    /// created, and the forms they touched. Returns `None` if the node is not a resource.
+    ///
+    /// This only considers the actions taken directly by those scripts, so it cannot account for
+    /// functionality the page itself provides using values the scripts set up.
+    pub fn breakage_risk(&self, resource_node: NodeId) -> Option<BreakageReport> {
+        let resource = self.nodes.get(&resource_node)?;
+        let url = resource.as_resource()?.to_string();
+
+        let mut effects = HashSet::new();
+        self.incoming_edges(resource)
+            .filter(|edge| matches!(edge.edge_type, EdgeType::RequestStart { .. }))
+            .for_each(|request| effects.extend(self.all_downstream_effects_of(request).into_iter().map(|edge| edge.id)));
+        let effects = effects.into_iter().map(|edge_id| &self.edges[&edge_id]).collect::<Vec<_>>();
+
+        // Scripts that made the request also receive its response, but would still have run
+        let mut scripts = effects.iter()
+            .filter(|edge| is_execution(&edge.edge_type))
+            .map(|edge| edge.target)
+            .collect::<Vec<_>>();
+        scripts.sort_unstable();
+        scripts.dedup();
+
+        let mut script_actions = scripts.iter()
+            .flat_map(|script| self.outgoing_edges(&self.nodes[script]))
+            .collect::<Vec<_>>();
+        script_actions.sort_unstable_by_key(|edge| edge.id);
+
+        let dom = self.final_dom();
+        let tag_name = |node_id: &NodeId| dom.elements.get(node_id).map(|element| element.tag_name.clone());
+
+        let event_listeners = script_actions.iter().filter_map(|edge| self.listener_registration(edge)).collect::<Vec<_>>();
+
+        let mut created_elements = script_actions.iter()
+            .filter(|edge| matches!(edge.edge_type, EdgeType::CreateNode {}))
+            .filter_map(|edge| {
+                let element = dom.elements.get(&edge.target)?;
+                let attached = dom.ancestors(element).last()
+                    .is_some_and(|root| dom.roots.binary_search(&root.node_id).is_ok());
+                Some((edge.target, CreatedElement {
+                    node_id: format!("{}", edge.target),
+                    tag_name: element.tag_name.clone(),
+                    script_node_id: format!("{}", edge.source),
+                    attached,
+                }))
+            })
+            .collect::<Vec<_>>();
+        created_elements.sort_by_key(|(node_id, _)| *node_id);
+
+        let is_form_related = |node_id: &NodeId| dom.elements.get(node_id).is_some_and(|element| {
+            FORM_TAGS.contains(&element.tag_name.as_str()) || dom.ancestors(element).any(|ancestor| ancestor.tag_name == "form")
+        });
+        let form_interactions = script_actions.iter()
+            .filter(|edge| is_form_related(&edge.target))
+            .map(|edge| FormInteraction {
+                edge_id: format!("{}", edge.id),
+                action: edge.edge_type.type_name().to_string(),
+                script_node_id: format!("{}", edge.source),
+                node_id: format!("{}", edge.target),
+                tag_name: tag_name(&edge.target).unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+
+        let risk = if !form_interactions.is_empty() || event_listeners.iter().any(|listener| INPUT_EVENTS.contains(&listener.event.as_str())) {
+            BreakageRisk::High
+        } else if !event_listeners.is_empty() || created_elements.iter().any(|(_, element)| element.attached) {
+            BreakageRisk::Medium
+        } else {
+            BreakageRisk::Low
+        };
+
+        Some(BreakageReport {
+            node_id: format!("{}", resource_node),
+            url,
+            risk,
+            scripts: scripts.iter().map(|script| format!("{}", script)).collect(),
+            downstream_requests: effects.iter().filter(|edge| matches!(edge.edge_type, EdgeType::RequestStart { .. })).count(),
+            event_listeners,
+            created_elements: created_elements.into_iter().map(|(_, element)| element).collect(),
+            form_interactions,
+        })
+    }
+
+    fn listener_registration(&self, edge: &Edge) -> Option<ListenerRegistration> {
+        match &edge.edge_type {
+            EdgeType::AddEventListener { key, .. } => Some(ListenerRegistration {
+                edge_id: format!("{}", edge.id),
+                script_node_id: format!("{}", edge.source),
+                target_node_id: format!("{}", edge.target),
+                event: key.clone(),
+                dispatches: self.direct_downstream_effects_of(edge).len(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the script node with the given script id, recorded in the same frame as the given
+    /// item, if there is one.
+    fn script_with_id<I: HasFrameId + Copy>(&self, script_id: ScriptId, item: I) -> Option<&Node> {
+        self.nodes_of_kind(NodeKind::Script).find(|script| {
+            script.as_script().is_some_and(|data| data.script_id == script_id) && crate::graph::is_same_frame_context(script.id, item)
+        })
+    }
+
+    /// Lists every event listener registered during the recording, in registration order, with
+    /// the element it was registered on, the script handling it, and whether it was later
+    /// removed or ever fired.
+    pub fn event_listeners(&self) -> Vec<EventListenerInfo> {
+        let mut registrations = self.edges_of_kind(EdgeKind::AddEventListener).collect::<Vec<_>>();
+        registrations.sort_unstable_by_key(|edge| edge.id);
+        registrations.into_iter().filter_map(|edge| {
+            let (key, event_listener_id, script_id) = match &edge.edge_type {
+                EdgeType::AddEventListener { key, event_listener_id, script_id } => (key, *event_listener_id, *script_id),
+                _ => return None,
+            };
+            let target = self.target_node(edge);
+            let script = self.script_with_id(script_id, edge.id);
+
+            // The listener is removed by the next removal of it, unless it is registered again first
+            let mut changes = self.incoming_edges(target).filter(|other| other.id != edge.id && happened_before(edge, other) && match &other.edge_type {
+                EdgeType::AddEventListener { key: other_key, event_listener_id: other_id, .. } |
+                EdgeType::RemoveEventListener { key: other_key, event_listener_id: other_id, .. } => other_key == key && *other_id == event_listener_id,
+                _ => false,
+            }).collect::<Vec<_>>();
+            changes.sort_unstable_by_key(|other| (other.edge_timestamp, other.id));
+            let removal = changes.first().filter(|other| matches!(other.edge_type, EdgeType::RemoveEventListener { .. }));
+
+            let mut dispatches = self.direct_downstream_effects_of(edge);
+            dispatches.sort_unstable_by_key(|dispatch| dispatch.id);
+
+            Some(EventListenerInfo {
+                edge_id: format!("{}", edge.id),
+                event: key.clone(),
+                event_listener_id,
+                target_node_id: format!("{}", target.id),
+                target_type: target.node_type.type_name().to_string(),
+                target_tag_name: match &target.node_type {
+                    NodeType::HtmlElement { tag_name, .. } |
+                    NodeType::FrameOwner { tag_name, .. } |
+                    NodeType::DomRoot { tag_name, .. } => Some(tag_name.clone()),
+                    _ => None,
+                },
+                registering_node_id: format!("{}", edge.source),
+                script_id,
+                script_node_id: script.map(|script| format!("{}", script.id)),
+                script_url: script.and_then(|script| script.as_script()?.url.map(str::to_string)),
+                removal_edge_id: removal.map(|removal| format!("{}", removal.id)),
+                dispatch_edge_ids: dispatches.iter().map(|dispatch| format!("{}", dispatch.id)).collect(),
+            })
+        }).collect()
+    }
+
+    /// Lists every script run from an inline event handler attribute, such as `onclick`, with the
+    /// element owning the attribute. Results are sorted by script node, then by element and
+    /// attribute.
+    pub fn inline_handler_scripts(&self) -> Vec<InlineHandlerScript> {
+        let mut handlers = BTreeMap::<(NodeId, NodeId, &str), Vec<&Edge>>::new();
+        self.edges_of_kind(EdgeKind::ExecuteFromAttribute).for_each(|edge| {
+            if let EdgeType::ExecuteFromAttribute { attr_name } = &edge.edge_type {
+                handlers.entry((edge.target, edge.source, attr_name)).or_default().push(edge);
+            }
+        });
+        handlers.into_iter().filter_map(|((script, element, attr_name), mut executions)| {
+            let script_id = self.nodes[&script].as_script()?.script_id;
+            executions.sort_unstable_by_key(|edge| edge.id);
+            let mut set_attributes = executions.iter()
+                .flat_map(|execution| self.direct_upstream_causes_of(execution))
+                .collect::<Vec<_>>();
+            set_attributes.sort_unstable_by_key(|edge| edge.id);
+            set_attributes.dedup();
+            Some(InlineHandlerScript {
+                script_node_id: format!("{}", script),
+                script_id,
+                element_node_id: format!("{}", element),
+                element_tag_name: match &self.nodes[&element].node_type {
+                    NodeType::HtmlElement { tag_name, .. } |
+                    NodeType::FrameOwner { tag_name, .. } |
+                    NodeType::DomRoot { tag_name, .. } => Some(tag_name.clone()),
+                    _ => None,
+                },
+                attr_name: attr_name.to_string(),
+                execution_edge_ids: executions.iter().map(|edge| format!("{}", edge.id)).collect(),
+                set_attribute_edge_ids: set_attributes.iter().map(|edge| format!("{}", edge.id)).collect(),
+            })
+        }).collect()
+    }
+
+    /// Lists every binding event triggered by a script, in id order, with the position in the
+    /// script that triggered it and the bindings it used.
+    pub fn binding_events(&self) -> Vec<BindingEventInfo> {
+        let mut events = self.edges_of_kind(EdgeKind::BindingEvent).collect::<Vec<_>>();
+        events.sort_unstable_by_key(|edge| edge.id);
+        events.into_iter().filter_map(|edge| {
+            let script_position = edge.as_binding_event()?;
+            let script = self.source_node(edge);
+            let event = self.target_node(edge);
+
+            let mut bindings = self.direct_downstream_effects_of(edge);
+            bindings.sort_unstable_by_key(|binding| binding.id);
+
+            Some(BindingEventInfo {
+                edge_id: format!("{}", edge.id),
+                binding_event_node_id: format!("{}", event.id),
+                binding_event: event.as_binding_event()?.to_string(),
+                script_node_id: format!("{}", script.id),
+                script_id: script.as_script().map(|data| data.script_id),
+                script_url: script.as_script().and_then(|data| data.url.map(str::to_string)),
+                script_position,
+                bindings: bindings.into_iter().filter_map(|binding| {
+                    let node = self.target_node(binding);
+                    let (name, binding_type) = node.as_binding()?;
+                    Some(BindingUse {
+                        edge_id: format!("{}", binding.id),
+                        binding_node_id: format!("{}", node.id),
+                        binding: name.to_string(),
+                        binding_type: binding_type.to_string(),
+                    })
+                }).collect(),
+            })
+        }).collect()
+    }
+
+    /// Summarizes everything the given script did during the recording: the requests it made, the
+    /// DOM nodes it changed, the storage it used, the event listeners it registered, the APIs it
+    /// called, and the scripts it executed. Returns `None` if the node is not a script.
+    ///
+    /// Only the script's own actions are included, not those of the scripts it executed.
+    pub fn script_activity(&self, script_node: NodeId) -> Option<ScriptActivity> {
+        let script = self.nodes.get(&script_node)?;
+        let data = script.as_script()?;
+
+        let mut actions = self.outgoing_edges(script).collect::<Vec<_>>();
+        actions.sort_unstable_by_key(|edge| edge.id);
+
+        // Groups actions by target, in order of each target's first action
+        fn group<'a, K: Clone + Eq + std::hash::Hash>(actions: impl Iterator<Item=(K, &'a Edge)>) -> Vec<(K, Vec<String>)> {
+            let mut groups = Vec::<(K, Vec<String>)>::new();
+            let mut indices = HashMap::new();
+            actions.for_each(|(target, edge)| {
+                let index = *indices.entry(target.clone()).or_insert_with(|| {
+                    groups.push((target, vec![]));
+                    groups.len() - 1
+                });
+                groups[index].1.push(format!("{}", edge.id));
+            });
+            groups
+        }
+        let action_groups = |groups: Vec<(String, Vec<String>)>| groups.into_iter()
+            .map(|(target, edge_ids)| ActionGroup { target, count: edge_ids.len(), edge_ids })
+            .collect::<Vec<_>>();
+        let storage_accesses = |is_access: fn(&EdgeType) -> Option<&String>| {
+            let accesses = actions.iter().filter_map(|edge| {
+                let key = is_access(&edge.edge_type)?;
+                Some(((self.target_node(edge).node_type.type_name(), key), *edge))
+            });
+            group(accesses).into_iter().map(|((storage, key), edge_ids)| {
+                StorageAccess { storage: storage.to_string(), key: key.clone(), count: edge_ids.len(), edge_ids }
+            }).collect::<Vec<_>>()
+        };
+        let target_ids = |is_action: fn(&EdgeType) -> bool| {
+            let mut node_ids = Vec::new();
+            actions.iter().filter(|edge| is_action(&edge.edge_type)).for_each(|edge| if !node_ids.contains(&edge.target) {
+                node_ids.push(edge.target);
+            });
+            node_ids.into_iter().map(|node_id| format!("{}", node_id)).collect::<Vec<_>>()
+        };
+        let api_calls = |is_api: fn(&NodeType) -> Option<&String>| {
+            action_groups(group(actions.iter()
+                .filter(|edge| matches!(edge.edge_type, EdgeType::JsCall { .. }))
+                .filter_map(|edge| Some((is_api(&self.target_node(edge).node_type)?.clone(), *edge)))))
+        };
+
+        Some(ScriptActivity {
+            node_id: format!("{}", script.id),
+            script_id: data.script_id,
+            url: data.url.map(str::to_string),
+            requests: actions.iter().filter_map(|edge| match &edge.edge_type {
+                EdgeType::RequestStart { request_id, request_type, .. } => Some(ScriptRequest {
+                    request_id: *request_id,
+                    edge_id: format!("{}", edge.id),
+                    url: self.target_node(edge).as_resource()?.to_string(),
+                    request_type: request_type.clone(),
+                }),
+                _ => None,
+            }).collect(),
+            created_nodes: target_ids(|edge_type| matches!(edge_type, EdgeType::CreateNode {})),
+            modified_nodes: action_groups(group(actions.iter()
+                .filter(|edge| matches!(edge.edge_type, EdgeType::InsertNode { .. } | EdgeType::SetAttribute { .. } | EdgeType::DeleteAttribute { .. }))
+                .map(|edge| (format!("{}", edge.target), *edge)))),
+            removed_nodes: target_ids(|edge_type| matches!(edge_type, EdgeType::RemoveNode {} | EdgeType::DeleteNode {})),
+            storage_reads: storage_accesses(|edge_type| match edge_type {
+                EdgeType::ReadStorageCall { key } => Some(key),
+                _ => None,
+            }),
+            storage_writes: storage_accesses(|edge_type| match edge_type {
+                EdgeType::StorageSet { key, .. } | EdgeType::DeleteStorage { key } | EdgeType::ClearStorage { key } => Some(key),
+                _ => None,
+            }),
+            event_listeners: actions.iter().filter_map(|edge| self.listener_registration(edge)).collect(),
+            web_api_calls: api_calls(|node_type| match node_type {
+                NodeType::WebApi { method } => Some(method),
+                _ => None,
+            }),
+            js_builtin_calls: api_calls(|node_type| match node_type {
+                NodeType::JsBuiltin { method } => Some(method),
+                _ => None,
+            }),
+            child_scripts: actions.iter()
+                .filter(|edge| matches!(edge.edge_type, EdgeType::Execute {}))
+                .filter(|edge| matches!(self.target_node(edge).node_type, NodeType::Script { .. }))
+                .map(|edge| format!("{}", edge.target))
+                .collect(),
+        })
+    }
+
+    /// Checks the structural invariants that the other algorithms rely on, returning every
+    /// violation found. An empty result means the graph is safe to analyze.
+    ///
+    /// Node and edge timestamps are expected to be offsets from the start of the recording. Only
+    /// whether they fall within the recording is checked, not whether they increase with ids.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+
+        let mut edges = self.edges.values().collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|edge| edge.id);
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+
+        edges.iter().for_each(|edge| {
+            [edge.source, edge.target].iter()
+                .filter(|node_id| !self.nodes.contains_key(node_id))
+                .for_each(|missing_node_id| issues.push(ValidationIssue::DanglingEdge { edge_id: edge.id, missing_node_id: *missing_node_id }));
+        });
+
+        let frame_context = |id: &dyn HasFrameId| (id.get_frame_id(), id.get_document());
+
+        let mut parser_counts = HashMap::<(Option<FrameId>, usize), usize>::new();
+        nodes.iter().for_each(|node| {
+            let count = parser_counts.entry(frame_context(&node.id)).or_default();
+            if matches!(node.node_type, NodeType::Parser {}) {
+                *count += 1;
+            }
+        });
+        let mut parser_counts = parser_counts.into_iter().filter(|(_, count)| *count != 1).collect::<Vec<_>>();
+        parser_counts.sort_unstable();
+        issues.extend(parser_counts.into_iter().map(|((frame_id, document), count)| ValidationIssue::ParserCount { frame_id, document, count }));
+
+        let dom_node_ids = nodes.iter().filter_map(|node| match node.node_type {
+            NodeType::HtmlElement { node_id, .. } |
+            NodeType::DomRoot { node_id, .. } |
+            NodeType::FrameOwner { node_id, .. } |
+            NodeType::TextNode { node_id, .. } => Some((frame_context(&node.id), node_id)),
+            _ => None,
+        }).collect::<HashSet<_>>();
+        edges.iter().for_each(|edge| if let EdgeType::InsertNode { parent, .. } = edge.edge_type {
+            if !dom_node_ids.contains(&(frame_context(&edge.id), parent)) {
+                issues.push(ValidationIssue::MissingInsertParent { edge_id: edge.id, parent });
+            }
+        });
+
+        let request_key = |edge: &Edge, request_id: usize| (frame_context(&edge.id), request_id);
+        let started = edges.iter().filter_map(|edge| match edge.edge_type {
+            EdgeType::RequestStart { request_id, .. } => Some(request_key(edge, request_id)),
+            _ => None,
+        }).collect::<HashSet<_>>();
+        let finished = edges.iter().filter_map(|edge| match edge.edge_type {
+            EdgeType::RequestComplete { request_id, .. } | EdgeType::RequestError { request_id, .. } => Some(request_key(edge, request_id)),
+            _ => None,
+        }).collect::<HashSet<_>>();
+        edges.iter().for_each(|edge| match edge.edge_type {
+            EdgeType::RequestStart { request_id, .. } if !finished.contains(&request_key(edge, request_id)) => {
+                issues.push(ValidationIssue::UnfinishedRequest { edge_id: edge.id, request_id });
+            }
+            EdgeType::RequestComplete { request_id, .. } |
+            EdgeType::RequestError { request_id, .. } if !started.contains(&request_key(edge, request_id)) => {
+                issues.push(ValidationIssue::UnstartedRequest { edge_id: edge.id, request_id });
+            }
+            _ => (),
+        });
+
+        let duration = self.desc.time.end.saturating_sub(self.desc.time.start) as isize;
+        let in_range = |timestamp: Timestamp| (0..=duration).contains(&timestamp.as_millis());
+        nodes.iter()
+            .filter(|node| !in_range(node.node_timestamp))
+            .for_each(|node| issues.push(ValidationIssue::NodeTimestampOutOfRange { node_id: node.id, timestamp: node.node_timestamp }));
+        edges.iter()
+            .filter_map(|edge| edge.edge_timestamp.filter(|timestamp| !in_range(*timestamp)).map(|timestamp| (edge.id, timestamp)))
+            .for_each(|(edge_id, timestamp)| issues.push(ValidationIssue::EdgeTimestampOutOfRange { edge_id, timestamp }));
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod validate_tests {
+    use std::convert::TryFrom;
+
+    use crate::graph::{EdgeId, NodeId, Timestamp, ValidationIssue};
+
+    #[test]
+    fn issues_are_detected() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">parser</data><data key="d1">2</data><data key="d2">0</data></node>
+<node id="n3"><data key="d0">DOM root</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://example.com/</data><data key="d4">#document</data><data key="d5">false</data><data key="d6">1</data></node>
+<node id="n4"><data key="d0">HTML element</data><data key="d1">4</data><data key="d2">5</data><data key="d4">div</data><data key="d5">false</data><data key="d6">2</data></node>
+<node id="n5"><data key="d0">resource</data><data key="d1">5</data><data key="d2">0</data><data key="d3">https://example.com/a.js</data></node>
+<edge id="e6" source="n1" target="n4"><data key="d16">insert node</data><data key="d17">6</data><data key="d18">1</data><data key="d19">7</data></edge>
+<edge id="e7" source="n1" target="n5"><data key="d16">request start</data><data key="d17">7</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e8" source="n1" target="n9"><data key="d16">create node</data><data key="d17">8</data><data key="d18">1</data></edge>"#);
+        let edge = |id: &str| EdgeId::try_from(id).unwrap();
+        let node = |id: &str| NodeId::try_from(id).unwrap();
+
+        assert_eq!(graph.validate(), [
+            ValidationIssue::DanglingEdge { edge_id: edge("e8"), missing_node_id: node("n9") },
+            ValidationIssue::ParserCount { frame_id: None, document: 0, count: 2 },
+            ValidationIssue::MissingInsertParent { edge_id: edge("e6"), parent: 7 },
+            ValidationIssue::UnfinishedRequest { edge_id: edge("e7"), request_id: 1 },
+            ValidationIssue::NodeTimestampOutOfRange { node_id: node("n4"), timestamp: Timestamp::from(5) },
+        ]);
+    }
+}
+
+#[cfg(test)]
+mod redirect_tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_for_redirect_options() {
+        let rules = [
+            "||a.com^$script,redirect=noop.js:10",
+            "||b.com^$image,redirect-rule=1x1.gif,domain=c.com",
+            "@@||d.com^$redirect=noop.js",
+            "||e.com^$script",
+            "example.com##.ad",
+        ].iter().map(|rule| rule.to_string()).collect::<Vec<_>>();
+
+        let resources = placeholder_redirect_resources(&rules);
+        let resources = resources.iter().map(|resource| (resource.name.as_str(), &resource.kind)).collect::<Vec<_>>();
+        assert_eq!(resources, vec![
+            ("1x1.gif", &ResourceType::Mime(MimeType::ImageGif)),
+            ("noop.js", &ResourceType::Mime(MimeType::ApplicationJavascript)),
+        ]);
+    }
+}
+
+#[cfg(test)]
+mod redirect_chain_tests {
+    #[test]
+    fn redirects_are_followed() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://a.com/x</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://a.com/b</data></node>
+<edge id="e10" source="n1" target="n2"><data key="d16">request start</data><data key="d17">10</data><data key="d18">1</data><data key="d28">5</data><data key="d29">Image</data><data key="d30">started</data></edge>
+<edge id="e11" source="n2" target="n1"><data key="d16">request response</data><data key="d17">11</data><data key="d18">2</data><data key="d28">5</data><data key="d30">redirect</data><data key="d32">HTTP/1.1 302 Found
+Location: /b</data></edge>
+<edge id="e12" source="n1" target="n3"><data key="d16">request start</data><data key="d17">12</data><data key="d18">3</data><data key="d28">5</data><data key="d29">Image</data><data key="d30">started</data></edge>
+<edge id="e13" source="n3" target="n1"><data key="d16">request response</data><data key="d17">13</data><data key="d18">4</data><data key="d28">5</data><data key="d30">redirect</data><data key="d32">HTTP/1.1 301 Moved Permanently
+Location: https://c.com/</data></edge>
+<edge id="e14" source="n3" target="n1"><data key="d16">request complete</data><data key="d17">14</data><data key="d18">5</data><data key="d28">5</data><data key="d29">image</data><data key="d30">complete</data><data key="d32">HTTP/1.1 200 OK</data><data key="d33">43</data></edge>"#);
+
+        let chain = graph.redirect_chain(5, None);
+        let hops = chain.iter().map(|hop| (hop.url.as_str(), hop.start_edge_id.as_deref(), hop.response_edge_id.as_deref(), hop.status_code)).collect::<Vec<_>>();
+        assert_eq!(hops, [
+            ("https://a.com/x", Some("e10"), Some("e11"), Some(302)),
+            ("https://a.com/b", Some("e12"), Some("e13"), Some(301)),
+            ("https://c.com/", None, Some("e14"), Some(200)),
+        ]);
+        assert_eq!(chain[0].location.as_deref(), Some("https://a.com/b"));
+
+        let edge = |id: &str| graph.edges.values().find(|edge| format!("{}", edge.id) == id).unwrap();
+        let ids = |edges: Vec<&crate::graph::Edge>| edges.iter().map(|edge| format!("{}", edge.id)).collect::<Vec<_>>();
+        assert_eq!(ids(graph.direct_downstream_effects_of(edge("e11"))), ["e12"]);
+        assert_eq!(ids(graph.direct_downstream_effects_of(edge("e13"))), ["e14"]);
+        assert_eq!(ids(graph.direct_upstream_causes_of(edge("e12"))), ["e11"]);
+        assert_eq!(ids(graph.direct_upstream_causes_of(edge("e11"))), ["e10"]);
+    }
+}
+
+#[cfg(test)]
+mod listener_tests {
+    #[test]
+    fn listener_removal_and_dispatch() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">script</data><data key="d1">1</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n2"><data key="d0">HTML element</data><data key="d1">2</data><data key="d2">0</data><data key="d4">button</data><data key="d5">false</data><data key="d6">5</data></node>
+<edge id="e3" source="n1" target="n2"><data key="d16">add event listener</data><data key="d17">3</data><data key="d18">1</data><data key="d21">click</data><data key="d27">1</data><data key="d26">11</data></edge>
+<edge id="e4" source="n2" target="n1"><data key="d16">event listener</data><data key="d17">4</data><data key="d18">2</data><data key="d21">click</data><data key="d27">1</data></edge>
+<edge id="e5" source="n1" target="n2"><data key="d16">remove event listener</data><data key="d17">5</data><data key="d18">3</data><data key="d21">click</data><data key="d27">1</data><data key="d26">11</data></edge>
+<edge id="e6" source="n1" target="n2"><data key="d16">add event listener</data><data key="d17">6</data><data key="d18">4</data><data key="d21">keydown</data><data key="d27">2</data><data key="d26">11</data></edge>"#);
+
+        let listeners = graph.event_listeners();
+        assert_eq!(listeners.len(), 2);
+        assert_eq!(listeners[0].target_tag_name.as_deref(), Some("button"));
+        assert_eq!(listeners[0].script_node_id.as_deref(), Some("n1"));
+        assert_eq!(listeners[0].removal_edge_id.as_deref(), Some("e5"));
+        assert_eq!(listeners[0].dispatch_edge_ids, ["e4"]);
+        assert_eq!(listeners[1].event, "keydown");
+        assert_eq!(listeners[1].removal_edge_id, None);
+        assert!(listeners[1].dispatch_edge_ids.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod binding_tests {
+    #[test]
+    fn binding_events_tie_to_script_positions() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">script</data><data key="d1">1</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n2"><data key="d0">binding event</data><data key="d1">2</data><data key="d2">0</data><data key="d15">Navigator.userAgent</data></node>
+<node id="n3"><data key="d0">binding</data><data key="d1">3</data><data key="d2">0</data><data key="d13">Navigator.userAgent</data><data key="d14">attribute</data></node>
+<edge id="e4" source="n1" target="n2"><data key="d16">binding event</data><data key="d17">4</data><data key="d18">1</data><data key="d25">42</data></edge>
+<edge id="e5" source="n2" target="n3"><data key="d16">binding</data><data key="d17">5</data><data key="d18">1</data></edge>"#);
+
+        let events = graph.binding_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].script_node_id, "n1");
+        assert_eq!(events[0].script_position, 42);
+        assert_eq!(events[0].bindings.len(), 1);
+        assert_eq!(events[0].bindings[0].binding_type, "attribute");
+
+        let binding = graph.edges.values().find(|edge| matches!(edge.edge_type, crate::types::EdgeType::Binding {})).unwrap();
+        assert_eq!(graph.direct_upstream_causes_of(binding).iter().map(|edge| format!("{}", edge.id)).collect::<Vec<_>>(), ["e4"]);
+    }
+}
+
+#[cfg(test)]
+mod inline_handler_tests {
+    #[test]
+    fn inline_handlers_are_attributed() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">HTML element</data><data key="d1">2</data><data key="d2">0</data><data key="d4">button</data><data key="d5">false</data><data key="d6">5</data></node>
+<node id="n3"><data key="d0">script</data><data key="d1">3</data><data key="d2">0</data><data key="d8">inline inside generated attribute</data><data key="d9">12</data><data key="d10">fetch('/track')</data></node>
+<node id="n4"><data key="d0">resource</data><data key="d1">4</data><data key="d2">0</data><data key="d3">https://example.com/track</data></node>
+<node id="n8"><data key="d0">DOM root</data><data key="d1">8</data><data key="d2">0</data><data key="d3">https://example.com/</data><data key="d4">#document</data><data key="d5">false</data><data key="d6">1</data></node>
+<edge id="e9" source="n1" target="n2"><data key="d16">insert node</data><data key="d17">9</data><data key="d18">0</data><data key="d19">1</data></edge>
+<edge id="e5" source="n1" target="n2"><data key="d16">set attribute</data><data key="d17">5</data><data key="d18">1</data><data key="d21">onclick</data><data key="d20">fetch('/track')</data><data key="d22">false</data></edge>
+<edge id="e6" source="n2" target="n3"><data key="d16">execute from attribute</data><data key="d17">6</data><data key="d18">2</data><data key="d23">onclick</data></edge>
+<edge id="e7" source="n3" target="n4"><data key="d16">request start</data><data key="d17">7</data><data key="d18">3</data><data key="d28">1</data><data key="d29">Fetch</data><data key="d30">started</data></edge>"#);
+
+        let handlers = graph.inline_handler_scripts();
+        assert_eq!(handlers.len(), 1);
+        assert_eq!(handlers[0].script_node_id, "n3");
+        assert_eq!(handlers[0].element_tag_name.as_deref(), Some("button"));
+        assert_eq!(handlers[0].attr_name, "onclick");
+        assert_eq!(handlers[0].execution_edge_ids, ["e6"]);
+        assert_eq!(handlers[0].set_attribute_edge_ids, ["e5"]);
+
+        let ids = |edges: Vec<&crate::graph::Edge>| edges.iter().map(|edge| format!("{}", edge.id)).collect::<Vec<_>>();
+        let set_attribute = graph.edges.values().find(|edge| format!("{}", edge.id) == "e5").unwrap();
+        let request = graph.edges.values().find(|edge| format!("{}", edge.id) == "e7").unwrap();
+        assert_eq!(ids(graph.all_downstream_effects_of(set_attribute)), ["e6", "e7"]);
+        assert_eq!(ids(graph.direct_upstream_causes_of(request)), ["e6"]);
+        assert_eq!(ids(graph.all_upstream_causes_of(request)), ["e5", "e6"]);
+        assert_eq!(graph.dom_root_for_edge(request).map(|dom_root| format!("{}", dom_root.id)).as_deref(), Some("n8"));
+
+        let node = |id: &str| graph.nodes.values().find(|node| format!("{}", node.id) == id).unwrap();
+        assert_eq!(ids(graph.all_downstream_effects_of_node(node("n1"))), ["e9", "e5", "e6", "e7"]);
+        assert_eq!(ids(graph.all_downstream_effects_of_node(node("n3"))), ["e7"]);
+        let requests = graph.all_downstream_requests_of_node_nested(node("n2"), 1).unwrap();
+        assert_eq!(requests.iter().map(|request| request.request_id).collect::<Vec<_>>(), [1]);
+        assert!(graph.all_downstream_requests_of_node_nested(node("n4"), 1).unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod provenance_tests {
+    use crate::graph::ScriptOrigin;
+
+    #[test]
+    fn provenance_through_injected_text_and_eval() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">HTML element</data><data key="d1">2</data><data key="d2">0</data><data key="d4">script</data><data key="d5">false</data><data key="d6">5</data></node>
+<node id="n3"><data key="d0">text node</data><data key="d1">3</data><data key="d2">0</data><data key="d7">inject()</data><data key="d5">false</data><data key="d6">6</data></node>
+<node id="n4"><data key="d0">script</data><data key="d1">4</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10">inject()</data></node>
+<node id="n5"><data key="d0">HTML element</data><data key="d1">5</data><data key="d2">0</data><data key="d4">script</data><data key="d5">false</data><data key="d6">7</data></node>
+<node id="n6"><data key="d0">text node</data><data key="d1">6</data><data key="d2">0</data><data key="d7">eval('x')</data><data key="d5">false</data><data key="d6">8</data></node>
+<node id="n7"><data key="d0">script</data><data key="d1">7</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">12</data><data key="d10">eval('x')</data></node>
+<node id="n8"><data key="d0">script</data><data key="d1">8</data><data key="d2">0</data><data key="d8">eval</data><data key="d9">13</data><data key="d10">x</data></node>
+<edge id="e10" source="n1" target="n3"><data key="d16">insert node</data><data key="d17">10</data><data key="d18">1</data><data key="d19">5</data></edge>
+<edge id="e11" source="n2" target="n4"><data key="d16">execute</data><data key="d17">11</data><data key="d18">2</data></edge>
+<edge id="e12" source="n4" target="n6"><data key="d16">insert node</data><data key="d17">12</data><data key="d18">3</data><data key="d19">7</data></edge>
+<edge id="e13" source="n5" target="n7"><data key="d16">execute</data><data key="d17">13</data><data key="d18">4</data></edge>
+<edge id="e14" source="n7" target="n8"><data key="d16">execute</data><data key="d17">14</data><data key="d18">5</data></edge>"#);
+
+        let script = graph.nodes.keys().find(|id| format!("{}", id) == "n8").copied().unwrap();
+        let chain = graph.script_provenance(script);
+        assert_eq!(chain.iter().map(|step| (step.script_node_id.as_str(), step.origin)).collect::<Vec<_>>(), [
+            ("n8", ScriptOrigin::Eval),
+            ("n7", ScriptOrigin::InjectedText),
+            ("n4", ScriptOrigin::InlineHtml),
+        ]);
+        assert_eq!(chain[1].cause_edge_id.as_deref(), Some("e12"));
+        assert_eq!(chain[2].cause_edge_id.as_deref(), Some("e10"));
+    }
+}
+
+#[cfg(test)]
+mod attribute_history_tests {
+    use std::convert::TryFrom;
+
+    use crate::graph::NodeId;
+
+    #[test]
+    fn attribute_values_over_time() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">HTML element</data><data key="d1">2</data><data key="d2">0</data><data key="d4">img</data><data key="d5">false</data><data key="d6">5</data></node>
+<node id="n3"><data key="d0">script</data><data key="d1">3</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">12</data><data key="d10"></data></node>
+<edge id="e7" source="n3" target="n2"><data key="d16">delete attribute</data><data key="d17">7</data><data key="d18">4</data><data key="d21">src</data><data key="d22">false</data></edge>
+<edge id="e5" source="n1" target="n2"><data key="d16">set attribute</data><data key="d17">5</data><data key="d18">1</data><data key="d21">src</data><data key="d20">a.png</data><data key="d22">false</data></edge>
+<edge id="e6" source="n3" target="n2"><data key="d16">set attribute</data><data key="d17">6</data><data key="d18">3</data><data key="d21">SRC</data><data key="d20">b.png</data><data key="d22">false</data></edge>
+<edge id="e8" source="n3" target="n2"><data key="d16">set attribute</data><data key="d17">8</data><data key="d18">2</data><data key="d21">src</data><data key="d20">red</data><data key="d22">true</data></edge>"#);
+
+        let node = |id: &str| NodeId::try_from(id).unwrap();
+        let history = graph.attribute_history(node("n2"), "src").into_iter()
+            .map(|(timestamp, value, actor)| (timestamp.as_millis(), value, actor))
+            .collect::<Vec<_>>();
+        assert_eq!(history, [
+            (1, Some("a.png".to_string()), node("n1")),
+            (3, Some("b.png".to_string()), node("n3")),
+            (4, None, node("n3")),
+        ]);
+        assert!(graph.attribute_history(node("n2"), "alt").is_empty());
+    }
+}
+
+#[cfg(test)]
+mod frame_tree_tests {
+    #[test]
+    fn local_frames_nest_under_their_documents() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">DOM root</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/</data><data key="d4">#document</data><data key="d5">false</data><data key="d6">1</data></node>
+<node id="n3"><data key="d0">frame owner</data><data key="d1">3</data><data key="d2">0</data><data key="d4">IFRAME</data><data key="d5">false</data><data key="d6">2</data></node>
+<node id="n4"><data key="d0">DOM root</data><data key="d1">4</data><data key="d2">0</data><data key="d3">about:blank</data><data key="d4">#document</data><data key="d5">false</data><data key="d6">3</data></node>
+<edge id="e5" source="n1" target="n3"><data key="d16">insert node</data><data key="d17">5</data><data key="d18">1</data><data key="d19">1</data></edge>
+<edge id="e6" source="n1" target="n3"><data key="d16">set attribute</data><data key="d17">6</data><data key="d18">2</data><data key="d21">src</data><data key="d20">about:blank</data><data key="d22">false</data></edge>
+<edge id="e7" source="n3" target="n4"><data key="d16">cross DOM</data><data key="d17">7</data><data key="d18">3</data></edge>"#);
+
+        let tree = graph.frame_tree();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].node_id, "n2");
+        let frame_owner = &tree[0].children[0];
+        assert_eq!(frame_owner.tag_name.as_deref(), Some("iframe"));
+        assert_eq!(frame_owner.url.as_deref(), Some("about:blank"));
+        assert_eq!(frame_owner.children[0].node_id, "n4");
+        assert_eq!(frame_owner.children[0].merged, None);
+    }
+}
+
+#[cfg(test)]
+mod waterfall_tests {
+    #[test]
+    fn requests_are_ordered_by_start() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/a.css</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://example.com/b.png</data></node>
+<edge id="e4" source="n1" target="n3"><data key="d16">request start</data><data key="d17">4</data><data key="d18">5</data><data key="d28">2</data><data key="d29">Image</data><data key="d30">started</data></edge>
+<edge id="e5" source="n1" target="n2"><data key="d16">request start</data><data key="d17">5</data><data key="d18">3</data><data key="d28">1</data><data key="d29">CSS</data><data key="d30">started</data></edge>
+<edge id="e6" source="n2" target="n1"><data key="d16">request complete</data><data key="d17">6</data><data key="d18">7</data><data key="d28">1</data><data key="d29">stylesheet</data><data key="d30">complete</data><data key="d32"></data><data key="d33">120</data><data key="d31">abc</data></edge>"#);
+
+        let waterfall = graph.request_waterfall();
+        assert_eq!(waterfall.iter().map(|entry| entry.request_id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(waterfall[0].url, "https://example.com/a.css");
+        assert_eq!(waterfall[0].complete.map(|timestamp| timestamp.as_millis()), Some(7));
+        assert_eq!(waterfall[0].size, Some(120));
+        assert_eq!(waterfall[0].resource_type.as_deref(), Some("stylesheet"));
+        assert_eq!(waterfall[0].initiator_chain, ["e5"]);
+        assert_eq!(waterfall[1].complete, None);
+        assert_eq!(waterfall[1].status, None);
+    }
+}
+
+#[cfg(test)]
+mod resource_info_tests {
+    #[test]
+    fn requests_and_blocks_are_collected() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://ads.example/a.js</data></node>
+<node id="n3"><data key="d0">ad filter</data><data key="d1">3</data><data key="d2">0</data><data key="d12">||ads.example^</data></node>
+<edge id="e4" source="n1" target="n2"><data key="d16">request start</data><data key="d17">4</data><data key="d18">3</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e5" source="n2" target="n1"><data key="d16">request complete</data><data key="d17">5</data><data key="d18">4</data><data key="d28">1</data><data key="d29">script</data><data key="d30">complete</data><data key="d32"></data><data key="d33">50</data><data key="d31">abc</data></edge>
+<edge id="e6" source="n1" target="n2"><data key="d16">request start</data><data key="d17">6</data><data key="d18">8</data><data key="d28">2</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e7" source="n2" target="n1"><data key="d16">request error</data><data key="d17">7</data><data key="d18">9</data><data key="d28">2</data><data key="d30">error</data><data key="d32"></data><data key="d33">0</data></edge>
+<edge id="e8" source="n3" target="n2"><data key="d16">resource block</data><data key="d17">8</data><data key="d18">8</data></edge>"#);
+        let node_id = graph.nodes.keys().find(|node_id| format!("{}", node_id) == "n2").copied().unwrap();
+
+        let info = graph.resource_info(node_id).unwrap();
+        assert_eq!(info.requests.iter().map(|request| request.request_id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(info.requests[0].finish_edge_id.as_deref(), Some("e5"));
+        assert_eq!(info.requests[0].response_hash.as_deref(), Some("abc"));
+        assert_eq!(info.requests[0].size, Some(50));
+        assert_eq!(info.requests[1].status.as_deref(), Some("error"));
+        assert!(info.is_blocked());
+        assert_eq!(info.blocked_by[0].rule.as_deref(), Some("||ads.example^"));
+    }
+}
+
+#[cfg(test)]
+mod downstream_nesting_tests {
+    use crate::graph::DownstreamError;
+
+    // a.js is loaded by a script element, and sets the src of another script element twice to
+    // load b.js, which in turn loads an image.
+    const NODES_AND_EDGES: &str = r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">HTML element</data><data key="d1">2</data><data key="d2">0</data><data key="d4">script</data><data key="d5">false</data><data key="d6">2</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://example.com/a.js</data></node>
+<node id="n4"><data key="d0">script</data><data key="d1">4</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n5"><data key="d0">HTML element</data><data key="d1">5</data><data key="d2">0</data><data key="d4">script</data><data key="d5">false</data><data key="d6">3</data></node>
+<node id="n6"><data key="d0">resource</data><data key="d1">6</data><data key="d2">0</data><data key="d3">https://example.com/b.js</data></node>
+<node id="n7"><data key="d0">script</data><data key="d1">7</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">12</data><data key="d10"></data></node>
+<node id="n8"><data key="d0">resource</data><data key="d1">8</data><data key="d2">0</data><data key="d3">https://example.com/pixel.png</data></node>
+<edge id="e10" source="n2" target="n3"><data key="d16">request start</data><data key="d17">10</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e11" source="n3" target="n2"><data key="d16">request complete</data><data key="d17">11</data><data key="d18">2</data><data key="d28">1</data><data key="d29">script</data><data key="d30">complete</data><data key="d32"></data><data key="d33">10</data></edge>
+<edge id="e12" source="n2" target="n4"><data key="d16">execute</data><data key="d17">12</data><data key="d18">3</data></edge>
+<edge id="e13" source="n4" target="n5"><data key="d16">set attribute</data><data key="d17">13</data><data key="d18">4</data><data key="d21">src</data><data key="d20">b.js</data><data key="d22">false</data></edge>
+<edge id="e14" source="n4" target="n5"><data key="d16">set attribute</data><data key="d17">14</data><data key="d18">5</data><data key="d21">src</data><data key="d20">b.js</data><data key="d22">false</data></edge>
+<edge id="e15" source="n5" target="n6"><data key="d16">request start</data><data key="d17">15</data><data key="d18">6</data><data key="d28">2</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e16" source="n6" target="n5"><data key="d16">request complete</data><data key="d17">16</data><data key="d18">7</data><data key="d28">2</data><data key="d29">script</data><data key="d30">complete</data><data key="d32"></data><data key="d33">10</data></edge>
+<edge id="e17" source="n5" target="n7"><data key="d16">execute</data><data key="d17">17</data><data key="d18">8</data></edge>
+<edge id="e18" source="n7" target="n8"><data key="d16">request start</data><data key="d17">18</data><data key="d18">9</data><data key="d28">3</data><data key="d29">Image</data><data key="d30">started</data></edge>"#;
+
+    #[test]
+    fn requests_reachable_along_several_paths_are_reported_once() {
+        let graph = crate::test_util::parse(NODES_AND_EDGES);
+        let start = graph.edges_for_request_id(1, None)[0];
+
+        let requests = graph.all_downstream_requests_nested(start, 2).unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].request_id, 2);
+        assert_eq!(requests[0].children.iter().map(|request| request.request_id).collect::<Vec<_>>(), [3]);
+    }
+
+    #[test]
+    fn requests_nested_too_deeply_are_an_error() {
+        let graph = crate::test_util::parse(NODES_AND_EDGES);
+        let start = graph.edges_for_request_id(1, None)[0];
+
+        let result = graph.all_downstream_requests_nested(start, 1);
+        assert!(matches!(result, Err(DownstreamError::MaxDepthExceeded { edge_id, max_depth: 1 }) if format!("{}", edge_id) == "e15"));
+    }
+}
+
+#[cfg(test)]
+mod blocking_effects_tests {
+    #[test]
+    fn blocks_have_no_downstream_effects() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://ads.example/a.js</data></node>
+<node id="n3"><data key="d0">ad filter</data><data key="d1">3</data><data key="d2">0</data><data key="d12">||ads.example^</data></node>
+<node id="n4"><data key="d0">shieldsAds shield</data><data key="d1">4</data><data key="d2">0</data></node>
+<edge id="e5" source="n1" target="n2"><data key="d16">request start</data><data key="d17">5</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e6" source="n3" target="n2"><data key="d16">resource block</data><data key="d17">6</data><data key="d18">2</data></edge>
+<edge id="e7" source="n4" target="n3"><data key="d16">shield</data><data key="d17">7</data><data key="d18">2</data></edge>"#);
+        let edge = |id: &str| graph.edges.values().find(|edge| format!("{}", edge.id) == id).unwrap();
+
+        assert!(graph.all_downstream_effects_of(edge("e6")).is_empty());
+        assert!(graph.all_downstream_effects_of(edge("e7")).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod dom_root_cache_tests {
+    use std::convert::TryFrom;
+
+    use crate::graph::NodeId;
+    use crate::types::EdgeType;
+
+    #[test]
+    fn dom_roots_follow_modifications() {
+        let mut graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">DOM root</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/</data><data key="d4">#document</data><data key="d5">false</data><data key="d6">1</data></node>
+<node id="n3"><data key="d0">DOM root</data><data key="d1">3</data><data key="d2">0</data><data key="d3">about:blank</data><data key="d4">#document</data><data key="d5">false</data><data key="d6">4</data></node>
+<node id="n4"><data key="d0">HTML element</data><data key="d1">4</data><data key="d2">1</data><data key="d4">div</data><data key="d5">false</data><data key="d6">2</data></node>
+<edge id="e5" source="n1" target="n4"><data key="d16">create node</data><data key="d17">5</data><data key="d18">1</data></edge>
+<edge id="e6" source="n1" target="n4"><data key="d16">insert node</data><data key="d17">6</data><data key="d18">2</data><data key="d19">1</data></edge>"#);
+        let node = |id: &str| NodeId::try_from(id).unwrap();
+        let dom_root = |graph: &crate::graph::PageGraph| graph.dom_root_for_html_node(&graph.nodes[&node("n4")]).map(|dom_root| dom_root.id);
+        assert_eq!(dom_root(&graph), Some(node("n2")));
+
+        graph.edges.values_mut().for_each(|edge| if let EdgeType::InsertNode { parent, .. } = &mut edge.edge_type {
+            *parent = 4;
+        });
+        graph.invalidate_indexes();
+
+        assert_eq!(dom_root(&graph), Some(node("n3")));
     }
 }
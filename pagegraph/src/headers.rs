@@ -0,0 +1,100 @@
+//! Parsing for the raw response headers recorded on `RequestResponse`, `RequestComplete`, and
+//! `RequestError` edges.
+
+/// Response headers parsed from a raw header block, borrowed from the edge they were recorded on.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ResponseHeaders<'a> {
+    /// The HTTP status line, e.g. `HTTP/1.1 200 OK`, if one was recorded.
+    pub status_line: Option<&'a str>,
+    /// Header names and values in the order they were recorded, with surrounding whitespace
+    /// removed. Lines without a colon are kept as names with empty values.
+    pub headers: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> ResponseHeaders<'a> {
+    /// Splits a raw header block into individual headers. An HTTP status line, if present, is
+    /// kept separately, and blank lines are ignored.
+    pub fn parse(raw: &'a str) -> Self {
+        let mut status_line = None;
+        let headers = raw.lines()
+            .filter(|line| {
+                if line.starts_with("HTTP/") {
+                    status_line = Some(*line);
+                    false
+                } else {
+                    !line.trim().is_empty()
+                }
+            })
+            .map(|line| match line.split_once(':') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => (line.trim(), ""),
+            })
+            .collect();
+        Self { status_line, headers }
+    }
+
+    /// Returns the values of every header with the given name, compared case-insensitively.
+    pub fn get_all<'s>(&'s self, name: &'s str) -> impl Iterator<Item=&'a str> + 's {
+        self.headers.iter()
+            .filter(move |(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    }
+
+    /// Returns the value of the first header with the given name, compared case-insensitively.
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.get_all(name).next()
+    }
+
+    /// Returns the numeric status code from the status line, if one was recorded.
+    pub fn status_code(&self) -> Option<u16> {
+        self.status_line?.split(' ').nth(1)?.parse().ok()
+    }
+
+    /// Returns the value of the `Content-Type` header.
+    pub fn content_type(&self) -> Option<&'a str> {
+        self.get("content-type")
+    }
+
+    /// Returns the value of every `Set-Cookie` header.
+    pub fn set_cookie_headers(&self) -> Vec<&'a str> {
+        self.get_all("set-cookie").collect()
+    }
+
+    /// Returns the name and value of each cookie set by the response, ignoring cookie attributes.
+    pub fn set_cookies(&self) -> Vec<(&'a str, &'a str)> {
+        self.get_all("set-cookie")
+            .filter_map(|header| header.split(';').next())
+            .filter_map(|cookie| cookie.split_once('='))
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .collect()
+    }
+
+    /// Returns the directives of every `Cache-Control` header, e.g. `max-age=3600`.
+    pub fn cache_control(&self) -> Vec<&'a str> {
+        self.get_all("cache-control")
+            .flat_map(|header| header.split(','))
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod headers_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_headers() {
+        let headers = ResponseHeaders::parse("HTTP/1.1 200 OK\nContent-Type: text/html; charset=utf-8\n\nset-cookie: a=b; Path=/\nSet-Cookie: c = d\nCache-Control: no-cache, max-age=0\ncache-control: private\nmalformed\n");
+        assert_eq!(headers.status_line, Some("HTTP/1.1 200 OK"));
+        assert_eq!(headers.status_code(), Some(200));
+        assert_eq!(headers.content_type(), Some("text/html; charset=utf-8"));
+        assert_eq!(headers.set_cookie_headers(), vec!["a=b; Path=/", "c = d"]);
+        assert_eq!(headers.set_cookies(), vec![("a", "b"), ("c", "d")]);
+        assert_eq!(headers.cache_control(), vec!["no-cache", "max-age=0", "private"]);
+        assert_eq!(headers.get("malformed"), Some(""));
+        assert_eq!(headers.get("expires"), None);
+
+        assert_eq!(ResponseHeaders::parse(""), ResponseHeaders::default());
+    }
+}
@@ -0,0 +1,179 @@
+//! Detection of identifiers shared between third parties through request URLs, as in cookie
+//! syncing.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::graph::{Edge, Node, PageGraph};
+use crate::types::{EdgeKind, EdgeType, NodeType};
+use crate::url_utils;
+
+/// Query parameter values shorter than this are not considered identifiers.
+pub const MIN_IDENTIFIER_LENGTH: usize = 8;
+
+/// Query parameter values with less Shannon entropy than this, in bits per character, are not
+/// considered identifiers. This excludes most words, timestamps, and other low-variety values.
+pub const MIN_IDENTIFIER_ENTROPY: f64 = 3.0;
+
+/// Returns the Shannon entropy of `value`, in bits per character.
+pub fn shannon_entropy(value: &str) -> f64 {
+    let mut counts = HashMap::<char, usize>::new();
+    value.chars().for_each(|c| *counts.entry(c).or_default() += 1);
+    let length = value.chars().count() as f64;
+    counts.values().map(|count| {
+        let p = *count as f64 / length;
+        -p * p.log2()
+    }).sum()
+}
+
+/// Whether a query parameter value looks like it could identify a user. URLs are excluded, since
+/// pages commonly pass their own URL to third parties.
+fn is_identifier(value: &str) -> bool {
+    value.len() >= MIN_IDENTIFIER_LENGTH &&
+        shannon_entropy(value) >= MIN_IDENTIFIER_ENTROPY &&
+        url::Url::parse(value).is_err()
+}
+
+/// A third-party request carrying a shared identifier.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IdentifierOccurrence {
+    pub request_edge_id: String,
+    pub request_id: usize,
+    pub url: String,
+    /// The site the request was made to.
+    pub domain: String,
+    /// The query parameter carrying the identifier.
+    pub param: String,
+    /// The script that made the request, or that set the `src` of the element that made it.
+    pub script_node_id: Option<String>,
+    pub script_url: Option<String>,
+}
+
+/// A query parameter value sent to more than one third-party site.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SharedIdentifier {
+    pub value: String,
+    /// Shannon entropy of the value, in bits per character.
+    pub entropy: f64,
+    /// The sites the value was sent to, sorted.
+    pub domains: Vec<String>,
+    /// Every third-party request carrying the value, in id order.
+    pub occurrences: Vec<IdentifierOccurrence>,
+}
+
+/// Two third-party sites that were sent the same identifiers, and so may be syncing them.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SyncPartners {
+    pub domain_a: String,
+    pub domain_b: String,
+    /// The number of distinct identifiers sent to both sites.
+    pub shared_identifiers: usize,
+}
+
+/// The identifiers shared between third parties in a graph, as reported by
+/// [`PageGraph::shared_identifiers`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct IdentifierSharing {
+    /// Shared identifiers, sorted by value.
+    pub identifiers: Vec<SharedIdentifier>,
+    /// Pairs of sites sharing identifiers, with the pairs sharing the most first.
+    pub partners: Vec<SyncPartners>,
+}
+
+impl PageGraph {
+    /// Returns the script responsible for a request: the script that started it, or the script
+    /// that most recently set the `src` of the element that started it.
+    fn script_responsible_for(&self, request: &Edge) -> Option<&Node> {
+        let requester = self.source_node(request);
+        if matches!(requester.node_type, NodeType::Script { .. }) {
+            return Some(requester);
+        }
+        self.direct_upstream_causes_of(request).into_iter()
+            .map(|cause| self.source_node(cause))
+            .find(|actor| matches!(actor.node_type, NodeType::Script { .. }))
+    }
+
+    /// Finds high-entropy query parameter values that were sent to more than one third-party
+    /// site, which may be identifiers being shared between them, e.g. by cookie syncing. Only
+    /// requests to sites other than that of the document making them are considered.
+    pub fn shared_identifiers(&self) -> IdentifierSharing {
+        let mut occurrences = BTreeMap::<String, Vec<IdentifierOccurrence>>::new();
+
+        let mut requests = self.edges_of_kind(EdgeKind::RequestStart).collect::<Vec<_>>();
+        requests.sort_unstable_by_key(|edge| edge.id);
+        requests.into_iter().for_each(|request| {
+            let url = match &self.target_node(request).node_type {
+                NodeType::Resource { url } => url,
+                _ => return,
+            };
+            if url_utils::is_third_party(url, &self.document_url_for(request.id)) != Some(true) {
+                return;
+            }
+            let (domain, parsed) = match (url_utils::site(url), url::Url::parse(url)) {
+                (Some(domain), Ok(parsed)) => (domain, parsed),
+                _ => return,
+            };
+            let request_id = match request.edge_type {
+                EdgeType::RequestStart { request_id, .. } => request_id,
+                _ => return,
+            };
+            let script = self.script_responsible_for(request);
+
+            parsed.query_pairs().filter(|(_, value)| is_identifier(value)).for_each(|(param, value)| {
+                occurrences.entry(value.into_owned()).or_default().push(IdentifierOccurrence {
+                    request_edge_id: format!("{}", request.id),
+                    request_id,
+                    url: url.clone(),
+                    domain: domain.clone(),
+                    param: param.into_owned(),
+                    script_node_id: script.map(|script| format!("{}", script.id)),
+                    script_url: script.and_then(|script| match &script.node_type {
+                        NodeType::Script { url, .. } => url.clone(),
+                        _ => None,
+                    }),
+                });
+            });
+        });
+
+        let mut partners = BTreeMap::<(String, String), usize>::new();
+        let identifiers = occurrences.into_iter().filter_map(|(value, occurrences)| {
+            let domains = occurrences.iter().map(|occurrence| occurrence.domain.clone()).collect::<BTreeSet<_>>();
+            if domains.len() < 2 {
+                return None;
+            }
+            let domains = domains.into_iter().collect::<Vec<_>>();
+            domains.iter().enumerate().for_each(|(i, domain_a)| {
+                domains[i + 1..].iter().for_each(|domain_b| {
+                    *partners.entry((domain_a.clone(), domain_b.clone())).or_default() += 1;
+                });
+            });
+            Some(SharedIdentifier {
+                entropy: shannon_entropy(&value),
+                value,
+                domains,
+                occurrences,
+            })
+        }).collect();
+
+        let mut partners = partners.into_iter()
+            .map(|((domain_a, domain_b), shared_identifiers)| SyncPartners { domain_a, domain_b, shared_identifiers })
+            .collect::<Vec<_>>();
+        partners.sort_by_key(|partners| std::cmp::Reverse(partners.shared_identifiers));
+
+        IdentifierSharing { identifiers, partners }
+    }
+}
+
+#[cfg(test)]
+mod identifiers_tests {
+    use super::*;
+
+    #[test]
+    fn test_is_identifier() {
+        assert!(is_identifier("abc123def456"));
+        assert!(is_identifier("3f2a9c1e-77b0-4d2e-9a61-0c5be8f1d4a7"));
+        assert!(!is_identifier("abc123"));
+        assert!(!is_identifier("1700000000000"));
+        assert!(!is_identifier("homepage"));
+        assert!(!is_identifier("https://example.com/page"));
+    }
+}
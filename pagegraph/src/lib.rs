@@ -2,3 +2,29 @@ pub mod graph;
 mod graph_algos;
 pub mod types;
 pub mod from_xml;
+pub mod export;
+pub mod headers;
+pub mod url_utils;
+pub mod diff;
+pub mod dom;
+pub mod cosmetic;
+pub mod suggest;
+pub mod corpus;
+pub mod storage;
+pub mod identifiers;
+pub mod fingerprinting;
+pub mod call_graph;
+pub mod pixels;
+pub mod search;
+pub mod entities;
+pub mod weight;
+pub mod third_party;
+pub mod stats;
+pub mod timeline;
+pub mod centrality;
+pub mod paths;
+pub mod walk;
+pub mod consequences;
+
+#[cfg(test)]
+pub(crate) mod test_util;
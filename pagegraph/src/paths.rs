@@ -0,0 +1,160 @@
+//! Finding the chains of edges that connect two nodes, to answer questions like "how is this
+//! tracker connected to this DOM element?".
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::graph::{Edge, NodeId, PageGraph, Timestamp};
+use crate::types::EdgeKind;
+
+/// Restricts the paths found by [`PageGraph::paths_between`].
+#[derive(Debug, Clone)]
+pub struct PathConstraints {
+    /// Leave out `Structure` edges, which describe the shape of the graph rather than anything
+    /// that happened.
+    pub exclude_structure: bool,
+    /// Only follow edges recorded no earlier than the previous edge in the path. Edges without a
+    /// timestamp can always be followed.
+    pub respect_timestamps: bool,
+    /// Follow edges against their direction too.
+    pub undirected: bool,
+    /// The most edges a path may have.
+    pub max_length: usize,
+    /// Stop after finding this many paths.
+    pub max_paths: usize,
+}
+
+impl Default for PathConstraints {
+    fn default() -> Self {
+        Self {
+            exclude_structure: true,
+            respect_timestamps: false,
+            undirected: false,
+            max_length: 6,
+            max_paths: 10,
+        }
+    }
+}
+
+/// A single edge in a [`GraphPath`], in the direction it was followed.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PathStep {
+    pub edge_id: String,
+    pub edge_type: String,
+    pub from: String,
+    pub to: String,
+    pub timestamp: Option<Timestamp>,
+    /// Whether the edge was followed against its direction.
+    pub reversed: bool,
+}
+
+/// A chain of edges from one node to another, as reported by [`PageGraph::paths_between`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GraphPath {
+    pub steps: Vec<PathStep>,
+}
+
+impl PageGraph {
+    /// Returns the edges that can be followed from a node, with the node each leads to and
+    /// whether it is followed against its direction. Of several edges of the same type between
+    /// the same nodes, only the earliest that can be followed is kept.
+    fn path_edges(&self, node_id: NodeId, after: Option<Timestamp>, constraints: &PathConstraints) -> Vec<(&Edge, NodeId, bool)> {
+        let node = &self.nodes[&node_id];
+        let outgoing = self.outgoing_edges(node).map(|edge| (edge, edge.target, false));
+        let incoming = self.incoming_edges(node).map(|edge| (edge, edge.source, true)).filter(|_| constraints.undirected);
+
+        let mut earliest = BTreeMap::<(NodeId, bool, &str), &Edge>::new();
+        outgoing.chain(incoming)
+            .filter(|(edge, ..)| !(constraints.exclude_structure && edge.edge_type.kind() == EdgeKind::Structure))
+            .filter(|(edge, ..)| !constraints.respect_timestamps || match (after, edge.edge_timestamp) {
+                (Some(after), Some(timestamp)) => timestamp >= after,
+                _ => true,
+            })
+            .for_each(|(edge, next, reversed)| {
+                let kept = earliest.entry((next, reversed, edge.edge_type.type_name())).or_insert(edge);
+                if (edge.edge_timestamp, edge.id) < (kept.edge_timestamp, kept.id) {
+                    *kept = edge;
+                }
+            });
+        earliest.into_iter().map(|((next, reversed, _), edge)| (edge, next, reversed)).collect()
+    }
+
+    /// Finds paths of edges leading from one node to another that satisfy the given constraints,
+    /// shortest first. Paths never visit the same node twice.
+    ///
+    /// The number of candidate paths grows quickly with their length, so `max_length` should be
+    /// kept small on large graphs.
+    pub fn paths_between(&self, source: NodeId, target: NodeId, constraints: &PathConstraints) -> Vec<GraphPath> {
+        if !self.nodes.contains_key(&source) || !self.nodes.contains_key(&target) {
+            return vec![];
+        }
+
+        let mut paths = vec![];
+        let mut queue = VecDeque::from([(vec![source], Vec::<(&Edge, bool)>::new())]);
+        while let Some((nodes, edges)) = queue.pop_front() {
+            if paths.len() >= constraints.max_paths {
+                break;
+            }
+            let current = *nodes.last().unwrap();
+            if current == target && !edges.is_empty() {
+                paths.push(GraphPath {
+                    steps: edges.iter().zip(nodes.windows(2)).map(|((edge, reversed), pair)| PathStep {
+                        edge_id: format!("{}", edge.id),
+                        edge_type: edge.edge_type.type_name().to_string(),
+                        from: format!("{}", pair[0]),
+                        to: format!("{}", pair[1]),
+                        timestamp: edge.edge_timestamp,
+                        reversed: *reversed,
+                    }).collect(),
+                });
+                continue;
+            }
+            if edges.len() >= constraints.max_length {
+                continue;
+            }
+            let after = edges.iter().rev().find_map(|(edge, _)| edge.edge_timestamp);
+            self.path_edges(current, after, constraints).into_iter()
+                .filter(|(_, next, _)| !nodes.contains(next))
+                .for_each(|(edge, next, reversed)| {
+                    let mut nodes = nodes.clone();
+                    nodes.push(next);
+                    let mut edges = edges.clone();
+                    edges.push((edge, reversed));
+                    queue.push_back((nodes, edges));
+                });
+        }
+        paths
+    }
+}
+
+#[cfg(test)]
+mod paths_tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_between() {
+        let graph = crate::test_util::parse(r#"<node id="n2"><data key="d0">script</data><data key="d1">2</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n3"><data key="d0">HTML element</data><data key="d1">3</data><data key="d2">0</data><data key="d4">img</data><data key="d5">false</data><data key="d6">5</data></node>
+<node id="n4"><data key="d0">resource</data><data key="d1">4</data><data key="d2">0</data><data key="d3">https://tracker.net/p.gif</data></node>
+<edge id="e5" source="n2" target="n3"><data key="d16">create node</data><data key="d17">5</data><data key="d18">2</data></edge>
+<edge id="e6" source="n2" target="n3"><data key="d16">set attribute</data><data key="d17">6</data><data key="d18">5</data><data key="d21">src</data><data key="d20">https://tracker.net/p.gif</data><data key="d22">false</data></edge>
+<edge id="e7" source="n3" target="n4"><data key="d16">request start</data><data key="d17">7</data><data key="d18">3</data><data key="d28">1</data><data key="d29">Image</data><data key="d30">started</data></edge>
+<edge id="e8" source="n2" target="n4"><data key="d16">structure</data><data key="d17">8</data></edge>"#);
+        let node_id = |id: &str| graph.nodes.keys().find(|node_id| format!("{}", node_id) == id).copied().unwrap();
+        let edge_ids = |paths: &[GraphPath]| paths.iter()
+            .map(|path| path.steps.iter().map(|step| step.edge_id.as_str()).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>();
+
+        let paths = graph.paths_between(node_id("n2"), node_id("n4"), &PathConstraints::default());
+        assert_eq!(edge_ids(&paths), ["e5 e7", "e6 e7"]);
+
+        let ordered = PathConstraints { respect_timestamps: true, ..Default::default() };
+        assert_eq!(edge_ids(&graph.paths_between(node_id("n2"), node_id("n4"), &ordered)), ["e5 e7"]);
+
+        let with_structure = PathConstraints { exclude_structure: false, ..Default::default() };
+        assert_eq!(edge_ids(&graph.paths_between(node_id("n2"), node_id("n4"), &with_structure))[0], "e8");
+
+        assert!(graph.paths_between(node_id("n4"), node_id("n2"), &PathConstraints::default()).is_empty());
+        let undirected = PathConstraints { undirected: true, max_paths: 1, ..Default::default() };
+        assert!(graph.paths_between(node_id("n4"), node_id("n2"), &undirected)[0].steps[0].reversed);
+    }
+}
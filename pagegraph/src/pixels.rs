@@ -0,0 +1,230 @@
+//! Detection of elements styled to be invisible, such as tracking pixels, from the attributes and
+//! inline styles set on them.
+
+use std::collections::BTreeSet;
+
+use crate::graph::{Edge, Node, PageGraph};
+use crate::types::{EdgeType, NodeKind, NodeType};
+
+/// Images at most this many pixels wide and high are too small to be seen.
+pub const MAX_PIXEL_SIZE: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HidingTechnique {
+    /// `display: none`, or the `hidden` attribute.
+    DisplayNone,
+    /// `visibility: hidden`.
+    VisibilityHidden,
+    /// A width or height of zero.
+    ZeroSize,
+    /// An image with a width and height of at most [`MAX_PIXEL_SIZE`].
+    TinyImage,
+}
+
+/// A request made by an invisible element.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InvisibleElementRequest {
+    /// The `RequestStart` edge from the element to the resource.
+    pub edge_id: String,
+    pub request_id: usize,
+    pub url: String,
+}
+
+/// An element styled to be invisible, as reported by [`PageGraph::invisible_elements`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InvisibleElement {
+    pub node_id: String,
+    /// The tag name, in lowercase.
+    pub tag_name: String,
+    /// The distinct techniques used to hide the element, sorted.
+    pub techniques: Vec<HidingTechnique>,
+    /// The `SetAttribute` edges that hid the element, in id order.
+    pub edge_ids: Vec<String>,
+    /// The scripts that created or hid the element, in id order. Elements created and hidden by
+    /// the parser have none.
+    pub script_node_ids: Vec<String>,
+    /// The requests made by the element, in id order.
+    pub requests: Vec<InvisibleElementRequest>,
+}
+
+/// Parses a CSS length or HTML size attribute into pixels, ignoring any `px` unit.
+fn parse_size(value: &str) -> Option<f64> {
+    let value = value.trim();
+    value.strip_suffix("px").unwrap_or(value).trim().parse::<f64>().ok()
+}
+
+/// Returns the dimension and size set by a width or height declaration or attribute.
+fn size_declaration(property: &str, value: &str) -> Option<(bool, f64)> {
+    let is_width = match property {
+        "width" => true,
+        "height" => false,
+        _ => return None,
+    };
+    parse_size(value).map(|size| (is_width, size))
+}
+
+/// Splits the contents of a `style` attribute into lowercase property names and values, without
+/// any `!important`.
+fn style_declarations(style: &str) -> impl Iterator<Item=(String, String)> + '_ {
+    style.split(';').filter_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        let value = value.trim().to_ascii_lowercase();
+        let value = value.strip_suffix("!important").unwrap_or(&value).trim().to_string();
+        Some((property.trim().to_ascii_lowercase(), value))
+    })
+}
+
+impl PageGraph {
+    /// Returns the elements and frame owners that were styled to be invisible at any point during
+    /// the recording, along with the scripts responsible and any requests the elements made. This
+    /// catches tracking pixels, as well as hidden frames used for tracking or fingerprinting.
+    ///
+    /// An element is hidden if it is given `display: none`, `visibility: hidden`, or a width or
+    /// height of zero, through its inline style or its `style`, `width`, `height`, or `hidden`
+    /// attributes. Images given a width and height of at most [`MAX_PIXEL_SIZE`] are hidden too.
+    /// Results are in node id order.
+    pub fn invisible_elements(&self) -> Vec<InvisibleElement> {
+        let mut elements = self.nodes_of_kind(NodeKind::HtmlElement)
+            .chain(self.nodes_of_kind(NodeKind::FrameOwner))
+            .collect::<Vec<_>>();
+        elements.sort_unstable_by_key(|element| element.id);
+        elements.into_iter().filter_map(|element| self.invisible_element(element)).collect()
+    }
+
+    fn invisible_element(&self, element: &Node) -> Option<InvisibleElement> {
+        let tag_name = match &element.node_type {
+            NodeType::HtmlElement { tag_name, .. } |
+            NodeType::FrameOwner { tag_name, .. } => tag_name.to_ascii_lowercase(),
+            _ => return None,
+        };
+
+        let mut edges = self.incoming_edges(element).collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|edge| edge.id);
+
+        let mut techniques = BTreeSet::new();
+        let mut hiding_edges = Vec::<&Edge>::new();
+        let mut tiny_size_edges = Vec::<&Edge>::new();
+        let (mut tiny_width, mut tiny_height) = (false, false);
+        edges.iter().for_each(|edge| {
+            let (key, value, is_style) = match &edge.edge_type {
+                EdgeType::SetAttribute { key, value, is_style } => (key.to_ascii_lowercase(), value.as_deref().unwrap_or_default(), *is_style),
+                _ => return,
+            };
+            let declarations = if key == "style" {
+                style_declarations(value).collect::<Vec<_>>()
+            } else if is_style || key == "width" || key == "height" {
+                vec![(key.clone(), value.trim().to_ascii_lowercase())]
+            } else if key == "hidden" {
+                vec![("display".to_string(), "none".to_string())]
+            } else {
+                vec![]
+            };
+
+            let mut hides = false;
+            declarations.iter().for_each(|(property, value)| {
+                let technique = match (property.as_str(), value.as_str()) {
+                    ("display", "none") => Some(HidingTechnique::DisplayNone),
+                    ("visibility", "hidden") => Some(HidingTechnique::VisibilityHidden),
+                    (property, value) => match size_declaration(property, value) {
+                        Some((_, size)) if size <= 0.0 => Some(HidingTechnique::ZeroSize),
+                        Some((is_width, size)) if size <= MAX_PIXEL_SIZE && tag_name == "img" => {
+                            match is_width {
+                                true => tiny_width = true,
+                                false => tiny_height = true,
+                            }
+                            tiny_size_edges.push(edge);
+                            None
+                        }
+                        _ => None,
+                    },
+                };
+                if let Some(technique) = technique {
+                    techniques.insert(technique);
+                    hides = true;
+                }
+            });
+            if hides {
+                hiding_edges.push(edge);
+            }
+        });
+        if tiny_width && tiny_height {
+            techniques.insert(HidingTechnique::TinyImage);
+            hiding_edges.extend(tiny_size_edges);
+            hiding_edges.sort_unstable_by_key(|edge| edge.id);
+            hiding_edges.dedup_by_key(|edge| edge.id);
+        }
+        if techniques.is_empty() {
+            return None;
+        }
+
+        let script_node_ids = edges.iter()
+            .filter(|edge| matches!(edge.edge_type, EdgeType::CreateNode {}) || hiding_edges.iter().any(|hiding| hiding.id == edge.id))
+            .map(|edge| self.source_node(edge))
+            .filter(|node| matches!(node.node_type, NodeType::Script { .. }))
+            .map(|script| script.id)
+            .collect::<BTreeSet<_>>();
+
+        let mut requests = self.outgoing_edges(element)
+            .filter_map(|edge| Some((edge, edge.as_request_start()?)))
+            .collect::<Vec<_>>();
+        requests.sort_unstable_by_key(|(edge, _)| edge.id);
+
+        Some(InvisibleElement {
+            node_id: format!("{}", element.id),
+            tag_name,
+            techniques: techniques.into_iter().collect(),
+            edge_ids: hiding_edges.iter().map(|edge| format!("{}", edge.id)).collect(),
+            script_node_ids: script_node_ids.into_iter().map(|node_id| format!("{}", node_id)).collect(),
+            requests: requests.into_iter().map(|(edge, request)| InvisibleElementRequest {
+                edge_id: format!("{}", edge.id),
+                request_id: request.request_id,
+                url: self.target_node(edge).as_resource().unwrap_or_default().to_string(),
+            }).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod pixels_tests {
+    use super::*;
+
+    #[test]
+    fn test_style_declarations() {
+        let declarations = style_declarations("Display: none !important; width:1px;;bogus").collect::<Vec<_>>();
+        assert_eq!(declarations, [
+            ("display".to_string(), "none".to_string()),
+            ("width".to_string(), "1px".to_string()),
+        ]);
+        assert_eq!(parse_size(" 0px "), Some(0.0));
+        assert_eq!(parse_size("auto"), None);
+    }
+
+    #[test]
+    fn test_invisible_elements() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">script</data><data key="d1">2</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n3"><data key="d0">HTML element</data><data key="d1">3</data><data key="d2">0</data><data key="d4">IMG</data><data key="d5">false</data><data key="d6">5</data></node>
+<node id="n4"><data key="d0">resource</data><data key="d1">4</data><data key="d2">0</data><data key="d3">https://pixel.com/1x1.gif</data></node>
+<node id="n5"><data key="d0">HTML element</data><data key="d1">5</data><data key="d2">0</data><data key="d4">div</data><data key="d5">false</data><data key="d6">6</data></node>
+<node id="n6"><data key="d0">HTML element</data><data key="d1">6</data><data key="d2">0</data><data key="d4">img</data><data key="d5">false</data><data key="d6">7</data></node>
+<edge id="e10" source="n2" target="n3"><data key="d16">create node</data><data key="d17">10</data><data key="d18">1</data></edge>
+<edge id="e11" source="n2" target="n3"><data key="d16">set attribute</data><data key="d17">11</data><data key="d18">2</data><data key="d21">width</data><data key="d20">1</data><data key="d22">false</data></edge>
+<edge id="e12" source="n2" target="n3"><data key="d16">set attribute</data><data key="d17">12</data><data key="d18">3</data><data key="d21">height</data><data key="d20">1px</data><data key="d22">true</data></edge>
+<edge id="e13" source="n3" target="n4"><data key="d16">request start</data><data key="d17">13</data><data key="d18">4</data><data key="d28">7</data><data key="d29">Image</data><data key="d30">started</data></edge>
+<edge id="e14" source="n1" target="n5"><data key="d16">set attribute</data><data key="d17">14</data><data key="d18">5</data><data key="d21">style</data><data key="d20">color: red; visibility: hidden</data><data key="d22">false</data></edge>
+<edge id="e15" source="n1" target="n6"><data key="d16">set attribute</data><data key="d17">15</data><data key="d18">6</data><data key="d21">width</data><data key="d20">1</data><data key="d22">false</data></edge>"#);
+
+        let invisible = graph.invisible_elements();
+        assert_eq!(invisible.len(), 2);
+        assert_eq!(invisible[0].node_id, "n3");
+        assert_eq!(invisible[0].tag_name, "img");
+        assert_eq!(invisible[0].techniques, [HidingTechnique::TinyImage]);
+        assert_eq!(invisible[0].edge_ids, ["e11", "e12"]);
+        assert_eq!(invisible[0].script_node_ids, ["n2"]);
+        assert_eq!(invisible[0].requests[0].url, "https://pixel.com/1x1.gif");
+        assert_eq!(invisible[1].node_id, "n5");
+        assert_eq!(invisible[1].techniques, [HidingTechnique::VisibilityHidden]);
+        assert!(invisible[1].script_node_ids.is_empty());
+    }
+}
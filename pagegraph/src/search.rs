@@ -0,0 +1,175 @@
+//! Searching the URLs and script sources recorded in a graph, so that nodes can be found without
+//! knowing their ids.
+
+use regex::Regex;
+
+use crate::graph::PageGraph;
+use crate::types::{NodeKind, NodeType, ScriptId};
+
+/// A pattern to search for with [`PageGraph::find_resources_matching`] or
+/// [`PageGraph::scripts_containing`].
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// Matches text containing the given string.
+    Substring(String),
+    /// Matches text containing a match for the given regular expression.
+    Regex(Regex),
+    /// Matches text entirely matched by a glob, where `*` matches any run of characters and `?`
+    /// matches any single character. Stored as the equivalent anchored regular expression.
+    Glob(Regex),
+}
+
+impl Pattern {
+    pub fn substring(pattern: &str) -> Self {
+        Self::Substring(pattern.to_string())
+    }
+
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self::Regex)
+    }
+
+    pub fn glob(pattern: &str) -> Self {
+        let translated = pattern.split('*')
+            .map(|part| part.split('?').map(regex::escape).collect::<Vec<_>>().join("."))
+            .collect::<Vec<_>>()
+            .join(".*");
+        Self::Glob(Regex::new(&format!("^{}$", translated)).expect("Escaped glob should be a valid regex"))
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Self::Substring(pattern) => text.contains(pattern.as_str()),
+            Self::Regex(regex) | Self::Glob(regex) => regex.is_match(text),
+        }
+    }
+
+    /// Returns the byte offsets of the start and end of each non-overlapping match in the text.
+    pub fn find_offsets(&self, text: &str) -> Vec<(usize, usize)> {
+        match self {
+            Self::Substring(pattern) if pattern.is_empty() => vec![],
+            Self::Substring(pattern) => text.match_indices(pattern.as_str())
+                .map(|(start, matched)| (start, start + matched.len()))
+                .collect(),
+            Self::Regex(regex) | Self::Glob(regex) => regex.find_iter(text)
+                .map(|found| (found.start(), found.end()))
+                .collect(),
+        }
+    }
+}
+
+/// Selects which URLs [`PageGraph::find_resources_matching`] searches, in addition to those of
+/// Resource nodes.
+#[derive(Debug, Clone, Default)]
+pub struct UrlSearchOptions {
+    /// Also search the URLs that scripts were fetched from.
+    pub scripts: bool,
+    /// Also search the document URLs of DOM roots.
+    pub dom_roots: bool,
+}
+
+/// A node whose URL matched a [`Pattern`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct UrlMatch {
+    pub node_id: String,
+    pub node_type: String,
+    pub url: String,
+}
+
+/// A script whose source matched a [`Pattern`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScriptMatch {
+    pub node_id: String,
+    pub script_id: ScriptId,
+    /// The URL the script was fetched from, or `None` for inline scripts.
+    pub url: Option<String>,
+    /// The byte offsets of the start and end of each match in the searched source.
+    pub offsets: Vec<(usize, usize)>,
+}
+
+impl PageGraph {
+    /// Finds every Resource node whose URL matches the given pattern, and optionally every script
+    /// and DOM root, sorted by node id.
+    pub fn find_resources_matching(&self, pattern: &Pattern, options: &UrlSearchOptions) -> Vec<UrlMatch> {
+        let mut matches = self.nodes.values().filter_map(|node| {
+            let url = match &node.node_type {
+                NodeType::Resource { url } => url,
+                NodeType::Script { url: Some(url), .. } if options.scripts => url,
+                NodeType::DomRoot { url: Some(url), .. } if options.dom_roots => url,
+                _ => return None,
+            };
+            if !pattern.is_match(url) {
+                return None;
+            }
+            Some((node.id, node.node_type.type_name(), url))
+        }).collect::<Vec<_>>();
+        matches.sort_unstable_by_key(|(node_id, ..)| *node_id);
+        matches.into_iter().map(|(node_id, node_type, url)| UrlMatch {
+            node_id: format!("{}", node_id),
+            node_type: node_type.to_string(),
+            url: url.clone(),
+        }).collect()
+    }
+
+    /// Finds every script whose source matches the given pattern, sorted by node id. Sources are
+    /// recorded with HTML entities escaped; if `decode_entities` is set they are decoded before
+    /// searching, as `request_id_info --source` prints them, and offsets refer to the decoded
+    /// source.
+    pub fn scripts_containing(&self, pattern: &Pattern, decode_entities: bool) -> Vec<ScriptMatch> {
+        let mut matches = self.nodes_of_kind(NodeKind::Script).filter_map(|node| {
+            let script = node.as_script()?;
+            let offsets = if decode_entities {
+                pattern.find_offsets(&html_escape::decode_html_entities(script.source))
+            } else {
+                pattern.find_offsets(script.source)
+            };
+            if offsets.is_empty() {
+                return None;
+            }
+            Some((node.id, ScriptMatch {
+                node_id: format!("{}", node.id),
+                script_id: script.script_id,
+                url: script.url.map(str::to_string),
+                offsets,
+            }))
+        }).collect::<Vec<_>>();
+        matches.sort_unstable_by_key(|(node_id, _)| *node_id);
+        matches.into_iter().map(|(_, script_match)| script_match).collect()
+    }
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::*;
+
+    #[test]
+    fn test_glob() {
+        let pattern = Pattern::glob("https://*.example.com/?.js");
+        assert!(pattern.is_match("https://cdn.example.com/a.js"));
+        assert!(!pattern.is_match("https://cdn.example.com/ab.js"));
+        assert!(!pattern.is_match("https://cdn.example.com/a.json"));
+        assert!(Pattern::glob("*[1].js").is_match("https://example.com/x[1].js"));
+    }
+
+    #[test]
+    fn test_find_resources_matching() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">resource</data><data key="d1">1</data><data key="d2">0</data><data key="d3">https://tracker.net/t.js</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/style.css</data></node>
+<node id="n3"><data key="d0">script</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://tracker.net/t.js</data><data key="d8">classic</data><data key="d9">11</data><data key="d10">if (a &amp;lt; b) fbq('init'); fbq('track');</data></node>"#);
+
+        let pattern = Pattern::regex(r"tracker\.net/.*\.js$").unwrap();
+        let matches = graph.find_resources_matching(&pattern, &UrlSearchOptions::default());
+        assert_eq!(matches.iter().map(|m| m.node_id.as_str()).collect::<Vec<_>>(), ["n1"]);
+        let matches = graph.find_resources_matching(&pattern, &UrlSearchOptions { scripts: true, ..Default::default() });
+        assert_eq!(matches.iter().map(|m| m.node_id.as_str()).collect::<Vec<_>>(), ["n1", "n3"]);
+        assert_eq!(matches[1].node_type, "script");
+        assert_eq!(graph.find_resources_matching(&Pattern::substring("example.com"), &UrlSearchOptions::default()).len(), 1);
+
+        let pattern = Pattern::regex(r"fbq\('\w+'\)").unwrap();
+        let matches = graph.scripts_containing(&pattern, false);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].script_id, 11);
+        assert_eq!(matches[0].offsets, [(14, 25), (27, 39)]);
+        assert_eq!(graph.scripts_containing(&Pattern::substring("a < b"), false).len(), 0);
+        assert_eq!(graph.scripts_containing(&Pattern::substring("a < b"), true)[0].offsets, [(4, 9)]);
+    }
+}
@@ -0,0 +1,94 @@
+//! Summary statistics about a graph, for getting a quick sense of what happened during a page
+//! load or comparing pages across a corpus.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::graph::{HasFrameId, PageGraph};
+use crate::types::{EdgeKind, NodeKind};
+
+/// Counts of what was recorded in a graph, as reported by [`PageGraph::stats`]. Each breakdown is
+/// keyed by name, in alphabetical order.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct GraphStats {
+    pub nodes: usize,
+    pub edges: usize,
+    pub nodes_by_type: BTreeMap<String, usize>,
+    pub edges_by_type: BTreeMap<String, usize>,
+    /// HTML elements and frame owners recorded, whether or not they were ever inserted.
+    pub dom_elements_created: usize,
+    /// HTML elements and frame owners still in a document when the recording ended.
+    pub dom_elements_retained: usize,
+    /// Requests by the type given when they started, counting each redirect separately.
+    pub requests_by_type: BTreeMap<String, usize>,
+    /// Request ids by the status they finished with, or `pending` if they never finished.
+    pub requests_by_status: BTreeMap<String, usize>,
+    /// Scripts by how they came to run, as in [`ScriptOrigin`](crate::graph::ScriptOrigin).
+    pub scripts_by_origin: BTreeMap<String, usize>,
+    /// Remote frames recorded, whether or not their graphs were merged in.
+    pub remote_frames: usize,
+    /// Remote frames whose graphs were merged in.
+    pub merged_frames: usize,
+    /// The length of the recording, in milliseconds.
+    pub duration_ms: u64,
+}
+
+impl PageGraph {
+    /// Counts the nodes and edges of each type in the graph, along with the DOM elements created
+    /// and retained, requests by type and status, scripts by origin, and frames.
+    pub fn stats(&self) -> GraphStats {
+        let mut stats = GraphStats {
+            nodes: self.nodes.len(),
+            edges: self.edges.len(),
+            duration_ms: self.desc.time.end.saturating_sub(self.desc.time.start),
+            ..Default::default()
+        };
+
+        self.nodes.values().for_each(|node| *stats.nodes_by_type.entry(node.node_type.type_name().to_string()).or_default() += 1);
+        self.edges.values().for_each(|edge| *stats.edges_by_type.entry(edge.edge_type.type_name().to_string()).or_default() += 1);
+
+        stats.dom_elements_created = self.nodes_of_kind(NodeKind::HtmlElement).count() + self.nodes_of_kind(NodeKind::FrameOwner).count();
+        let dom = self.final_dom();
+        stats.dom_elements_retained = dom.roots.iter().map(|root| dom.descendants(*root).len()).sum();
+
+        self.edges_of_kind(EdgeKind::RequestStart).filter_map(|edge| edge.as_request_start()).for_each(|request| {
+            *stats.requests_by_type.entry(request.request_type.as_str().to_string()).or_default() += 1;
+        });
+        self.all_request_ids().into_iter().for_each(|(_, _, _, _, _, status)| {
+            *stats.requests_by_status.entry(status.unwrap_or_else(|| "pending".to_string())).or_default() += 1;
+        });
+
+        self.nodes_of_kind(NodeKind::Script).for_each(|script| {
+            if let Some(step) = self.script_provenance(script.id).first() {
+                *stats.scripts_by_origin.entry(step.origin.as_str().to_string()).or_default() += 1;
+            }
+        });
+
+        stats.remote_frames = self.nodes_of_kind(NodeKind::RemoteFrame).count();
+        stats.merged_frames = self.nodes.keys().filter_map(|node_id| node_id.get_frame_id()).collect::<BTreeSet<_>>().len();
+        stats
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    #[test]
+    fn test_stats() {
+        // A recording lasting two and a half seconds
+        let header = crate::test_util::HEADER.replace("<start>1</start><end>2</end>", "<start>1000</start><end>3500</end>");
+        let graph = crate::test_util::parse_with_header(&header, r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/a.png</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://example.com/b.png</data></node>
+<edge id="e4" source="n1" target="n2"><data key="d16">request start</data><data key="d17">4</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Image</data><data key="d30">started</data></edge>
+<edge id="e5" source="n2" target="n1"><data key="d16">request complete</data><data key="d17">5</data><data key="d18">2</data><data key="d28">1</data><data key="d29">image</data><data key="d30">complete</data><data key="d32"></data><data key="d33">10</data></edge>
+<edge id="e6" source="n1" target="n3"><data key="d16">request start</data><data key="d17">6</data><data key="d18">3</data><data key="d28">2</data><data key="d29">Image</data><data key="d30">started</data></edge>"#);
+
+        let stats = graph.stats();
+        assert_eq!((stats.nodes, stats.edges, stats.duration_ms), (3, 3, 2500));
+        assert_eq!(stats.nodes_by_type["resource"], 2);
+        assert_eq!(stats.edges_by_type["request start"], 2);
+        assert_eq!(stats.requests_by_type["image"], 2);
+        assert_eq!(stats.requests_by_status["complete"], 1);
+        assert_eq!(stats.requests_by_status["pending"], 1);
+        assert_eq!(stats.merged_frames, 0);
+    }
+}
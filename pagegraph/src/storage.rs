@@ -0,0 +1,503 @@
+//! Analysis of the cookies and web storage that scripts used during a page load.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use base64::Engine as _;
+use md5::{Digest, Md5};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha1::Sha1;
+use sha2::Sha256;
+
+use crate::graph::{Edge, Node, NodeId, PageGraph};
+use crate::graph_algos::happened_before;
+use crate::types::{EdgeKind, EdgeType, NodeKind, NodeType};
+use crate::url_utils;
+
+/// The number of each kind of access made to a key or by a script.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct AccessCounts {
+    pub reads: usize,
+    pub writes: usize,
+    pub deletes: usize,
+    pub clears: usize,
+}
+
+impl AccessCounts {
+    pub(crate) fn count(&mut self, edge_type: &EdgeType) {
+        match edge_type {
+            EdgeType::ReadStorageCall { .. } => self.reads += 1,
+            EdgeType::StorageSet { .. } => self.writes += 1,
+            EdgeType::DeleteStorage { .. } => self.deletes += 1,
+            EdgeType::ClearStorage { .. } => self.clears += 1,
+            _ => (),
+        }
+    }
+}
+
+/// Every access made to a single key of a storage area.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct KeyUsage {
+    /// The type of storage area: `cookie jar`, `local storage`, or `session storage`.
+    pub storage: String,
+    pub key: String,
+    #[serde(flatten)]
+    pub counts: AccessCounts,
+    /// The scripts or other actors that accessed the key, in node id order.
+    pub actors: Vec<String>,
+    pub edge_ids: Vec<String>,
+}
+
+/// Every storage access made by a single script, or by another actor such as the parser.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ActorUsage {
+    pub node_id: String,
+    pub node_type: String,
+    /// The URL the script was loaded from, or `None` for inline scripts and other actors.
+    pub url: Option<String>,
+    /// Whether the script was loaded from a different site than the document it ran in, or `None`
+    /// if it has no URL.
+    pub is_third_party: Option<bool>,
+    #[serde(flatten)]
+    pub counts: AccessCounts,
+    /// The storage areas and keys accessed, sorted.
+    pub keys: Vec<(String, String)>,
+    pub edge_ids: Vec<String>,
+}
+
+/// The storage used by a page, as reported by [`PageGraph::storage_report`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct StorageReport {
+    /// Usage of each key, sorted by storage area and key.
+    pub keys: Vec<KeyUsage>,
+    /// Usage by each actor, in node id order.
+    pub actors: Vec<ActorUsage>,
+}
+
+/// An attribute of a cookie, such as `Path=/` or `Secure`, as written by the script that set it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CookieAttribute {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/// A cookie written to or read from `document.cookie`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// Always empty for cookies that were read, since `document.cookie` doesn't return attributes.
+    pub attributes: Vec<CookieAttribute>,
+}
+
+impl Cookie {
+    /// Parses a single cookie as written to `document.cookie`, e.g. `uid=abc; Path=/; Secure`.
+    /// As in browsers, a cookie without a `=` has an empty name.
+    pub fn parse_set_cookie(value: &str) -> Self {
+        let mut parts = value.split(';');
+        let (name, value) = split_pair(parts.next().unwrap_or_default());
+        let attributes = parts
+            .filter(|attribute| !attribute.trim().is_empty())
+            .map(|attribute| match attribute.split_once('=') {
+                Some((name, value)) => CookieAttribute { name: name.trim().to_string(), value: Some(value.trim().to_string()) },
+                None => CookieAttribute { name: attribute.trim().to_string(), value: None },
+            })
+            .collect();
+        Self { name, value, attributes }
+    }
+
+    /// Parses the cookies returned by reading `document.cookie`, e.g. `a=b; uid=abc`.
+    pub fn parse_cookie_string(value: &str) -> Vec<Self> {
+        value.split(';')
+            .filter(|pair| !pair.trim().is_empty())
+            .map(|pair| {
+                let (name, value) = split_pair(pair);
+                Self { name, value, attributes: vec![] }
+            })
+            .collect()
+    }
+}
+
+fn split_pair(pair: &str) -> (String, String) {
+    match pair.split_once('=') {
+        Some((name, value)) => (name.trim().to_string(), value.trim().to_string()),
+        None => (String::new(), pair.trim().to_string()),
+    }
+}
+
+/// A script setting a cookie through `document.cookie`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CookieWrite {
+    pub edge_id: String,
+    pub node_id: String,
+    pub url: Option<String>,
+    /// Whether the cookie was set by a script loaded from a different site than the document it
+    /// ran in, or `None` if the script has no URL.
+    pub is_third_party: Option<bool>,
+    /// The cookie written, or `None` if no value was recorded.
+    pub cookie: Option<Cookie>,
+}
+
+/// A script reading `document.cookie`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CookieRead {
+    pub edge_id: String,
+    /// The `StorageReadResult` edge returning the cookies to the script, if one was recorded.
+    pub result_edge_id: Option<String>,
+    pub node_id: String,
+    pub url: Option<String>,
+    pub is_third_party: Option<bool>,
+    pub cookies: Vec<Cookie>,
+}
+
+/// The cookies used by a page, as reported by [`PageGraph::cookie_accesses`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct CookieAccesses {
+    pub writes: Vec<CookieWrite>,
+    pub reads: Vec<CookieRead>,
+}
+
+/// Stored values shorter than this are too likely to appear in requests by coincidence to be
+/// reported as leaks.
+pub const MIN_LEAKED_VALUE_LENGTH: usize = 6;
+
+/// The characters escaped by JavaScript's `encodeURIComponent`.
+const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-').remove(b'_').remove(b'.').remove(b'!').remove(b'~')
+    .remove(b'*').remove(b'\'').remove(b'(').remove(b')');
+
+/// How a stored value was transformed before it was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakEncoding {
+    Plain,
+    /// Escaped as by `encodeURIComponent`.
+    UrlEncoded,
+    /// Base64-encoded with the standard or URL-safe alphabet, with or without padding.
+    Base64,
+    /// Hashed, and hex-encoded in either case.
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+/// Returns each encoding of `value` to search for, with the hashes in lowercase.
+fn encodings(value: &str) -> Vec<(LeakEncoding, String)> {
+    let mut encodings = vec![(LeakEncoding::Plain, value.to_string())];
+    let url_encoded = utf8_percent_encode(value, URI_COMPONENT).to_string();
+    if url_encoded != value {
+        encodings.push((LeakEncoding::UrlEncoded, url_encoded));
+    }
+    let standard = base64::engine::general_purpose::STANDARD_NO_PAD.encode(value);
+    let url_safe = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(value);
+    if url_safe != standard {
+        encodings.push((LeakEncoding::Base64, url_safe));
+    }
+    encodings.push((LeakEncoding::Base64, standard));
+    encodings.push((LeakEncoding::Md5, format!("{:x}", Md5::digest(value))));
+    encodings.push((LeakEncoding::Sha1, format!("{:x}", Sha1::digest(value))));
+    encodings.push((LeakEncoding::Sha256, format!("{:x}", Sha256::digest(value))));
+    encodings
+}
+
+/// Returns the first encoding of a value found in `haystack`. Hashes are matched regardless of
+/// case.
+fn find_encoding(encodings: &[(LeakEncoding, String)], haystack: &str) -> Option<LeakEncoding> {
+    let lowercase = haystack.to_ascii_lowercase();
+    encodings.iter().find(|(encoding, needle)| match encoding {
+        LeakEncoding::Md5 | LeakEncoding::Sha1 | LeakEncoding::Sha256 => lowercase.contains(needle.as_str()),
+        _ => haystack.contains(needle.as_str()),
+    }).map(|(encoding, _)| *encoding)
+}
+
+/// Where in a request a stored value was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakLocation {
+    Url,
+    /// PageGraph only records the headers of responses, so a value found in one indicates that
+    /// the server already knew it. `Set-Cookie` headers are not searched, since they are how
+    /// servers send cookies rather than receive them.
+    ResponseHeader,
+}
+
+/// A value read from storage that was later sent in a request, as reported by
+/// [`PageGraph::storage_leaks`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StorageLeak {
+    /// The type of storage area: `cookie jar`, `local storage`, or `session storage`.
+    pub storage: String,
+    /// The storage key, or for cookies, the name of the cookie.
+    pub key: String,
+    pub value: String,
+    /// The `StorageReadResult` edge returning the value, and the script that read it.
+    pub read_edge_id: String,
+    pub node_id: String,
+    pub script_url: Option<String>,
+    /// The `RequestStart` edge of the request carrying the value, and the node that made it.
+    pub request_edge_id: String,
+    pub request_id: usize,
+    pub requester_node_id: String,
+    pub url: String,
+    /// The site the value was sent to.
+    pub domain: Option<String>,
+    pub is_third_party: Option<bool>,
+    pub location: LeakLocation,
+    /// The name of the header the value was found in, for values found in headers.
+    pub header: Option<String>,
+    pub encoding: LeakEncoding,
+}
+
+fn storage_key(edge_type: &EdgeType) -> Option<&str> {
+    match edge_type {
+        EdgeType::StorageSet { key, .. } |
+        EdgeType::ReadStorageCall { key } |
+        EdgeType::DeleteStorage { key } |
+        EdgeType::ClearStorage { key } => Some(key),
+        _ => None,
+    }
+}
+
+impl PageGraph {
+    /// Returns every read, write, deletion, and clear of the cookie jar and of local and session
+    /// storage, in id order.
+    pub(crate) fn storage_accesses(&self) -> Vec<&Edge> {
+        let mut accesses = [NodeKind::CookieJar, NodeKind::LocalStorage, NodeKind::SessionStorage].iter()
+            .flat_map(|kind| self.nodes_of_kind(*kind))
+            .flat_map(|storage| self.incoming_edges(storage))
+            .filter(|edge| storage_key(&edge.edge_type).is_some())
+            .collect::<Vec<_>>();
+        accesses.sort_unstable_by_key(|edge| edge.id);
+        accesses
+    }
+
+    /// Returns the URL of a script, and whether it was loaded from a different site than the
+    /// document it ran in. Both are `None` for inline scripts and actors other than scripts.
+    fn actor_url(&self, actor: &Node) -> (Option<String>, Option<bool>) {
+        let url = match &actor.node_type {
+            NodeType::Script { url, .. } => url.clone(),
+            _ => None,
+        };
+        let is_third_party = url.as_deref().and_then(|url| url_utils::is_third_party(url, &self.document_url_for(actor.id)));
+        (url, is_third_party)
+    }
+
+    /// Aggregates every access to the cookie jar and to local and session storage, both by key
+    /// and by the script responsible for it.
+    pub fn storage_report(&self) -> StorageReport {
+        let mut keys = BTreeMap::<(&str, &str), (KeyUsage, BTreeSet<NodeId>)>::new();
+        let mut actors = BTreeMap::<NodeId, (ActorUsage, BTreeSet<(&str, &str)>)>::new();
+
+        self.storage_accesses().into_iter().for_each(|edge| {
+            let storage = self.target_node(edge).node_type.type_name();
+            let key = storage_key(&edge.edge_type).unwrap();
+
+            let (key_usage, key_actors) = keys.entry((storage, key)).or_insert_with(|| (KeyUsage {
+                storage: storage.to_string(),
+                key: key.to_string(),
+                counts: AccessCounts::default(),
+                actors: vec![],
+                edge_ids: vec![],
+            }, BTreeSet::new()));
+            key_usage.counts.count(&edge.edge_type);
+            key_usage.edge_ids.push(format!("{}", edge.id));
+            key_actors.insert(edge.source);
+
+            let (actor_usage, actor_keys) = actors.entry(edge.source).or_insert_with(|| {
+                let actor = self.source_node(edge);
+                let (url, is_third_party) = self.actor_url(actor);
+                (ActorUsage {
+                    node_id: format!("{}", actor.id),
+                    node_type: actor.node_type.type_name().to_string(),
+                    url,
+                    is_third_party,
+                    counts: AccessCounts::default(),
+                    keys: vec![],
+                    edge_ids: vec![],
+                }, BTreeSet::new())
+            });
+            actor_usage.counts.count(&edge.edge_type);
+            actor_usage.edge_ids.push(format!("{}", edge.id));
+            actor_keys.insert((storage, key));
+        });
+
+        StorageReport {
+            keys: keys.into_values().map(|(mut key_usage, key_actors)| {
+                key_usage.actors = key_actors.into_iter().map(|actor| format!("{}", actor)).collect();
+                key_usage
+            }).collect(),
+            actors: actors.into_values().map(|(mut actor_usage, actor_keys)| {
+                actor_usage.keys = actor_keys.into_iter().map(|(storage, key)| (storage.to_string(), key.to_string())).collect();
+                actor_usage
+            }).collect(),
+        }
+    }
+
+    /// Returns every cookie set or read through `document.cookie`, parsed into names, values, and
+    /// attributes, along with the script responsible for each access, in id order.
+    pub fn cookie_accesses(&self) -> CookieAccesses {
+        let mut accesses = CookieAccesses::default();
+        self.storage_accesses().into_iter()
+            .filter(|edge| matches!(self.target_node(edge).node_type, NodeType::CookieJar {}))
+            .for_each(|edge| {
+                let actor = self.source_node(edge);
+                let (url, is_third_party) = self.actor_url(actor);
+                match &edge.edge_type {
+                    EdgeType::StorageSet { value, .. } => accesses.writes.push(CookieWrite {
+                        edge_id: format!("{}", edge.id),
+                        node_id: format!("{}", actor.id),
+                        url,
+                        is_third_party,
+                        cookie: value.as_deref().map(Cookie::parse_set_cookie),
+                    }),
+                    EdgeType::ReadStorageCall { .. } => {
+                        let result = self.storage_read_result(edge);
+                        let cookies = match result.map(|result| &result.edge_type) {
+                            Some(EdgeType::StorageReadResult { value: Some(value), .. }) => Cookie::parse_cookie_string(value),
+                            _ => vec![],
+                        };
+                        accesses.reads.push(CookieRead {
+                            edge_id: format!("{}", edge.id),
+                            result_edge_id: result.map(|result| format!("{}", result.id)),
+                            node_id: format!("{}", actor.id),
+                            url,
+                            is_third_party,
+                            cookies,
+                        });
+                    }
+                    _ => (),
+                }
+            });
+        accesses
+    }
+
+    /// Finds values read from cookies or local or session storage that were later sent in the
+    /// URL or recorded in the response headers of a request, whether as they were read,
+    /// URL-encoded, base64-encoded, or hashed with MD5, SHA-1, or SHA-256. Cookies are matched
+    /// individually. Each value is reported once per request and location, with the first read
+    /// that returned it, in read order.
+    pub fn storage_leaks(&self) -> Vec<StorageLeak> {
+        let requests = self.edges_of_kind(EdgeKind::RequestStart)
+            .filter_map(|edge| match &self.target_node(edge).node_type {
+                NodeType::Resource { url } => Some((edge, url)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut reads = [NodeKind::CookieJar, NodeKind::LocalStorage, NodeKind::SessionStorage].iter()
+            .flat_map(|kind| self.nodes_of_kind(*kind))
+            .flat_map(|storage| self.outgoing_edges(storage))
+            .filter(|edge| matches!(edge.edge_type, EdgeType::StorageReadResult { value: Some(_), .. }))
+            .collect::<Vec<_>>();
+        reads.sort_unstable_by_key(|edge| edge.id);
+
+        let mut reported = HashSet::new();
+        let mut leaks = vec![];
+        reads.into_iter().for_each(|read| {
+            let storage = self.source_node(read);
+            let values = match (&storage.node_type, &read.edge_type) {
+                (NodeType::CookieJar {}, EdgeType::StorageReadResult { value: Some(value), .. }) => {
+                    Cookie::parse_cookie_string(value).into_iter().map(|cookie| (cookie.name, cookie.value)).collect()
+                }
+                (_, EdgeType::StorageReadResult { key, value: Some(value) }) => vec![(key.clone(), value.clone())],
+                _ => vec![],
+            };
+            let script = self.target_node(read);
+            let (script_url, _) = self.actor_url(script);
+
+            values.into_iter().filter(|(_, value)| value.len() >= MIN_LEAKED_VALUE_LENGTH).for_each(|(key, value)| {
+                let encodings = encodings(&value);
+                requests.iter().filter(|(request, _)| happened_before(read, request)).for_each(|(request, url)| {
+                    let request_id = request.request_id().unwrap();
+                    let resource = self.target_node(request);
+                    let headers = self.outgoing_edges(resource)
+                        .filter(|completion| completion.request_id() == Some(request_id))
+                        .filter_map(|completion| completion.parsed_headers())
+                        .flat_map(|headers| headers.headers)
+                        .filter(|(name, _)| !name.eq_ignore_ascii_case("set-cookie"));
+                    let found = find_encoding(&encodings, url).map(|encoding| (LeakLocation::Url, None, encoding)).into_iter()
+                        .chain(headers.filter_map(|(name, header_value)| {
+                            find_encoding(&encodings, header_value).map(|encoding| (LeakLocation::ResponseHeader, Some(name.to_string()), encoding))
+                        }));
+
+                    found.for_each(|(location, header, encoding)| {
+                        if !reported.insert((storage.id, key.clone(), value.clone(), request.id, location, header.clone())) {
+                            return;
+                        }
+                        leaks.push(StorageLeak {
+                            storage: storage.node_type.type_name().to_string(),
+                            key: key.clone(),
+                            value: value.clone(),
+                            read_edge_id: format!("{}", read.id),
+                            node_id: format!("{}", script.id),
+                            script_url: script_url.clone(),
+                            request_edge_id: format!("{}", request.id),
+                            request_id,
+                            requester_node_id: format!("{}", request.source),
+                            url: url.to_string(),
+                            domain: url_utils::site(url),
+                            is_third_party: url_utils::is_third_party(url, &self.document_url_for(request.id)),
+                            location,
+                            header,
+                            encoding,
+                        });
+                    });
+                });
+            });
+        });
+        leaks
+    }
+}
+
+#[cfg(test)]
+mod storage_tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_report() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">script</data><data key="d1">1</data><data key="d2">0</data><data key="d3">https://tracker.net/t.js</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n2"><data key="d0">script</data><data key="d1">2</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">12</data><data key="d10"></data></node>
+<node id="n3"><data key="d0">local storage</data><data key="d1">3</data><data key="d2">0</data></node>
+<node id="n4"><data key="d0">cookie jar</data><data key="d1">4</data><data key="d2">0</data></node>
+<edge id="e5" source="n1" target="n3"><data key="d16">storage set</data><data key="d17">5</data><data key="d18">0</data><data key="d21">uid</data><data key="d20">abc</data></edge>
+<edge id="e6" source="n2" target="n3"><data key="d16">read storage call</data><data key="d17">6</data><data key="d18">0</data><data key="d21">uid</data></edge>
+<edge id="e7" source="n2" target="n3"><data key="d16">delete storage</data><data key="d17">7</data><data key="d18">0</data><data key="d21">uid</data></edge>
+<edge id="e8" source="n1" target="n4"><data key="d16">read storage call</data><data key="d17">8</data><data key="d18">0</data><data key="d21"></data></edge>"#);
+        let report = graph.storage_report();
+
+        assert_eq!(report.keys.len(), 2);
+        assert_eq!((report.keys[0].storage.as_str(), report.keys[0].key.as_str()), ("cookie jar", ""));
+        let uid = &report.keys[1];
+        assert_eq!(uid.counts, AccessCounts { reads: 1, writes: 1, deletes: 1, clears: 0 });
+        assert_eq!(uid.actors, ["n1", "n2"]);
+
+        assert_eq!(report.actors.len(), 2);
+        assert_eq!(report.actors[0].is_third_party, Some(true));
+        assert_eq!(report.actors[0].keys.len(), 2);
+        assert_eq!(report.actors[1].url, None);
+        assert_eq!(report.actors[1].edge_ids, ["e6", "e7"]);
+    }
+    #[test]
+    fn test_parse_cookies() {
+        let cookie = Cookie::parse_set_cookie("uid=abc=123; Path=/; Secure; ");
+        assert_eq!((cookie.name.as_str(), cookie.value.as_str()), ("uid", "abc=123"));
+        assert_eq!(cookie.attributes, [
+            CookieAttribute { name: "Path".to_string(), value: Some("/".to_string()) },
+            CookieAttribute { name: "Secure".to_string(), value: None },
+        ]);
+        assert_eq!(Cookie::parse_set_cookie("flag").name, "");
+
+        let cookies = Cookie::parse_cookie_string("a=b; uid=abc123");
+        assert_eq!(cookies.iter().map(|cookie| (cookie.name.as_str(), cookie.value.as_str())).collect::<Vec<_>>(), [("a", "b"), ("uid", "abc123")]);
+        assert!(Cookie::parse_cookie_string("").is_empty());
+    }
+    #[test]
+    fn test_find_encoding() {
+        let encodings = encodings("uid:42/x+y");
+        assert_eq!(find_encoding(&encodings, "https://t.net/?v=uid:42/x+y"), Some(LeakEncoding::Plain));
+        assert_eq!(find_encoding(&encodings, "https://t.net/?v=uid%3A42%2Fx%2By"), Some(LeakEncoding::UrlEncoded));
+        assert_eq!(find_encoding(&encodings, "https://t.net/?v=dWlkOjQyL3greQ"), Some(LeakEncoding::Base64));
+        assert_eq!(find_encoding(&encodings, "https://t.net/?v=21DC6F65488023C894F4F3F850E8F07A"), Some(LeakEncoding::Md5));
+        assert_eq!(find_encoding(&encodings, "https://t.net/e120d819eed423430cb51d699487eaf9f736f77a2af1d23a449fc9eccaabb0aa.gif"), Some(LeakEncoding::Sha256));
+        assert_eq!(find_encoding(&encodings, "https://t.net/?v=uid:43"), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! Generation of candidate network filter rules from the behavior recorded in a graph.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::graph::{Edge, Node, PageGraph};
+use crate::types::{EdgeKind, EdgeType, NodeKind, NodeType};
+use crate::url_utils;
+
+/// Query parameters commonly used to carry click or campaign identifiers.
+pub const DEFAULT_TRACKING_PARAMS: [&str; 15] = [
+    "utm_source", "utm_medium", "utm_campaign", "utm_term", "utm_content",
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_eid", "_hsenc", "_hsmi", "mkt_tok",
+];
+
+/// Selects the heuristics used by [`PageGraph::suggest_filters`].
+#[derive(Debug, Clone)]
+pub struct SuggestOptions {
+    /// Suggest blocking third-party scripts that read or write local storage, session storage, or
+    /// cookies.
+    pub storage_access: bool,
+    /// Suggest removing these query parameters from the requests that carry them.
+    pub tracking_params: Vec<String>,
+    /// Suggest blocking scripts that cause at least this many requests, directly or through the
+    /// scripts and requests they cause in turn.
+    pub fan_out_threshold: Option<usize>,
+}
+
+impl Default for SuggestOptions {
+    fn default() -> Self {
+        Self {
+            storage_access: true,
+            tracking_params: DEFAULT_TRACKING_PARAMS.iter().map(|param| param.to_string()).collect(),
+            fan_out_threshold: Some(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Heuristic {
+    ThirdPartyStorageAccess,
+    TrackingParameter,
+    HighFanOut,
+}
+
+/// An observation from the graph supporting a suggested filter.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Evidence {
+    pub heuristic: Heuristic,
+    /// The script or resource node that the observation concerns.
+    pub node_id: String,
+    /// The requests that fetched the script or resource.
+    pub request_ids: Vec<usize>,
+    /// The edges that triggered the heuristic: the storage accesses, the requests carrying the
+    /// tracking parameter, or the requests caused by the script.
+    pub edge_ids: Vec<String>,
+}
+
+/// A candidate network filter rule, in ABP syntax, as reported by
+/// [`PageGraph::suggest_filters`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SuggestedFilter {
+    pub rule: String,
+    pub evidence: Vec<Evidence>,
+}
+
+/// Suggests a rule blocking the script at `url`. Third-party scripts are blocked by host, while
+/// first-party scripts are blocked by path alone, to avoid breaking the rest of the site.
+fn script_rule(url: &str, document_url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok().filter(|url| matches!(url.scheme(), "http" | "https"))?;
+    let host = parsed.host_str()?;
+    match url_utils::is_third_party(url, document_url) {
+        Some(true) => Some(format!("||{}^$script,third-party", host)),
+        _ => Some(format!("||{}{}$script", host, parsed.path())),
+    }
+}
+
+fn is_storage_access(edge_type: &EdgeType) -> bool {
+    matches!(edge_type,
+        EdgeType::StorageSet { .. } |
+        EdgeType::ReadStorageCall { .. } |
+        EdgeType::DeleteStorage { .. } |
+        EdgeType::ClearStorage { .. })
+}
+
+impl PageGraph {
+    /// Suggests network filter rules for the behavior that the heuristics in `options` consider to
+    /// be tracking, along with the nodes and edges that led to each suggestion. Results are sorted
+    /// by rule.
+    ///
+    /// Suggestions are only candidates for review by a filter list author, and are not checked
+    /// against any existing filter lists.
+    pub fn suggest_filters(&self, options: &SuggestOptions) -> Vec<SuggestedFilter> {
+        let mut requests_by_url = HashMap::<&str, Vec<&Edge>>::new();
+        self.edges_of_kind(EdgeKind::RequestStart).for_each(|edge| {
+            if let NodeType::Resource { url } = &self.target_node(edge).node_type {
+                requests_by_url.entry(url).or_default().push(edge);
+            }
+        });
+        let request_ids = |edges: &[&Edge]| edges.iter().filter_map(|edge| match edge.edge_type {
+            EdgeType::RequestStart { request_id, .. } => Some(request_id),
+            _ => None,
+        }).collect::<Vec<_>>();
+        let edge_ids = |edges: &[&Edge]| edges.iter().map(|edge| format!("{}", edge.id)).collect::<Vec<_>>();
+
+        let mut suggestions = BTreeMap::<String, Vec<Evidence>>::new();
+
+        self.nodes_of_kind(NodeKind::Script).for_each(|script| {
+            let url = match &script.node_type {
+                NodeType::Script { url: Some(url), .. } => url,
+                _ => return,
+            };
+            let rule = match script_rule(url, &self.document_url_for(script.id)) {
+                Some(rule) => rule,
+                None => return,
+            };
+            let script_requests = requests_by_url.get(url.as_str()).map(Vec::as_slice).unwrap_or_default();
+
+            if options.storage_access && rule.ends_with(",third-party") {
+                let accesses = self.outgoing_edges(script)
+                    .filter(|edge| is_storage_access(&edge.edge_type))
+                    .collect::<Vec<_>>();
+                if !accesses.is_empty() {
+                    suggestions.entry(rule.clone()).or_default().push(Evidence {
+                        heuristic: Heuristic::ThirdPartyStorageAccess,
+                        node_id: format!("{}", script.id),
+                        request_ids: request_ids(script_requests),
+                        edge_ids: edge_ids(&accesses),
+                    });
+                }
+            }
+
+            if let Some(threshold) = options.fan_out_threshold {
+                let caused_requests = self.requests_caused_by_script(script);
+                if !caused_requests.is_empty() && caused_requests.len() >= threshold {
+                    suggestions.entry(rule).or_default().push(Evidence {
+                        heuristic: Heuristic::HighFanOut,
+                        node_id: format!("{}", script.id),
+                        request_ids: request_ids(script_requests),
+                        edge_ids: edge_ids(&caused_requests),
+                    });
+                }
+            }
+        });
+
+        self.nodes_of_kind(NodeKind::Resource).for_each(|resource| {
+            let url = match &resource.node_type {
+                NodeType::Resource { url } => url,
+                _ => return,
+            };
+            let parsed = match url::Url::parse(url) {
+                Ok(parsed) => parsed,
+                Err(_) => return,
+            };
+            let host = match parsed.host_str() {
+                Some(host) => host,
+                None => return,
+            };
+            let params = parsed.query_pairs()
+                .map(|(param, _)| param.into_owned())
+                .filter(|param| options.tracking_params.contains(param))
+                .collect::<BTreeSet<_>>();
+            let resource_requests = requests_by_url.get(url.as_str()).map(Vec::as_slice).unwrap_or_default();
+            params.into_iter().for_each(|param| {
+                suggestions.entry(format!("||{}^$removeparam={}", host, param)).or_default().push(Evidence {
+                    heuristic: Heuristic::TrackingParameter,
+                    node_id: format!("{}", resource.id),
+                    request_ids: request_ids(resource_requests),
+                    edge_ids: edge_ids(resource_requests),
+                });
+            });
+        });
+
+        suggestions.into_iter().map(|(rule, evidence)| SuggestedFilter { rule, evidence }).collect()
+    }
+
+    /// Returns the request start edges for every request that would not have been made had the
+    /// script not run, in id order.
+    fn requests_caused_by_script(&self, script: &Node) -> Vec<&Edge> {
+        let mut requests = self.incoming_edges(script)
+            .filter(|edge| matches!(edge.edge_type, EdgeType::Execute {}))
+            .flat_map(|execution| self.all_downstream_effects_of(execution))
+            .chain(self.outgoing_edges(script).filter(|edge| matches!(edge.edge_type, EdgeType::RequestStart { .. })))
+            .filter(|edge| matches!(edge.edge_type, EdgeType::RequestStart { .. }))
+            .collect::<Vec<_>>();
+        requests.sort_unstable_by_key(|edge| edge.id);
+        requests.dedup_by_key(|edge| edge.id);
+        requests
+    }
+}
+
+#[cfg(test)]
+mod suggest_tests {
+    use super::*;
+
+    #[test]
+    fn test_script_rule() {
+        assert_eq!(script_rule("https://cdn.tracker.net/t.js?v=2", "https://example.com/").as_deref(), Some("||cdn.tracker.net^$script,third-party"));
+        assert_eq!(script_rule("https://static.example.com/js/app.js?v=2", "https://www.example.com/").as_deref(), Some("||static.example.com/js/app.js$script"));
+        assert_eq!(script_rule("chrome-extension://abc/content.js", "https://example.com/"), None);
+    }
+}
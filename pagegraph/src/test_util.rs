@@ -0,0 +1,60 @@
+//! GraphML fixtures for tests, sharing a single set of key declarations so that each test only
+//! needs to spell out its nodes and edges.
+
+use crate::graph::PageGraph;
+
+/// The start of a root frame's graph for `https://example.com/`, declaring every node and edge
+/// attribute used by the tests, up to and including the opening `graph` tag.
+pub(crate) const HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+<key id="d0" for="node" attr.name="node type" attr.type="string"/>
+<key id="d1" for="node" attr.name="id" attr.type="int"/>
+<key id="d2" for="node" attr.name="timestamp" attr.type="int"/>
+<key id="d3" for="node" attr.name="url" attr.type="string"/>
+<key id="d4" for="node" attr.name="tag name" attr.type="string"/>
+<key id="d5" for="node" attr.name="is deleted" attr.type="string"/>
+<key id="d6" for="node" attr.name="node id" attr.type="int"/>
+<key id="d7" for="node" attr.name="text" attr.type="string"/>
+<key id="d8" for="node" attr.name="script type" attr.type="string"/>
+<key id="d9" for="node" attr.name="script id" attr.type="int"/>
+<key id="d10" for="node" attr.name="source" attr.type="string"/>
+<key id="d11" for="node" attr.name="method" attr.type="string"/>
+<key id="d12" for="node" attr.name="rule" attr.type="string"/>
+<key id="d13" for="node" attr.name="binding" attr.type="string"/>
+<key id="d14" for="node" attr.name="binding type" attr.type="string"/>
+<key id="d15" for="node" attr.name="binding event" attr.type="string"/>
+<key id="d16" for="edge" attr.name="edge type" attr.type="string"/>
+<key id="d17" for="edge" attr.name="id" attr.type="int"/>
+<key id="d18" for="edge" attr.name="timestamp" attr.type="int"/>
+<key id="d19" for="edge" attr.name="parent" attr.type="int"/>
+<key id="d20" for="edge" attr.name="value" attr.type="string"/>
+<key id="d21" for="edge" attr.name="key" attr.type="string"/>
+<key id="d22" for="edge" attr.name="is style" attr.type="string"/>
+<key id="d23" for="edge" attr.name="attr name" attr.type="string"/>
+<key id="d24" for="edge" attr.name="args" attr.type="string"/>
+<key id="d25" for="edge" attr.name="script position" attr.type="int"/>
+<key id="d26" for="edge" attr.name="script id" attr.type="int"/>
+<key id="d27" for="edge" attr.name="event listener id" attr.type="int"/>
+<key id="d28" for="edge" attr.name="request id" attr.type="int"/>
+<key id="d29" for="edge" attr.name="resource type" attr.type="string"/>
+<key id="d30" for="edge" attr.name="status" attr.type="string"/>
+<key id="d31" for="edge" attr.name="response hash" attr.type="string"/>
+<key id="d32" for="edge" attr.name="headers" attr.type="string"/>
+<key id="d33" for="edge" attr.name="size" attr.type="string"/>
+<key id="d34" for="node" attr.name="frame id" attr.type="string"/>
+<desc><version>0.7.0</version><about>test</about><url>https://example.com/</url><is_root>true</is_root><frame_id>0000000000000000000000000000000A</frame_id><time><start>1</start><end>2</end></time></desc>
+<graph id="G" edgedefault="directed">
+"#;
+
+/// Closes the tags opened by [`HEADER`].
+pub(crate) const FOOTER: &str = "\n</graph></graphml>";
+
+/// Parses a graph made up of the given nodes and edges, between [`HEADER`] and [`FOOTER`].
+pub(crate) fn parse(nodes_and_edges: &str) -> PageGraph {
+    parse_with_header(HEADER, nodes_and_edges)
+}
+
+/// Parses a graph made up of the given nodes and edges, with a variant of [`HEADER`].
+pub(crate) fn parse_with_header(header: &str, nodes_and_edges: &str) -> PageGraph {
+    crate::from_xml::read_from_reader(format!("{}{}{}", header, nodes_and_edges, FOOTER).as_bytes()).unwrap()
+}
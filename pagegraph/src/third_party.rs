@@ -0,0 +1,160 @@
+//! A summary of every third-party site a page contacted, how it came to be contacted, and what
+//! its scripts did, for quick audits of a site.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::graph::{Node, NodeId, PageGraph};
+use crate::storage::AccessCounts;
+use crate::types::{EdgeKind, NodeKind, NodeType};
+use crate::url_utils;
+
+/// The actor at the root of the initiator chain of a request to a third party.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ThirdPartyInitiator {
+    pub node_id: String,
+    pub node_type: String,
+    /// The URL of the script or document, if it has one.
+    pub url: Option<String>,
+}
+
+/// Everything a single third-party site did on a page, as reported by
+/// [`PageGraph::third_parties`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ThirdParty {
+    pub site: String,
+    /// Requests to the site made by documents of other sites.
+    pub requests: usize,
+    /// The total size of the responses to those requests, where it could be determined.
+    pub bytes: u64,
+    /// The actors that the requests can be traced back to, in node id order.
+    pub initiators: Vec<ThirdPartyInitiator>,
+    /// Scripts loaded from the site into documents of other sites, in node id order.
+    pub scripts: Vec<String>,
+    /// Storage accesses made by those scripts.
+    pub storage: AccessCounts,
+    /// Event listeners registered by those scripts.
+    pub event_listeners: usize,
+}
+
+impl ThirdParty {
+    fn new(site: String) -> Self {
+        Self {
+            site,
+            requests: 0,
+            bytes: 0,
+            initiators: vec![],
+            scripts: vec![],
+            storage: AccessCounts::default(),
+            event_listeners: 0,
+        }
+    }
+}
+
+impl PageGraph {
+    /// Returns the third-party site of a URL used within the document of the given item, or
+    /// `None` if it is first-party or either URL has no site.
+    fn third_party_site(&self, url: &str, item: NodeId) -> Option<String> {
+        match url_utils::is_third_party(url, &self.document_url_for(item)) {
+            Some(true) => url_utils::site(url),
+            _ => None,
+        }
+    }
+
+    fn initiator(&self, node: &Node) -> ThirdPartyInitiator {
+        let url = match &node.node_type {
+            NodeType::Script { url, .. } | NodeType::DomRoot { url, .. } => url.clone(),
+            _ => None,
+        };
+        ThirdPartyInitiator {
+            node_id: format!("{}", node.id),
+            node_type: node.node_type.type_name().to_string(),
+            url,
+        }
+    }
+
+    /// Summarizes every third-party site contacted by the page and its merged frames: the
+    /// requests made to it and their sizes, the actors those requests trace back to, and the
+    /// scripts loaded from it along with their storage accesses and event listeners. Whether a
+    /// URL is third-party is judged relative to the document it was used in. Sites are returned
+    /// with the most requested first.
+    pub fn third_parties(&self) -> Vec<ThirdParty> {
+        let mut sites = BTreeMap::<String, (ThirdParty, BTreeSet<NodeId>)>::new();
+
+        self.edges_of_kind(EdgeKind::RequestStart).for_each(|start| {
+            let resource = self.target_node(start);
+            let site = match resource.as_resource().and_then(|url| self.third_party_site(url, resource.id)) {
+                Some(site) => site,
+                None => return,
+            };
+            let (third_party, initiators) = sites.entry(site.clone()).or_insert_with(|| (ThirdParty::new(site), BTreeSet::new()));
+            third_party.requests += 1;
+            let root = self.initiator_chain(start)[0];
+            initiators.insert(root.source);
+        });
+        self.edges_of_kind(EdgeKind::RequestComplete).for_each(|complete| {
+            let resource = self.source_node(complete);
+            let size = complete.as_request_complete().and_then(|complete| complete.size);
+            let site = resource.as_resource().and_then(|url| self.third_party_site(url, resource.id));
+            if let (Some(size), Some((third_party, _))) = (size, site.and_then(|site| sites.get_mut(&site))) {
+                third_party.bytes += size;
+            }
+        });
+
+        // Scripts from third-party sites, and the sites they belong to
+        let mut scripts = BTreeMap::<NodeId, String>::new();
+        self.nodes_of_kind(NodeKind::Script).for_each(|script| {
+            if let NodeType::Script { url: Some(url), .. } = &script.node_type {
+                if let Some(site) = self.third_party_site(url, script.id) {
+                    sites.entry(site.clone()).or_insert_with(|| (ThirdParty::new(site.clone()), BTreeSet::new()));
+                    scripts.insert(script.id, site);
+                }
+            }
+        });
+        scripts.iter().for_each(|(script, site)| sites.get_mut(site).unwrap().0.scripts.push(format!("{}", script)));
+        self.storage_accesses().into_iter().for_each(|edge| {
+            if let Some(site) = scripts.get(&edge.source) {
+                sites.get_mut(site).unwrap().0.storage.count(&edge.edge_type);
+            }
+        });
+        self.edges_of_kind(EdgeKind::AddEventListener).for_each(|edge| {
+            if let Some(site) = scripts.get(&edge.source) {
+                sites.get_mut(site).unwrap().0.event_listeners += 1;
+            }
+        });
+
+        let mut third_parties = sites.into_values().map(|(mut third_party, initiators)| {
+            third_party.initiators = initiators.into_iter().map(|node_id| self.initiator(&self.nodes[&node_id])).collect();
+            third_party
+        }).collect::<Vec<_>>();
+        third_parties.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.site.cmp(&b.site)));
+        third_parties
+    }
+}
+
+#[cfg(test)]
+mod third_party_tests {
+    #[test]
+    fn test_third_parties() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://cdn.tracker.net/t.js</data></node>
+<node id="n3"><data key="d0">script</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://cdn.tracker.net/t.js</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n4"><data key="d0">resource</data><data key="d1">4</data><data key="d2">0</data><data key="d3">https://example.com/a.css</data></node>
+<node id="n5"><data key="d0">local storage</data><data key="d1">5</data><data key="d2">0</data></node>
+<node id="n6"><data key="d0">DOM root</data><data key="d1">6</data><data key="d2">0</data><data key="d3">https://example.com/</data><data key="d4">HTML</data><data key="d5">false</data><data key="d6">1</data></node>
+<edge id="e7" source="n1" target="n2"><data key="d16">request start</data><data key="d17">7</data><data key="d18">1</data><data key="d28">1</data><data key="d29">Script</data><data key="d30">started</data></edge>
+<edge id="e8" source="n2" target="n1"><data key="d16">request complete</data><data key="d17">8</data><data key="d18">2</data><data key="d28">1</data><data key="d29">script</data><data key="d30">complete</data><data key="d32"></data><data key="d33">500</data></edge>
+<edge id="e9" source="n1" target="n4"><data key="d16">request start</data><data key="d17">9</data><data key="d18">3</data><data key="d28">2</data><data key="d29">CSS</data><data key="d30">started</data></edge>
+<edge id="e10" source="n3" target="n5"><data key="d16">storage set</data><data key="d17">10</data><data key="d18">4</data><data key="d21">uid</data><data key="d20">1</data></edge>
+<edge id="e11" source="n3" target="n6"><data key="d16">add event listener</data><data key="d17">11</data><data key="d18">5</data><data key="d21">click</data><data key="d27">1</data><data key="d26">11</data></edge>"#);
+
+        let third_parties = graph.third_parties();
+        assert_eq!(third_parties.len(), 1);
+        let tracker = &third_parties[0];
+        assert_eq!(tracker.site, "tracker.net");
+        assert_eq!((tracker.requests, tracker.bytes), (1, 500));
+        assert_eq!(tracker.initiators.iter().map(|initiator| initiator.node_type.as_str()).collect::<Vec<_>>(), ["parser"]);
+        assert_eq!(tracker.scripts, ["n3"]);
+        assert_eq!(tracker.storage.writes, 1);
+        assert_eq!(tracker.event_listeners, 1);
+    }
+}
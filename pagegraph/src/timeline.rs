@@ -0,0 +1,138 @@
+//! A histogram of what a page was doing over the course of its load, for plotting when it was
+//! busiest and with what.
+
+use std::collections::BTreeMap;
+
+use crate::graph::PageGraph;
+use crate::types::EdgeKind;
+
+/// The kinds of activity counted by [`PageGraph::activity_timeline`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityCategory {
+    /// Requests starting, receiving responses, completing, or failing.
+    Network,
+    /// DOM nodes being created, inserted, removed, or deleted, and attribute and text changes.
+    DomMutation,
+    /// Scripts being executed, calling Web APIs and builtins, and registering or handling events.
+    ScriptExecution,
+    /// Reads, writes, deletions, and clears of cookies and web storage.
+    Storage,
+}
+
+impl ActivityCategory {
+    /// Returns the category of activity an edge represents, or `None` for edges that describe
+    /// the structure of the graph rather than something the page did.
+    pub fn of(kind: EdgeKind) -> Option<Self> {
+        match kind {
+            EdgeKind::RequestStart |
+            EdgeKind::RequestResponse |
+            EdgeKind::RequestComplete |
+            EdgeKind::RequestError => Some(Self::Network),
+            EdgeKind::CreateNode |
+            EdgeKind::InsertNode |
+            EdgeKind::RemoveNode |
+            EdgeKind::DeleteNode |
+            EdgeKind::SetAttribute |
+            EdgeKind::DeleteAttribute |
+            EdgeKind::TextChange => Some(Self::DomMutation),
+            EdgeKind::Execute |
+            EdgeKind::ExecuteFromAttribute |
+            EdgeKind::JsCall |
+            EdgeKind::JsResult |
+            EdgeKind::AddEventListener |
+            EdgeKind::RemoveEventListener |
+            EdgeKind::EventListener |
+            EdgeKind::Binding |
+            EdgeKind::BindingEvent => Some(Self::ScriptExecution),
+            EdgeKind::StorageSet |
+            EdgeKind::StorageReadResult |
+            EdgeKind::DeleteStorage |
+            EdgeKind::ReadStorageCall |
+            EdgeKind::ClearStorage => Some(Self::Storage),
+            EdgeKind::Filter |
+            EdgeKind::Structure |
+            EdgeKind::CrossDom |
+            EdgeKind::ResourceBlock |
+            EdgeKind::Shield |
+            EdgeKind::StorageBucket |
+            EdgeKind::Unknown => None,
+        }
+    }
+}
+
+/// The number of edges of each category recorded within a single bucket of time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct ActivityBucket {
+    /// The start of the bucket, in milliseconds since navigation start.
+    pub start_ms: isize,
+    pub network: usize,
+    pub dom_mutation: usize,
+    pub script_execution: usize,
+    pub storage: usize,
+}
+
+impl ActivityBucket {
+    fn count(&mut self, category: ActivityCategory) {
+        match category {
+            ActivityCategory::Network => self.network += 1,
+            ActivityCategory::DomMutation => self.dom_mutation += 1,
+            ActivityCategory::ScriptExecution => self.script_execution += 1,
+            ActivityCategory::Storage => self.storage += 1,
+        }
+    }
+}
+
+/// The activity on a page over time, as reported by [`PageGraph::activity_timeline`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ActivityTimeline {
+    pub bucket_ms: usize,
+    /// Every bucket from the first activity to the last, including empty ones, in time order.
+    pub buckets: Vec<ActivityBucket>,
+}
+
+impl PageGraph {
+    /// Counts the edges of each [`ActivityCategory`] recorded within each `bucket_ms`-long span
+    /// of time since navigation start. Edges without a timestamp are left out.
+    ///
+    /// Panics if `bucket_ms` is zero.
+    pub fn activity_timeline(&self, bucket_ms: usize) -> ActivityTimeline {
+        assert!(bucket_ms > 0, "Timeline buckets must be at least 1ms long");
+        let bucket_ms_signed = bucket_ms as isize;
+
+        let mut counts = BTreeMap::<isize, ActivityBucket>::new();
+        self.edges.values().for_each(|edge| {
+            let (timestamp, category) = match (edge.edge_timestamp, ActivityCategory::of(edge.edge_type.kind())) {
+                (Some(timestamp), Some(category)) => (timestamp, category),
+                _ => return,
+            };
+            let bucket = timestamp.as_millis().div_euclid(bucket_ms_signed);
+            counts.entry(bucket).or_default().count(category);
+        });
+
+        let (first, last) = match (counts.keys().next(), counts.keys().next_back()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return ActivityTimeline { bucket_ms, buckets: vec![] },
+        };
+        let buckets = (first..=last).map(|bucket| ActivityBucket {
+            start_ms: bucket * bucket_ms_signed,
+            ..counts.remove(&bucket).unwrap_or_default()
+        }).collect();
+        ActivityTimeline { bucket_ms, buckets }
+    }
+}
+
+#[cfg(test)]
+mod timeline_tests {
+    #[test]
+    fn test_activity_timeline() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/a.png</data></node>
+<edge id="e3" source="n1" target="n2"><data key="d16">request start</data><data key="d17">3</data><data key="d18">-5</data><data key="d28">1</data><data key="d29">Image</data><data key="d30">started</data></edge>
+<edge id="e4" source="n1" target="n2"><data key="d16">request start</data><data key="d17">4</data><data key="d18">25</data><data key="d28">2</data><data key="d29">Image</data><data key="d30">started</data></edge>
+<edge id="e5" source="n1" target="n2"><data key="d16">request start</data><data key="d17">5</data><data key="d18">29</data><data key="d28">3</data><data key="d29">Image</data><data key="d30">started</data></edge>"#);
+
+        let timeline = graph.activity_timeline(10);
+        assert_eq!(timeline.buckets.iter().map(|bucket| (bucket.start_ms, bucket.network)).collect::<Vec<_>>(), [(-10, 1), (0, 0), (10, 0), (20, 2)]);
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use crate::graph::FrameId;
 
 /// HtmlElementId represents the unsigned integer identifier that Blink uses
@@ -287,10 +289,122 @@ pub enum NodeType {
     FingerprintingShield {},
     FingerprintingV2Shield {},
     Extensions {},
+    /// A node type not known to this version of the crate, retained when a
+    /// graph is read in tolerant mode (see
+    /// [`ParseOptions`](crate::from_xml::ParseOptions)).
+    Unknown {
+        /// The `node type` value recorded in the graph.
+        type_name: String,
+        /// All other data items recorded on the node, keyed by attribute name.
+        attrs: BTreeMap<String, String>,
+    },
+}
+
+impl NodeType {
+    /// The `node type` used for this type of node in GraphML.
+    pub fn type_name(&self) -> &str {
+        match self {
+            Self::Extensions { .. } => "extensions",
+            Self::RemoteFrame { .. } => "remote frame",
+            Self::Resource { .. } => "resource",
+            Self::AdFilter { .. } => "ad filter",
+            Self::TrackerFilter => "tracker filter",
+            Self::FingerprintingFilter => "fingerprinting filter",
+            Self::WebApi { .. } => "web API",
+            Self::JsBuiltin { .. } => "JS builtin",
+            Self::HtmlElement { .. } => "HTML element",
+            Self::TextNode { .. } => "text node",
+            Self::DomRoot { .. } => "DOM root",
+            Self::FrameOwner { .. } => "frame owner",
+            Self::Storage { .. } => "storage",
+            Self::LocalStorage { .. } => "local storage",
+            Self::SessionStorage { .. } => "session storage",
+            Self::CookieJar { .. } => "cookie jar",
+            Self::Script { .. } => "script",
+            Self::Parser { .. } => "parser",
+            Self::BraveShields { .. } => "Brave Shields",
+            Self::AdsShield { .. } => "shieldsAds shield",
+            Self::TrackersShield { .. } => "trackers shield",
+            Self::JavascriptShield { .. } => "javascript shield",
+            Self::FingerprintingShield { .. } => "fingerprinting shield",
+            Self::FingerprintingV2Shield { .. } => "fingerprintingV2 shield",
+            Self::Binding { .. } => "binding",
+            Self::BindingEvent { .. } => "binding event",
+            Self::Unknown { type_name, .. } => type_name,
+        }
+    }
+
+    /// The kind of this node, for use with indexed lookups.
+    pub fn kind(&self) -> NodeKind {
+        match self {
+            Self::Extensions { .. } => NodeKind::Extensions,
+            Self::RemoteFrame { .. } => NodeKind::RemoteFrame,
+            Self::Resource { .. } => NodeKind::Resource,
+            Self::AdFilter { .. } => NodeKind::AdFilter,
+            Self::TrackerFilter => NodeKind::TrackerFilter,
+            Self::FingerprintingFilter => NodeKind::FingerprintingFilter,
+            Self::WebApi { .. } => NodeKind::WebApi,
+            Self::JsBuiltin { .. } => NodeKind::JsBuiltin,
+            Self::HtmlElement { .. } => NodeKind::HtmlElement,
+            Self::TextNode { .. } => NodeKind::TextNode,
+            Self::DomRoot { .. } => NodeKind::DomRoot,
+            Self::FrameOwner { .. } => NodeKind::FrameOwner,
+            Self::Storage { .. } => NodeKind::Storage,
+            Self::LocalStorage { .. } => NodeKind::LocalStorage,
+            Self::SessionStorage { .. } => NodeKind::SessionStorage,
+            Self::CookieJar { .. } => NodeKind::CookieJar,
+            Self::Script { .. } => NodeKind::Script,
+            Self::Parser { .. } => NodeKind::Parser,
+            Self::BraveShields { .. } => NodeKind::BraveShields,
+            Self::AdsShield { .. } => NodeKind::AdsShield,
+            Self::TrackersShield { .. } => NodeKind::TrackersShield,
+            Self::JavascriptShield { .. } => NodeKind::JavascriptShield,
+            Self::FingerprintingShield { .. } => NodeKind::FingerprintingShield,
+            Self::FingerprintingV2Shield { .. } => NodeKind::FingerprintingV2Shield,
+            Self::Binding { .. } => NodeKind::Binding,
+            Self::BindingEvent { .. } => NodeKind::BindingEvent,
+            Self::Unknown { .. } => NodeKind::Unknown,
+        }
+    }
+}
+
+/// The kind of a [`NodeType`], without any of its associated data. Every unknown node type
+/// shares the `Unknown` kind.
+///
+/// Kinds are cheap to copy and compare, and can be matched exhaustively without destructuring.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, serde::Serialize)]
+pub enum NodeKind {
+    Extensions,
+    RemoteFrame,
+    Resource,
+    AdFilter,
+    TrackerFilter,
+    FingerprintingFilter,
+    WebApi,
+    JsBuiltin,
+    HtmlElement,
+    TextNode,
+    DomRoot,
+    FrameOwner,
+    Storage,
+    LocalStorage,
+    SessionStorage,
+    CookieJar,
+    Script,
+    Parser,
+    BraveShields,
+    AdsShield,
+    TrackersShield,
+    JavascriptShield,
+    FingerprintingShield,
+    FingerprintingV2Shield,
+    Binding,
+    BindingEvent,
+    Unknown,
 }
 
 #[derive(Clone, PartialEq, Debug)]
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub enum RequestType {
     Image,
     Script,
@@ -433,21 +547,31 @@ pub enum EdgeType {
         response_hash: Option<String>,
         request_id: usize,
         headers: String,
-        size: String,
+        /// Size of the response body in bytes, if it could be determined.
+        size: Option<u64>,
     },
     RequestError {
         status: String,
         request_id: usize,
         value: Option<String>,
         headers: String,
-        size: String,
+        size: Option<u64>,
     },
     RequestStart {
         request_type: RequestType,
         status: String,
         request_id: usize,
     },
-    RequestResponse, // TODO
+    /// A response received for a request before it finished, such as a redirect, from the
+    /// resource to the requester. A redirected request continues with a new `RequestStart` edge
+    /// to the redirect target, under the same request id.
+    RequestResponse {
+        request_id: usize,
+        status: String,
+        headers: String,
+        /// Size of the response body in bytes, if it could be determined.
+        size: Option<u64>,
+    },
     AddEventListener {
         key: String,
         event_listener_id: usize,
@@ -526,4 +650,131 @@ pub enum EdgeType {
     Shield {},
     ResourceBlock {},
     StorageBucket {},
+    /// An edge type not known to this version of the crate, retained when a
+    /// graph is read in tolerant mode (see
+    /// [`ParseOptions`](crate::from_xml::ParseOptions)).
+    Unknown {
+        /// The `edge type` value recorded in the graph.
+        type_name: String,
+        /// All other data items recorded on the edge, keyed by attribute name.
+        attrs: BTreeMap<String, String>,
+    },
+}
+
+impl EdgeType {
+    /// The `edge type` used for this type of edge in GraphML.
+    pub fn type_name(&self) -> &str {
+        match self {
+            Self::Filter { .. } => "filter",
+            Self::Structure { .. } => "structure",
+            Self::CrossDom { .. } => "cross DOM",
+            Self::ResourceBlock { .. } => "resource block",
+            Self::Shield { .. } => "shield",
+            Self::TextChange { .. } => "text change",
+            Self::RemoveNode { .. } => "remove node",
+            Self::DeleteNode { .. } => "delete node",
+            Self::InsertNode { .. } => "insert node",
+            Self::CreateNode { .. } => "create node",
+            Self::JsResult { .. } => "js result",
+            Self::JsCall { .. } => "js call",
+            Self::RequestComplete { .. } => "request complete",
+            Self::RequestError { .. } => "request error",
+            Self::RequestStart { .. } => "request start",
+            Self::RequestResponse { .. } => "request response",
+            Self::AddEventListener { .. } => "add event listener",
+            Self::RemoveEventListener { .. } => "remove event listener",
+            Self::EventListener { .. } => "event listener",
+            Self::StorageSet { .. } => "storage set",
+            Self::StorageReadResult { .. } => "storage read result",
+            Self::DeleteStorage { .. } => "delete storage",
+            Self::ReadStorageCall { .. } => "read storage call",
+            Self::ClearStorage { .. } => "clear storage",
+            Self::StorageBucket { .. } => "storage bucket",
+            Self::ExecuteFromAttribute { .. } => "execute from attribute",
+            Self::Execute { .. } => "execute",
+            Self::SetAttribute { .. } => "set attribute",
+            Self::DeleteAttribute { .. } => "delete attribute",
+            Self::Binding { .. } => "binding",
+            Self::BindingEvent { .. } => "binding event",
+            Self::Unknown { type_name, .. } => type_name,
+        }
+    }
+
+    /// The kind of this edge, for use with indexed lookups.
+    pub fn kind(&self) -> EdgeKind {
+        match self {
+            Self::Filter { .. } => EdgeKind::Filter,
+            Self::Structure { .. } => EdgeKind::Structure,
+            Self::CrossDom { .. } => EdgeKind::CrossDom,
+            Self::ResourceBlock { .. } => EdgeKind::ResourceBlock,
+            Self::Shield { .. } => EdgeKind::Shield,
+            Self::TextChange { .. } => EdgeKind::TextChange,
+            Self::RemoveNode { .. } => EdgeKind::RemoveNode,
+            Self::DeleteNode { .. } => EdgeKind::DeleteNode,
+            Self::InsertNode { .. } => EdgeKind::InsertNode,
+            Self::CreateNode { .. } => EdgeKind::CreateNode,
+            Self::JsResult { .. } => EdgeKind::JsResult,
+            Self::JsCall { .. } => EdgeKind::JsCall,
+            Self::RequestComplete { .. } => EdgeKind::RequestComplete,
+            Self::RequestError { .. } => EdgeKind::RequestError,
+            Self::RequestStart { .. } => EdgeKind::RequestStart,
+            Self::RequestResponse { .. } => EdgeKind::RequestResponse,
+            Self::AddEventListener { .. } => EdgeKind::AddEventListener,
+            Self::RemoveEventListener { .. } => EdgeKind::RemoveEventListener,
+            Self::EventListener { .. } => EdgeKind::EventListener,
+            Self::StorageSet { .. } => EdgeKind::StorageSet,
+            Self::StorageReadResult { .. } => EdgeKind::StorageReadResult,
+            Self::DeleteStorage { .. } => EdgeKind::DeleteStorage,
+            Self::ReadStorageCall { .. } => EdgeKind::ReadStorageCall,
+            Self::ClearStorage { .. } => EdgeKind::ClearStorage,
+            Self::StorageBucket { .. } => EdgeKind::StorageBucket,
+            Self::ExecuteFromAttribute { .. } => EdgeKind::ExecuteFromAttribute,
+            Self::Execute { .. } => EdgeKind::Execute,
+            Self::SetAttribute { .. } => EdgeKind::SetAttribute,
+            Self::DeleteAttribute { .. } => EdgeKind::DeleteAttribute,
+            Self::Binding { .. } => EdgeKind::Binding,
+            Self::BindingEvent { .. } => EdgeKind::BindingEvent,
+            Self::Unknown { .. } => EdgeKind::Unknown,
+        }
+    }
+}
+
+/// The kind of an [`EdgeType`], without any of its associated data. Every unknown edge type
+/// shares the `Unknown` kind.
+///
+/// Kinds are cheap to copy and compare, and can be matched exhaustively without destructuring.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, serde::Serialize)]
+pub enum EdgeKind {
+    Filter,
+    Structure,
+    CrossDom,
+    ResourceBlock,
+    Shield,
+    TextChange,
+    RemoveNode,
+    DeleteNode,
+    InsertNode,
+    CreateNode,
+    JsResult,
+    JsCall,
+    RequestComplete,
+    RequestError,
+    RequestStart,
+    RequestResponse,
+    AddEventListener,
+    RemoveEventListener,
+    EventListener,
+    StorageSet,
+    StorageReadResult,
+    DeleteStorage,
+    ReadStorageCall,
+    ClearStorage,
+    StorageBucket,
+    ExecuteFromAttribute,
+    Execute,
+    SetAttribute,
+    DeleteAttribute,
+    Binding,
+    BindingEvent,
+    Unknown,
 }
@@ -0,0 +1,95 @@
+//! Helpers for interpreting the URLs recorded in a graph. None of these panic; URLs and hosts that
+//! cannot be interpreted produce `None` instead.
+
+use std::net::Ipv6Addr;
+
+/// Returns the host of a URL, in its canonical ASCII form. IPv6 addresses are enclosed in
+/// brackets.
+pub fn host(url: &str) -> Option<String> {
+    url::Url::parse(url).ok()?.host().map(|host| host.to_string())
+}
+
+/// Returns the registrable domain (eTLD+1) of a host, e.g. `example.co.uk` for
+/// `www.example.co.uk`. Internationalized hosts are converted to punycode first.
+///
+/// IP addresses are returned in canonical form, since they have no registrable domain but still
+/// identify a single site. Returns `None` if the host is empty or unparsable, or is itself a
+/// public suffix.
+pub fn registrable_domain(host: &str) -> Option<String> {
+    // Hosts taken from elsewhere than a URL may not bracket IPv6 addresses.
+    if let Ok(ip) = host.parse::<Ipv6Addr>() {
+        return Some(url::Host::<String>::Ipv6(ip).to_string());
+    }
+    match url::Host::parse(host.trim_end_matches('.')).ok()? {
+        url::Host::Domain(domain) => addr::parse_domain_name(&domain).ok()?.root().map(str::to_string),
+        ip => Some(ip.to_string()),
+    }
+}
+
+/// Returns the site of a URL: the registrable domain of its host, or the host itself if it has
+/// none.
+pub fn site(url: &str) -> Option<String> {
+    let host = host(url)?;
+    Some(registrable_domain(&host).unwrap_or(host))
+}
+
+/// Returns whether `url` is third-party to a document at `document_url`, i.e. whether they belong
+/// to different sites. Returns `None` if either URL has no host.
+pub fn is_third_party(url: &str, document_url: &str) -> Option<bool> {
+    Some(site(url)? != site(document_url)?)
+}
+
+/// Returns the serialized origin of a URL, e.g. `https://example.com:8080`. Returns `None` if the
+/// URL is unparsable or has an opaque origin, like `data:` URLs.
+pub fn origin(url: &str) -> Option<String> {
+    let origin = url::Url::parse(url).ok()?.origin();
+    if origin.is_tuple() {
+        Some(origin.ascii_serialization())
+    } else {
+        None
+    }
+}
+
+/// Normalizes a URL for comparison: the scheme and host are lowercased, internationalized hosts
+/// are converted to punycode, default ports and dot segments are removed, and the fragment is
+/// dropped. Returns `None` if the URL is unparsable.
+pub fn normalize(url: &str) -> Option<String> {
+    let mut url = url::Url::parse(url).ok()?;
+    url.set_fragment(None);
+    Some(url.into())
+}
+
+#[cfg(test)]
+mod url_utils_tests {
+    use super::*;
+
+    #[test]
+    fn test_registrable_domain() {
+        assert_eq!(registrable_domain("www.example.co.uk").as_deref(), Some("example.co.uk"));
+        assert_eq!(registrable_domain("Example.COM.").as_deref(), Some("example.com"));
+        assert_eq!(registrable_domain("bücher.example").as_deref(), Some("xn--bcher-kva.example"));
+        assert_eq!(registrable_domain("192.168.0.1").as_deref(), Some("192.168.0.1"));
+        assert_eq!(registrable_domain("[::1]").as_deref(), Some("[::1]"));
+        assert_eq!(registrable_domain("::1").as_deref(), Some("[::1]"));
+        assert_eq!(registrable_domain("co.uk"), None);
+        assert_eq!(registrable_domain(""), None);
+        assert_eq!(registrable_domain("exa mple.com"), None);
+    }
+
+    #[test]
+    fn test_partiness() {
+        assert_eq!(is_third_party("https://cdn.example.com/a.js", "https://www.example.com/"), Some(false));
+        assert_eq!(is_third_party("https://tracker.net/t.js", "https://example.com/"), Some(true));
+        assert_eq!(is_third_party("http://127.0.0.1:8080/", "http://127.0.0.1/"), Some(false));
+        assert_eq!(is_third_party("data:text/plain,hi", "https://example.com/"), None);
+    }
+
+    #[test]
+    fn test_origin_and_normalize() {
+        assert_eq!(origin("HTTPS://Example.com:443/a?b#c").as_deref(), Some("https://example.com"));
+        assert_eq!(origin("http://example.com:8080/").as_deref(), Some("http://example.com:8080"));
+        assert_eq!(origin("data:text/plain,hi"), None);
+        assert_eq!(normalize("HTTP://Example.com:80/a/../b?c#d").as_deref(), Some("http://example.com/b?c"));
+        assert_eq!(normalize("not a url"), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! A configurable breadth- or depth-first traversal of a graph, so that analyses can walk the
+//! nodes reachable from a starting point without reimplementing the bookkeeping each time.
+//!
+//! ```no_run
+//! # let graph: pagegraph::graph::PageGraph = unimplemented!();
+//! # let script = unimplemented!();
+//! use pagegraph::types::EdgeType;
+//! use pagegraph::walk::{GraphWalker, WalkDirection};
+//!
+//! let reached = GraphWalker::new(&graph)
+//!     .direction(WalkDirection::Outgoing)
+//!     .edge_filter(|edge| !matches!(edge.edge_type, EdgeType::Structure {}))
+//!     .max_depth(3)
+//!     .walk(script)
+//!     .map(|step| step.node.id)
+//!     .collect::<Vec<_>>();
+//! ```
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::graph::{Edge, Node, NodeId, PageGraph};
+
+/// Which edges a [`GraphWalker`] follows from each node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkDirection {
+    /// Follow edges from their source to their target.
+    Outgoing,
+    /// Follow edges from their target back to their source.
+    Incoming,
+    /// Follow edges either way.
+    Both,
+}
+
+/// The order in which a [`GraphWalker`] visits nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// Visit every node at one depth before any deeper node.
+    BreadthFirst,
+    /// Follow each branch as deep as it goes before backtracking.
+    DepthFirst,
+}
+
+/// A node visited by a [`Walk`].
+#[derive(Debug, Clone, Copy)]
+pub struct WalkStep<'a> {
+    pub node: &'a Node,
+    /// The edge followed to reach the node, or `None` for the starting node.
+    pub edge: Option<&'a Edge>,
+    /// The number of edges between the starting node and this one.
+    pub depth: usize,
+}
+
+/// Configures a traversal of a graph. Each node is visited at most once, by the first edge that
+/// reaches it.
+pub struct GraphWalker<'a> {
+    graph: &'a PageGraph,
+    direction: WalkDirection,
+    order: WalkOrder,
+    edge_filter: Box<dyn Fn(&Edge) -> bool + 'a>,
+    max_depth: Option<usize>,
+}
+
+impl<'a> GraphWalker<'a> {
+    /// Creates a breadth-first walker following outgoing edges of any type, to any depth.
+    pub fn new(graph: &'a PageGraph) -> Self {
+        Self {
+            graph,
+            direction: WalkDirection::Outgoing,
+            order: WalkOrder::BreadthFirst,
+            edge_filter: Box::new(|_| true),
+            max_depth: None,
+        }
+    }
+
+    pub fn direction(mut self, direction: WalkDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn order(mut self, order: WalkOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Only follow edges for which `filter` returns `true`.
+    pub fn edge_filter(mut self, filter: impl Fn(&Edge) -> bool + 'a) -> Self {
+        self.edge_filter = Box::new(filter);
+        self
+    }
+
+    /// Don't follow edges from nodes this many edges away from the starting node.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Starts walking from the given node, which is visited first. Nothing is visited if the node
+    /// is not in the graph.
+    pub fn walk(self, start: NodeId) -> Walk<'a> {
+        let mut pending = VecDeque::new();
+        if let Some(node) = self.graph.nodes.get(&start) {
+            pending.push_back(WalkStep { node, edge: None, depth: 0 });
+        }
+        Walk {
+            walker: self,
+            pending,
+            visited: HashSet::new(),
+        }
+    }
+}
+
+/// An iterator over the nodes reached by a [`GraphWalker`].
+pub struct Walk<'a> {
+    walker: GraphWalker<'a>,
+    pending: VecDeque<WalkStep<'a>>,
+    visited: HashSet<NodeId>,
+}
+
+impl<'a> Walk<'a> {
+    /// Returns the edges followed from a node, and the node each leads to, in id order.
+    fn next_edges(&self, node: &'a Node) -> Vec<(&'a Edge, NodeId)> {
+        let graph = self.walker.graph;
+        let outgoing = graph.outgoing_edges(node).map(|edge| (edge, edge.target));
+        let incoming = graph.incoming_edges(node).map(|edge| (edge, edge.source));
+        let mut edges = match self.walker.direction {
+            WalkDirection::Outgoing => outgoing.collect::<Vec<_>>(),
+            WalkDirection::Incoming => incoming.collect(),
+            WalkDirection::Both => outgoing.chain(incoming).collect(),
+        };
+        edges.retain(|(edge, next)| !self.visited.contains(next) && (self.walker.edge_filter)(edge));
+        edges.sort_unstable_by_key(|(edge, _)| edge.id);
+        edges
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = WalkStep<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let step = match self.walker.order {
+                WalkOrder::BreadthFirst => self.pending.pop_front()?,
+                WalkOrder::DepthFirst => self.pending.pop_back()?,
+            };
+            if !self.visited.insert(step.node.id) {
+                continue;
+            }
+            if !matches!(self.walker.max_depth, Some(max_depth) if step.depth >= max_depth) {
+                let mut next = self.next_edges(step.node).into_iter().map(|(edge, next)| WalkStep {
+                    node: &self.walker.graph.nodes[&next],
+                    edge: Some(edge),
+                    depth: step.depth + 1,
+                }).collect::<Vec<_>>();
+                // Depth-first walks pop from the back, so push in reverse to visit in id order
+                if self.walker.order == WalkOrder::DepthFirst {
+                    next.reverse();
+                }
+                self.pending.extend(next);
+            }
+            return Some(step);
+        }
+    }
+}
+
+#[cfg(test)]
+mod walk_tests {
+    use super::*;
+    use crate::types::EdgeType;
+
+    #[test]
+    fn test_graph_walker() {
+        let graph = crate::test_util::parse(r#"<node id="n2"><data key="d0">script</data><data key="d1">2</data><data key="d2">0</data><data key="d8">classic</data><data key="d9">11</data><data key="d10"></data></node>
+<node id="n3"><data key="d0">HTML element</data><data key="d1">3</data><data key="d2">0</data><data key="d4">img</data><data key="d5">false</data><data key="d6">5</data></node>
+<node id="n4"><data key="d0">resource</data><data key="d1">4</data><data key="d2">0</data><data key="d3">https://tracker.net/p.gif</data></node>
+<edge id="e5" source="n2" target="n3"><data key="d16">create node</data><data key="d17">5</data><data key="d18">2</data></edge>
+<edge id="e6" source="n2" target="n3"><data key="d16">set attribute</data><data key="d17">6</data><data key="d18">5</data><data key="d21">src</data><data key="d20">https://tracker.net/p.gif</data><data key="d22">false</data></edge>
+<edge id="e7" source="n3" target="n4"><data key="d16">request start</data><data key="d17">7</data><data key="d18">3</data><data key="d28">1</data><data key="d29">Image</data><data key="d30">started</data></edge>
+<edge id="e8" source="n2" target="n4"><data key="d16">structure</data><data key="d17">8</data></edge>"#);
+        let node_id = |id: &str| graph.nodes.keys().find(|node_id| format!("{}", node_id) == id).copied().unwrap();
+        let visited = |walk: Walk| walk
+            .map(|step| format!("{}:{}", step.node.id, step.edge.map(|edge| format!("{}", edge.id)).unwrap_or_default()))
+            .collect::<Vec<_>>();
+
+        let walk = GraphWalker::new(&graph).walk(node_id("n2"));
+        assert_eq!(visited(walk), ["n2:", "n3:e5", "n4:e8"]);
+
+        let no_structure = || GraphWalker::new(&graph)
+            .edge_filter(|edge| !matches!(edge.edge_type, EdgeType::Structure {}));
+        assert_eq!(visited(no_structure().walk(node_id("n2"))), ["n2:", "n3:e5", "n4:e7"]);
+        assert_eq!(visited(no_structure().max_depth(1).walk(node_id("n2"))), ["n2:", "n3:e5"]);
+        assert_eq!(visited(no_structure().walk(node_id("n4"))), ["n4:"]);
+        assert_eq!(
+            visited(no_structure().direction(WalkDirection::Incoming).order(WalkOrder::DepthFirst).walk(node_id("n4"))),
+            ["n4:", "n3:e7", "n2:e5"],
+        );
+
+        let depths = GraphWalker::new(&graph).direction(WalkDirection::Both).walk(node_id("n3"))
+            .map(|step| step.depth)
+            .collect::<Vec<_>>();
+        assert_eq!(depths, [0, 1, 1]);
+    }
+}
@@ -0,0 +1,138 @@
+//! Accounting of the bytes a page loaded, broken down by resource type, party, frame, and entity.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::entities::EntityMap;
+use crate::graph::{HasFrameId, PageGraph};
+use crate::types::EdgeKind;
+use crate::url_utils;
+
+/// The number and size of the responses in a [`PageWeight`] breakdown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct WeightCounts {
+    pub responses: usize,
+    /// Responses to a resource that had already been loaded, which were likely served from the
+    /// cache.
+    pub cached: usize,
+    /// Responses whose size could not be determined.
+    pub unknown_size: usize,
+    /// The total size of the fetched responses.
+    pub bytes: u64,
+    /// The total size of the cached responses.
+    pub cached_bytes: u64,
+}
+
+impl WeightCounts {
+    fn count(&mut self, size: Option<u64>, cached: bool) {
+        self.responses += 1;
+        match (size, cached) {
+            (None, _) => self.unknown_size += 1,
+            (Some(size), false) => self.bytes += size,
+            (Some(size), true) => self.cached_bytes += size,
+        }
+        if cached {
+            self.cached += 1;
+        }
+    }
+}
+
+/// The responses sharing a resource type, party, frame, or entity.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WeightBucket {
+    pub name: String,
+    #[serde(flatten)]
+    pub counts: WeightCounts,
+}
+
+/// The bytes a page loaded, as reported by [`PageGraph::page_weight`]. Each breakdown is sorted
+/// with the most fetched bytes first.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct PageWeight {
+    pub total: WeightCounts,
+    /// By the resource type reported when each request completed.
+    pub by_resource_type: Vec<WeightBucket>,
+    /// `first-party` or `third-party`, relative to the document that made each request, or
+    /// `unknown` if either URL has no site.
+    pub by_party: Vec<WeightBucket>,
+    /// By the id of the frame each request was made in.
+    pub by_frame: Vec<WeightBucket>,
+    /// By the entity owning each resource, or its site if it has no known owner. Empty unless an
+    /// entity map was given.
+    pub by_entity: Vec<WeightBucket>,
+}
+
+fn buckets(counts: BTreeMap<String, WeightCounts>) -> Vec<WeightBucket> {
+    let mut buckets = counts.into_iter().map(|(name, counts)| WeightBucket { name, counts }).collect::<Vec<_>>();
+    buckets.sort_by(|a, b| b.counts.bytes.cmp(&a.counts.bytes).then_with(|| a.name.cmp(&b.name)));
+    buckets
+}
+
+impl PageGraph {
+    /// Sums the sizes of every completed response, overall and by resource type, party, frame,
+    /// and, if an entity map is given, entity. A response is considered cached if an earlier
+    /// response was recorded for the same resource in the same frame.
+    pub fn page_weight(&self, entities: Option<&EntityMap>) -> PageWeight {
+        let mut completes = self.edges_of_kind(EdgeKind::RequestComplete).collect::<Vec<_>>();
+        completes.sort_unstable_by_key(|edge| (edge.edge_timestamp, edge.id));
+
+        let mut weight = PageWeight::default();
+        let mut by_resource_type = BTreeMap::<String, WeightCounts>::new();
+        let mut by_party = BTreeMap::<String, WeightCounts>::new();
+        let mut by_frame = BTreeMap::<String, WeightCounts>::new();
+        let mut by_entity = BTreeMap::<String, WeightCounts>::new();
+        let mut loaded = HashSet::new();
+        completes.into_iter().for_each(|edge| {
+            let complete = match edge.as_request_complete() {
+                Some(complete) => complete,
+                None => return,
+            };
+            let url = self.source_node(edge).as_resource().unwrap_or_default();
+            let cached = !loaded.insert(edge.source);
+
+            weight.total.count(complete.size, cached);
+            by_resource_type.entry(complete.resource_type.to_string()).or_default().count(complete.size, cached);
+            let party = match url_utils::is_third_party(url, &self.document_url_for(edge.id)) {
+                Some(true) => "third-party",
+                Some(false) => "first-party",
+                None => "unknown",
+            };
+            by_party.entry(party.to_string()).or_default().count(complete.size, cached);
+            let frame_id = edge.id.get_frame_id().unwrap_or(self.desc.frame_id);
+            by_frame.entry(format!("{}", frame_id)).or_default().count(complete.size, cached);
+            if let Some((entity, _)) = entities.and_then(|entities| entities.entity_or_site(url)) {
+                by_entity.entry(entity).or_default().count(complete.size, cached);
+            }
+        });
+
+        weight.by_resource_type = buckets(by_resource_type);
+        weight.by_party = buckets(by_party);
+        weight.by_frame = buckets(by_frame);
+        weight.by_entity = buckets(by_entity);
+        weight
+    }
+}
+
+#[cfg(test)]
+mod weight_tests {
+    #[test]
+    fn test_page_weight() {
+        let graph = crate::test_util::parse(r#"<node id="n1"><data key="d0">parser</data><data key="d1">1</data><data key="d2">0</data></node>
+<node id="n2"><data key="d0">resource</data><data key="d1">2</data><data key="d2">0</data><data key="d3">https://example.com/logo.png</data></node>
+<node id="n3"><data key="d0">resource</data><data key="d1">3</data><data key="d2">0</data><data key="d3">https://cdn.other.net/lib.js</data></node>
+<edge id="e4" source="n2" target="n1"><data key="d16">request complete</data><data key="d17">4</data><data key="d18">2</data><data key="d28">1</data><data key="d29">image</data><data key="d30">complete</data><data key="d32"></data><data key="d33">300</data></edge>
+<edge id="e5" source="n2" target="n1"><data key="d16">request complete</data><data key="d17">5</data><data key="d18">5</data><data key="d28">2</data><data key="d29">image</data><data key="d30">complete</data><data key="d32"></data><data key="d33">300</data></edge>
+<edge id="e6" source="n3" target="n1"><data key="d16">request complete</data><data key="d17">6</data><data key="d18">4</data><data key="d28">3</data><data key="d29">script</data><data key="d30">complete</data><data key="d32"></data><data key="d33">1000</data></edge>"#);
+
+        let weight = graph.page_weight(None);
+        assert_eq!((weight.total.responses, weight.total.cached, weight.total.bytes, weight.total.cached_bytes), (3, 1, 1300, 300));
+        assert_eq!(weight.by_resource_type.iter().map(|bucket| bucket.name.as_str()).collect::<Vec<_>>(), ["script", "image"]);
+        assert_eq!(weight.by_party[0].name, "third-party");
+        assert_eq!(weight.by_party[1].counts.cached, 1);
+        assert_eq!(weight.by_frame[0].name, "0000000000000000000000000000000A");
+        assert!(weight.by_entity.is_empty());
+
+        let entities = crate::entities::EntityMap::from_json(r#"{"Other": {"properties": ["other.net"]}}"#).unwrap();
+        let weight = graph.page_weight(Some(&entities));
+        assert_eq!(weight.by_entity.iter().map(|bucket| bucket.name.as_str()).collect::<Vec<_>>(), ["Other", "example.com"]);
+    }
+}